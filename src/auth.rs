@@ -0,0 +1,88 @@
+//! OAuth login for `dbtoon auth login`.
+
+use std::net::TcpListener;
+
+use reqwest::Client;
+
+use crate::backend::oauth;
+use crate::cli::AuthLoginArgs;
+use crate::config::{self, BackendConfig, DatabricksAuth, TomlConfig};
+use crate::error::DbtoonError;
+
+/// Sign the user in to the profile's workspace and save the tokens to the cache.
+///
+/// Uses the browser unless `--device-code` is given or the machine looks
+/// headless (an SSH session, or Linux without a display).
+pub async fn login(args: &AuthLoginArgs, toml_config: &TomlConfig) -> Result<(), DbtoonError> {
+    let profile = config::load_profile(toml_config, &args.profile)?;
+    let (host, client_id, token_cache) = match config::build_backend_config(&profile, None, None)? {
+        BackendConfig::Databricks {
+            host,
+            auth: DatabricksAuth::OAuthU2M { client_id, token_cache },
+            ..
+        } => (host, client_id, token_cache),
+        _ => {
+            return Err(DbtoonError::Config {
+                message: format!(
+                    "profile '{}' is not a databricks profile with auth = \"oauth-u2m\"",
+                    args.profile
+                ),
+            });
+        }
+    };
+
+    let client = Client::new();
+    let tokens = if args.device_code || is_headless() {
+        oauth::device_login(&client, &host, &client_id, |device| {
+            let url = device
+                .verification_uri_complete
+                .as_deref()
+                .unwrap_or(&device.verification_uri);
+            eprintln!("To log in, visit {} and enter the code {}", url, device.user_code);
+        })
+        .await?
+    } else {
+        let listener = TcpListener::bind(("127.0.0.1", args.port)).map_err(|e| DbtoonError::Auth {
+            message: format!(
+                "cannot listen for the login redirect on port {}: {} (try --port or --device-code)",
+                args.port, e
+            ),
+        })?;
+        oauth::browser_login(&client, &host, &client_id, listener, |url| {
+            eprintln!("Opening your browser to log in. If it does not open, visit:\n\n  {}\n", url);
+            open_browser(url);
+        })
+        .await?
+    };
+
+    tokens.save(&host, &client_id, &token_cache)?;
+    eprintln!("Logged in to {} (tokens cached in {})", host, token_cache.display());
+    Ok(())
+}
+
+fn is_headless() -> bool {
+    if config::env_non_empty("SSH_CONNECTION").is_some() {
+        return true;
+    }
+    cfg!(target_os = "linux")
+        && config::env_non_empty("DISPLAY").is_none()
+        && config::env_non_empty("WAYLAND_DISPLAY").is_none()
+}
+
+/// Best effort: the URL is also printed, so a failure here is not an error.
+fn open_browser(url: &str) {
+    let mut command = if cfg!(target_os = "macos") {
+        std::process::Command::new("open")
+    } else if cfg!(windows) {
+        let mut command = std::process::Command::new("rundll32");
+        command.arg("url.dll,FileProtocolHandler");
+        command
+    } else {
+        std::process::Command::new("xdg-open")
+    };
+    let _ = command
+        .arg(url)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn();
+}
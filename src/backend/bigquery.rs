@@ -0,0 +1,844 @@
+//! BigQuery backend over the REST API (`jobs.query` and `jobs.getQueryResults`),
+//! signing in with a service account key.
+
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use reqwest::{Client, RequestBuilder, Response};
+use rsa::RsaPrivateKey;
+use rsa::pkcs8::DecodePrivateKey;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::backend::databricks::host_url;
+use crate::backend::retry::{Idempotency, RetryPolicy, send_with_retry};
+use crate::backend::snowflake::rs256_jwt;
+use crate::backend::value::{parse_date32, parse_decimal, parse_time_micros, parse_timestamp_micros};
+use crate::backend::{
+    Backend, CellValue, ColumnMeta, QueryResult, ResultSink, StreamSummary, http_client,
+};
+use crate::cancel::CancelToken;
+use crate::error::DbtoonError;
+use crate::params::{QueryParam, to_at_named};
+
+const DEFAULT_HOST: &str = "https://bigquery.googleapis.com";
+
+const DEFAULT_TOKEN_URI: &str = "https://oauth2.googleapis.com/token";
+
+const SCOPE: &str = "https://www.googleapis.com/auth/bigquery";
+
+/// Lifetime requested for the signed assertion; Google allows at most an hour.
+const ASSERTION_LIFETIME: Duration = Duration::from_secs(3600);
+
+/// Access tokens are replaced this long before Google says they expire.
+const TOKEN_EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+/// Rows requested per result page.
+const PAGE_SIZE: usize = 10_000;
+
+/// Longest one request waits on the server for the job to finish.
+const MAX_WAIT_MS: u64 = 10_000;
+
+/// Credentials from a service account JSON key file.
+pub struct ServiceAccount {
+    pub client_email: String,
+    pub private_key_id: Option<String>,
+    pub project_id: Option<String>,
+    pub token_uri: String,
+    key: RsaPrivateKey,
+}
+
+#[derive(Deserialize)]
+struct KeyFile {
+    #[serde(rename = "type")]
+    key_type: Option<String>,
+    project_id: Option<String>,
+    private_key_id: Option<String>,
+    private_key: String,
+    client_email: String,
+    token_uri: Option<String>,
+}
+
+impl ServiceAccount {
+    /// A signed JWT assertion, issued at `issued_at` (seconds since the epoch),
+    /// to exchange for an access token at `token_uri`.
+    pub fn assertion(&self, issued_at: u64) -> String {
+        let mut header = serde_json::json!({ "alg": "RS256", "typ": "JWT" });
+        if let Some(kid) = &self.private_key_id {
+            header["kid"] = kid.clone().into();
+        }
+        let claims = serde_json::json!({
+            "iss": self.client_email,
+            "scope": SCOPE,
+            "aud": self.token_uri,
+            "iat": issued_at,
+            "exp": issued_at + ASSERTION_LIFETIME.as_secs(),
+        });
+        rs256_jwt(&header, &claims, &self.key)
+    }
+}
+
+/// Read a service account JSON key file, as downloaded from the Cloud console.
+pub fn load_service_account(path: &Path) -> Result<ServiceAccount, DbtoonError> {
+    let text = std::fs::read_to_string(path).map_err(|e| DbtoonError::Config {
+        message: format!("cannot read service account key '{}': {}", path.display(), e),
+    })?;
+    let file: KeyFile = serde_json::from_str(&text).map_err(|e| DbtoonError::Config {
+        message: format!("invalid service account key '{}': {}", path.display(), e),
+    })?;
+    if let Some(key_type) = file.key_type.as_deref()
+        && key_type != "service_account"
+    {
+        return Err(DbtoonError::Config {
+            message: format!(
+                "'{}' is a '{}' credential, not a service account key",
+                path.display(),
+                key_type
+            ),
+        });
+    }
+    let key = RsaPrivateKey::from_pkcs8_pem(&file.private_key).map_err(|e| DbtoonError::Config {
+        message: format!("invalid private key in '{}': {}", path.display(), e),
+    })?;
+    Ok(ServiceAccount {
+        client_email: file.client_email,
+        private_key_id: file.private_key_id,
+        project_id: file.project_id,
+        token_uri: file.token_uri.unwrap_or_else(|| DEFAULT_TOKEN_URI.to_string()),
+        key,
+    })
+}
+
+pub struct BigQueryBackend {
+    project: String,
+    account: Option<ServiceAccount>,
+    dataset: Option<String>,
+    location: Option<String>,
+    host: Option<String>,
+    dry_run: bool,
+    cancel: CancelToken,
+    retry: RetryPolicy,
+    verbose: bool,
+    client: Client,
+    token: Mutex<Option<(Instant, String)>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct QueryRequest {
+    query: String,
+    use_legacy_sql: bool,
+    dry_run: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_results: Option<usize>,
+    timeout_ms: u64,
+    job_timeout_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    location: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    default_dataset: Option<DatasetReference>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parameter_mode: Option<&'static str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    query_parameters: Vec<serde_json::Value>,
+    format_options: FormatOptions,
+    /// Makes resubmitting the same request return the original job.
+    request_id: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DatasetReference {
+    project_id: String,
+    dataset_id: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FormatOptions {
+    /// Timestamps as integer microseconds rather than floating-point seconds.
+    use_int64_timestamp: bool,
+}
+
+/// A `jobs.query` or `jobs.getQueryResults` response: the job's state and,
+/// once it is complete, the schema and one page of rows.
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct QueryResponse {
+    job_complete: Option<bool>,
+    job_reference: Option<JobReference>,
+    schema: Option<TableSchema>,
+    #[serde(default)]
+    rows: Vec<Row>,
+    page_token: Option<String>,
+    total_rows: Option<String>,
+    total_bytes_processed: Option<String>,
+    num_dml_affected_rows: Option<String>,
+    #[serde(default)]
+    errors: Vec<ErrorProto>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct JobReference {
+    job_id: String,
+    location: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct TableSchema {
+    #[serde(default)]
+    fields: Vec<Field>,
+}
+
+/// A column in a result schema; `RECORD` columns nest their own `fields`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Field {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_name: String,
+    /// `NULLABLE`, `REQUIRED` or `REPEATED`.
+    pub mode: Option<String>,
+    #[serde(default)]
+    pub fields: Vec<Field>,
+    pub precision: Option<String>,
+    pub scale: Option<String>,
+}
+
+impl Field {
+    fn repeated(&self) -> bool {
+        self.mode.as_deref() == Some("REPEATED")
+    }
+
+    fn record(&self) -> bool {
+        matches!(self.type_name.as_str(), "RECORD" | "STRUCT")
+    }
+}
+
+#[derive(Deserialize)]
+struct Row {
+    #[serde(default)]
+    f: Vec<Cell>,
+}
+
+#[derive(Deserialize)]
+struct Cell {
+    #[serde(default)]
+    v: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct ErrorProto {
+    reason: Option<String>,
+    message: Option<String>,
+}
+
+impl ErrorProto {
+    fn format_message(&self) -> String {
+        match (&self.reason, &self.message) {
+            (Some(reason), Some(msg)) => format!("[{}] {}", reason, msg),
+            (Some(reason), None) => format!("[{}]", reason),
+            (None, Some(msg)) => msg.clone(),
+            (None, None) => "unknown error".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: ErrorBody,
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    message: Option<String>,
+    #[serde(default)]
+    errors: Vec<ErrorProto>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<u64>,
+}
+
+impl BigQueryBackend {
+    /// A backend running jobs in `project`, signed in as `account` (or
+    /// anonymously, for an emulator).
+    pub fn new(
+        project: String,
+        account: Option<ServiceAccount>,
+        dataset: Option<String>,
+        location: Option<String>,
+    ) -> Self {
+        Self {
+            project,
+            account,
+            dataset,
+            location,
+            host: None,
+            dry_run: false,
+            cancel: CancelToken::default(),
+            retry: RetryPolicy::default(),
+            verbose: false,
+            client: http_client(),
+            token: Mutex::new(None),
+        }
+    }
+
+    /// Send requests to `host` instead of `bigquery.googleapis.com`, e.g. an emulator.
+    pub fn with_host(mut self, host: Option<String>) -> Self {
+        self.host = host;
+        self
+    }
+
+    /// Validate queries and estimate the bytes they would process instead of running them.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Retry transient HTTP failures under `retry`.
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Log retries to stderr.
+    pub fn with_verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    /// Cancel the job in BigQuery when `cancel` fires.
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    fn base_url(&self) -> String {
+        let host = self.host.as_deref().unwrap_or(DEFAULT_HOST);
+        format!("{}/bigquery/v2/projects/{}", host_url(host), self.project)
+    }
+
+    /// An access token for the service account, fetched again shortly before
+    /// the cached one expires; `None` without an account.
+    async fn token(&self) -> Result<Option<String>, DbtoonError> {
+        let Some(account) = &self.account else {
+            return Ok(None);
+        };
+        let mut cached = self.token.lock().await;
+        if let Some((expires_at, token)) = cached.as_ref()
+            && Instant::now() < *expires_at
+        {
+            return Ok(Some(token.clone()));
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let assertion = account.assertion(now);
+        let resp = send_with_retry(&self.retry, Idempotency::Idempotent, self.verbose, "token request", || {
+            self.client.post(&account.token_uri).form(&[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                ("assertion", assertion.as_str()),
+            ])
+        })
+        .await
+        .map_err(|e| DbtoonError::Connection {
+            message: format!("failed to request access token: {}", e),
+        })?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(DbtoonError::Auth {
+                message: format!(
+                    "token request for service account '{}' failed (HTTP {}): {}",
+                    account.client_email,
+                    status.as_u16(),
+                    body.trim()
+                ),
+            });
+        }
+        let token: TokenResponse = resp.json().await.map_err(|e| DbtoonError::Auth {
+            message: format!("failed to parse token response: {}", e),
+        })?;
+        let lifetime = Duration::from_secs(token.expires_in.unwrap_or(ASSERTION_LIFETIME.as_secs()));
+        *cached = Some((
+            Instant::now() + lifetime.saturating_sub(TOKEN_EXPIRY_MARGIN),
+            token.access_token.clone(),
+        ));
+        Ok(Some(token.access_token))
+    }
+
+    fn authorized(&self, request: RequestBuilder, token: Option<&str>) -> RequestBuilder {
+        let request = request.header("User-Agent", concat!("dbtoon/", env!("CARGO_PKG_VERSION")));
+        match token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// Start the query with `jobs.query`, which waits briefly for it to finish.
+    async fn submit(
+        &self,
+        sql: &str,
+        params: &[QueryParam],
+        page_size: usize,
+        timeout_secs: u64,
+    ) -> Result<QueryResponse, DbtoonError> {
+        let (sql, bound) = to_at_named(sql, params)?;
+        let request = QueryRequest {
+            query: sql,
+            use_legacy_sql: false,
+            dry_run: self.dry_run,
+            max_results: (!self.dry_run).then_some(page_size),
+            timeout_ms: MAX_WAIT_MS.min(timeout_secs.saturating_mul(1000)),
+            job_timeout_ms: timeout_secs.saturating_mul(1000),
+            location: self.location.clone(),
+            default_dataset: self.dataset.as_deref().map(|dataset| match dataset.split_once('.') {
+                Some((project, dataset)) => DatasetReference {
+                    project_id: project.to_string(),
+                    dataset_id: dataset.to_string(),
+                },
+                None => DatasetReference {
+                    project_id: self.project.clone(),
+                    dataset_id: dataset.to_string(),
+                },
+            }),
+            parameter_mode: (!bound.is_empty()).then_some("NAMED"),
+            query_parameters: bound.into_iter().map(query_parameter).collect(),
+            format_options: FormatOptions { use_int64_timestamp: true },
+            request_id: request_id()?,
+        };
+
+        let url = format!("{}/queries", self.base_url());
+        let token = self.token().await?;
+        // The request id lets BigQuery recognize a resubmission, so retrying cannot run the query twice
+        let resp = send_with_retry(&self.retry, Idempotency::Idempotent, self.verbose, "query submit", || {
+            self.authorized(self.client.post(&url), token.as_deref()).json(&request)
+        })
+        .await
+        .map_err(|e| DbtoonError::Connection {
+            message: format!("failed to execute statement: {}", e),
+        })?;
+        read_response(resp).await
+    }
+
+    /// One page of a job's results, waiting up to `wait_ms` for the job to finish.
+    async fn fetch_results(
+        &self,
+        job: &JobReference,
+        page_token: Option<&str>,
+        page_size: usize,
+        wait_ms: u64,
+    ) -> Result<QueryResponse, DbtoonError> {
+        let url = format!("{}/queries/{}", self.base_url(), job.job_id);
+        let mut query: Vec<(&str, String)> = vec![
+            ("maxResults", page_size.to_string()),
+            ("timeoutMs", wait_ms.to_string()),
+            ("formatOptions.useInt64Timestamp", "true".to_string()),
+        ];
+        if let Some(location) = &job.location {
+            query.push(("location", location.clone()));
+        }
+        if let Some(page_token) = page_token {
+            query.push(("pageToken", page_token.to_string()));
+        }
+
+        let token = self.token().await?;
+        let resp = send_with_retry(&self.retry, Idempotency::Idempotent, self.verbose, "result page fetch", || {
+            self.authorized(self.client.get(&url), token.as_deref()).query(&query)
+        })
+        .await
+        .map_err(|e| DbtoonError::Connection {
+            message: format!("failed to fetch result page: {}", e),
+        })?;
+        read_response(resp).await
+    }
+
+    /// Ask BigQuery to stop a job. Best effort: failures are ignored.
+    async fn cancel_job(&self, job: &JobReference) {
+        let url = format!("{}/jobs/{}/cancel", self.base_url(), job.job_id);
+        let Ok(token) = self.token().await else {
+            return;
+        };
+        let mut request = self.authorized(self.client.post(&url), token.as_deref());
+        if let Some(location) = &job.location {
+            request = request.query(&[("location", location)]);
+        }
+        let _ = request.send().await;
+    }
+
+    /// The next page of `job`'s results, waiting for the job to finish first;
+    /// cancelling the job when `cancel` fires or `timeout_secs` have passed since `start`.
+    async fn next_page(
+        &self,
+        job: &JobReference,
+        page_token: Option<&str>,
+        page_size: usize,
+        start: Instant,
+        timeout_secs: u64,
+    ) -> Result<QueryResponse, DbtoonError> {
+        let remaining = Duration::from_secs(timeout_secs).saturating_sub(start.elapsed());
+        if remaining.is_zero() {
+            self.cancel_job(job).await;
+            return Err(DbtoonError::Timeout {
+                seconds: timeout_secs,
+            });
+        }
+        let wait_ms = MAX_WAIT_MS.min(remaining.as_millis().try_into().unwrap_or(u64::MAX));
+        tokio::select! {
+            page = self.fetch_results(job, page_token, page_size, wait_ms) => page,
+            _ = self.cancel.cancelled() => {
+                self.cancel_job(job).await;
+                Err(DbtoonError::Cancelled)
+            }
+        }
+    }
+
+    /// Run the query and feed its pages to `sink` until the last one, or until
+    /// `limit` rows have been written and more remain.
+    async fn run(
+        &self,
+        sql: &str,
+        params: &[QueryParam],
+        limit: Option<usize>,
+        timeout_secs: u64,
+        sink: &mut dyn ResultSink,
+    ) -> Result<StreamSummary, DbtoonError> {
+        let start = Instant::now();
+        let page_size = limit.map_or(PAGE_SIZE, |lim| lim.saturating_add(1).min(PAGE_SIZE));
+        let mut page = tokio::select! {
+            page = self.submit(sql, params, page_size, timeout_secs) => page?,
+            _ = self.cancel.cancelled() => return Err(DbtoonError::Cancelled),
+        };
+
+        if self.dry_run {
+            return dry_run_summary(page, sink);
+        }
+
+        let job = page.job_reference.clone().ok_or_else(|| DbtoonError::Query {
+            message: "no jobReference in query response".to_string(),
+        })?;
+        while page.job_complete != Some(true) {
+            page = self.next_page(&job, None, page_size, start, timeout_secs).await?;
+        }
+
+        let fields = page.schema.take().unwrap_or_default().fields;
+        let columns: Vec<ColumnMeta> = fields
+            .iter()
+            .map(|f| ColumnMeta {
+                name: f.name.clone(),
+                type_name: normalize_bigquery_type(f),
+            })
+            .collect();
+        sink.begin(&columns)?;
+
+        let mut summary = StreamSummary {
+            total_rows: page.total_rows.as_deref().and_then(|n| n.parse().ok()),
+            rows_affected: page.num_dml_affected_rows.as_deref().and_then(|n| n.parse().ok()),
+            ..StreamSummary::default()
+        };
+        summary.messages = page.errors.iter().map(ErrorProto::format_message).collect();
+
+        loop {
+            let has_more = page.page_token.is_some();
+            let mut rows: Vec<Vec<CellValue>> = page
+                .rows
+                .iter()
+                .map(|row| {
+                    row.f
+                        .iter()
+                        .enumerate()
+                        .map(|(i, cell)| match (fields.get(i), columns.get(i)) {
+                            (Some(field), Some(col)) => bigquery_cell(&cell.v, field, &col.type_name),
+                            _ => bigquery_cell(&cell.v, &Field::default(), ""),
+                        })
+                        .collect()
+                })
+                .collect();
+
+            if let Some(lim) = limit {
+                let remaining = lim.saturating_sub(summary.rows);
+                if rows.len() > remaining || (rows.len() == remaining && has_more) {
+                    rows.truncate(remaining);
+                    summary.truncated = true;
+                }
+            }
+            if !rows.is_empty() {
+                sink.write_batch(&rows)?;
+                summary.rows += rows.len();
+            }
+
+            let Some(page_token) = page.page_token.take() else {
+                break;
+            };
+            if summary.truncated {
+                break;
+            }
+            page = self
+                .next_page(&job, Some(&page_token), page_size, start, timeout_secs)
+                .await?;
+        }
+        Ok(summary)
+    }
+}
+
+impl Backend for BigQueryBackend {
+    async fn execute(
+        &self,
+        sql: &str,
+        params: &[QueryParam],
+        limit: Option<usize>,
+        timeout_secs: u64,
+    ) -> Result<QueryResult, DbtoonError> {
+        let (mut result, summary) = self
+            .execute_streaming(sql, params, limit, timeout_secs, QueryResult::default())
+            .await?;
+        result.truncated = summary.truncated;
+        result.total_rows = summary.total_rows;
+        result.messages = summary.messages;
+        result.rows_affected = summary.rows_affected;
+        Ok(result)
+    }
+
+    async fn execute_streaming<S: ResultSink + 'static>(
+        &self,
+        sql: &str,
+        params: &[QueryParam],
+        limit: Option<usize>,
+        timeout_secs: u64,
+        mut sink: S,
+    ) -> Result<(S, StreamSummary), DbtoonError> {
+        let summary = self.run(sql, params, limit, timeout_secs, &mut sink).await?;
+        Ok((sink, summary))
+    }
+}
+
+/// The schema of a dry-run query, without rows, and its bytes-processed estimate.
+fn dry_run_summary(page: QueryResponse, sink: &mut dyn ResultSink) -> Result<StreamSummary, DbtoonError> {
+    let columns: Vec<ColumnMeta> = page
+        .schema
+        .unwrap_or_default()
+        .fields
+        .iter()
+        .map(|f| ColumnMeta {
+            name: f.name.clone(),
+            type_name: normalize_bigquery_type(f),
+        })
+        .collect();
+    sink.begin(&columns)?;
+
+    let bytes: u64 = page
+        .total_bytes_processed
+        .as_deref()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(0);
+    Ok(StreamSummary {
+        messages: vec![format!(
+            "dry run: query would process {} bytes ({})",
+            bytes,
+            format_bytes(bytes)
+        )],
+        ..StreamSummary::default()
+    })
+}
+
+/// `bytes` in the largest binary unit that keeps the value at or above 1.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.2} {}", value, UNITS[unit])
+}
+
+/// A unique `requestId` for `jobs.query`.
+fn request_id() -> Result<String, DbtoonError> {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).map_err(|e| DbtoonError::Query {
+        message: format!("cannot generate request id: {}", e),
+    })?;
+    Ok(format!("dbtoon-{}", bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>()))
+}
+
+/// Parse a `jobs.query` or `jobs.getQueryResults` response, mapping error
+/// statuses to errors carrying BigQuery's reason and message.
+async fn read_response(resp: Response) -> Result<QueryResponse, DbtoonError> {
+    let status = resp.status().as_u16();
+    if resp.status().is_success() {
+        return resp.json().await.map_err(|e| DbtoonError::Query {
+            message: format!("failed to parse response: {}", e),
+        });
+    }
+
+    let body = resp.text().await.unwrap_or_default();
+    let detail = match serde_json::from_str::<ErrorResponse>(&body) {
+        Ok(ErrorResponse { error }) => match error.errors.first() {
+            Some(first) if first.reason.is_some() => first.format_message(),
+            _ => error.message.unwrap_or_default(),
+        },
+        Err(_) => body.trim().to_string(),
+    };
+    Err(match status {
+        401 => DbtoonError::Auth {
+            message: "authentication failed (check the service account key)".to_string(),
+        },
+        403 => DbtoonError::Auth {
+            message: if detail.is_empty() { "access denied".to_string() } else { detail },
+        },
+        400 | 404 if !detail.is_empty() => DbtoonError::Query { message: detail },
+        _ if detail.is_empty() => DbtoonError::Connection {
+            message: format!("HTTP error: {}", status),
+        },
+        _ => DbtoonError::Connection {
+            message: format!("HTTP error: {}: {}", status, detail),
+        },
+    })
+}
+
+/// A `queryParameters` entry binding `param` under its name.
+///
+/// The type suffix picks the BigQuery type (`TIMESTAMP_NTZ` and `DATETIME2`
+/// become `DATETIME`); untyped values are `STRING`s.
+pub fn query_parameter(param: &QueryParam) -> serde_json::Value {
+    let base = param
+        .type_name
+        .as_deref()
+        .and_then(|t| t.split('(').next())
+        .map(|t| t.trim().to_uppercase());
+    let type_name = match base.as_deref() {
+        Some("TINYINT" | "SMALLINT" | "INT" | "INTEGER" | "BIGINT") => "INT64",
+        Some("FLOAT" | "REAL" | "DOUBLE") => "FLOAT64",
+        Some("DECIMAL" | "NUMERIC") => "NUMERIC",
+        Some("BOOLEAN" | "BIT") => "BOOL",
+        Some("DATE") => "DATE",
+        Some("TIME") => "TIME",
+        Some("TIMESTAMP") => "TIMESTAMP",
+        Some("TIMESTAMP_NTZ" | "DATETIME2") => "DATETIME",
+        _ => "STRING",
+    };
+    let value = param
+        .typed_value()
+        .to_text()
+        .map_or_else(|| param.value.clone(), |v| v.into_owned());
+    serde_json::json!({
+        "name": param.name,
+        "parameterType": { "type": type_name },
+        "parameterValue": { "value": value },
+    })
+}
+
+/// Normalize a BigQuery schema field to the SQL type names
+/// `format_columnar::sql_type_to_arrow` understands.
+///
+/// `DATETIME` (a civil time) becomes `TIMESTAMP_NTZ`. `NUMERIC` keeps its
+/// declared precision and scale, or (38,9). Repeated fields become
+/// `ARRAY<type>`, and they, records, `BIGNUMERIC`, `GEOGRAPHY`, `JSON` and
+/// other types without a columnar counterpart are shown as text.
+pub fn normalize_bigquery_type(field: &Field) -> String {
+    let scalar = match field.type_name.as_str() {
+        "STRING" => "VARCHAR".to_string(),
+        "BYTES" => "VARBINARY".to_string(),
+        "INTEGER" | "INT64" => "BIGINT".to_string(),
+        "FLOAT" | "FLOAT64" => "DOUBLE".to_string(),
+        "BOOLEAN" | "BOOL" => "BOOLEAN".to_string(),
+        "NUMERIC" => match (&field.precision, &field.scale) {
+            (Some(p), Some(s)) => format!("DECIMAL({},{})", p, s),
+            (Some(p), None) => format!("DECIMAL({},0)", p),
+            _ => "DECIMAL(38,9)".to_string(),
+        },
+        "TIMESTAMP" => "TIMESTAMP".to_string(),
+        "DATETIME" => "TIMESTAMP_NTZ".to_string(),
+        "DATE" => "DATE".to_string(),
+        "TIME" => "TIME".to_string(),
+        "RECORD" | "STRUCT" => "STRUCT".to_string(),
+        other => other.to_string(),
+    };
+    if field.repeated() {
+        format!("ARRAY<{}>", scalar)
+    } else {
+        scalar
+    }
+}
+
+/// Decode one cell value, given its schema field and normalized type.
+///
+/// Scalars arrive as strings (`TIMESTAMP` as integer microseconds, `BYTES` as
+/// base64). Repeated and record values become JSON text with field names, and
+/// anything that does not parse as its type stays `Text`.
+pub fn bigquery_cell(value: &serde_json::Value, field: &Field, type_name: &str) -> CellValue {
+    use serde_json::Value;
+
+    if field.repeated() || field.record() {
+        return match value {
+            Value::Null => CellValue::Null,
+            _ => CellValue::Text(plain_json(value, field, field.repeated()).to_string()),
+        };
+    }
+    let base = type_name.split('(').next().unwrap_or_default();
+    match value {
+        Value::Null => CellValue::Null,
+        Value::String(s) => {
+            let parsed = match base {
+                "BIGINT" => s.parse().ok().map(CellValue::Int64),
+                "DOUBLE" => s.parse().ok().map(CellValue::Float64),
+                "BOOLEAN" => match s.as_str() {
+                    "true" => Some(CellValue::Bool(true)),
+                    "false" => Some(CellValue::Bool(false)),
+                    _ => None,
+                },
+                "DECIMAL" => parse_decimal(s).map(|(value, scale)| CellValue::Decimal { value, scale }),
+                "DATE" => parse_date32(s).map(CellValue::Date),
+                "TIME" => parse_time_micros(s).map(CellValue::Time),
+                "TIMESTAMP" => s.parse().ok().map(CellValue::Timestamp),
+                "TIMESTAMP_NTZ" => parse_timestamp_micros(s).map(CellValue::Timestamp),
+                "VARBINARY" => STANDARD.decode(s).ok().map(CellValue::Bytes),
+                _ => None,
+            };
+            parsed.unwrap_or_else(|| CellValue::Text(s.clone()))
+        }
+        other => CellValue::Text(other.to_string()),
+    }
+}
+
+/// A repeated or record value as plain JSON: `[{"v": x}, ...]` becomes
+/// `[x, ...]` and `{"f": [{"v": x}, ...]}` an object keyed by field name.
+fn plain_json(value: &serde_json::Value, field: &Field, repeated: bool) -> serde_json::Value {
+    use serde_json::Value;
+
+    match value {
+        Value::Array(items) if repeated => Value::Array(
+            items
+                .iter()
+                .map(|item| plain_json(item.get("v").unwrap_or(&Value::Null), field, false))
+                .collect(),
+        ),
+        Value::Object(record) if field.record() => {
+            let cells = record.get("f").and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+            Value::Object(
+                field
+                    .fields
+                    .iter()
+                    .zip(cells)
+                    .map(|(sub, cell)| {
+                        let v = cell.get("v").unwrap_or(&Value::Null);
+                        (sub.name.clone(), plain_json(v, sub, sub.repeated()))
+                    })
+                    .collect(),
+            )
+        }
+        other => other.clone(),
+    }
+}
@@ -0,0 +1,572 @@
+//! ClickHouse backend over the HTTP interface.
+//!
+//! Results are requested as `JSONCompactEachRowWithNamesAndTypes`, whose header
+//! lines carry the ClickHouse type of every column, and read line by line as
+//! the server streams them.
+
+use std::time::Duration;
+
+use reqwest::{Client, RequestBuilder, Response};
+use secrecy::{ExposeSecret, SecretString};
+
+use crate::backend::retry::{Idempotency, RetryPolicy, send_with_retry};
+use crate::backend::trino::server_url;
+use crate::backend::value::{parse_date32, parse_decimal, parse_timestamp_micros};
+use crate::backend::{
+    Backend, CellValue, ColumnMeta, QueryResult, ResultSink, STREAM_BATCH_SIZE, StreamSummary,
+    http_client,
+};
+use crate::cancel::CancelToken;
+use crate::error::DbtoonError;
+use crate::params::{QueryParam, to_braced};
+
+/// Output format requested for every query without its own `FORMAT` clause.
+const OUTPUT_FORMAT: &str = "JSONCompactEachRowWithNamesAndTypes";
+
+/// Port of the HTTPS interface, used for a bare host without a port.
+const DEFAULT_HTTPS_PORT: u16 = 8443;
+
+/// Exception codes the server raises for a bad user name or password.
+const AUTH_EXCEPTION_CODES: &[&str] = &["192", "193", "194", "516"];
+
+/// Most of an unexpected response body quoted in an error.
+const MAX_ERROR_BYTES: usize = 4096;
+
+pub struct ClickHouseBackend {
+    host: String,
+    port: Option<u16>,
+    database: Option<String>,
+    user: String,
+    password: Option<SecretString>,
+    cancel: CancelToken,
+    retry: RetryPolicy,
+    verbose: bool,
+    client: Client,
+}
+
+impl ClickHouseBackend {
+    pub fn new(
+        host: String,
+        port: Option<u16>,
+        database: Option<String>,
+        user: String,
+        password: Option<SecretString>,
+    ) -> Self {
+        Self {
+            host,
+            port,
+            database,
+            user,
+            password,
+            cancel: CancelToken::default(),
+            retry: RetryPolicy::default(),
+            verbose: false,
+            client: http_client(),
+        }
+    }
+
+    /// Retry transient HTTP failures under `retry`.
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Log retries to stderr.
+    pub fn with_verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    /// Kill the query on the server when `cancel` fires.
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Base URL of the HTTP interface. A bare hostname is reached over HTTPS on
+    /// port 8443 unless `port` says otherwise.
+    fn url(&self) -> String {
+        let bare = !self.host.contains("://") && !self.host.contains(':');
+        let port = self.port.or(bare.then_some(DEFAULT_HTTPS_PORT));
+        format!("{}/", server_url(&self.host, port))
+    }
+
+    /// `request` with the user, password and default database.
+    fn authorized(&self, request: RequestBuilder) -> RequestBuilder {
+        let mut request = request
+            .header("X-ClickHouse-User", &self.user)
+            .header("User-Agent", concat!("dbtoon/", env!("CARGO_PKG_VERSION")));
+        if let Some(password) = &self.password {
+            request = request.header("X-ClickHouse-Key", password.expose_secret());
+        }
+        if let Some(database) = &self.database {
+            request = request.header("X-ClickHouse-Database", database);
+        }
+        request
+    }
+
+    /// Send the statement under `query_id` and return the response once its
+    /// headers arrive, with the rows still to be read.
+    async fn submit(
+        &self,
+        sql: &str,
+        params: &[QueryParam],
+        limit: Option<usize>,
+        timeout_secs: u64,
+        query_id: &str,
+    ) -> Result<Response, DbtoonError> {
+        let (sql, bound) = to_braced(sql, params, param_type)?;
+
+        let mut settings: Vec<(String, String)> = vec![
+            ("query_id".into(), query_id.to_string()),
+            ("default_format".into(), OUTPUT_FORMAT.into()),
+            ("date_time_output_format".into(), "iso".into()),
+            ("output_format_json_quote_decimals".into(), "1".into()),
+            ("output_format_json_quote_denormals".into(), "1".into()),
+            ("http_write_exception_in_output_format".into(), "0".into()),
+            ("max_execution_time".into(), timeout_secs.to_string()),
+        ];
+        if let Some(limit) = limit {
+            // The server stops one row past the limit, which marks the result truncated
+            settings.push(("max_result_rows".into(), limit.saturating_add(1).to_string()));
+            settings.push(("result_overflow_mode".into(), "break".into()));
+        }
+        for param in bound {
+            settings.push((format!("param_{}", param.name), param_value(param)));
+        }
+
+        let url = self.url();
+        // A statement that reached the server may have run, so only retry rejections
+        let resp = send_with_retry(&self.retry, Idempotency::NotIdempotent, self.verbose, "query submit", || {
+            self.authorized(self.client.post(&url)).query(&settings).body(sql.clone())
+        })
+        .await
+        .map_err(|e| DbtoonError::Connection {
+            message: format!("failed to execute statement: {}", e),
+        })?;
+
+        if !resp.status().is_success() {
+            return Err(response_error(resp).await);
+        }
+        if let Some(format) = resp.headers().get("X-ClickHouse-Format").and_then(|v| v.to_str().ok())
+            && format != OUTPUT_FORMAT
+        {
+            return Err(DbtoonError::Query {
+                message: format!(
+                    "query asked for FORMAT {}; remove the FORMAT clause and choose the output with --format",
+                    format
+                ),
+            });
+        }
+        Ok(resp)
+    }
+
+    /// Submit the statement and feed its rows to `sink`.
+    async fn run(
+        &self,
+        sql: &str,
+        params: &[QueryParam],
+        limit: Option<usize>,
+        timeout_secs: u64,
+        query_id: &str,
+        sink: &mut dyn ResultSink,
+    ) -> Result<StreamSummary, DbtoonError> {
+        let resp = self.submit(sql, params, limit, timeout_secs, query_id).await?;
+        let written_rows = written_rows(&resp);
+        let mut summary = stream_rows(resp, limit, sink).await?;
+        if summary.rows == 0 && !summary.truncated {
+            summary.rows_affected = written_rows;
+        }
+        Ok(summary)
+    }
+
+    /// Ask the server to stop `query_id`. Best effort: failures are ignored.
+    async fn kill_query(&self, query_id: &str) {
+        let sql = format!("KILL QUERY WHERE query_id = '{}' ASYNC", query_id);
+        let _ = self.authorized(self.client.post(self.url())).body(sql).send().await;
+    }
+}
+
+impl Backend for ClickHouseBackend {
+    async fn execute(
+        &self,
+        sql: &str,
+        params: &[QueryParam],
+        limit: Option<usize>,
+        timeout_secs: u64,
+    ) -> Result<QueryResult, DbtoonError> {
+        let (mut result, summary) = self
+            .execute_streaming(sql, params, limit, timeout_secs, QueryResult::default())
+            .await?;
+        result.truncated = summary.truncated;
+        result.rows_affected = summary.rows_affected;
+        Ok(result)
+    }
+
+    async fn execute_streaming<S: ResultSink + 'static>(
+        &self,
+        sql: &str,
+        params: &[QueryParam],
+        limit: Option<usize>,
+        timeout_secs: u64,
+        mut sink: S,
+    ) -> Result<(S, StreamSummary), DbtoonError> {
+        let query_id = new_query_id()?;
+        let outcome = tokio::select! {
+            outcome = self.run(sql, params, limit, timeout_secs, &query_id, &mut sink) => outcome,
+            _ = self.cancel.cancelled() => Err(DbtoonError::Cancelled),
+            _ = tokio::time::sleep(Duration::from_secs(timeout_secs)) => {
+                Err(DbtoonError::Timeout { seconds: timeout_secs })
+            }
+        };
+        match outcome {
+            Ok(summary) => Ok((sink, summary)),
+            Err(e) => {
+                if matches!(e, DbtoonError::Cancelled | DbtoonError::Timeout { .. }) {
+                    self.kill_query(&query_id).await;
+                }
+                Err(e)
+            }
+        }
+    }
+}
+
+/// A fresh `query_id`, so a running query can be killed by name.
+fn new_query_id() -> Result<String, DbtoonError> {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).map_err(|e| DbtoonError::Query {
+        message: format!("cannot generate query id: {}", e),
+    })?;
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    Ok(format!("dbtoon-{}", hex))
+}
+
+/// Rows an `INSERT` wrote, from the `X-ClickHouse-Summary` progress header.
+fn written_rows(resp: &Response) -> Option<usize> {
+    let header = resp.headers().get("X-ClickHouse-Summary")?.to_str().ok()?;
+    let summary: serde_json::Value = serde_json::from_str(header).ok()?;
+    let rows = summary.get("written_rows")?.as_str()?.parse().ok()?;
+    (rows > 0).then_some(rows)
+}
+
+/// Map an error response to an error, using the exception code the server sends with it.
+async fn response_error(resp: Response) -> DbtoonError {
+    let status = resp.status().as_u16();
+    let code = resp
+        .headers()
+        .get("X-ClickHouse-Exception-Code")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let body = resp.text().await.unwrap_or_default();
+    let detail = body.trim().to_string();
+
+    match code {
+        Some(code) if AUTH_EXCEPTION_CODES.contains(&code.as_str()) => DbtoonError::Auth {
+            message: exception_message(&detail),
+        },
+        Some(_) => DbtoonError::Query {
+            message: exception_message(&detail),
+        },
+        None => match status {
+            401 | 403 => DbtoonError::Auth {
+                message: "authentication failed (check user and password)".to_string(),
+            },
+            404 => DbtoonError::Config {
+                message: "HTTP interface not found (is this a ClickHouse server?)".to_string(),
+            },
+            _ if detail.is_empty() => DbtoonError::Connection {
+                message: format!("HTTP error: {}", status),
+            },
+            _ => DbtoonError::Connection {
+                message: format!("HTTP error: {}: {}", status, detail),
+            },
+        },
+    }
+}
+
+/// The `Code: N. DB::Exception: ...` line in `text`, or `text` itself.
+fn exception_message(text: &str) -> String {
+    match text.find("Code: ") {
+        Some(start) => text[start..].lines().next().unwrap_or_default().trim().to_string(),
+        None => text.to_string(),
+    }
+}
+
+/// What to do after one line of output.
+enum Step {
+    Continue,
+    /// The limit is reached and another row arrived.
+    Truncated,
+    /// The line is not part of the output format: the server failed mid-stream.
+    Unexpected,
+}
+
+/// Decodes `JSONCompactEachRowWithNamesAndTypes` lines into batches for a sink:
+/// a line of column names, a line of types, then one JSON array per row.
+struct RowDecoder<'a> {
+    sink: &'a mut dyn ResultSink,
+    limit: Option<usize>,
+    names: Option<Vec<String>>,
+    columns: Option<Vec<ColumnMeta>>,
+    batch: Vec<Vec<CellValue>>,
+    summary: StreamSummary,
+}
+
+impl RowDecoder<'_> {
+    fn line(&mut self, line: &[u8]) -> Result<Step, DbtoonError> {
+        let line = line.trim_ascii();
+        if line.is_empty() {
+            return Ok(Step::Continue);
+        }
+        let Ok(serde_json::Value::Array(items)) = serde_json::from_slice(line) else {
+            return Ok(Step::Unexpected);
+        };
+
+        let Some(names) = &self.names else {
+            self.names = Some(items.iter().map(json_text).collect());
+            return Ok(Step::Continue);
+        };
+        let Some(columns) = &self.columns else {
+            let meta: Vec<ColumnMeta> = names
+                .iter()
+                .zip(&items)
+                .map(|(name, type_name)| ColumnMeta {
+                    name: name.clone(),
+                    type_name: normalize_clickhouse_type(&json_text(type_name)),
+                })
+                .collect();
+            self.sink.begin(&meta)?;
+            self.columns = Some(meta);
+            return Ok(Step::Continue);
+        };
+
+        if self.limit.is_some_and(|lim| self.summary.rows + self.batch.len() >= lim) {
+            self.summary.truncated = true;
+            return Ok(Step::Truncated);
+        }
+        let row = items
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| clickhouse_cell(cell, columns.get(i).map_or("", |c| &c.type_name)))
+            .collect();
+        self.batch.push(row);
+        if self.batch.len() >= STREAM_BATCH_SIZE {
+            self.flush()?;
+        }
+        Ok(Step::Continue)
+    }
+
+    fn flush(&mut self) -> Result<(), DbtoonError> {
+        if !self.batch.is_empty() {
+            self.sink.write_batch(&self.batch)?;
+            self.summary.rows += self.batch.len();
+            self.batch.clear();
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<StreamSummary, DbtoonError> {
+        self.flush()?;
+        if self.columns.is_none() {
+            self.sink.begin(&[])?;
+        }
+        Ok(self.summary)
+    }
+}
+
+/// A JSON string's contents, or the value's JSON text.
+fn json_text(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Read the response body line by line into `sink`, stopping once `limit` rows
+/// have been written and another arrives.
+///
+/// An exception raised after the rows began streaming is appended to the body
+/// as text; it surfaces as a query error.
+async fn stream_rows(
+    mut resp: Response,
+    limit: Option<usize>,
+    sink: &mut dyn ResultSink,
+) -> Result<StreamSummary, DbtoonError> {
+    let read_error = |e: reqwest::Error| DbtoonError::Connection {
+        message: format!("failed to read query results: {}", e),
+    };
+    let mut decoder = RowDecoder {
+        sink,
+        limit,
+        names: None,
+        columns: None,
+        batch: Vec::new(),
+        summary: StreamSummary::default(),
+    };
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut done = false;
+
+    while !done {
+        match resp.chunk().await.map_err(read_error)? {
+            Some(chunk) => buffer.extend_from_slice(&chunk),
+            None => done = true,
+        }
+        let mut start = 0;
+        while let Some(end) = next_line_end(&buffer, start, done) {
+            match decoder.line(&buffer[start..end])? {
+                Step::Continue => start = (end + 1).min(buffer.len()),
+                Step::Truncated => return decoder.finish(),
+                Step::Unexpected => {
+                    let mut rest = buffer.split_off(start);
+                    while rest.len() < MAX_ERROR_BYTES
+                        && let Some(chunk) = resp.chunk().await.map_err(read_error)?
+                    {
+                        rest.extend_from_slice(&chunk);
+                    }
+                    rest.truncate(MAX_ERROR_BYTES);
+                    return Err(DbtoonError::Query {
+                        message: exception_message(String::from_utf8_lossy(&rest).trim()),
+                    });
+                }
+            }
+        }
+        buffer.drain(..start);
+    }
+    decoder.finish()
+}
+
+/// End of the complete line starting at `start`; at the end of the body, the
+/// unterminated remainder counts as a line.
+fn next_line_end(buffer: &[u8], start: usize, at_end: bool) -> Option<usize> {
+    match buffer[start..].iter().position(|&b| b == b'\n') {
+        Some(pos) => Some(start + pos),
+        None if at_end && start < buffer.len() => Some(buffer.len()),
+        None => None,
+    }
+}
+
+/// Strip the `Nullable(...)` and `LowCardinality(...)` wrappers, which do not
+/// change how values are decoded.
+fn unwrap_type_modifiers(mut type_name: &str) -> &str {
+    loop {
+        let inner = ["Nullable(", "LowCardinality("]
+            .iter()
+            .find_map(|prefix| type_name.strip_prefix(prefix)?.strip_suffix(')'));
+        match inner {
+            Some(inner) => type_name = inner.trim(),
+            None => return type_name,
+        }
+    }
+}
+
+/// Normalize a ClickHouse column type to the SQL type names
+/// `format_columnar::sql_type_to_arrow` understands.
+///
+/// `Nullable` and `LowCardinality` are unwrapped. Unsigned integers widen to
+/// the next signed type (`UInt64` to `DECIMAL(20,0)`), `DateTime` and
+/// `DateTime64` of any precision and time zone become `TIMESTAMP`, and enums
+/// become `VARCHAR`. Types without a columnar counterpart (`Int128`, `UUID`,
+/// `Array(...)`, `Map(...)`, ...) keep their ClickHouse name and are shown as text.
+pub fn normalize_clickhouse_type(type_name: &str) -> String {
+    let inner = unwrap_type_modifiers(type_name.trim());
+    let (base, args) = match inner.split_once('(') {
+        Some((base, rest)) => (base.trim(), rest.strip_suffix(')').unwrap_or(rest)),
+        None => (inner, ""),
+    };
+    let scale = || args.trim().parse::<u8>().unwrap_or(0);
+    match base {
+        "Bool" => "BOOLEAN".to_string(),
+        "UInt8" => "TINYINT".to_string(),
+        "Int8" | "Int16" => "SMALLINT".to_string(),
+        "UInt16" | "Int32" => "INT".to_string(),
+        "UInt32" | "Int64" => "BIGINT".to_string(),
+        "UInt64" => "DECIMAL(20,0)".to_string(),
+        "Float32" => "REAL".to_string(),
+        "Float64" => "DOUBLE".to_string(),
+        "Decimal" => match args.split_once(',') {
+            Some((p, s)) => format!("DECIMAL({},{})", p.trim(), s.trim()),
+            None => format!("DECIMAL({},0)", args.trim()),
+        },
+        "Decimal32" => format!("DECIMAL(9,{})", scale()),
+        "Decimal64" => format!("DECIMAL(18,{})", scale()),
+        "Decimal128" => format!("DECIMAL(38,{})", scale()),
+        "String" | "FixedString" | "Enum8" | "Enum16" => "VARCHAR".to_string(),
+        "Date" | "Date32" => "DATE".to_string(),
+        "DateTime" | "DateTime64" => "TIMESTAMP".to_string(),
+        _ => inner.to_string(),
+    }
+}
+
+/// Decode one JSON result value, given its normalized column type.
+///
+/// 64-bit integers, decimals, dates and timestamps (in UTC) arrive as strings,
+/// as do NaN and infinities; other numbers and booleans as JSON scalars.
+/// Arrays, maps and tuples become JSON text, and anything that does not parse
+/// as its type stays `Text`.
+pub fn clickhouse_cell(value: &serde_json::Value, type_name: &str) -> CellValue {
+    use serde_json::Value;
+
+    let base = type_name.split('(').next().unwrap_or_default();
+    match value {
+        Value::Null => CellValue::Null,
+        Value::Bool(b) => CellValue::Bool(*b),
+        Value::Number(n) => match (base, n.as_i64()) {
+            ("TINYINT" | "SMALLINT" | "INT" | "BIGINT", Some(i)) => CellValue::Int64(i),
+            ("REAL" | "DOUBLE", _) => n.as_f64().map_or_else(|| CellValue::Text(n.to_string()), CellValue::Float64),
+            ("DECIMAL", _) => parse_decimal(&n.to_string())
+                .map_or_else(|| CellValue::Text(n.to_string()), |(value, scale)| CellValue::Decimal { value, scale }),
+            _ => CellValue::Text(n.to_string()),
+        },
+        Value::String(s) => {
+            let parsed = match base {
+                "BIGINT" => s.parse().ok().map(CellValue::Int64),
+                "REAL" | "DOUBLE" => s.parse().ok().map(CellValue::Float64),
+                "DECIMAL" => parse_decimal(s).map(|(value, scale)| CellValue::Decimal { value, scale }),
+                "DATE" => parse_date32(s).map(CellValue::Date),
+                "TIMESTAMP" => parse_timestamp_micros(s).map(CellValue::Timestamp),
+                _ => None,
+            };
+            parsed.unwrap_or_else(|| CellValue::Text(s.clone()))
+        }
+        Value::Array(_) | Value::Object(_) => CellValue::Text(value.to_string()),
+    }
+}
+
+/// The ClickHouse type a parameter is bound as in its `{name:Type}` placeholder.
+///
+/// Untyped parameters are `String`; timestamps are read in UTC, matching how
+/// results are returned.
+pub fn param_type(param: &QueryParam) -> String {
+    let Some(type_name) = &param.type_name else {
+        return "String".to_string();
+    };
+    let upper = type_name.trim().to_uppercase();
+    let base = upper.split('(').next().unwrap_or_default().trim();
+    match base {
+        "TINYINT" | "SMALLINT" | "INT" | "INTEGER" | "BIGINT" => "Int64".to_string(),
+        "REAL" => "Float32".to_string(),
+        "FLOAT" | "DOUBLE" => "Float64".to_string(),
+        "DECIMAL" | "NUMERIC" => match upper.find('(') {
+            Some(open) => format!("Decimal{}", upper[open..].replace(' ', "")),
+            None => {
+                let scale = parse_decimal(&param.value).map_or(0, |(_, scale)| scale);
+                format!("Decimal(38,{})", scale)
+            }
+        },
+        "BOOLEAN" | "BIT" => "Bool".to_string(),
+        "DATE" => "Date32".to_string(),
+        "TIMESTAMP" | "TIMESTAMP_NTZ" | "DATETIME2" => "DateTime64(6, 'UTC')".to_string(),
+        _ => "String".to_string(),
+    }
+}
+
+/// A parameter's `param_<name>` value, in the escaped text form the server
+/// parses query parameters with.
+pub fn param_value(param: &QueryParam) -> String {
+    let text = match param.typed_value() {
+        CellValue::Bool(b) => b.to_string(),
+        CellValue::Timestamp(_) => param.value.replacen('T', " ", 1),
+        _ => param.value.clone(),
+    };
+    text.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n")
+}
@@ -1,19 +1,38 @@
-use crate::backend::{Backend, CellValue, ColumnMeta, QueryResult};
+use crate::backend::retry::{Idempotency, RetryPolicy, send_with_retry};
+use crate::backend::{
+    Backend, CellValue, ColumnMeta, QueryResult, ResultSink, StreamSummary, http_client,
+};
+use crate::backend::oauth;
+use crate::cancel::CancelToken;
+use crate::config::DatabricksAuth;
 use crate::error::DbtoonError;
+use crate::params::QueryParam;
+use arrow::ipc::reader::StreamReader;
 use reqwest::Client;
-use secrecy::{ExposeSecret, SecretString};
+use secrecy::ExposeSecret;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 use std::time::{Duration, Instant};
 
 pub struct DatabricksBackend {
     host: String,
-    token: SecretString,
+    auth: DatabricksAuth,
     warehouse_id: String,
     catalog: Option<String>,
     schema: Option<String>,
+    download_concurrency: usize,
+    cancel: CancelToken,
+    retry: RetryPolicy,
+    verbose: bool,
     client: Client,
 }
 
+/// Default number of EXTERNAL_LINKS chunks downloaded at once.
+pub const DEFAULT_DOWNLOAD_CONCURRENCY: usize = 4;
+
+/// How long the submit request waits for the statement before returning its id.
+const SUBMIT_WAIT: &str = "5s";
+
 /// Warehouse info for list-warehouses subcommand.
 #[derive(Debug)]
 pub struct WarehouseInfo {
@@ -36,6 +55,21 @@ struct StatementRequest {
     catalog: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     schema: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    disposition: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<&'static str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    parameters: Vec<StatementParameter>,
+}
+
+/// A value bound to a `:name` marker in the statement text.
+#[derive(Serialize)]
+struct StatementParameter {
+    name: String,
+    value: String,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    type_name: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -69,10 +103,13 @@ impl StatementError {
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Default)]
 struct Manifest {
     schema: Option<SchemaInfo>,
     truncated: Option<bool>,
+    total_row_count: Option<usize>,
+    total_chunk_count: Option<usize>,
+    format: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -86,9 +123,23 @@ struct ColumnInfo {
     type_name: Option<String>,
 }
 
+/// One chunk of an INLINE result, either embedded in the statement response
+/// or fetched from `/statements/{id}/result/chunks/{n}`.
 #[derive(Deserialize)]
 struct ResultData {
     data_array: Option<Vec<Vec<Option<String>>>>,
+    next_chunk_index: Option<usize>,
+    next_chunk_internal_link: Option<String>,
+    external_links: Option<Vec<ExternalLink>>,
+}
+
+/// A presigned URL for one ARROW_STREAM chunk of an EXTERNAL_LINKS result.
+#[derive(Deserialize)]
+struct ExternalLink {
+    chunk_index: usize,
+    external_link: String,
+    #[serde(default)]
+    http_headers: HashMap<String, String>,
 }
 
 #[derive(Deserialize)]
@@ -108,28 +159,56 @@ struct WarehouseEntry {
 impl DatabricksBackend {
     pub fn new(
         host: String,
-        token: SecretString,
+        auth: DatabricksAuth,
         warehouse_id: String,
         catalog: Option<String>,
         schema: Option<String>,
     ) -> Self {
-        let client = Client::new();
+        let client = http_client();
         Self {
             host,
-            token,
+            auth,
             warehouse_id,
             catalog,
             schema,
+            download_concurrency: DEFAULT_DOWNLOAD_CONCURRENCY,
+            cancel: CancelToken::default(),
+            retry: RetryPolicy::default(),
+            verbose: false,
             client,
         }
     }
 
+    /// Set how many EXTERNAL_LINKS chunks are downloaded in parallel (minimum 1).
+    pub fn with_download_concurrency(mut self, concurrency: usize) -> Self {
+        self.download_concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Retry transient HTTP failures under `retry`.
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Log retries to stderr.
+    pub fn with_verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    /// Stop the statement on the warehouse when `cancel` fires.
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
     fn base_url(&self) -> String {
-        format!("https://{}/api/2.0/sql", self.host)
+        format!("{}/api/2.0/sql", host_url(&self.host))
     }
 
-    fn auth_header(&self) -> String {
-        format!("Bearer {}", self.token.expose_secret())
+    async fn auth_header(&self) -> Result<String, DbtoonError> {
+        auth_header(&self.client, &self.host, &self.auth).await
     }
 
     async fn poll_statement(&self, statement_id: &str, timeout_secs: u64) -> Result<StatementResponse, DbtoonError> {
@@ -138,30 +217,28 @@ impl DatabricksBackend {
 
         loop {
             if start.elapsed().as_secs() >= timeout_secs {
-                // Cancel the statement
-                let cancel_url = format!("{}/statements/{}/cancel", self.base_url(), statement_id);
-                let _ = self
-                    .client
-                    .post(&cancel_url)
-                    .header("Authorization", self.auth_header())
-                    .send()
-                    .await;
+                self.cancel_statement(statement_id).await;
                 return Err(DbtoonError::Timeout {
                     seconds: timeout_secs,
                 });
             }
 
-            tokio::time::sleep(Duration::from_secs(2)).await;
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(2)) => {}
+                _ = self.cancel.cancelled() => {
+                    self.cancel_statement(statement_id).await;
+                    return Err(DbtoonError::Cancelled);
+                }
+            }
 
-            let resp = self
-                .client
-                .get(&url)
-                .header("Authorization", self.auth_header())
-                .send()
-                .await
-                .map_err(|e| DbtoonError::Connection {
-                    message: format!("failed to poll statement: {}", e),
-                })?;
+            let auth_header = self.auth_header().await?;
+            let resp = send_with_retry(&self.retry, Idempotency::Idempotent, self.verbose, "statement poll", || {
+                self.client.get(&url).header("Authorization", &auth_header)
+            })
+            .await
+            .map_err(|e| DbtoonError::Connection {
+                message: format!("failed to poll statement: {}", e),
+            })?;
 
             let status_code = resp.status();
             if !status_code.is_success() {
@@ -193,6 +270,20 @@ impl DatabricksBackend {
         }
     }
 
+    /// Ask the warehouse to stop a statement. Best effort: failures are ignored.
+    async fn cancel_statement(&self, statement_id: &str) {
+        let cancel_url = format!("{}/statements/{}/cancel", self.base_url(), statement_id);
+        let Ok(auth_header) = self.auth_header().await else {
+            return;
+        };
+        let _ = self
+            .client
+            .post(&cancel_url)
+            .header("Authorization", auth_header)
+            .send()
+            .await;
+    }
+
     fn map_http_error(status: u16) -> DbtoonError {
         match status {
             401 => DbtoonError::Auth {
@@ -210,11 +301,52 @@ impl DatabricksBackend {
         }
     }
 
-    fn parse_response(response: StatementResponse) -> Result<QueryResult, DbtoonError> {
-        let manifest = response.manifest.unwrap_or(Manifest {
-            schema: None,
-            truncated: None,
-        });
+    /// Fetch the chunk following `current`, preferring the server-provided internal link.
+    async fn fetch_next_chunk(
+        &self,
+        statement_id: &str,
+        current: &ResultData,
+    ) -> Result<Option<ResultData>, DbtoonError> {
+        let url = match (&current.next_chunk_internal_link, current.next_chunk_index) {
+            (Some(link), _) => format!("{}{}", host_url(&self.host), link),
+            (None, Some(index)) => format!(
+                "{}/statements/{}/result/chunks/{}",
+                self.base_url(),
+                statement_id,
+                index
+            ),
+            (None, None) => return Ok(None),
+        };
+
+        let auth_header = self.auth_header().await?;
+        let resp = send_with_retry(&self.retry, Idempotency::Idempotent, self.verbose, "chunk fetch", || {
+            self.client.get(&url).header("Authorization", &auth_header)
+        })
+        .await
+        .map_err(|e| DbtoonError::Connection {
+            message: format!("failed to fetch result chunk: {}", e),
+        })?;
+
+        let status_code = resp.status();
+        if !status_code.is_success() {
+            return Err(Self::map_http_error(status_code.as_u16()));
+        }
+
+        let chunk: ResultData = resp.json().await.map_err(|e| DbtoonError::Query {
+            message: format!("failed to parse result chunk: {}", e),
+        })?;
+        Ok(Some(chunk))
+    }
+
+    /// Feed a succeeded statement's result to `sink`, following chunk links until
+    /// the result is exhausted or `limit` rows have been written.
+    async fn stream_response(
+        &self,
+        response: StatementResponse,
+        limit: Option<usize>,
+        sink: &mut dyn ResultSink,
+    ) -> Result<StreamSummary, DbtoonError> {
+        let manifest = response.manifest.unwrap_or_default();
 
         let columns = manifest
             .schema
@@ -226,62 +358,211 @@ impl DatabricksBackend {
                 type_name: c.type_name.unwrap_or_else(|| "STRING".to_string()),
             })
             .collect::<Vec<_>>();
+        sink.begin(&columns)?;
+
+        let statement_id = response.statement_id.unwrap_or_default();
+
+        if manifest.format.as_deref() == Some("ARROW_STREAM") {
+            let links = response
+                .result
+                .and_then(|r| r.external_links)
+                .unwrap_or_default();
+            let total_chunks = manifest.total_chunk_count.unwrap_or(links.len());
+            let (written, truncated) = self
+                .stream_external_links(&statement_id, links, total_chunks, limit, sink)
+                .await?;
+            return Ok(StreamSummary {
+                rows: written,
+                truncated: truncated || manifest.truncated.unwrap_or(false),
+                total_rows: manifest.total_row_count,
+                ..StreamSummary::default()
+            });
+        }
 
-        let rows = response
-            .result
-            .and_then(|r| r.data_array)
-            .unwrap_or_default()
-            .into_iter()
-            .map(|row| {
-                row.into_iter()
-                    .map(|cell| match cell {
-                        Some(s) => CellValue::Text(s),
-                        None => CellValue::Null,
-                    })
-                    .collect()
-            })
-            .collect();
+        let mut truncated = manifest.truncated.unwrap_or(false);
+        let mut written = 0usize;
+        let mut chunk = response.result;
+
+        while let Some(data) = chunk {
+            let mut rows: Vec<Vec<CellValue>> = data
+                .data_array
+                .as_deref()
+                .unwrap_or_default()
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .enumerate()
+                        .map(|(i, cell)| match (cell, columns.get(i)) {
+                            (Some(s), Some(col)) => CellValue::from_typed_text(s, &col.type_name),
+                            (Some(s), None) => CellValue::Text(s.clone()),
+                            (None, _) => CellValue::Null,
+                        })
+                        .collect()
+                })
+                .collect();
+
+            let has_more = data.next_chunk_index.is_some() || data.next_chunk_internal_link.is_some();
+
+            if let Some(lim) = limit {
+                let remaining = lim.saturating_sub(written);
+                if rows.len() > remaining || (rows.len() == remaining && has_more) {
+                    rows.truncate(remaining);
+                    truncated = true;
+                }
+            }
 
-        let truncated = manifest.truncated.unwrap_or(false);
+            if !rows.is_empty() {
+                sink.write_batch(&rows)?;
+                written += rows.len();
+            }
 
-        Ok(QueryResult {
-            columns,
-            rows,
-            total_rows: None,
+            if limit.is_some_and(|lim| written >= lim) {
+                break;
+            }
+
+            chunk = self.fetch_next_chunk(&statement_id, &data).await?;
+        }
+
+        Ok(StreamSummary {
+            rows: written,
             truncated,
+            total_rows: manifest.total_row_count,
+            ..StreamSummary::default()
         })
     }
-}
 
-impl Backend for DatabricksBackend {
-    async fn execute(
+    /// Download ARROW_STREAM chunks (up to `download_concurrency` at a time) and
+    /// feed their record batches to `sink` in chunk order.
+    ///
+    /// Returns the rows written and whether `limit` cut the result short.
+    async fn stream_external_links(
+        &self,
+        statement_id: &str,
+        links: Vec<ExternalLink>,
+        total_chunks: usize,
+        limit: Option<usize>,
+        sink: &mut dyn ResultSink,
+    ) -> Result<(usize, bool), DbtoonError> {
+        let mut known: HashMap<usize, ExternalLink> =
+            links.into_iter().map(|l| (l.chunk_index, l)).collect();
+        let mut in_flight = Downloads::default();
+        let mut next_chunk = 0usize;
+        let mut written = 0usize;
+        let mut truncated = false;
+
+        loop {
+            while in_flight.0.len() < self.download_concurrency && next_chunk < total_chunks {
+                let auth_header = self.auth_header().await?;
+                let download = download_chunk(
+                    self.client.clone(),
+                    format!(
+                        "{}/statements/{}/result/chunks/{}",
+                        self.base_url(),
+                        statement_id,
+                        next_chunk
+                    ),
+                    auth_header,
+                    known.remove(&next_chunk),
+                    self.retry,
+                    self.verbose,
+                );
+                in_flight.0.push_back(tokio::spawn(download));
+                next_chunk += 1;
+            }
+
+            // The handle stays queued while awaited, so dropping this future aborts it too
+            let Some(handle) = in_flight.0.front_mut() else {
+                break;
+            };
+            let joined = handle.await;
+            in_flight.0.pop_front();
+            let bytes = joined.map_err(|e| DbtoonError::Query {
+                message: format!("task join error: {}", e),
+            })??;
+
+            let reader = StreamReader::try_new(std::io::Cursor::new(bytes), None).map_err(|e| {
+                DbtoonError::Query {
+                    message: format!("failed to decode Arrow result chunk: {}", e),
+                }
+            })?;
+
+            for batch in reader {
+                let mut batch = batch.map_err(|e| DbtoonError::Query {
+                    message: format!("failed to decode Arrow result chunk: {}", e),
+                })?;
+                if let Some(lim) = limit {
+                    let remaining = lim.saturating_sub(written);
+                    if batch.num_rows() > remaining {
+                        batch = batch.slice(0, remaining);
+                        truncated = true;
+                    }
+                }
+                if batch.num_rows() > 0 {
+                    sink.write_arrow_batch(&batch)?;
+                    written += batch.num_rows();
+                }
+                if truncated {
+                    break;
+                }
+            }
+
+            if limit.is_some_and(|lim| written >= lim) {
+                truncated |= !in_flight.0.is_empty() || next_chunk < total_chunks;
+                break;
+            }
+        }
+
+        Ok((written, truncated))
+    }
+
+    /// Submit a statement and wait for it to reach a terminal state.
+    ///
+    /// With `arrow`, results are requested as EXTERNAL_LINKS + ARROW_STREAM,
+    /// which lifts the 25 MiB inline cap and keeps values natively typed.
+    async fn run_statement(
         &self,
         sql: &str,
+        params: &[QueryParam],
         limit: Option<usize>,
         timeout_secs: u64,
-    ) -> Result<QueryResult, DbtoonError> {
+        arrow: bool,
+    ) -> Result<StatementResponse, DbtoonError> {
         let url = format!("{}/statements/", self.base_url());
 
         let request = StatementRequest {
             warehouse_id: self.warehouse_id.clone(),
             statement: sql.to_string(),
-            wait_timeout: "50s".to_string(),
+            // Short, so a cancel during submission is acted on within seconds;
+            // longer statements are polled, which honours cancel
+            wait_timeout: SUBMIT_WAIT.to_string(),
             on_wait_timeout: "CONTINUE".to_string(),
             row_limit: limit,
             catalog: self.catalog.clone(),
             schema: self.schema.clone(),
+            disposition: arrow.then_some("EXTERNAL_LINKS"),
+            format: arrow.then_some("ARROW_STREAM"),
+            parameters: params
+                .iter()
+                .map(|p| StatementParameter {
+                    name: p.name.clone(),
+                    value: p.value.clone(),
+                    type_name: p.type_name.clone(),
+                })
+                .collect(),
         };
 
-        let resp = self
-            .client
-            .post(&url)
-            .header("Authorization", self.auth_header())
-            .json(&request)
-            .send()
-            .await
-            .map_err(|e| DbtoonError::Connection {
-                message: format!("failed to execute statement: {}", e),
-            })?;
+        // A submission that reached the warehouse may have run, so only retry rejections
+        let auth_header = self.auth_header().await?;
+        let resp = send_with_retry(&self.retry, Idempotency::NotIdempotent, self.verbose, "statement submit", || {
+            self.client
+                .post(&url)
+                .header("Authorization", &auth_header)
+                .json(&request)
+        })
+        .await
+        .map_err(|e| DbtoonError::Connection {
+            message: format!("failed to execute statement: {}", e),
+        })?;
 
         let status_code = resp.status();
         if !status_code.is_success() {
@@ -293,8 +574,18 @@ impl Backend for DatabricksBackend {
                 message: format!("failed to parse response: {}", e),
             })?;
 
+        // Cancelled while waiting for the submission: the statement id is only known now
+        if self.cancel.is_cancelled() {
+            if let ("PENDING" | "RUNNING", Some(statement_id)) =
+                (response.status.state.as_str(), &response.statement_id)
+            {
+                self.cancel_statement(statement_id).await;
+            }
+            return Err(DbtoonError::Cancelled);
+        }
+
         match response.status.state.as_str() {
-            "SUCCEEDED" => Self::parse_response(response),
+            "SUCCEEDED" => Ok(response),
             "FAILED" => {
                 let msg = response
                     .status
@@ -309,8 +600,7 @@ impl Backend for DatabricksBackend {
                     .ok_or_else(|| DbtoonError::Query {
                         message: "no statement_id in pending response".to_string(),
                     })?;
-                let polled = self.poll_statement(&statement_id, timeout_secs).await?;
-                Self::parse_response(polled)
+                self.poll_statement(&statement_id, timeout_secs).await
             }
             other => Err(DbtoonError::Query {
                 message: format!("unexpected statement state: {}", other),
@@ -319,20 +609,177 @@ impl Backend for DatabricksBackend {
     }
 }
 
-/// List available Databricks SQL warehouses.
+impl Backend for DatabricksBackend {
+    async fn execute(
+        &self,
+        sql: &str,
+        params: &[QueryParam],
+        limit: Option<usize>,
+        timeout_secs: u64,
+    ) -> Result<QueryResult, DbtoonError> {
+        let (mut result, summary) = self
+            .execute_streaming(sql, params, limit, timeout_secs, QueryResult::default())
+            .await?;
+        result.truncated = summary.truncated;
+        result.total_rows = summary.total_rows;
+        Ok(result)
+    }
+
+    async fn execute_streaming<S: ResultSink + 'static>(
+        &self,
+        sql: &str,
+        params: &[QueryParam],
+        limit: Option<usize>,
+        timeout_secs: u64,
+        mut sink: S,
+    ) -> Result<(S, StreamSummary), DbtoonError> {
+        let response = self
+            .run_statement(sql, params, limit, timeout_secs, sink.accepts_arrow())
+            .await?;
+        // The statement has finished; cancelling only stops the download
+        let summary = tokio::select! {
+            summary = self.stream_response(response, limit, &mut sink) => summary?,
+            _ = self.cancel.cancelled() => return Err(DbtoonError::Cancelled),
+        };
+        Ok((sink, summary))
+    }
+}
+
+/// Chunk downloads in chunk order, aborted when dropped so that an early return
+/// or a cancelled query does not leave them running.
+#[derive(Default)]
+struct Downloads(VecDeque<tokio::task::JoinHandle<Result<Vec<u8>, DbtoonError>>>);
+
+impl Drop for Downloads {
+    fn drop(&mut self) {
+        for handle in &self.0 {
+            handle.abort();
+        }
+    }
+}
+
+/// Resolve a chunk's presigned link (fetching it if the statement response did
+/// not include one) and download the Arrow IPC stream it points to.
+///
+/// Presigned URLs go straight to cloud storage, so the workspace token is only
+/// sent to the chunk metadata endpoint, never to the download itself.
+async fn download_chunk(
+    client: Client,
+    chunk_url: String,
+    auth_header: String,
+    link: Option<ExternalLink>,
+    retry: RetryPolicy,
+    verbose: bool,
+) -> Result<Vec<u8>, DbtoonError> {
+    let link = match link {
+        Some(link) => link,
+        None => {
+            let resp = send_with_retry(&retry, Idempotency::Idempotent, verbose, "chunk link fetch", || {
+                client.get(&chunk_url).header("Authorization", &auth_header)
+            })
+            .await
+            .map_err(|e| DbtoonError::Connection {
+                    message: format!("failed to fetch result chunk: {}", e),
+                })?;
+
+            let status_code = resp.status();
+            if !status_code.is_success() {
+                return Err(DatabricksBackend::map_http_error(status_code.as_u16()));
+            }
+
+            let chunk: ResultData = resp.json().await.map_err(|e| DbtoonError::Query {
+                message: format!("failed to parse result chunk: {}", e),
+            })?;
+            chunk
+                .external_links
+                .and_then(|links| links.into_iter().next())
+                .ok_or_else(|| DbtoonError::Query {
+                    message: format!("no external link in result chunk {}", chunk_url),
+                })?
+        }
+    };
+
+    let what = format!("chunk {} download", link.chunk_index);
+    let resp = send_with_retry(&retry, Idempotency::Idempotent, verbose, &what, || {
+        let mut request = client.get(&link.external_link);
+        for (name, value) in &link.http_headers {
+            request = request.header(name, value);
+        }
+        request
+    })
+    .await
+    .map_err(|e| DbtoonError::Connection {
+        message: format!("failed to download result chunk {}: {}", link.chunk_index, e),
+    })?;
+
+    let status_code = resp.status();
+    if !status_code.is_success() {
+        return Err(DbtoonError::Connection {
+            message: format!(
+                "failed to download result chunk {}: HTTP error: {}",
+                link.chunk_index,
+                status_code.as_u16()
+            ),
+        });
+    }
+
+    let bytes = resp.bytes().await.map_err(|e| DbtoonError::Connection {
+        message: format!("failed to download result chunk {}: {}", link.chunk_index, e),
+    })?;
+    Ok(bytes.to_vec())
+}
+
+/// Base URL for a workspace host. A bare hostname gets `https://`; an explicit
+/// scheme is kept as-is (e.g. a local stand-in server).
+pub(crate) fn host_url(host: &str) -> String {
+    if host.starts_with("https://") || host.starts_with("http://") {
+        host.trim_end_matches('/').to_string()
+    } else {
+        format!("https://{}", host)
+    }
+}
+
+/// `Authorization` header value for `auth`, fetching an OAuth token if needed.
+async fn auth_header(
+    client: &Client,
+    host: &str,
+    auth: &DatabricksAuth,
+) -> Result<String, DbtoonError> {
+    match auth {
+        DatabricksAuth::Token(token) => Ok(format!("Bearer {}", token.expose_secret())),
+        DatabricksAuth::OAuthM2M {
+            client_id,
+            client_secret,
+        } => {
+            let token = oauth::m2m_token(client, host, client_id, client_secret).await?;
+            Ok(format!("Bearer {}", token.expose_secret()))
+        }
+        DatabricksAuth::OAuthU2M {
+            client_id,
+            token_cache,
+        } => {
+            let token = oauth::u2m_token(client, host, client_id, token_cache).await?;
+            Ok(format!("Bearer {}", token.expose_secret()))
+        }
+    }
+}
+
+/// List available Databricks SQL warehouses, retrying transient failures under `retry`.
 pub async fn list_warehouses(
     host: &str,
-    token: &SecretString,
+    auth: &DatabricksAuth,
+    retry: &RetryPolicy,
+    verbose: bool,
 ) -> Result<Vec<WarehouseInfo>, DbtoonError> {
     let client = Client::new();
-    let url = format!("https://{}/api/2.0/sql/warehouses/", host);
-
-    let resp = client
-        .get(&url)
-        .header("Authorization", format!("Bearer {}", token.expose_secret()))
-        .send()
-        .await
-        .map_err(|e| DbtoonError::Connection {
+    let url = format!("{}/api/2.0/sql/warehouses/", host_url(host));
+    let auth_header = auth_header(&client, host, auth).await?;
+
+    let resp = send_with_retry(retry, Idempotency::Idempotent, verbose, "warehouse list", || {
+        client.get(&url).header("Authorization", &auth_header)
+    })
+    .await
+    .map_err(|e| DbtoonError::Connection {
             message: format!("failed to list warehouses: {}", e),
         })?;
 
@@ -0,0 +1,63 @@
+//! Connections kept open between queries once `reuse_connections` has been
+//! called, one cache per backend, keyed by everything that shapes a session.
+
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+struct Entry<K, C> {
+    key: K,
+    since: Instant,
+    connection: C,
+}
+
+pub(crate) struct IdleConnections<K, C> {
+    entries: Mutex<Vec<Entry<K, C>>>,
+}
+
+impl<K: PartialEq, C> IdleConnections<K, C> {
+    pub(crate) const fn new() -> Self {
+        Self {
+            entries: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn entries(&self) -> MutexGuard<'_, Vec<Entry<K, C>>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The most recently returned connection for `key`, if any.
+    pub(crate) fn take(&self, key: &K) -> Option<C> {
+        let mut entries = self.entries();
+        let index = entries.iter().rposition(|e| e.key == *key)?;
+        Some(entries.remove(index).connection)
+    }
+
+    /// Keep `connection` for the next query to `key` while connections are
+    /// reused; otherwise hand it back for the caller to close.
+    pub(crate) fn put(&self, key: K, connection: C) -> Option<C> {
+        if super::connection_idle_timeout().is_none() {
+            return Some(connection);
+        }
+        self.entries().push(Entry {
+            key,
+            since: Instant::now(),
+            connection,
+        });
+        None
+    }
+
+    /// Remove the connections unused for at least `idle_timeout`, for the
+    /// caller to close outside the lock.
+    pub(crate) fn expired(&self, idle_timeout: Duration) -> Vec<C> {
+        let mut entries = self.entries();
+        let (expired, kept) = std::mem::take(&mut *entries)
+            .into_iter()
+            .partition(|e| e.since.elapsed() >= idle_timeout);
+        *entries = kept;
+        expired.into_iter().map(|e| e.connection).collect()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries().len()
+    }
+}
@@ -1,7 +1,108 @@
+pub mod bigquery;
+pub mod clickhouse;
 pub mod databricks;
+mod idle;
+pub mod mysql;
+pub mod oauth;
+pub mod odbc;
+pub mod postgres;
+pub mod retry;
+pub mod snowflake;
+pub mod sqlite;
 pub mod sqlserver;
+pub mod trino;
+pub mod value;
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use arrow::record_batch::RecordBatch;
 
 use crate::error::DbtoonError;
+use crate::params::QueryParam;
+
+pub use value::CellValue;
+
+/// Number of rows delivered to a `ResultSink` per batch.
+pub const STREAM_BATCH_SIZE: usize = 5000;
+
+/// How long an unused connection stays open for the next query, once
+/// `reuse_connections` has been called.
+static IDLE_TIMEOUT: OnceLock<Duration> = OnceLock::new();
+
+/// Keep connections open between queries in this process, closing those unused
+/// for `idle_timeout`: ODBC, SQLite, PostgreSQL and MySQL connections are
+/// returned to a cache, and the HTTP backends share one client whose pool keeps
+/// TLS sessions alive.
+///
+/// Without this call every query opens and closes its own connection. Only the
+/// first call takes effect.
+pub fn reuse_connections(idle_timeout: Duration) {
+    let _ = IDLE_TIMEOUT.set(idle_timeout);
+}
+
+/// The idle timeout set by `reuse_connections`, if connections are reused.
+pub(crate) fn connection_idle_timeout() -> Option<Duration> {
+    IDLE_TIMEOUT.get().copied()
+}
+
+/// What a query leaves of its connection's session for the next query on it,
+/// once the connection is cached by `reuse_connections`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SessionReuse {
+    /// Queries are independent. PostgreSQL and MySQL connections are reset
+    /// before they are cached (`DISCARD ALL`, `COM_CHANGE_USER`); ODBC and
+    /// SQLite connections, which cannot be, are closed instead.
+    #[default]
+    Reset,
+    /// As `Reset`, but every statement is a plain query, so an ODBC or SQLite
+    /// connection is cached unchanged once the query succeeds.
+    ReadOnly,
+    /// Queries continue one session, as the shell's statements do: `USE`, `SET`,
+    /// temporary tables and open transactions carry over.
+    Keep,
+}
+
+/// Close cached connections that have been unused for longer than the idle timeout.
+pub fn close_idle_connections() {
+    if let Some(idle_timeout) = connection_idle_timeout() {
+        close_idle(idle_timeout);
+    }
+}
+
+/// Close every cached connection, as when the process shuts down.
+pub fn close_all_connections() {
+    close_idle(Duration::ZERO);
+}
+
+fn close_idle(idle_timeout: Duration) {
+    odbc::close_idle(idle_timeout);
+    sqlite::close_idle(idle_timeout);
+    postgres::close_idle(idle_timeout);
+    mysql::close_idle(idle_timeout);
+}
+
+/// Number of cached connections waiting for a query.
+pub fn idle_connection_count() -> usize {
+    odbc::idle_count() + sqlite::idle_count() + postgres::idle_count() + mysql::idle_count()
+}
+
+/// The client for a backend's HTTP requests: the shared one while connections
+/// are reused, otherwise a new one.
+pub(crate) fn http_client() -> reqwest::Client {
+    static SHARED: OnceLock<reqwest::Client> = OnceLock::new();
+    let Some(idle_timeout) = connection_idle_timeout() else {
+        return reqwest::Client::new();
+    };
+    SHARED
+        .get_or_init(|| {
+            reqwest::Client::builder()
+                .pool_idle_timeout(idle_timeout)
+                .build()
+                .unwrap_or_default()
+        })
+        .clone()
+}
 
 /// Metadata for a single result column.
 #[derive(Debug, Clone)]
@@ -10,28 +111,167 @@ pub struct ColumnMeta {
     pub type_name: String,
 }
 
-/// A single cell value from a query result.
-#[derive(Debug, Clone)]
-pub enum CellValue {
-    Text(String),
-    Null,
-}
-
 /// The output of executing a query, before TOON serialization.
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct QueryResult {
     pub columns: Vec<ColumnMeta>,
     pub rows: Vec<Vec<CellValue>>,
     pub total_rows: Option<usize>,
     pub truncated: bool,
+    /// Informational messages from the server (e.g. SQL Server `PRINT` output).
+    pub messages: Vec<String>,
+    /// Rows changed by statements without a result set, when the backend reports it.
+    pub rows_affected: Option<usize>,
+}
+
+/// Row count and truncation state of a streamed result.
+#[derive(Debug, Clone, Default)]
+pub struct StreamSummary {
+    pub rows: usize,
+    pub truncated: bool,
+    /// Total rows in the full result, when the backend reports it.
+    pub total_rows: Option<usize>,
+    /// Informational messages from the server, in the order they arrived.
+    pub messages: Vec<String>,
+    /// Rows changed by statements without a result set, when the backend reports it.
+    pub rows_affected: Option<usize>,
+}
+
+impl StreamSummary {
+    /// The message shown alongside a truncated result, or `None` if it is complete.
+    pub fn truncation_message(&self) -> Option<String> {
+        self.truncated.then(|| {
+            format!(
+                "Showing {} rows. Use --no-limit to return all rows.",
+                self.rows
+            )
+        })
+    }
+}
+
+/// Consumer of a query result delivered one row batch at a time.
+///
+/// Backends call `begin` once with the column metadata, then `write_batch` for
+/// each fetched batch. Finalizing the output is left to the caller, since only
+/// it knows the truncation state once the backend returns.
+///
+/// A statement without a result set, e.g. an UPDATE, calls `begin` with no
+/// columns and reports its count in `StreamSummary::rows_affected`.
+///
+/// A batch that returns several result sets calls `next_result` between sets,
+/// followed by `begin` for the next one.
+pub trait ResultSink: Send {
+    fn begin(&mut self, columns: &[ColumnMeta]) -> Result<(), DbtoonError>;
+    fn write_batch(&mut self, rows: &[Vec<CellValue>]) -> Result<(), DbtoonError>;
+
+    /// Close the current result set, summarized by `previous`, before another begins.
+    ///
+    /// The default rejects further result sets.
+    fn next_result(&mut self, _previous: StreamSummary) -> Result<(), DbtoonError> {
+        Err(DbtoonError::Query {
+            message: "query returned more than one result set".to_string(),
+        })
+    }
+
+    /// Whether the sink takes native Arrow batches via `write_arrow_batch`.
+    ///
+    /// Backends that can fetch Arrow data directly use this to skip the
+    /// `CellValue` round trip.
+    fn accepts_arrow(&self) -> bool {
+        false
+    }
+
+    fn write_arrow_batch(&mut self, _batch: &RecordBatch) -> Result<(), DbtoonError> {
+        Err(DbtoonError::Format {
+            message: "output format does not accept Arrow record batches".to_string(),
+        })
+    }
+}
+
+/// Buffers every batch in memory.
+impl ResultSink for QueryResult {
+    fn begin(&mut self, columns: &[ColumnMeta]) -> Result<(), DbtoonError> {
+        self.columns = columns.to_vec();
+        Ok(())
+    }
+
+    fn write_batch(&mut self, rows: &[Vec<CellValue>]) -> Result<(), DbtoonError> {
+        self.rows.extend_from_slice(rows);
+        Ok(())
+    }
+}
+
+/// Buffers every result set in memory, in order.
+impl ResultSink for Vec<QueryResult> {
+    fn begin(&mut self, columns: &[ColumnMeta]) -> Result<(), DbtoonError> {
+        self.push(QueryResult {
+            columns: columns.to_vec(),
+            ..QueryResult::default()
+        });
+        Ok(())
+    }
+
+    fn write_batch(&mut self, rows: &[Vec<CellValue>]) -> Result<(), DbtoonError> {
+        if let Some(current) = self.last_mut() {
+            current.rows.extend_from_slice(rows);
+        }
+        Ok(())
+    }
+
+    fn next_result(&mut self, previous: StreamSummary) -> Result<(), DbtoonError> {
+        if let Some(current) = self.last_mut() {
+            current.truncated = previous.truncated;
+            current.total_rows = previous.total_rows;
+            current.messages = previous.messages;
+            current.rows_affected = previous.rows_affected;
+        }
+        Ok(())
+    }
 }
 
 /// Trait for database backends.
+///
+/// `params` bind values to the `:name` markers in `sql`. A query that is
+/// cancelled or times out is also stopped on the server where the protocol
+/// allows it, so the server does not keep working on a statement nobody will read.
 pub trait Backend {
     fn execute(
         &self,
         sql: &str,
+        params: &[QueryParam],
         limit: Option<usize>,
         timeout_secs: u64,
     ) -> impl std::future::Future<Output = Result<QueryResult, DbtoonError>> + Send;
+
+    /// Execute a query and feed its rows to `sink` as they are fetched.
+    ///
+    /// Returns the sink so the caller can finalize it. The default implementation
+    /// buffers the full result via `execute` and replays it in batches.
+    fn execute_streaming<S: ResultSink + 'static>(
+        &self,
+        sql: &str,
+        params: &[QueryParam],
+        limit: Option<usize>,
+        timeout_secs: u64,
+        mut sink: S,
+    ) -> impl std::future::Future<Output = Result<(S, StreamSummary), DbtoonError>> + Send
+    where
+        Self: Sync,
+    {
+        async move {
+            let result = self.execute(sql, params, limit, timeout_secs).await?;
+            sink.begin(&result.columns)?;
+            for batch in result.rows.chunks(STREAM_BATCH_SIZE) {
+                sink.write_batch(batch)?;
+            }
+            let summary = StreamSummary {
+                rows: result.rows.len(),
+                truncated: result.truncated,
+                total_rows: result.total_rows,
+                messages: result.messages,
+                rows_affected: result.rows_affected,
+            };
+            Ok((sink, summary))
+        }
+    }
 }
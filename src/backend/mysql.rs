@@ -0,0 +1,597 @@
+//! Native MySQL and MariaDB backend over the client/server protocol (mysql_async).
+
+use std::time::Duration;
+
+use mysql_async::consts::{ColumnFlags, ColumnType};
+use mysql_async::prelude::{Protocol, Queryable};
+use mysql_async::{ChangeUserOpts, Column, Conn, DriverError, Opts, OptsBuilder, Params, SslOpts, Value};
+use secrecy::{ExposeSecret, SecretString};
+
+use crate::backend::idle::IdleConnections;
+use crate::backend::value::{
+    days_from_civil, parse_date32, parse_decimal, parse_time_micros, parse_timestamp_micros,
+};
+use crate::backend::{
+    Backend, CellValue, ColumnMeta, QueryResult, ResultSink, STREAM_BATCH_SIZE, SessionReuse,
+    StreamSummary,
+};
+use crate::cancel::CancelToken;
+use crate::config::MySqlSslMode;
+use crate::error::DbtoonError;
+use crate::params::{QueryParam, to_positional};
+
+/// Character set number MySQL reports for binary strings and blobs.
+const BINARY_CHARSET: u16 = 63;
+
+const MICROS_PER_DAY: i64 = 86_400_000_000;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct MySqlBackend {
+    host: String,
+    port: u16,
+    database: Option<String>,
+    user: String,
+    password: Option<SecretString>,
+    ssl_mode: MySqlSslMode,
+    session: SessionReuse,
+    cancel: CancelToken,
+}
+
+impl MySqlBackend {
+    pub fn new(
+        host: String,
+        port: u16,
+        database: Option<String>,
+        user: String,
+        password: Option<SecretString>,
+        ssl_mode: MySqlSslMode,
+    ) -> Self {
+        Self {
+            host,
+            port,
+            database,
+            user,
+            password,
+            ssl_mode,
+            session: SessionReuse::default(),
+            cancel: CancelToken::default(),
+        }
+    }
+
+    /// Whether the session is reset before it is cached for the next query.
+    pub fn with_session(mut self, session: SessionReuse) -> Self {
+        self.session = session;
+        self
+    }
+
+    /// Stop the running statement with `KILL QUERY` when `cancel` fires.
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    fn opts(&self, ssl: Option<SslOpts>) -> Opts {
+        OptsBuilder::default()
+            .ip_or_hostname(self.host.clone())
+            .tcp_port(self.port)
+            .user(Some(self.user.clone()))
+            .pass(self.password.as_ref().map(|p| p.expose_secret().to_string()))
+            .db_name(self.database.clone())
+            // TIMESTAMP values are converted to the session zone; show them in UTC.
+            // Setup commands run again after a session reset.
+            .setup(vec!["SET time_zone = '+00:00'"])
+            .ssl_opts(ssl)
+            .into()
+    }
+
+    /// Connect, returning the options used so a second connection can kill the query.
+    ///
+    /// Under `preferred`, a server without TLS support is connected to in plain text.
+    async fn connect(&self) -> Result<(Conn, Opts), DbtoonError> {
+        let connecting = async {
+            let opts = self.opts(ssl_opts(self.ssl_mode));
+            match Conn::new(opts.clone()).await {
+                Ok(conn) => Ok((conn, opts)),
+                Err(mysql_async::Error::Driver(DriverError::NoClientSslFlagFromServer))
+                    if self.ssl_mode == MySqlSslMode::Preferred =>
+                {
+                    let opts = self.opts(None);
+                    Conn::new(opts.clone()).await.map(|conn| (conn, opts))
+                }
+                Err(e) => Err(e),
+            }
+        };
+        match tokio::time::timeout(CONNECT_TIMEOUT, connecting).await {
+            Ok(connected) => connected.map_err(|e| DbtoonError::Connection {
+                message: format!("connection failed: {}", describe(&e)),
+            }),
+            Err(_) => Err(DbtoonError::Connection {
+                message: format!("connection timed out after {} seconds", CONNECT_TIMEOUT.as_secs()),
+            }),
+        }
+    }
+
+    /// A cached connection with the same settings that still answers (see
+    /// `backend::reuse_connections`), or a new one. Also returns the key to
+    /// check it back in under, and whether it was reused.
+    async fn checkout(&self) -> Result<(Opts, Conn, Opts, bool), DbtoonError> {
+        let key = self.opts(ssl_opts(self.ssl_mode));
+        while let Some((mut conn, opts)) = IDLE.take(&key) {
+            if conn.ping().await.is_ok() {
+                return Ok((key, conn, opts, true));
+            }
+        }
+        let (conn, opts) = self.connect().await?;
+        Ok((key, conn, opts, false))
+    }
+}
+
+/// Connections kept between queries, by the options they were asked for,
+/// with the options they were opened with.
+static IDLE: IdleConnections<Opts, (Conn, Opts)> = IdleConnections::new();
+
+pub(crate) fn close_idle(idle_timeout: Duration) {
+    drop(IDLE.expired(idle_timeout));
+}
+
+pub(crate) fn idle_count() -> usize {
+    IDLE.len()
+}
+
+/// TLS options matching `ssl_mode`: `preferred` and `required` encrypt without
+/// checking the certificate, `verify_ca` checks the chain, and `verify_identity`
+/// also checks the host name.
+fn ssl_opts(ssl_mode: MySqlSslMode) -> Option<SslOpts> {
+    match ssl_mode {
+        MySqlSslMode::Disabled => None,
+        MySqlSslMode::Preferred | MySqlSslMode::Required => {
+            Some(SslOpts::default().with_danger_accept_invalid_certs(true))
+        }
+        MySqlSslMode::VerifyCa => Some(SslOpts::default().with_danger_skip_domain_validation(true)),
+        MySqlSslMode::VerifyIdentity => Some(SslOpts::default()),
+    }
+}
+
+/// Keep `conn` for the next query with the same settings while connections
+/// are reused, with `COM_CHANGE_USER` first unless `reuse` keeps it as is, or
+/// disconnect it.
+async fn check_in(key: Opts, mut conn: Conn, opts: Opts, reuse: SessionReuse) {
+    // Unlike `COM_RESET_CONNECTION`, this also undoes a `USE`
+    if reuse != SessionReuse::Keep
+        && super::connection_idle_timeout().is_some()
+        && conn.change_user(ChangeUserOpts::default()).await.is_err()
+    {
+        let _ = conn.disconnect().await;
+        return;
+    }
+    if let Some((conn, _)) = IDLE.put(key, (conn, opts)) {
+        let _ = conn.disconnect().await;
+    }
+}
+
+/// Stop the statement running on connection `id`, from a second connection.
+async fn kill_query(opts: Opts, id: u32) {
+    if let Ok(Ok(mut conn)) = tokio::time::timeout(CONNECT_TIMEOUT, Conn::new(opts)).await {
+        let _ = conn.query_drop(format!("KILL QUERY {}", id)).await;
+        let _ = conn.disconnect().await;
+    }
+}
+
+impl Backend for MySqlBackend {
+    async fn execute(
+        &self,
+        sql: &str,
+        params: &[QueryParam],
+        limit: Option<usize>,
+        timeout_secs: u64,
+    ) -> Result<QueryResult, DbtoonError> {
+        let (mut result, summary) = self
+            .execute_streaming(sql, params, limit, timeout_secs, QueryResult::default())
+            .await?;
+        result.truncated = summary.truncated;
+        result.messages = summary.messages;
+        result.rows_affected = summary.rows_affected;
+        Ok(result)
+    }
+
+    async fn execute_streaming<S: ResultSink + 'static>(
+        &self,
+        sql: &str,
+        params: &[QueryParam],
+        limit: Option<usize>,
+        timeout_secs: u64,
+        mut sink: S,
+    ) -> Result<(S, StreamSummary), DbtoonError> {
+        let (sql, bound) = to_positional(sql, params)?;
+        let values: Vec<Value> = bound.into_iter().map(bind_value).collect();
+        let (key, mut conn, opts, reused) = self.checkout().await?;
+
+        // The server stops each SELECT one row past the limit, which marks it truncated
+        let select_limit = match limit {
+            Some(limit) => Some(limit.saturating_add(1).to_string()),
+            None if reused => Some("DEFAULT".to_string()),
+            None => None,
+        };
+        if let Some(select_limit) = select_limit {
+            conn.query_drop(format!("SET SESSION sql_select_limit = {}", select_limit))
+                .await
+                .map_err(|e| query_error("failed to set row limit", &e))?;
+        }
+
+        let connection_id = conn.id();
+        let outcome = tokio::select! {
+            outcome = run_statement(&mut conn, &sql, values, limit, &mut sink) => outcome,
+            _ = self.cancel.cancelled() => Err(DbtoonError::Cancelled),
+            _ = tokio::time::sleep(Duration::from_secs(timeout_secs)) => {
+                Err(DbtoonError::Timeout { seconds: timeout_secs })
+            }
+        };
+        let summary = match outcome {
+            Ok(summary) => summary,
+            Err(e @ (DbtoonError::Cancelled | DbtoonError::Timeout { .. })) => {
+                kill_query(opts, connection_id).await;
+                return Err(e);
+            }
+            Err(e) => {
+                check_in(key, conn, opts, self.session).await;
+                return Err(e);
+            }
+        };
+        check_in(key, conn, opts, self.session).await;
+        Ok((sink, summary))
+    }
+}
+
+/// Run one statement and stream every result set it returns into `sink`.
+///
+/// Statements with parameters are prepared and use the binary protocol; others
+/// run as plain text queries, which MySQL accepts for every statement type.
+/// Warnings the statement raised are returned as messages.
+async fn run_statement(
+    conn: &mut Conn,
+    sql: &str,
+    values: Vec<Value>,
+    limit: Option<usize>,
+    sink: &mut dyn ResultSink,
+) -> Result<StreamSummary, DbtoonError> {
+    let mut summary = if values.is_empty() {
+        let result = conn
+            .query_iter(sql)
+            .await
+            .map_err(|e| query_error("query execution failed", &e))?;
+        stream_results(result, limit, sink).await?
+    } else {
+        let result = conn
+            .exec_iter(sql, Params::Positional(values))
+            .await
+            .map_err(|e| query_error("query execution failed", &e))?;
+        stream_results(result, limit, sink).await?
+    };
+
+    if conn.get_warnings() > 0 {
+        let warnings: Vec<(String, u32, String)> = conn
+            .query("SHOW WARNINGS")
+            .await
+            .map_err(|e| query_error("failed to read warnings", &e))?;
+        summary.messages.extend(
+            warnings
+                .into_iter()
+                .map(|(level, code, message)| format!("{} {}: {}", level, code, message)),
+        );
+    }
+    Ok(summary)
+}
+
+/// Feed each result set to `sink`, separated by `next_result`.
+///
+/// `limit` applies to each result set separately; the returned summary is the
+/// last set's, carrying the rows affected by statements without a result set.
+async fn stream_results<P: Protocol>(
+    mut result: mysql_async::QueryResult<'_, 'static, P>,
+    limit: Option<usize>,
+    sink: &mut dyn ResultSink,
+) -> Result<StreamSummary, DbtoonError> {
+    let mut rows_affected: Option<usize> = None;
+    let mut last: Option<StreamSummary> = None;
+
+    loop {
+        let columns = result.columns_ref().to_vec();
+        if columns.is_empty() {
+            let count = usize::try_from(result.affected_rows()).unwrap_or(usize::MAX);
+            *rows_affected.get_or_insert(0) += count;
+        } else {
+            if let Some(previous) = last.take() {
+                sink.next_result(previous)?;
+            }
+            last = Some(fetch_result_set(&mut result, &columns, limit, sink).await?);
+        }
+
+        if result.is_empty() {
+            break;
+        }
+        if columns.is_empty() {
+            // Step past the row count to the next result set
+            result.for_each(drop).await.map_err(|e| query_error("fetch error", &e))?;
+        }
+    }
+
+    let mut summary = match last {
+        Some(summary) => summary,
+        None => {
+            sink.begin(&[])?;
+            StreamSummary::default()
+        }
+    };
+    summary.rows_affected = rows_affected;
+    Ok(summary)
+}
+
+/// Stream the current result set into `sink` in `STREAM_BATCH_SIZE` batches,
+/// keeping at most `limit` rows.
+///
+/// Rows past the limit are read and discarded; `sql_select_limit` keeps that
+/// to one row for plain SELECTs.
+async fn fetch_result_set<P: Protocol>(
+    result: &mut mysql_async::QueryResult<'_, 'static, P>,
+    columns: &[Column],
+    limit: Option<usize>,
+    sink: &mut dyn ResultSink,
+) -> Result<StreamSummary, DbtoonError> {
+    let metas: Vec<ColumnMeta> = columns.iter().map(column_meta).collect();
+    sink.begin(&metas)?;
+
+    let mut total = 0usize;
+    let mut truncated = false;
+    let mut failed: Option<DbtoonError> = None;
+    let mut batch = Vec::with_capacity(STREAM_BATCH_SIZE);
+    result
+        .for_each(|row| {
+            if truncated || failed.is_some() {
+                return;
+            }
+            if limit.is_some_and(|limit| total + batch.len() >= limit) {
+                truncated = true;
+                return;
+            }
+            let cells = row
+                .unwrap()
+                .into_iter()
+                .zip(columns)
+                .map(|(value, column)| decode_value(column, value))
+                .collect();
+            batch.push(cells);
+            if batch.len() == STREAM_BATCH_SIZE {
+                match sink.write_batch(&batch) {
+                    Ok(()) => {
+                        total += batch.len();
+                        batch.clear();
+                    }
+                    Err(e) => failed = Some(e),
+                }
+            }
+        })
+        .await
+        .map_err(|e| query_error("fetch error", &e))?;
+    if let Some(e) = failed {
+        return Err(e);
+    }
+    if !batch.is_empty() {
+        sink.write_batch(&batch)?;
+        total += batch.len();
+    }
+
+    Ok(StreamSummary {
+        rows: total,
+        truncated,
+        ..StreamSummary::default()
+    })
+}
+
+fn query_error(context: &str, e: &mysql_async::Error) -> DbtoonError {
+    DbtoonError::Query {
+        message: format!("{}: {}", context, describe(e)),
+    }
+}
+
+/// The server's `ERROR code (state): message` for server errors, or the error itself otherwise.
+fn describe(e: &mysql_async::Error) -> String {
+    match e {
+        mysql_async::Error::Server(server) => server.to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn column_meta(column: &Column) -> ColumnMeta {
+    ColumnMeta {
+        name: column.name_str().into_owned(),
+        type_name: normalize_mysql_type(column),
+    }
+}
+
+/// Normalize a MySQL column type to the SQL type names
+/// `format_columnar::sql_type_to_arrow` understands.
+///
+/// Unsigned integers widen to the next signed type (`BIGINT UNSIGNED` becomes
+/// `DECIMAL(20,0)`), `TINYINT` becomes `SMALLINT` since `TINYINT` maps to an
+/// unsigned byte, and `BIT(1)` becomes `BOOLEAN`. Text and blob columns become
+/// `VARCHAR` or `VARBINARY` by character set. Types without a columnar
+/// counterpart keep their MySQL name, e.g. `JSON` or `GEOMETRY`.
+pub fn normalize_mysql_type(column: &Column) -> String {
+    let unsigned = column.flags().contains(ColumnFlags::UNSIGNED_FLAG);
+    let binary = column.character_set() == BINARY_CHARSET;
+    let name = match column.column_type() {
+        ColumnType::MYSQL_TYPE_TINY if unsigned => "TINYINT",
+        ColumnType::MYSQL_TYPE_TINY | ColumnType::MYSQL_TYPE_YEAR => "SMALLINT",
+        ColumnType::MYSQL_TYPE_SHORT if unsigned => "INT",
+        ColumnType::MYSQL_TYPE_SHORT => "SMALLINT",
+        ColumnType::MYSQL_TYPE_INT24 => "INT",
+        ColumnType::MYSQL_TYPE_LONG if unsigned => "BIGINT",
+        ColumnType::MYSQL_TYPE_LONG => "INT",
+        ColumnType::MYSQL_TYPE_LONGLONG if unsigned => "DECIMAL(20,0)",
+        ColumnType::MYSQL_TYPE_LONGLONG => "BIGINT",
+        ColumnType::MYSQL_TYPE_FLOAT => "REAL",
+        ColumnType::MYSQL_TYPE_DOUBLE => "DOUBLE",
+        ColumnType::MYSQL_TYPE_DECIMAL | ColumnType::MYSQL_TYPE_NEWDECIMAL => {
+            // The display length counts the sign and the decimal point
+            let scale = u32::from(column.decimals());
+            let precision = column
+                .column_length()
+                .saturating_sub(u32::from(scale > 0))
+                .saturating_sub(u32::from(!unsigned));
+            return format!("DECIMAL({},{})", precision, scale);
+        }
+        ColumnType::MYSQL_TYPE_BIT if column.column_length() == 1 => "BOOLEAN",
+        ColumnType::MYSQL_TYPE_BIT => "BIGINT",
+        ColumnType::MYSQL_TYPE_DATE | ColumnType::MYSQL_TYPE_NEWDATE => "DATE",
+        ColumnType::MYSQL_TYPE_DATETIME
+        | ColumnType::MYSQL_TYPE_DATETIME2
+        | ColumnType::MYSQL_TYPE_TIMESTAMP
+        | ColumnType::MYSQL_TYPE_TIMESTAMP2 => "TIMESTAMP",
+        ColumnType::MYSQL_TYPE_TIME | ColumnType::MYSQL_TYPE_TIME2 => "TIME",
+        ColumnType::MYSQL_TYPE_JSON => "JSON",
+        ColumnType::MYSQL_TYPE_STRING if binary => "BINARY",
+        ColumnType::MYSQL_TYPE_STRING => "CHAR",
+        ColumnType::MYSQL_TYPE_VARCHAR
+        | ColumnType::MYSQL_TYPE_VAR_STRING
+        | ColumnType::MYSQL_TYPE_TINY_BLOB
+        | ColumnType::MYSQL_TYPE_MEDIUM_BLOB
+        | ColumnType::MYSQL_TYPE_LONG_BLOB
+        | ColumnType::MYSQL_TYPE_BLOB
+            if binary =>
+        {
+            "VARBINARY"
+        }
+        ColumnType::MYSQL_TYPE_VARCHAR
+        | ColumnType::MYSQL_TYPE_VAR_STRING
+        | ColumnType::MYSQL_TYPE_TINY_BLOB
+        | ColumnType::MYSQL_TYPE_MEDIUM_BLOB
+        | ColumnType::MYSQL_TYPE_LONG_BLOB
+        | ColumnType::MYSQL_TYPE_BLOB
+        | ColumnType::MYSQL_TYPE_ENUM
+        | ColumnType::MYSQL_TYPE_SET => "VARCHAR",
+        other => {
+            return format!("{:?}", other).trim_start_matches("MYSQL_TYPE_").to_string();
+        }
+    };
+    name.to_string()
+}
+
+/// Decode one value of `column` into a cell.
+///
+/// Prepared statements deliver numbers, dates and times natively; text queries
+/// (and decimals, bits and strings in either protocol) deliver bytes, which are
+/// parsed by column type. Zero dates and times outside a single day stay text.
+pub fn decode_value(column: &Column, value: Value) -> CellValue {
+    let unsigned_bigint = column.column_type() == ColumnType::MYSQL_TYPE_LONGLONG
+        && column.flags().contains(ColumnFlags::UNSIGNED_FLAG);
+    match value {
+        Value::NULL => CellValue::Null,
+        Value::Int(v) => CellValue::Int64(v),
+        Value::UInt(v) if unsigned_bigint => CellValue::Decimal { value: v.into(), scale: 0 },
+        Value::UInt(v) => match i64::try_from(v) {
+            Ok(v) => CellValue::Int64(v),
+            Err(_) => CellValue::Decimal { value: v.into(), scale: 0 },
+        },
+        Value::Float(f) => CellValue::from_f32(f),
+        Value::Double(d) => CellValue::Float64(d),
+        Value::Date(year, month, day, hour, minute, second, micros) => {
+            let date = days_from_civil(year.into(), month.into(), day.into());
+            let time = (i64::from(hour) * 3600 + i64::from(minute) * 60 + i64::from(second))
+                * 1_000_000
+                + i64::from(micros);
+            match date {
+                Some(days) if is_date(column) => CellValue::Date(days),
+                Some(days) => CellValue::Timestamp(i64::from(days) * MICROS_PER_DAY + time),
+                None if is_date(column) => CellValue::Text(format!("{:04}-{:02}-{:02}", year, month, day)),
+                None => CellValue::Text(format!(
+                    "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+                    year, month, day, hour, minute, second
+                )),
+            }
+        }
+        Value::Time(negative, days, hours, minutes, seconds, micros) => {
+            if !negative && days == 0 {
+                let secs = i64::from(hours) * 3600 + i64::from(minutes) * 60 + i64::from(seconds);
+                CellValue::Time(secs * 1_000_000 + i64::from(micros))
+            } else {
+                let sign = if negative { "-" } else { "" };
+                let hours = u64::from(days) * 24 + u64::from(hours);
+                let fraction = if micros == 0 { String::new() } else { format!(".{:06}", micros) };
+                CellValue::Text(format!("{}{:02}:{:02}:{:02}{}", sign, hours, minutes, seconds, fraction))
+            }
+        }
+        Value::Bytes(bytes) => decode_bytes(column, bytes),
+    }
+}
+
+fn is_date(column: &Column) -> bool {
+    matches!(column.column_type(), ColumnType::MYSQL_TYPE_DATE | ColumnType::MYSQL_TYPE_NEWDATE)
+}
+
+/// Decode a value sent as bytes: text in MySQL's notation for most types,
+/// a big-endian integer for `BIT`, and raw bytes for binary strings.
+fn decode_bytes(column: &Column, bytes: Vec<u8>) -> CellValue {
+    let column_type = column.column_type();
+    if column_type == ColumnType::MYSQL_TYPE_BIT {
+        let bits = bytes.iter().fold(0u64, |acc, b| (acc << 8) | u64::from(*b));
+        return if column.column_length() == 1 {
+            CellValue::Bool(bits != 0)
+        } else {
+            decode_value(column, Value::UInt(bits))
+        };
+    }
+    // JSON is sent with the binary character set but is always UTF-8
+    if column.character_set() == BINARY_CHARSET && column_type != ColumnType::MYSQL_TYPE_JSON {
+        return CellValue::Bytes(bytes);
+    }
+    let text = match String::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(e) => return CellValue::Bytes(e.into_bytes()),
+    };
+
+    let parsed = match column_type {
+        ColumnType::MYSQL_TYPE_LONGLONG if column.flags().contains(ColumnFlags::UNSIGNED_FLAG) => {
+            parse_decimal(&text).map(|(value, scale)| CellValue::Decimal { value, scale })
+        }
+        ColumnType::MYSQL_TYPE_TINY
+        | ColumnType::MYSQL_TYPE_SHORT
+        | ColumnType::MYSQL_TYPE_INT24
+        | ColumnType::MYSQL_TYPE_LONG
+        | ColumnType::MYSQL_TYPE_LONGLONG
+        | ColumnType::MYSQL_TYPE_YEAR => text.parse().ok().map(CellValue::Int64),
+        ColumnType::MYSQL_TYPE_FLOAT | ColumnType::MYSQL_TYPE_DOUBLE => {
+            text.parse().ok().map(CellValue::Float64)
+        }
+        ColumnType::MYSQL_TYPE_DECIMAL | ColumnType::MYSQL_TYPE_NEWDECIMAL => {
+            parse_decimal(&text).map(|(value, scale)| CellValue::Decimal { value, scale })
+        }
+        ColumnType::MYSQL_TYPE_DATE | ColumnType::MYSQL_TYPE_NEWDATE => {
+            parse_date32(&text).map(CellValue::Date)
+        }
+        ColumnType::MYSQL_TYPE_DATETIME
+        | ColumnType::MYSQL_TYPE_DATETIME2
+        | ColumnType::MYSQL_TYPE_TIMESTAMP
+        | ColumnType::MYSQL_TYPE_TIMESTAMP2 => parse_timestamp_micros(&text).map(CellValue::Timestamp),
+        ColumnType::MYSQL_TYPE_TIME | ColumnType::MYSQL_TYPE_TIME2 if !text.starts_with('-') => {
+            parse_time_micros(&text)
+                .filter(|micros| *micros < MICROS_PER_DAY)
+                .map(CellValue::Time)
+        }
+        _ => None,
+    };
+    parsed.unwrap_or(CellValue::Text(text))
+}
+
+/// A parameter as a MySQL value.
+///
+/// Numbers and booleans bind natively, so typed markers work in `LIMIT ?`;
+/// everything else is sent as text for the server to convert.
+pub fn bind_value(param: &QueryParam) -> Value {
+    match param.typed_value() {
+        CellValue::Int64(v) => Value::Int(v),
+        CellValue::Float64(v) => Value::Double(v),
+        CellValue::Bool(b) => Value::Int(i64::from(b)),
+        _ => Value::Bytes(param.value.clone().into_bytes()),
+    }
+}
@@ -0,0 +1,591 @@
+//! OAuth access tokens for Databricks.
+//!
+//! Two flows are supported:
+//!
+//! - **Machine-to-machine (M2M)**: a service principal's client credentials are
+//!   exchanged at the workspace's `/oidc/v1/token` endpoint with the
+//!   `client_credentials` grant.
+//! - **User-to-machine (U2M)**: `dbtoon auth login` signs a person in through the
+//!   browser (authorization code with PKCE and a localhost redirect) or, on
+//!   headless machines, the device code flow. The refresh token is kept in a
+//!   token cache file that only the user can read, and queries use it to mint
+//!   access tokens.
+//!
+//! Access tokens are cached for the life of the process, so a long-running
+//! `dbtoon mcp` reuses one token until it is about to expire.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use reqwest::Client;
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::Mutex;
+
+use crate::backend::databricks::host_url;
+use crate::error::DbtoonError;
+
+/// Tokens this close to expiry are refreshed before use.
+const REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+/// Lifetime assumed when the token response omits `expires_in`.
+const DEFAULT_LIFETIME: Duration = Duration::from_secs(3600);
+
+/// Public OAuth client that Databricks registers in every account for CLI tools.
+pub const DEFAULT_U2M_CLIENT_ID: &str = "databricks-cli";
+
+/// Localhost port the `databricks-cli` client is allowed to redirect to.
+pub const DEFAULT_REDIRECT_PORT: u16 = 8020;
+
+/// Scopes requested for user logins; `offline_access` yields a refresh token.
+const U2M_SCOPES: &str = "all-apis offline_access";
+
+/// How long a browser login may take before giving up.
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(300);
+
+const DEVICE_CODE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_in: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct TokenError {
+    error: String,
+    error_description: Option<String>,
+}
+
+impl TokenError {
+    fn message(&self) -> String {
+        match &self.error_description {
+            Some(description) => format!("{}: {}", self.error, description),
+            None => self.error.clone(),
+        }
+    }
+}
+
+struct CachedToken {
+    access_token: String,
+    expires_at: Instant,
+}
+
+/// Cached tokens keyed by token endpoint and client id.
+static TOKENS: LazyLock<Mutex<HashMap<(String, String), CachedToken>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// The workspace's OAuth token endpoint.
+pub fn token_endpoint(host: &str) -> String {
+    format!("{}/oidc/v1/token", host_url(host))
+}
+
+/// The workspace's OAuth authorization endpoint, opened in the browser on login.
+pub fn authorize_endpoint(host: &str) -> String {
+    format!("{}/oidc/v1/authorize", host_url(host))
+}
+
+/// An access token for the service principal `client_id`, from the cache or
+/// fetched anew when missing or about to expire.
+pub async fn m2m_token(
+    client: &Client,
+    host: &str,
+    client_id: &str,
+    client_secret: &SecretString,
+) -> Result<SecretString, DbtoonError> {
+    let endpoint = token_endpoint(host);
+    let key = (endpoint.clone(), client_id.to_string());
+
+    // Held across the request so concurrent callers share one fetch
+    let mut tokens = TOKENS.lock().await;
+    if let Some(token) = fresh_token(&tokens, &key) {
+        return Ok(token);
+    }
+
+    let resp = client
+        .post(&endpoint)
+        .basic_auth(client_id, Some(client_secret.expose_secret()))
+        .form(&[("grant_type", "client_credentials"), ("scope", "all-apis")])
+        .send()
+        .await
+        .map_err(|e| DbtoonError::Connection {
+            message: format!("failed to request OAuth token: {}", e),
+        })?;
+
+    let status = resp.status();
+    if !status.is_success() {
+        return Err(DbtoonError::Auth {
+            message: format!(
+                "OAuth token request for client '{}' failed (HTTP {})",
+                client_id,
+                status.as_u16()
+            ),
+        });
+    }
+
+    let token: TokenResponse = resp.json().await.map_err(|e| DbtoonError::Auth {
+        message: format!("failed to parse OAuth token response: {}", e),
+    })?;
+    let lifetime = token.expires_in.map_or(DEFAULT_LIFETIME, Duration::from_secs);
+    Ok(remember(&mut tokens, key, token.access_token, lifetime))
+}
+
+/// An access token for a user signed in with `dbtoon auth login`.
+///
+/// Uses the token cache file at `cache_path`, refreshing the access token with
+/// the stored refresh token when it is about to expire.
+pub async fn u2m_token(
+    client: &Client,
+    host: &str,
+    client_id: &str,
+    cache_path: &Path,
+) -> Result<SecretString, DbtoonError> {
+    let endpoint = token_endpoint(host);
+    let key = (endpoint.clone(), client_id.to_string());
+
+    let mut tokens = TOKENS.lock().await;
+    if let Some(token) = fresh_token(&tokens, &key) {
+        return Ok(token);
+    }
+
+    let mut cache = TokenCache::load(cache_path)?;
+    let cache_key = TokenCache::key(host, client_id);
+    let Some(stored) = cache.tokens.get(&cache_key) else {
+        return Err(DbtoonError::Auth {
+            message: format!(
+                "not logged in to {} — run `dbtoon auth login -P <profile>`",
+                host_url(host)
+            ),
+        });
+    };
+
+    let remaining = stored.remaining();
+    if remaining > REFRESH_MARGIN {
+        let access_token = stored.access_token.clone();
+        return Ok(remember(&mut tokens, key, access_token, remaining));
+    }
+
+    let Some(refresh_token) = stored.refresh_token.clone() else {
+        return Err(session_expired(host));
+    };
+    let resp = client
+        .post(&endpoint)
+        .form(&[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token.as_str()),
+            ("client_id", client_id),
+        ])
+        .send()
+        .await
+        .map_err(|e| DbtoonError::Connection {
+            message: format!("failed to refresh OAuth token: {}", e),
+        })?;
+    if !resp.status().is_success() {
+        return Err(session_expired(host));
+    }
+    let token: TokenResponse = resp.json().await.map_err(|e| DbtoonError::Auth {
+        message: format!("failed to parse OAuth token response: {}", e),
+    })?;
+
+    // Refresh tokens may rotate; keep the old one if the server did not send a new one
+    let stored = StoredToken::from_response(&token, Some(refresh_token));
+    cache.tokens.insert(cache_key, stored);
+    cache.save(cache_path)?;
+
+    let lifetime = token.expires_in.map_or(DEFAULT_LIFETIME, Duration::from_secs);
+    Ok(remember(&mut tokens, key, token.access_token, lifetime))
+}
+
+fn fresh_token(
+    tokens: &HashMap<(String, String), CachedToken>,
+    key: &(String, String),
+) -> Option<SecretString> {
+    tokens
+        .get(key)
+        .filter(|cached| cached.expires_at > Instant::now() + REFRESH_MARGIN)
+        .map(|cached| SecretString::from(cached.access_token.clone()))
+}
+
+fn remember(
+    tokens: &mut HashMap<(String, String), CachedToken>,
+    key: (String, String),
+    access_token: String,
+    lifetime: Duration,
+) -> SecretString {
+    let secret = SecretString::from(access_token.clone());
+    tokens.insert(
+        key,
+        CachedToken {
+            access_token,
+            expires_at: Instant::now() + lifetime,
+        },
+    );
+    secret
+}
+
+fn session_expired(host: &str) -> DbtoonError {
+    DbtoonError::Auth {
+        message: format!(
+            "OAuth session for {} has expired — run `dbtoon auth login -P <profile>` again",
+            host_url(host)
+        ),
+    }
+}
+
+// --- Token cache file ---
+
+/// Tokens from `dbtoon auth login`, stored as JSON readable only by the user.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TokenCache {
+    /// Keyed by workspace URL and client id (see `TokenCache::key`).
+    #[serde(default)]
+    pub tokens: HashMap<String, StoredToken>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredToken {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    /// Expiry of `access_token`, in seconds since the Unix epoch.
+    pub expires_at: u64,
+}
+
+impl StoredToken {
+    fn from_response(token: &TokenResponse, previous_refresh: Option<String>) -> Self {
+        let lifetime = token.expires_in.map_or(DEFAULT_LIFETIME, Duration::from_secs);
+        Self {
+            access_token: token.access_token.clone(),
+            refresh_token: token.refresh_token.clone().or(previous_refresh),
+            expires_at: unix_now().saturating_add(lifetime.as_secs()),
+        }
+    }
+
+    fn remaining(&self) -> Duration {
+        Duration::from_secs(self.expires_at.saturating_sub(unix_now()))
+    }
+}
+
+impl TokenCache {
+    pub fn key(host: &str, client_id: &str) -> String {
+        format!("{}#{}", host_url(host), client_id)
+    }
+
+    /// Read the cache; a missing file is an empty cache.
+    pub fn load(path: &Path) -> Result<Self, DbtoonError> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => {
+                return Err(DbtoonError::Config {
+                    message: format!("cannot read token cache {}: {}", path.display(), e),
+                });
+            }
+        };
+        serde_json::from_str(&content).map_err(|e| DbtoonError::Config {
+            message: format!("invalid token cache {}: {}", path.display(), e),
+        })
+    }
+
+    /// Write the cache, creating its directory and restricting the file to the user.
+    pub fn save(&self, path: &Path) -> Result<(), DbtoonError> {
+        let io_err = |e: std::io::Error| DbtoonError::Config {
+            message: format!("cannot write token cache {}: {}", path.display(), e),
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(io_err)?;
+        }
+        let content = serde_json::to_string_pretty(self).map_err(|e| DbtoonError::Config {
+            message: format!("cannot serialize token cache: {}", e),
+        })?;
+
+        // Write beside the cache and rename, so readers never see a partial file
+        let tmp = path.with_extension("json.tmp");
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&tmp).map_err(io_err)?;
+        std::io::Write::write_all(&mut file, content.as_bytes()).map_err(io_err)?;
+        drop(file);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o600))
+                .map_err(io_err)?;
+        }
+        std::fs::rename(&tmp, path).map_err(io_err)
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+// --- U2M login ---
+
+/// Tokens obtained by a login flow.
+pub struct LoginTokens {
+    response: TokenResponse,
+}
+
+impl LoginTokens {
+    /// Store the tokens in the cache file at `cache_path` for later queries.
+    pub fn save(&self, host: &str, client_id: &str, cache_path: &Path) -> Result<(), DbtoonError> {
+        let mut cache = TokenCache::load(cache_path)?;
+        cache.tokens.insert(
+            TokenCache::key(host, client_id),
+            StoredToken::from_response(&self.response, None),
+        );
+        cache.save(cache_path)
+    }
+}
+
+/// Sign in through the browser: authorization code flow with PKCE, receiving
+/// the code on `listener` (bound to a localhost port the client may redirect to).
+///
+/// `open` is handed the authorization URL to show the user.
+pub async fn browser_login(
+    client: &Client,
+    host: &str,
+    client_id: &str,
+    listener: std::net::TcpListener,
+    open: impl FnOnce(&str),
+) -> Result<LoginTokens, DbtoonError> {
+    let port = listener.local_addr()?.port();
+    let redirect_uri = format!("http://localhost:{}", port);
+    let verifier = random_token(32)?;
+    let state = random_token(16)?;
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+
+    let mut url = url::Url::parse(&authorize_endpoint(host)).map_err(|e| DbtoonError::Config {
+        message: format!("invalid databricks host '{}': {}", host, e),
+    })?;
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", client_id)
+        .append_pair("redirect_uri", &redirect_uri)
+        .append_pair("scope", U2M_SCOPES)
+        .append_pair("state", &state)
+        .append_pair("code_challenge", &challenge)
+        .append_pair("code_challenge_method", "S256");
+
+    listener.set_nonblocking(true)?;
+    let listener = tokio::net::TcpListener::from_std(listener)?;
+    open(url.as_str());
+
+    let code = tokio::time::timeout(LOGIN_TIMEOUT, receive_code(&listener, &state))
+        .await
+        .map_err(|_| DbtoonError::Auth {
+            message: "timed out waiting for the browser login".to_string(),
+        })??;
+
+    let resp = client
+        .post(token_endpoint(host))
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code.as_str()),
+            ("redirect_uri", redirect_uri.as_str()),
+            ("client_id", client_id),
+            ("code_verifier", verifier.as_str()),
+        ])
+        .send()
+        .await
+        .map_err(|e| DbtoonError::Connection {
+            message: format!("failed to exchange authorization code: {}", e),
+        })?;
+    token_response(resp).await
+}
+
+/// Wait for the browser's redirect and return its authorization code.
+async fn receive_code(listener: &tokio::net::TcpListener, state: &str) -> Result<String, DbtoonError> {
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+        let mut buf = vec![0u8; 8192];
+        let n = stream.read(&mut buf).await?;
+        let request = String::from_utf8_lossy(&buf[..n]);
+        let target = request
+            .lines()
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .unwrap_or("/");
+        let params: HashMap<String, String> = url::Url::parse(&format!("http://localhost{}", target))
+            .map(|u| u.query_pairs().into_owned().collect())
+            .unwrap_or_default();
+
+        // Browsers also ask for /favicon.ico and the like
+        if !params.contains_key("code") && !params.contains_key("error") {
+            let _ = stream
+                .write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                .await;
+            continue;
+        }
+
+        let outcome = if params.get("state").map(String::as_str) != Some(state) {
+            Err(DbtoonError::Auth {
+                message: "login redirect had an unexpected state; try again".to_string(),
+            })
+        } else if let Some(error) = params.get("error") {
+            let detail = params.get("error_description").map_or(String::new(), |d| format!(": {}", d));
+            Err(DbtoonError::Auth {
+                message: format!("login was refused ({}{})", error, detail),
+            })
+        } else {
+            Ok(params["code"].clone())
+        };
+
+        let page = match &outcome {
+            Ok(_) => "dbtoon login complete. You can close this tab.",
+            Err(_) => "dbtoon login failed. Return to the terminal for details.",
+        };
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            page.len(),
+            page
+        );
+        let _ = stream.write_all(response.as_bytes()).await;
+        return outcome;
+    }
+}
+
+/// What the user must do to approve a device code login.
+#[derive(Debug, Deserialize)]
+pub struct DeviceAuthorization {
+    device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    pub verification_uri_complete: Option<String>,
+    expires_in: Option<u64>,
+    interval: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct AuthorizationServerMetadata {
+    device_authorization_endpoint: Option<String>,
+}
+
+/// Sign in with the device code flow, for machines without a browser.
+///
+/// `prompt` is handed the code and URL the user must visit from another device.
+pub async fn device_login(
+    client: &Client,
+    host: &str,
+    client_id: &str,
+    prompt: impl FnOnce(&DeviceAuthorization),
+) -> Result<LoginTokens, DbtoonError> {
+    let metadata_url = format!("{}/oidc/.well-known/oauth-authorization-server", host_url(host));
+    let metadata: AuthorizationServerMetadata = client
+        .get(&metadata_url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| DbtoonError::Connection {
+            message: format!("failed to read OAuth metadata: {}", e),
+        })?
+        .json()
+        .await
+        .map_err(|e| DbtoonError::Connection {
+            message: format!("failed to parse OAuth metadata: {}", e),
+        })?;
+    let endpoint = metadata.device_authorization_endpoint.ok_or_else(|| DbtoonError::Config {
+        message: format!(
+            "{} does not support device code login — run `dbtoon auth login` without --device-code",
+            host_url(host)
+        ),
+    })?;
+
+    let resp = client
+        .post(&endpoint)
+        .form(&[("client_id", client_id), ("scope", U2M_SCOPES)])
+        .send()
+        .await
+        .map_err(|e| DbtoonError::Connection {
+            message: format!("failed to start device code login: {}", e),
+        })?;
+    if !resp.status().is_success() {
+        return Err(DbtoonError::Auth {
+            message: format!("device code login was refused (HTTP {})", resp.status().as_u16()),
+        });
+    }
+    let device: DeviceAuthorization = resp.json().await.map_err(|e| DbtoonError::Auth {
+        message: format!("failed to parse device code response: {}", e),
+    })?;
+    prompt(&device);
+
+    let mut interval = Duration::from_secs(device.interval.unwrap_or(5));
+    let deadline = Instant::now() + device.expires_in.map_or(LOGIN_TIMEOUT, Duration::from_secs);
+    loop {
+        tokio::time::sleep(interval).await;
+        if Instant::now() > deadline {
+            return Err(DbtoonError::Auth {
+                message: "device code expired before the login was approved".to_string(),
+            });
+        }
+
+        let resp = client
+            .post(token_endpoint(host))
+            .form(&[
+                ("grant_type", DEVICE_CODE_GRANT),
+                ("device_code", device.device_code.as_str()),
+                ("client_id", client_id),
+            ])
+            .send()
+            .await
+            .map_err(|e| DbtoonError::Connection {
+                message: format!("failed to poll device code login: {}", e),
+            })?;
+        if resp.status().is_success() {
+            return token_response(resp).await;
+        }
+
+        let error: TokenError = resp.json().await.map_err(|e| DbtoonError::Auth {
+            message: format!("failed to parse OAuth error response: {}", e),
+        })?;
+        match error.error.as_str() {
+            "authorization_pending" => {}
+            "slow_down" => interval += Duration::from_secs(5),
+            _ => {
+                return Err(DbtoonError::Auth {
+                    message: format!("device code login failed ({})", error.message()),
+                });
+            }
+        }
+    }
+}
+
+async fn token_response(resp: reqwest::Response) -> Result<LoginTokens, DbtoonError> {
+    if !resp.status().is_success() {
+        let status = resp.status().as_u16();
+        let detail = resp
+            .json::<TokenError>()
+            .await
+            .map_or_else(|_| format!("HTTP {}", status), |e| e.message());
+        return Err(DbtoonError::Auth {
+            message: format!("OAuth token request failed ({})", detail),
+        });
+    }
+    let response: TokenResponse = resp.json().await.map_err(|e| DbtoonError::Auth {
+        message: format!("failed to parse OAuth token response: {}", e),
+    })?;
+    Ok(LoginTokens { response })
+}
+
+/// `len` random bytes, base64url-encoded (PKCE verifiers and `state` values).
+fn random_token(len: usize) -> Result<String, DbtoonError> {
+    let mut bytes = vec![0u8; len];
+    getrandom::fill(&mut bytes).map_err(|e| DbtoonError::Auth {
+        message: format!("cannot generate random login state: {}", e),
+    })?;
+    Ok(URL_SAFE_NO_PAD.encode(bytes))
+}
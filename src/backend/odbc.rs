@@ -0,0 +1,721 @@
+//! Generic ODBC backend for any driver or DSN, and the ODBC plumbing it shares
+//! with the SQL Server backend: connecting, executing, typed column binding, and
+//! batch-wise fetching into a `ResultSink`.
+
+use crate::backend::idle::IdleConnections;
+use crate::backend::value::days_from_civil;
+use crate::backend::{
+    Backend, CellValue, ColumnMeta, QueryResult, ResultSink, STREAM_BATCH_SIZE, SessionReuse,
+    StreamSummary,
+};
+use crate::cancel::CancelToken;
+use crate::error::DbtoonError;
+use crate::params::{QueryParam, to_positional};
+use odbc_api::buffers::{AnySlice, BufferDesc, ColumnarAnyBuffer, NullableSlice};
+use odbc_api::handles::{
+    Connection, DiagnosticStream, Diagnostics, Environment, Record, SqlResult, SqlText, Statement,
+    slice_to_cow_utf8,
+};
+use odbc_api::parameter::InputParameter;
+use odbc_api::sys::{
+    AttrOdbcVersion, ConnectionAttribute, Date, HStmt, IS_POINTER, Pointer, SQLCancel,
+    SQLSetConnectAttr, SqlReturn, Time, Timestamp,
+};
+use odbc_api::{
+    Bit, ColumnDescription, Cursor, CursorImpl, DataType, IntoParameter, Nullability,
+    ParameterCollectionRef,
+};
+use secrecy::{ExposeSecret, SecretString};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+/// Backend for a profile with `backend = "odbc"`: a DSN or a full connection string.
+pub struct OdbcBackend {
+    connection_string: SecretString,
+    session: SessionReuse,
+    cancel: CancelToken,
+}
+
+impl OdbcBackend {
+    pub fn new(connection_string: SecretString) -> Self {
+        Self {
+            connection_string,
+            session: SessionReuse::default(),
+            cancel: CancelToken::default(),
+        }
+    }
+
+    /// Whether the connection is cached for the next query once this one is done.
+    pub fn with_session(mut self, session: SessionReuse) -> Self {
+        self.session = session;
+        self
+    }
+
+    /// Cancel the running statement with `SQLCancel` when `cancel` fires.
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+}
+
+impl Backend for OdbcBackend {
+    async fn execute(
+        &self,
+        sql: &str,
+        params: &[QueryParam],
+        limit: Option<usize>,
+        timeout_secs: u64,
+    ) -> Result<QueryResult, DbtoonError> {
+        let (mut result, summary) = self
+            .execute_streaming(sql, params, limit, timeout_secs, QueryResult::default())
+            .await?;
+        result.truncated = summary.truncated;
+        result.messages = summary.messages;
+        result.rows_affected = summary.rows_affected;
+        Ok(result)
+    }
+
+    async fn execute_streaming<S: ResultSink + 'static>(
+        &self,
+        sql: &str,
+        params: &[QueryParam],
+        limit: Option<usize>,
+        timeout_secs: u64,
+        sink: S,
+    ) -> Result<(S, StreamSummary), DbtoonError> {
+        let target = ConnectTarget {
+            connection_string: self.connection_string.expose_secret().to_string(),
+            attributes: Vec::new(),
+        };
+        execute_streaming(target, self.session, &self.cancel, sql, params, limit, timeout_secs, sink).await
+    }
+}
+
+/// Normalize an ODBC `DataType` enum value to a standard SQL type string.
+pub fn normalize_odbc_type(data_type: &DataType) -> String {
+    match data_type {
+        DataType::Unknown => "UNKNOWN".to_string(),
+        DataType::Char { length: Some(n) } => format!("CHAR({})", n),
+        DataType::Char { length: None } => "CHAR".to_string(),
+        DataType::WChar { length: Some(n) } => format!("NCHAR({})", n),
+        DataType::WChar { length: None } => "NCHAR".to_string(),
+        DataType::Varchar { length: Some(n) } => format!("VARCHAR({})", n),
+        DataType::Varchar { length: None } => "VARCHAR(MAX)".to_string(),
+        DataType::WVarchar { length: Some(n) } => format!("NVARCHAR({})", n),
+        DataType::WVarchar { length: None } => "NVARCHAR(MAX)".to_string(),
+        DataType::LongVarchar { .. } => "VARCHAR(MAX)".to_string(),
+        DataType::WLongVarchar { .. } => "NVARCHAR(MAX)".to_string(),
+        DataType::Integer => "INT".to_string(),
+        DataType::SmallInt => "SMALLINT".to_string(),
+        DataType::BigInt => "BIGINT".to_string(),
+        DataType::TinyInt => "TINYINT".to_string(),
+        DataType::Float { precision } => format!("FLOAT({})", precision),
+        DataType::Real => "REAL".to_string(),
+        DataType::Double => "FLOAT".to_string(),
+        DataType::Numeric { precision, scale } => format!("NUMERIC({},{})", precision, scale),
+        DataType::Decimal { precision, scale } => format!("DECIMAL({},{})", precision, scale),
+        DataType::Date => "DATE".to_string(),
+        DataType::Time { precision } => format!("TIME({})", precision),
+        DataType::Timestamp { precision } => format!("DATETIME2({})", precision),
+        DataType::Bit => "BIT".to_string(),
+        DataType::Binary { length: Some(n) } => format!("BINARY({})", n),
+        DataType::Binary { length: None } => "BINARY".to_string(),
+        DataType::Varbinary { length: Some(n) } => format!("VARBINARY({})", n),
+        DataType::Varbinary { length: None } => "VARBINARY(MAX)".to_string(),
+        DataType::LongVarbinary { .. } => "VARBINARY(MAX)".to_string(),
+        DataType::Other { .. } => "UNKNOWN".to_string(),
+    }
+}
+
+/// What `execute_streaming` connects with.
+#[derive(Clone, PartialEq)]
+pub(crate) struct ConnectTarget {
+    pub connection_string: String,
+    /// Driver-specific attributes set between allocating and connecting.
+    pub attributes: Vec<PreConnectAttribute>,
+}
+
+/// A pointer-valued connection attribute the driver copies before connecting.
+#[derive(Clone, PartialEq)]
+pub(crate) struct PreConnectAttribute {
+    pub attribute: i32,
+    pub value: Vec<u8>,
+    /// What the attribute holds, for error messages.
+    pub description: &'static str,
+}
+
+/// Run `sql` against `target` on a blocking thread, streaming every result set
+/// into `sink`, then cache or close the connection as `session` allows.
+/// `:name` markers are rewritten to `?` and `params` bound in marker order.
+///
+/// Firing `cancel` interrupts the statement with `SQLCancel` and returns
+/// `DbtoonError::Cancelled`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn execute_streaming<S: ResultSink + 'static>(
+    target: ConnectTarget,
+    session: SessionReuse,
+    cancel: &CancelToken,
+    sql: &str,
+    params: &[QueryParam],
+    limit: Option<usize>,
+    timeout_secs: u64,
+    mut sink: S,
+) -> Result<(S, StreamSummary), DbtoonError> {
+    // odbc-api Environment and connection are not Send, so we run in spawn_blocking
+    let sql = sql.to_string();
+    let params = params.to_vec();
+    let running = RunningStatement::new(cancel.clone());
+    let statement = running.clone();
+    let mut task = tokio::task::spawn_blocking(move || -> Result<(S, StreamSummary), DbtoonError> {
+        let (sql, bound) = to_positional(&sql, &params)?;
+        let values: Vec<Box<dyn InputParameter>> = bound.into_iter().map(odbc_parameter).collect();
+        let mut conn = checkout(&target)?;
+        let result = run_statement(&conn, &sql, &values, limit, timeout_secs, &statement, &mut sink);
+        let reusable = match (session, &result) {
+            // A statement the server rejected leaves the session as it was
+            (SessionReuse::Keep, Ok(_) | Err(DbtoonError::Query { .. })) => true,
+            // ODBC has no portable way to reset a session, so only one a plain query ran on is kept
+            (SessionReuse::ReadOnly, Ok(_)) => true,
+            // One that was cancelled or lost its connection may be in any state
+            _ => false,
+        };
+        if reusable {
+            check_in(&target, conn);
+        } else {
+            let _ = conn.disconnect();
+        }
+        result.map(|summary| (sink, summary))
+    });
+
+    let joined = tokio::select! {
+        joined = &mut task => joined,
+        _ = cancel.cancelled() => {
+            // Interrupts a blocking execute or fetch; `run_statement` notices between batches
+            running.cancel();
+            task.await
+        }
+    };
+    let result = joined.map_err(|e| DbtoonError::Query {
+        message: format!("task join error: {}", e),
+    })?;
+
+    match result {
+        // The driver reports an interrupted call as an ordinary query error
+        Err(_) if cancel.is_cancelled() => Err(DbtoonError::Cancelled),
+        result => result,
+    }
+}
+
+/// Raw handle of the statement `run_statement` is running, so another thread can
+/// `SQLCancel` it. The handle is cleared before the statement is freed.
+#[derive(Clone)]
+struct RunningStatement {
+    cancel: CancelToken,
+    handle: Arc<Mutex<Option<RawStatement>>>,
+}
+
+struct RawStatement(HStmt);
+
+// SAFETY: the handle is only used for `SQLCancel`, which ODBC allows from any
+// thread, and only while `run_statement` keeps the statement alive.
+unsafe impl Send for RawStatement {}
+
+impl RunningStatement {
+    fn new(cancel: CancelToken) -> Self {
+        Self {
+            cancel,
+            handle: Arc::default(),
+        }
+    }
+
+    /// Record `handle` as running until the returned guard drops.
+    fn register(&self, handle: HStmt) -> RunningGuard<'_> {
+        *self.handle.lock().unwrap_or_else(|e| e.into_inner()) = Some(RawStatement(handle));
+        RunningGuard(self)
+    }
+
+    fn cancel(&self) {
+        let running = self.handle.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(RawStatement(handle)) = *running {
+            // SAFETY: the lock keeps `run_statement` from freeing the statement meanwhile.
+            let _ = unsafe { SQLCancel(handle) };
+        }
+    }
+}
+
+struct RunningGuard<'a>(&'a RunningStatement);
+
+impl Drop for RunningGuard<'_> {
+    fn drop(&mut self) {
+        *self.0.handle.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+/// Execute `sql` on `conn` with `values` bound, feeding every result set to
+/// `sink` one fetched batch at a time.
+///
+/// Only one `STREAM_BATCH_SIZE`-row buffer is held in memory regardless of result size.
+/// Columns are bound to typed buffers so numbers, dates and bits arrive as native values.
+/// `limit` applies to each result set separately; the returned summary is the last set's.
+///
+/// Informational messages and rows affected are attached to the next result set,
+/// or to the last one once the batch is exhausted.
+///
+/// The statement is registered in `running` while it executes, and its cancel
+/// token is checked between batches.
+fn run_statement(
+    conn: &Connection<'_>,
+    sql: &str,
+    values: &[Box<dyn InputParameter>],
+    limit: Option<usize>,
+    timeout_secs: u64,
+    running: &RunningStatement,
+    sink: &mut dyn ResultSink,
+) -> Result<StreamSummary, DbtoonError> {
+    let allocated = conn.allocate_statement();
+    let mut stmt = connection_check(conn, allocated, "failed to allocate statement")?;
+    let set_timeout = stmt.set_query_timeout_sec(timeout_secs as usize);
+    check(&stmt, set_timeout, "failed to set query timeout")?;
+    // Declared after `stmt`, so it is dropped (and unregistered) first
+    let _running = running.register(stmt.as_sys());
+    let cancel = &running.cancel;
+    cancel.check()?;
+
+    let mut values = values;
+    // SAFETY: `values` outlives every call on the statement below.
+    unsafe { values.bind_parameters_to(&mut stmt) }.map_err(|e| DbtoonError::Query {
+        message: format!("failed to bind parameters: {}", e),
+    })?;
+
+    // Executed on the raw handle: `Connection::execute` makes further calls on the
+    // statement before returning, which clears the diagnostics holding PRINT output.
+    // SAFETY: the statement is freshly allocated and only `values` are bound to it.
+    let executed = unsafe { stmt.exec_direct(&SqlText::new(sql)) };
+    check(&stmt, executed, "query execution failed")?;
+
+    // Messages and row counts not yet attached to a result set
+    let mut pending = StreamSummary::default();
+    let mut last: Option<StreamSummary> = None;
+
+    loop {
+        cancel.check()?;
+        pending.messages.extend(diagnostic_messages(&stmt));
+
+        let num_cols = stmt.num_result_cols();
+        if check(&stmt, num_cols, "failed to get column count")?.unwrap_or(0) == 0 {
+            let count = stmt.row_count();
+            if let Ok(Some(count)) = check(&stmt, count, "failed to get row count")
+                && let Ok(count) = usize::try_from(count)
+            {
+                *pending.rows_affected.get_or_insert(0) += count;
+            }
+        } else {
+            if let Some(previous) = last.take() {
+                sink.next_result(previous)?;
+            }
+            // SAFETY: the statement is positioned on a result set with columns.
+            let cursor = unsafe { CursorImpl::new(&mut stmt) };
+            let (cursor, mut summary) = fetch_result_set(cursor, limit, cancel, sink)?;
+            // Keep the statement open: dropping the cursor would discard later result sets
+            cursor.into_stmt();
+            summary.messages = std::mem::take(&mut pending.messages);
+            summary.rows_affected = pending.rows_affected.take();
+            last = Some(summary);
+        }
+
+        // SAFETY: no buffers are bound to the statement at this point.
+        let more = unsafe { stmt.more_results() };
+        if check(&stmt, more, "failed to advance to next result set")?.is_none() {
+            break;
+        }
+    }
+
+    let mut summary = match last {
+        Some(summary) => summary,
+        None => {
+            sink.begin(&[])?;
+            StreamSummary::default()
+        }
+    };
+    summary.messages.extend(diagnostic_messages(&stmt));
+    summary.messages.append(&mut pending.messages);
+    if let Some(count) = pending.rows_affected {
+        *summary.rows_affected.get_or_insert(0) += count;
+    }
+    Ok(summary)
+}
+
+/// The process-wide ODBC environment, created on first use so cached
+/// connections can outlive the query that opened them.
+struct SharedEnvironment(Environment);
+
+// SAFETY: ODBC environment handles are thread safe; the driver manager
+// serializes calls on them.
+unsafe impl Sync for SharedEnvironment {}
+
+fn environment() -> Result<&'static Environment, DbtoonError> {
+    static ENVIRONMENT: OnceLock<Result<SharedEnvironment, String>> = OnceLock::new();
+    let shared = ENVIRONMENT.get_or_init(|| {
+        let env = match Environment::new() {
+            SqlResult::Success(env) | SqlResult::SuccessWithInfo(env) => env,
+            _ => return Err("ODBC environment error: failed to allocate environment".to_string()),
+        };
+        match connection_check(&env, env.declare_version(AttrOdbcVersion::Odbc3_80), "ODBC environment error") {
+            Ok(()) => Ok(SharedEnvironment(env)),
+            Err(DbtoonError::Connection { message }) => Err(message),
+            Err(e) => Err(e.to_string()),
+        }
+    });
+    match shared {
+        Ok(SharedEnvironment(env)) => Ok(env),
+        Err(message) => Err(DbtoonError::Connection {
+            message: message.clone(),
+        }),
+    }
+}
+
+static IDLE: IdleConnections<ConnectTarget, Connection<'static>> = IdleConnections::new();
+
+/// Disconnect cached connections unused for `idle_timeout`.
+pub(crate) fn close_idle(idle_timeout: Duration) {
+    // Disconnecting waits on the server, so it happens outside the lock
+    for mut connection in IDLE.expired(idle_timeout) {
+        let _ = connection.disconnect();
+    }
+}
+
+pub(crate) fn idle_count() -> usize {
+    IDLE.len()
+}
+
+/// A cached connection to `target` that is still alive, or a new one.
+fn checkout(target: &ConnectTarget) -> Result<Connection<'static>, DbtoonError> {
+    while let Some(mut cached) = IDLE.take(target) {
+        // A connection the server dropped while idle is closed and replaced
+        match cached.is_dead() {
+            SqlResult::Success(false) | SqlResult::SuccessWithInfo(false) => return Ok(cached),
+            _ => {
+                let _ = cached.disconnect();
+            }
+        }
+    }
+    connect(environment()?, target)
+}
+
+/// Keep `connection` for the next query to `target` while connections are
+/// reused (see `backend::reuse_connections`), or disconnect it.
+fn check_in(target: &ConnectTarget, connection: Connection<'static>) {
+    if let Some(mut connection) = IDLE.put(target.clone(), connection) {
+        let _ = connection.disconnect();
+    }
+}
+
+/// Open a connection to `target`, applying its pre-connect attributes first.
+///
+/// Connects on the raw handle because odbc-api offers no way to set a
+/// driver-specific attribute between allocating and connecting.
+fn connect<'env>(
+    env: &'env Environment,
+    target: &ConnectTarget,
+) -> Result<Connection<'env>, DbtoonError> {
+    let mut conn = connection_check(env, env.allocate_connection(), "failed to allocate connection")?;
+    connection_check(&conn, conn.set_login_timeout_sec(30), "failed to set login timeout")?;
+
+    for attribute in &target.attributes {
+        // SAFETY: the value outlives the call and the driver copies it.
+        let ret = unsafe {
+            SQLSetConnectAttr(
+                conn.as_sys(),
+                ConnectionAttribute(attribute.attribute),
+                attribute.value.as_ptr() as Pointer,
+                IS_POINTER,
+            )
+        };
+        let result = match ret {
+            SqlReturn::SUCCESS => SqlResult::Success(()),
+            SqlReturn::SUCCESS_WITH_INFO => SqlResult::SuccessWithInfo(()),
+            _ => SqlResult::Error { function: "SQLSetConnectAttr" },
+        };
+        let context = format!("failed to set {}", attribute.description);
+        connection_check(&conn, result, &context)?;
+    }
+
+    let connected = conn.connect_with_connection_string(&SqlText::new(&target.connection_string));
+    connection_check(&conn, connected, "connection failed")?;
+    Ok(conn)
+}
+
+/// Like `check`, for connection setup: errors are connection errors, and
+/// `SQL_NO_DATA` is not expected.
+fn connection_check<T>(
+    handle: &impl Diagnostics,
+    result: SqlResult<T>,
+    context: &str,
+) -> Result<T, DbtoonError> {
+    match check(handle, result, context) {
+        Ok(Some(value)) => Ok(value),
+        Ok(None) => Err(DbtoonError::Connection {
+            message: format!("{}: no data", context),
+        }),
+        Err(DbtoonError::Query { message }) => Err(DbtoonError::Connection { message }),
+        Err(e) => Err(e),
+    }
+}
+
+/// ODBC input parameter for a query parameter, typed by its declared SQL type.
+///
+/// Values without a native ODBC binding (dates, decimals, strings) are bound as
+/// text and converted by the server.
+fn odbc_parameter(param: &QueryParam) -> Box<dyn InputParameter> {
+    match param.typed_value() {
+        CellValue::Int64(n) => Box::new(n),
+        CellValue::Float64(f) => Box::new(f),
+        CellValue::Bool(b) => Box::new(Bit::from_bool(b)),
+        _ => Box::new(param.value.clone().into_parameter()),
+    }
+}
+
+/// Map an ODBC return code to `Ok(Some(_))` on success, `Ok(None)` for `SQL_NO_DATA`,
+/// or a query error built from the statement's diagnostics.
+fn check<T>(
+    stmt: &impl Diagnostics,
+    result: SqlResult<T>,
+    context: &str,
+) -> Result<Option<T>, DbtoonError> {
+    match result {
+        SqlResult::Success(value) | SqlResult::SuccessWithInfo(value) => Ok(Some(value)),
+        SqlResult::NoData => Ok(None),
+        SqlResult::Error { function } => {
+            let mut record = Record::with_capacity(512);
+            let error = if record.fill_from(stmt, 1) {
+                odbc_api::Error::Diagnostics { record, function }
+            } else {
+                odbc_api::Error::NoDiagnostics { function }
+            };
+            Err(DbtoonError::Query {
+                message: format!("{}: {}", context, error),
+            })
+        }
+        SqlResult::NeedData | SqlResult::StillExecuting => Err(DbtoonError::Query {
+            message: format!("{}: unexpected ODBC return state", context),
+        }),
+    }
+}
+
+/// Drain the diagnostic records of the last call on `stmt` as message text.
+///
+/// Only successful calls reach this point, so the records are informational:
+/// PRINT output, RAISERROR with severity 10 or lower, and driver warnings.
+fn diagnostic_messages(stmt: &impl Diagnostics) -> Vec<String> {
+    let mut messages = Vec::new();
+    let mut records = DiagnosticStream::new(stmt);
+    while let Some(record) = records.next() {
+        let text = slice_to_cow_utf8(&record.message);
+        messages.push(strip_driver_prefix(&text).to_string());
+    }
+    messages
+}
+
+/// Remove the `[vendor][driver][source]` tags ODBC drivers prepend to message text.
+pub fn strip_driver_prefix(message: &str) -> &str {
+    let mut rest = message;
+    while rest.starts_with('[')
+        && let Some(end) = rest.find(']')
+    {
+        rest = &rest[end + 1..];
+    }
+    rest
+}
+
+/// Describe, bind and drain one result set into `sink`, returning the cursor so
+/// the caller can move on to the next set.
+fn fetch_result_set<C: Cursor>(
+    mut cursor: C,
+    limit: Option<usize>,
+    cancel: &CancelToken,
+    sink: &mut dyn ResultSink,
+) -> Result<(C, StreamSummary), DbtoonError> {
+    // Extract column metadata
+    let num_cols = cursor.num_result_cols().map_err(|e| DbtoonError::Query {
+        message: format!("failed to get column count: {}", e),
+    })? as usize;
+
+    let mut columns = Vec::with_capacity(num_cols);
+    let mut buffer_descs = Vec::with_capacity(num_cols);
+
+    for i in 1..=num_cols as u16 {
+        let mut col_desc = ColumnDescription::default();
+        cursor
+            .describe_col(i, &mut col_desc)
+            .map_err(|e| DbtoonError::Query {
+                message: format!("failed to describe column {}: {}", i, e),
+            })?;
+
+        let name = col_desc.name_to_string().map_err(|e| DbtoonError::Query {
+            message: format!("failed to decode column name {}: {}", i, e),
+        })?;
+
+        columns.push(ColumnMeta {
+            name,
+            type_name: normalize_odbc_type(&col_desc.data_type),
+        });
+
+        let nullable = col_desc.nullability != Nullability::NoNulls;
+        buffer_descs.push(buffer_desc(col_desc.data_type, nullable));
+    }
+
+    sink.begin(&columns)?;
+
+    let buffer = ColumnarAnyBuffer::try_from_descs(STREAM_BATCH_SIZE, buffer_descs)
+        .map_err(|e| DbtoonError::Query {
+            message: format!("failed to create buffer: {}", e),
+        })?;
+
+    let mut row_set_cursor =
+        cursor.bind_buffer(buffer).map_err(|e| DbtoonError::Query {
+            message: format!("failed to bind buffer: {}", e),
+        })?;
+
+    let mut total = 0usize;
+    let mut truncated = false;
+
+    while let Some(batch) = row_set_cursor.fetch().map_err(|e| DbtoonError::Query {
+        message: format!("fetch error: {}", e),
+    })? {
+        cancel.check()?;
+        let remaining = limit.map_or(usize::MAX, |lim| lim - total);
+        let num_rows = batch.num_rows().min(remaining);
+        truncated = batch.num_rows() > remaining;
+
+        let mut cells: Vec<_> = columns
+            .iter()
+            .enumerate()
+            .map(|(col_idx, col)| {
+                column_cells(batch.column(col_idx), num_rows, &col.type_name).into_iter()
+            })
+            .collect();
+        let rows: Vec<Vec<CellValue>> = (0..num_rows)
+            .map(|_| {
+                cells
+                    .iter_mut()
+                    .map(|col| col.next().unwrap_or(CellValue::Null))
+                    .collect()
+            })
+            .collect();
+
+        if !rows.is_empty() {
+            sink.write_batch(&rows)?;
+            total += rows.len();
+        }
+
+        if truncated {
+            break;
+        }
+    }
+
+    let (cursor, _buffer) = row_set_cursor.unbind().map_err(|e| DbtoonError::Query {
+        message: format!("failed to unbind buffer: {}", e),
+    })?;
+
+    let summary = StreamSummary {
+        rows: total,
+        truncated,
+        ..StreamSummary::default()
+    };
+    Ok((cursor, summary))
+}
+
+/// Convert the first `len` values of a fetched column to cells.
+///
+/// Columns bound as text (DECIMAL, NUMERIC, TIME(n), character types) are parsed
+/// according to `type_name`.
+fn column_cells(column: AnySlice<'_>, len: usize, type_name: &str) -> Vec<CellValue> {
+    fn plain<T: Copy>(values: &[T], len: usize, f: impl Fn(T) -> CellValue) -> Vec<CellValue> {
+        values.iter().take(len).map(|v| f(*v)).collect()
+    }
+    fn nullable<T: Copy>(
+        values: NullableSlice<'_, T>,
+        len: usize,
+        f: impl Fn(T) -> CellValue,
+    ) -> Vec<CellValue> {
+        (0..len)
+            .map(|i| values.get(i).map_or(CellValue::Null, |v| f(*v)))
+            .collect()
+    }
+    let int = |n: i64| CellValue::Int64(n);
+
+    match column {
+        AnySlice::Text(view) => (0..len)
+            .map(|i| match view.get(i) {
+                Some(bytes) => CellValue::from_typed_text(&String::from_utf8_lossy(bytes), type_name),
+                None => CellValue::Null,
+            })
+            .collect(),
+        AnySlice::WText(view) => (0..len)
+            .map(|i| match view.get(i) {
+                Some(units) => CellValue::from_typed_text(&String::from_utf16_lossy(units), type_name),
+                None => CellValue::Null,
+            })
+            .collect(),
+        AnySlice::Binary(view) => (0..len)
+            .map(|i| view.get(i).map_or(CellValue::Null, |b| CellValue::Bytes(b.to_vec())))
+            .collect(),
+        AnySlice::Date(v) => plain(v, len, date_cell),
+        AnySlice::NullableDate(v) => nullable(v, len, date_cell),
+        AnySlice::Time(v) => plain(v, len, time_cell),
+        AnySlice::NullableTime(v) => nullable(v, len, time_cell),
+        AnySlice::Timestamp(v) => plain(v, len, timestamp_cell),
+        AnySlice::NullableTimestamp(v) => nullable(v, len, timestamp_cell),
+        AnySlice::F64(v) => plain(v, len, CellValue::Float64),
+        AnySlice::NullableF64(v) => nullable(v, len, CellValue::Float64),
+        AnySlice::F32(v) => plain(v, len, CellValue::from_f32),
+        AnySlice::NullableF32(v) => nullable(v, len, CellValue::from_f32),
+        AnySlice::I8(v) => plain(v, len, |n| int(n.into())),
+        AnySlice::NullableI8(v) => nullable(v, len, |n| int(n.into())),
+        AnySlice::I16(v) => plain(v, len, |n| int(n.into())),
+        AnySlice::NullableI16(v) => nullable(v, len, |n| int(n.into())),
+        AnySlice::I32(v) => plain(v, len, |n| int(n.into())),
+        AnySlice::NullableI32(v) => nullable(v, len, |n| int(n.into())),
+        AnySlice::I64(v) => plain(v, len, int),
+        AnySlice::NullableI64(v) => nullable(v, len, int),
+        AnySlice::U8(v) => plain(v, len, |n| int(n.into())),
+        AnySlice::NullableU8(v) => nullable(v, len, |n| int(n.into())),
+        AnySlice::Bit(v) => plain(v, len, |b| CellValue::Bool(b.as_bool())),
+        AnySlice::NullableBit(v) => nullable(v, len, |b| CellValue::Bool(b.as_bool())),
+        // Never bound: `buffer_desc` fetches decimals as text
+        AnySlice::Numeric(_) | AnySlice::NullableNumeric(_) => vec![CellValue::Null; len],
+    }
+}
+
+fn date_cell(d: Date) -> CellValue {
+    days_from_civil(d.year.into(), d.month.into(), d.day.into())
+        .map_or(CellValue::Null, CellValue::Date)
+}
+
+fn time_cell(t: Time) -> CellValue {
+    CellValue::Time(
+        (i64::from(t.hour) * 3600 + i64::from(t.minute) * 60 + i64::from(t.second)) * 1_000_000,
+    )
+}
+
+fn timestamp_cell(ts: Timestamp) -> CellValue {
+    let Some(days) = days_from_civil(ts.year.into(), ts.month.into(), ts.day.into()) else {
+        return CellValue::Null;
+    };
+    let seconds = i64::from(days) * 86_400
+        + i64::from(ts.hour) * 3600
+        + i64::from(ts.minute) * 60
+        + i64::from(ts.second);
+    // ODBC reports the fraction in nanoseconds
+    CellValue::Timestamp(seconds * 1_000_000 + i64::from(ts.fraction / 1000))
+}
+
+/// Typed ODBC buffer for a column, falling back to text where odbc-api has none.
+fn buffer_desc(data_type: DataType, nullable: bool) -> BufferDesc {
+    match data_type {
+        // SQL Server TINYINT is unsigned (0-255), which overflows the I8 default
+        DataType::TinyInt => BufferDesc::I16 { nullable },
+        other => BufferDesc::from_data_type(other, nullable)
+            .unwrap_or(BufferDesc::Text { max_str_len: 255 }),
+    }
+}
@@ -0,0 +1,771 @@
+//! Native PostgreSQL backend over the wire protocol (tokio-postgres).
+
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bytes::{BufMut, BytesMut};
+use fallible_iterator::FallibleIterator;
+use futures_util::TryStreamExt;
+use postgres_native_tls::MakeTlsConnector;
+use postgres_protocol::types as pg;
+use secrecy::{ExposeSecret, SecretString};
+use tokio_postgres::config::SslMode;
+use tokio_postgres::types::{FromSql, IsNull, Kind, ToSql, Type, to_sql_checked};
+use tokio_postgres::{AsyncMessage, Client, Column, Config};
+
+use crate::backend::idle::IdleConnections;
+use crate::backend::value::{
+    hex_decode, parse_bool, parse_date32, parse_decimal, parse_time_micros, parse_timestamp_micros,
+};
+use crate::backend::{
+    Backend, CellValue, ColumnMeta, QueryResult, ResultSink, STREAM_BATCH_SIZE, SessionReuse,
+    StreamSummary,
+};
+use crate::cancel::CancelToken;
+use crate::config::PostgresSslMode;
+use crate::error::DbtoonError;
+use crate::params::{QueryParam, to_numbered};
+
+/// Days from 1970-01-01 to 2000-01-01, the PostgreSQL date epoch.
+const PG_EPOCH_DAYS: i32 = 10_957;
+
+/// Microseconds from 1970-01-01 to 2000-01-01.
+const PG_EPOCH_MICROS: i64 = PG_EPOCH_DAYS as i64 * 86_400_000_000;
+
+pub struct PostgresBackend {
+    host: String,
+    port: u16,
+    database: Option<String>,
+    user: String,
+    password: Option<SecretString>,
+    sslmode: PostgresSslMode,
+    session: SessionReuse,
+    cancel: CancelToken,
+}
+
+impl PostgresBackend {
+    pub fn new(
+        host: String,
+        port: u16,
+        database: Option<String>,
+        user: String,
+        password: Option<SecretString>,
+        sslmode: PostgresSslMode,
+    ) -> Self {
+        Self {
+            host,
+            port,
+            database,
+            user,
+            password,
+            sslmode,
+            session: SessionReuse::default(),
+            cancel: CancelToken::default(),
+        }
+    }
+
+    /// Whether the session is reset before it is cached for the next query.
+    pub fn with_session(mut self, session: SessionReuse) -> Self {
+        self.session = session;
+        self
+    }
+
+    /// Cancel the running statement with a PostgreSQL cancel request when `cancel` fires.
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Connection settings, with `statement_timeout` set to `timeout_secs` for the session.
+    fn config(&self, timeout_secs: u64) -> Config {
+        let mut config = Config::new();
+        config
+            .host(&self.host)
+            .port(self.port)
+            .user(&self.user)
+            .application_name("dbtoon")
+            .connect_timeout(Duration::from_secs(30))
+            .options(format!("-c statement_timeout={}", timeout_secs.saturating_mul(1000)))
+            .ssl_mode(match self.sslmode {
+                PostgresSslMode::Disable => SslMode::Disable,
+                PostgresSslMode::Prefer => SslMode::Prefer,
+                _ => SslMode::Require,
+            });
+        if let Some(ref database) = self.database {
+            config.dbname(database);
+        }
+        if let Some(ref password) = self.password {
+            config.password(password.expose_secret());
+        }
+        config
+    }
+
+    /// A cached session with the same settings (see `backend::reuse_connections`),
+    /// or a new connection.
+    async fn checkout(&self, timeout_secs: u64) -> Result<(SessionKey, Session), DbtoonError> {
+        let key = (self.config(timeout_secs), self.sslmode);
+        while let Some(session) = IDLE.take(&key) {
+            if !session.client.is_closed() {
+                session.notices.lock().unwrap_or_else(|e| e.into_inner()).clear();
+                return Ok((key, session));
+            }
+        }
+        let session = connect(&key.0, self.sslmode).await?;
+        Ok((key, session))
+    }
+}
+
+/// An open connection, and the server notices (`RAISE NOTICE`, warnings)
+/// collected from it.
+struct Session {
+    client: Client,
+    tls: MakeTlsConnector,
+    notices: Arc<Mutex<Vec<String>>>,
+}
+
+type SessionKey = (Config, PostgresSslMode);
+
+/// Sessions kept between queries, by connection settings.
+static IDLE: IdleConnections<SessionKey, Session> = IdleConnections::new();
+
+pub(crate) fn close_idle(idle_timeout: Duration) {
+    drop(IDLE.expired(idle_timeout));
+}
+
+pub(crate) fn idle_count() -> usize {
+    IDLE.len()
+}
+
+async fn connect(config: &Config, sslmode: PostgresSslMode) -> Result<Session, DbtoonError> {
+    let tls = tls_connector(sslmode)?;
+    let (client, mut connection) =
+        config.connect(tls.clone()).await.map_err(|e| DbtoonError::Connection {
+            message: format!("connection failed: {}", describe(&e)),
+        })?;
+
+    let notices = Arc::new(Mutex::new(Vec::new()));
+    let collected = Arc::clone(&notices);
+    tokio::spawn(async move {
+        while let Some(message) = std::future::poll_fn(|cx| connection.poll_message(cx)).await {
+            match message {
+                Ok(AsyncMessage::Notice(notice)) => collected
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .push(notice.message().to_string()),
+                Ok(_) => {}
+                Err(_) => break,
+            }
+        }
+    });
+
+    Ok(Session { client, tls, notices })
+}
+
+/// TLS connector matching libpq's `sslmode`: `prefer` and `require` encrypt
+/// without checking the certificate, `verify-ca` checks the chain, and
+/// `verify-full` also checks the host name.
+fn tls_connector(sslmode: PostgresSslMode) -> Result<MakeTlsConnector, DbtoonError> {
+    let mut builder = native_tls::TlsConnector::builder();
+    match sslmode {
+        PostgresSslMode::Prefer | PostgresSslMode::Require => {
+            builder.danger_accept_invalid_certs(true);
+        }
+        PostgresSslMode::VerifyCa => {
+            builder.danger_accept_invalid_hostnames(true);
+        }
+        PostgresSslMode::Disable | PostgresSslMode::VerifyFull => {}
+    }
+    let connector = builder.build().map_err(|e| DbtoonError::Connection {
+        message: format!("failed to set up TLS: {}", e),
+    })?;
+    Ok(MakeTlsConnector::new(connector))
+}
+
+impl Backend for PostgresBackend {
+    async fn execute(
+        &self,
+        sql: &str,
+        params: &[QueryParam],
+        limit: Option<usize>,
+        timeout_secs: u64,
+    ) -> Result<QueryResult, DbtoonError> {
+        let (mut result, summary) = self
+            .execute_streaming(sql, params, limit, timeout_secs, QueryResult::default())
+            .await?;
+        result.truncated = summary.truncated;
+        result.messages = summary.messages;
+        result.rows_affected = summary.rows_affected;
+        Ok(result)
+    }
+
+    async fn execute_streaming<S: ResultSink + 'static>(
+        &self,
+        sql: &str,
+        params: &[QueryParam],
+        limit: Option<usize>,
+        timeout_secs: u64,
+        mut sink: S,
+    ) -> Result<(S, StreamSummary), DbtoonError> {
+        let (sql, bound) = to_numbered(sql, params)?;
+        let (key, session) = self.checkout(timeout_secs).await?;
+        let cancel_token = session.client.cancel_token();
+
+        let outcome = tokio::select! {
+            outcome = run_statement(&session.client, &sql, &bound, limit, &mut sink) => outcome,
+            _ = self.cancel.cancelled() => Err(DbtoonError::Cancelled),
+        };
+        let mut summary = match outcome {
+            Ok(summary) => summary,
+            Err(DbtoonError::Cancelled) => {
+                let _ = cancel_token.cancel_query(session.tls.clone()).await;
+                return Err(DbtoonError::Cancelled);
+            }
+            Err(e) => {
+                check_in(key, session, self.session).await;
+                return Err(e);
+            }
+        };
+        if summary.truncated {
+            // The rest of the rows are still streaming in; cut them off
+            let _ = cancel_token.cancel_query(session.tls.clone()).await;
+            // Queued behind the cut-off statement, this absorbs a cancel that
+            // arrives late, before the session is used again
+            if super::connection_idle_timeout().is_some() {
+                let _ = session.client.simple_query("SELECT 1").await;
+            }
+        }
+
+        summary.messages = std::mem::take(&mut *session.notices.lock().unwrap_or_else(|e| e.into_inner()));
+        check_in(key, session, self.session).await;
+        Ok((sink, summary))
+    }
+}
+
+/// Keep `session` for the next query with the same settings while connections
+/// are reused, with `DISCARD ALL` first unless `reuse` keeps it as is, or close it.
+async fn check_in(key: SessionKey, session: Session, reuse: SessionReuse) {
+    if super::connection_idle_timeout().is_none() {
+        return;
+    }
+    // Refused inside a transaction block, which closing the connection rolls back
+    if reuse != SessionReuse::Keep && session.client.batch_execute("DISCARD ALL").await.is_err() {
+        return;
+    }
+    drop(IDLE.put(key, session));
+}
+
+/// Prepare and run one statement, streaming its rows into `sink` in
+/// `STREAM_BATCH_SIZE` batches and stopping after `limit` rows.
+async fn run_statement(
+    client: &Client,
+    sql: &str,
+    params: &[&QueryParam],
+    limit: Option<usize>,
+    sink: &mut dyn ResultSink,
+) -> Result<StreamSummary, DbtoonError> {
+    let statement = client.prepare(sql).await.map_err(|e| query_error("query preparation failed", &e))?;
+    let values: Vec<PgParam<'_>> = params.iter().map(|p| PgParam(p)).collect();
+
+    if statement.columns().is_empty() {
+        let count = client
+            .execute_raw(&statement, values.iter())
+            .await
+            .map_err(|e| query_error("query execution failed", &e))?;
+        sink.begin(&[])?;
+        return Ok(StreamSummary {
+            rows_affected: usize::try_from(count).ok(),
+            ..StreamSummary::default()
+        });
+    }
+
+    let columns: Vec<ColumnMeta> = statement.columns().iter().map(column_meta).collect();
+    sink.begin(&columns)?;
+
+    let rows = client
+        .query_raw(&statement, values.iter())
+        .await
+        .map_err(|e| query_error("query execution failed", &e))?;
+    futures_util::pin_mut!(rows);
+
+    let mut total = 0usize;
+    let mut truncated = false;
+    let mut batch = Vec::with_capacity(STREAM_BATCH_SIZE);
+    while let Some(row) = rows.try_next().await.map_err(|e| query_error("fetch error", &e))? {
+        if limit.is_some_and(|limit| total + batch.len() >= limit) {
+            truncated = true;
+            break;
+        }
+        let cells = (0..row.len())
+            .map(|i| {
+                row.try_get::<_, PgCell>(i).map(|cell| cell.0).map_err(|e| DbtoonError::Query {
+                    message: format!("failed to decode column '{}': {}", columns[i].name, describe(&e)),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        batch.push(cells);
+        if batch.len() == STREAM_BATCH_SIZE {
+            sink.write_batch(&batch)?;
+            total += batch.len();
+            batch.clear();
+        }
+    }
+    if !batch.is_empty() {
+        sink.write_batch(&batch)?;
+        total += batch.len();
+    }
+
+    Ok(StreamSummary {
+        rows: total,
+        truncated,
+        ..StreamSummary::default()
+    })
+}
+
+fn query_error(context: &str, e: &tokio_postgres::Error) -> DbtoonError {
+    DbtoonError::Query {
+        message: format!("{}: {}", context, describe(e)),
+    }
+}
+
+/// The server's message (with its hint) for database errors, or the error chain otherwise.
+fn describe(e: &tokio_postgres::Error) -> String {
+    if let Some(db) = e.as_db_error() {
+        return match db.hint() {
+            Some(hint) => format!("{} (hint: {})", db.message(), hint),
+            None => db.message().to_string(),
+        };
+    }
+    match e.source() {
+        Some(source) => format!("{}: {}", e, source),
+        None => e.to_string(),
+    }
+}
+
+fn column_meta(column: &Column) -> ColumnMeta {
+    ColumnMeta {
+        name: column.name().to_string(),
+        type_name: normalize_pg_type(column.type_(), column.type_modifier()),
+    }
+}
+
+/// Normalize a PostgreSQL type and its type modifier to the SQL type names
+/// `format_columnar::sql_type_to_arrow` understands.
+///
+/// `timestamptz` becomes `TIMESTAMP` (values are in UTC). Types without a
+/// columnar counterpart keep their PostgreSQL name, e.g. `UUID` or `INT4[]`.
+pub fn normalize_pg_type(ty: &Type, type_modifier: i32) -> String {
+    // Length and precision modifiers are stored with an offset of 4
+    let length = (type_modifier >= 4).then(|| type_modifier - 4);
+    match *ty {
+        Type::BOOL => "BOOLEAN".to_string(),
+        Type::INT2 => "SMALLINT".to_string(),
+        Type::INT4 => "INT".to_string(),
+        Type::INT8 | Type::OID => "BIGINT".to_string(),
+        Type::FLOAT4 => "REAL".to_string(),
+        Type::FLOAT8 => "DOUBLE".to_string(),
+        Type::NUMERIC => match length {
+            Some(m) => format!("DECIMAL({},{})", (m >> 16) & 0xffff, m & 0x7ff),
+            None => "DECIMAL".to_string(),
+        },
+        Type::MONEY => "DECIMAL(19,2)".to_string(),
+        Type::TEXT | Type::NAME => "VARCHAR".to_string(),
+        Type::VARCHAR => match length {
+            Some(n) => format!("VARCHAR({})", n),
+            None => "VARCHAR".to_string(),
+        },
+        Type::BPCHAR => match length {
+            Some(n) => format!("CHAR({})", n),
+            None => "CHAR".to_string(),
+        },
+        Type::DATE => "DATE".to_string(),
+        Type::TIME => "TIME".to_string(),
+        Type::TIMESTAMP | Type::TIMESTAMPTZ => "TIMESTAMP".to_string(),
+        Type::BYTEA => "VARBINARY".to_string(),
+        _ => match ty.kind() {
+            Kind::Domain(inner) => normalize_pg_type(inner, type_modifier),
+            Kind::Array(element) => format!("{}[]", element.name().to_uppercase()),
+            _ => ty.name().to_uppercase(),
+        },
+    }
+}
+
+/// A result cell decoded from PostgreSQL's binary format.
+struct PgCell(CellValue);
+
+impl<'a> FromSql<'a> for PgCell {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(PgCell(decode_cell(ty, raw)?))
+    }
+
+    fn from_sql_null(_: &Type) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(PgCell(CellValue::Null))
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+}
+
+/// Decode one binary-format value of type `ty` into a cell.
+///
+/// Numbers, booleans, dates and times become native cells; other types become
+/// text in PostgreSQL's own notation where practical, and raw bytes otherwise.
+pub fn decode_cell(ty: &Type, raw: &[u8]) -> Result<CellValue, Box<dyn Error + Sync + Send>> {
+    let cell = match *ty {
+        Type::BOOL => CellValue::Bool(pg::bool_from_sql(raw)?),
+        Type::INT2 => CellValue::Int64(pg::int2_from_sql(raw)?.into()),
+        Type::INT4 => CellValue::Int64(pg::int4_from_sql(raw)?.into()),
+        Type::INT8 => CellValue::Int64(pg::int8_from_sql(raw)?),
+        Type::OID => CellValue::Int64(pg::oid_from_sql(raw)?.into()),
+        Type::FLOAT4 => CellValue::from_f32(pg::float4_from_sql(raw)?),
+        Type::FLOAT8 => CellValue::Float64(pg::float8_from_sql(raw)?),
+        Type::NUMERIC => decode_numeric(raw)?,
+        Type::MONEY => CellValue::Decimal { value: pg::int8_from_sql(raw)?.into(), scale: 2 },
+        Type::DATE => match pg::date_from_sql(raw)? {
+            i32::MAX => CellValue::Text("infinity".to_string()),
+            i32::MIN => CellValue::Text("-infinity".to_string()),
+            days => CellValue::Date(days + PG_EPOCH_DAYS),
+        },
+        Type::TIME => CellValue::Time(pg::time_from_sql(raw)?),
+        Type::TIMETZ => {
+            let micros = pg::time_from_sql(&raw[..8.min(raw.len())])?;
+            let zone = raw.get(8..12).map_or(0, |b| i32::from_be_bytes([b[0], b[1], b[2], b[3]]));
+            // The offset is stored in seconds west of UTC
+            let east = -zone;
+            let sign = if east < 0 { '-' } else { '+' };
+            let time = CellValue::Time(micros).to_text().unwrap_or_default().into_owned();
+            CellValue::Text(format!("{}{}{:02}:{:02}", time, sign, east.abs() / 3600, east.abs() / 60 % 60))
+        }
+        Type::TIMESTAMP | Type::TIMESTAMPTZ => match pg::timestamp_from_sql(raw)? {
+            i64::MAX => CellValue::Text("infinity".to_string()),
+            i64::MIN => CellValue::Text("-infinity".to_string()),
+            micros => micros
+                .checked_add(PG_EPOCH_MICROS)
+                .map_or(CellValue::Null, CellValue::Timestamp),
+        },
+        Type::INTERVAL => CellValue::Text(format_interval(raw)?),
+        Type::BYTEA => CellValue::Bytes(pg::bytea_from_sql(raw).to_vec()),
+        Type::UUID => CellValue::Text(format_uuid(pg::uuid_from_sql(raw)?)),
+        Type::JSONB => match raw.split_first() {
+            Some((1, json)) => CellValue::Text(std::str::from_utf8(json)?.to_string()),
+            _ => return Err("unsupported jsonb version".into()),
+        },
+        Type::INET | Type::CIDR => {
+            let inet = pg::inet_from_sql(raw)?;
+            let host_mask = if inet.addr().is_ipv4() { 32 } else { 128 };
+            if *ty == Type::INET && inet.netmask() == host_mask {
+                CellValue::Text(inet.addr().to_string())
+            } else {
+                CellValue::Text(format!("{}/{}", inet.addr(), inet.netmask()))
+            }
+        }
+        Type::CHAR => CellValue::Text(char::from(pg::char_from_sql(raw)? as u8).to_string()),
+        _ => match ty.kind() {
+            Kind::Domain(inner) => decode_cell(inner, raw)?,
+            Kind::Array(element) => CellValue::Text(format_array(element, raw)?),
+            // Text-like types (text, varchar, json, xml, enums, citext, ...) send UTF-8
+            _ => match std::str::from_utf8(raw) {
+                Ok(text) => CellValue::Text(text.to_string()),
+                Err(_) => CellValue::Bytes(raw.to_vec()),
+            },
+        },
+    };
+    Ok(cell)
+}
+
+/// Decode PostgreSQL's binary `numeric`: base-10000 digits with a weight,
+/// sign and display scale. NaN, infinities and values beyond `i128` stay text.
+fn decode_numeric(raw: &[u8]) -> Result<CellValue, Box<dyn Error + Sync + Send>> {
+    let word = |i: usize| -> Result<u16, Box<dyn Error + Sync + Send>> {
+        raw.get(i * 2..i * 2 + 2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+            .ok_or_else(|| "truncated numeric value".into())
+    };
+    let ndigits = usize::from(word(0)?);
+    let weight = i32::from(word(1)? as i16);
+    let sign = word(2)?;
+    let dscale = word(3)?;
+
+    match sign {
+        0xC000 => return Ok(CellValue::Text("NaN".to_string())),
+        0xD000 => return Ok(CellValue::Text("Infinity".to_string())),
+        0xF000 => return Ok(CellValue::Text("-Infinity".to_string())),
+        _ => {}
+    }
+
+    // value * 10^dscale, built from each base-10000 digit's power of ten
+    let mut value: Option<i128> = Some(0);
+    for i in 0..ndigits {
+        let digit = i128::from(word(4 + i)?);
+        let exponent = 4 * (weight - i as i32) + i32::from(dscale);
+        let term = if exponent >= 0 {
+            10i128.checked_pow(exponent as u32).and_then(|p| digit.checked_mul(p))
+        } else {
+            Some(10i128.checked_pow(exponent.unsigned_abs()).map_or(0, |p| digit / p))
+        };
+        value = value.zip(term).and_then(|(v, t)| v.checked_add(t));
+    }
+
+    match (value, i8::try_from(dscale)) {
+        (Some(value), Ok(scale)) => Ok(CellValue::Decimal {
+            value: if sign == 0x4000 { -value } else { value },
+            scale,
+        }),
+        _ => Ok(CellValue::Text(format_numeric_digits(raw, ndigits, weight, sign, dscale)?)),
+    }
+}
+
+/// Render a `numeric` too large for `i128` as decimal text.
+fn format_numeric_digits(
+    raw: &[u8],
+    ndigits: usize,
+    weight: i32,
+    sign: u16,
+    dscale: u16,
+) -> Result<String, Box<dyn Error + Sync + Send>> {
+    let mut digits = String::new();
+    for i in 0..ndigits {
+        let b = raw.get(8 + i * 2..10 + i * 2).ok_or("truncated numeric value")?;
+        digits.push_str(&format!("{:04}", u16::from_be_bytes([b[0], b[1]])));
+    }
+    // Position of the decimal point within `digits`
+    let point = 4 * (weight + 1);
+    let (integer, fraction) = if point <= 0 {
+        (String::from("0"), format!("{}{}", "0".repeat(point.unsigned_abs() as usize), digits))
+    } else {
+        let point = point as usize;
+        let padded = format!("{:0<point$}", digits);
+        (padded[..point].to_string(), padded[point..].to_string())
+    };
+    let integer = integer.trim_start_matches('0');
+    let integer = if integer.is_empty() { "0" } else { integer };
+    let fraction: String = format!("{:0<width$}", fraction, width = usize::from(dscale))
+        .chars()
+        .take(usize::from(dscale))
+        .collect();
+    let sign = if sign == 0x4000 { "-" } else { "" };
+    Ok(if fraction.is_empty() {
+        format!("{}{}", sign, integer)
+    } else {
+        format!("{}{}.{}", sign, integer, fraction)
+    })
+}
+
+fn format_uuid(bytes: [u8; 16]) -> String {
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+/// An interval in PostgreSQL's default output style, e.g. `1 year 2 mons 3 days 04:05:06`.
+fn format_interval(raw: &[u8]) -> Result<String, Box<dyn Error + Sync + Send>> {
+    if raw.len() != 16 {
+        return Err("invalid interval value".into());
+    }
+    let micros = pg::time_from_sql(&raw[..8])?;
+    let days = i32::from_be_bytes([raw[8], raw[9], raw[10], raw[11]]);
+    let months = i32::from_be_bytes([raw[12], raw[13], raw[14], raw[15]]);
+
+    let mut parts = Vec::new();
+    let unit = |n: i32, singular: &str, plural: &str| {
+        format!("{} {}", n, if n.abs() == 1 { singular } else { plural })
+    };
+    if months / 12 != 0 {
+        parts.push(unit(months / 12, "year", "years"));
+    }
+    if months % 12 != 0 {
+        parts.push(unit(months % 12, "mon", "mons"));
+    }
+    if days != 0 {
+        parts.push(unit(days, "day", "days"));
+    }
+    if micros != 0 || parts.is_empty() {
+        let sign = if micros < 0 { "-" } else { "" };
+        let time = CellValue::Time(micros.abs()).to_text().unwrap_or_default().into_owned();
+        parts.push(format!("{}{}", sign, time));
+    }
+    Ok(parts.join(" "))
+}
+
+/// An array in PostgreSQL's text notation, e.g. `{1,2,NULL}` or `{{a,b},{c,d}}`.
+fn format_array(element: &Type, raw: &[u8]) -> Result<String, Box<dyn Error + Sync + Send>> {
+    let array = pg::array_from_sql(raw)?;
+    let dimensions: Vec<usize> = array
+        .dimensions()
+        .map(|d| Ok(usize::try_from(d.len).unwrap_or(0)))
+        .collect()?;
+    let values: Vec<String> = array
+        .values()
+        .map(|value| {
+            Ok(match value {
+                None => "NULL".to_string(),
+                Some(bytes) => {
+                    let cell = decode_cell(element, bytes)?;
+                    let text = cell.to_text().unwrap_or_default().into_owned();
+                    match cell {
+                        CellValue::Text(_) | CellValue::Bytes(_) => quote_array_element(&text),
+                        _ => text,
+                    }
+                }
+            })
+        })
+        .collect()?;
+
+    if dimensions.is_empty() {
+        return Ok("{}".to_string());
+    }
+    let mut values = values.into_iter();
+    Ok(nest_array(&dimensions, &mut values))
+}
+
+fn nest_array(dimensions: &[usize], values: &mut impl Iterator<Item = String>) -> String {
+    let items: Vec<String> = match dimensions {
+        [] => return String::new(),
+        [len] => values.take(*len).collect(),
+        [len, rest @ ..] => (0..*len).map(|_| nest_array(rest, values)).collect(),
+    };
+    format!("{{{}}}", items.join(","))
+}
+
+fn quote_array_element(text: &str) -> String {
+    let needs_quotes = text.is_empty()
+        || text.eq_ignore_ascii_case("NULL")
+        || text.chars().any(|c| matches!(c, ',' | '{' | '}' | '"' | '\\') || c.is_whitespace());
+    if needs_quotes {
+        format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// A query parameter, encoded for whatever type the server inferred for its marker.
+#[derive(Debug)]
+struct PgParam<'a>(&'a QueryParam);
+
+impl ToSql for PgParam<'_> {
+    fn to_sql(&self, ty: &Type, out: &mut BytesMut) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        encode_param(&self.0.value, ty, out)
+            .map_err(|e| format!("parameter '{}': {}", self.0.name, e))?;
+        Ok(IsNull::No)
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+
+    to_sql_checked!();
+}
+
+/// Encode a parameter's text `value` in the binary format of `ty`.
+///
+/// The server infers each marker's type from the query, so a value is
+/// converted to that type here rather than by its `--param` type suffix.
+pub fn encode_param(value: &str, ty: &Type, out: &mut BytesMut) -> Result<(), DbtoonError> {
+    let invalid = || DbtoonError::Config {
+        message: format!("cannot convert '{}' to {}", value, ty.name()),
+    };
+    let trimmed = value.trim();
+    match *ty {
+        Type::BOOL => pg::bool_to_sql(parse_bool(trimmed).ok_or_else(invalid)?, out),
+        Type::INT2 => pg::int2_to_sql(trimmed.parse().map_err(|_| invalid())?, out),
+        Type::INT4 => pg::int4_to_sql(trimmed.parse().map_err(|_| invalid())?, out),
+        Type::INT8 => pg::int8_to_sql(trimmed.parse().map_err(|_| invalid())?, out),
+        Type::OID => pg::oid_to_sql(trimmed.parse().map_err(|_| invalid())?, out),
+        Type::FLOAT4 => pg::float4_to_sql(trimmed.parse().map_err(|_| invalid())?, out),
+        Type::FLOAT8 => pg::float8_to_sql(trimmed.parse().map_err(|_| invalid())?, out),
+        Type::NUMERIC => encode_numeric(trimmed, out).ok_or_else(invalid)?,
+        Type::DATE => {
+            let days = parse_date32(trimmed).ok_or_else(invalid)?;
+            pg::date_to_sql(days - PG_EPOCH_DAYS, out)
+        }
+        Type::TIME => pg::time_to_sql(parse_time_micros(trimmed).ok_or_else(invalid)?, out),
+        Type::TIMESTAMP | Type::TIMESTAMPTZ => {
+            let micros = parse_timestamp_micros(trimmed).ok_or_else(invalid)?;
+            pg::timestamp_to_sql(micros - PG_EPOCH_MICROS, out)
+        }
+        Type::UUID => {
+            let bytes = hex_decode(&trimmed.replace('-', "")).ok_or_else(invalid)?;
+            pg::uuid_to_sql(bytes.try_into().map_err(|_| invalid())?, out)
+        }
+        Type::BYTEA => {
+            let bytes = if trimmed.starts_with("0x") || trimmed.starts_with("0X") {
+                hex_decode(trimmed).ok_or_else(invalid)?
+            } else {
+                value.as_bytes().to_vec()
+            };
+            pg::bytea_to_sql(&bytes, out)
+        }
+        Type::JSONB => {
+            out.put_u8(1);
+            pg::text_to_sql(value, out)
+        }
+        Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME | Type::UNKNOWN | Type::JSON
+        | Type::XML => pg::text_to_sql(value, out),
+        _ => match ty.kind() {
+            Kind::Domain(inner) => encode_param(value, inner, out)?,
+            Kind::Enum(_) => pg::text_to_sql(value, out),
+            _ => {
+                return Err(DbtoonError::Config {
+                    message: format!(
+                        "cannot bind a parameter of type {}; cast the marker, e.g. CAST(:name AS text)",
+                        ty.name()
+                    ),
+                });
+            }
+        },
+    }
+    Ok(())
+}
+
+/// Encode decimal text as binary `numeric`. Returns `None` for malformed text.
+fn encode_numeric(text: &str, out: &mut BytesMut) -> Option<()> {
+    if text.eq_ignore_ascii_case("nan") {
+        out.put_slice(&[0, 0, 0, 0, 0xC0, 0, 0, 0]);
+        return Some(());
+    }
+    // Validates the text; digits are taken from it below
+    parse_decimal(text)?;
+    let negative = text.starts_with('-');
+    let unsigned = text.trim_start_matches(['-', '+']);
+    let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    let integer = integer.trim_start_matches('0');
+    let dscale = u16::try_from(fraction.len()).ok()?;
+
+    // Align both parts to base-10000 groups around the decimal point
+    let integer_width = integer.len().div_ceil(4) * 4;
+    let fraction_width = fraction.len().div_ceil(4) * 4;
+    let aligned = format!(
+        "{:0>iw$}{:0<fw$}",
+        integer,
+        fraction,
+        iw = integer_width,
+        fw = fraction_width
+    );
+    let mut groups: Vec<i16> = aligned
+        .as_bytes()
+        .chunks(4)
+        .map(|chunk| std::str::from_utf8(chunk).ok()?.parse().ok())
+        .collect::<Option<_>>()?;
+    let mut weight = i16::try_from(integer_width / 4).ok()? - 1;
+
+    // Drop leading and trailing zero groups
+    while groups.first() == Some(&0) {
+        groups.remove(0);
+        weight -= 1;
+    }
+    while groups.last() == Some(&0) {
+        groups.pop();
+    }
+    if groups.is_empty() {
+        weight = 0;
+    }
+
+    out.put_i16(i16::try_from(groups.len()).ok()?);
+    out.put_i16(weight);
+    out.put_u16(if negative && !groups.is_empty() { 0x4000 } else { 0 });
+    out.put_u16(dscale);
+    for group in groups {
+        out.put_i16(group);
+    }
+    Some(())
+}
@@ -0,0 +1,127 @@
+//! Retry policy for transient HTTP failures in REST backends.
+
+use std::time::Duration;
+
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{RequestBuilder, Response};
+
+use crate::verbose;
+
+/// Default total attempts per request, including the first.
+pub const DEFAULT_RETRY_ATTEMPTS: u32 = 4;
+
+/// Default delay before the first retry; it doubles on each further retry.
+pub const DEFAULT_RETRY_BACKOFF_MS: u64 = 500;
+
+/// Upper bound on the exponential backoff between attempts.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Upper bound on a server-requested `Retry-After` delay.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(120);
+
+/// How often, and how patiently, a failed request is retried.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Total attempts per request, including the first (minimum 1).
+    pub max_attempts: u32,
+    /// Delay before the first retry.
+    pub initial_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_RETRY_ATTEMPTS,
+            initial_backoff: Duration::from_millis(DEFAULT_RETRY_BACKOFF_MS),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retry number `retry` (1-based): the initial backoff doubled
+    /// for each earlier retry, capped at 30 seconds.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.initial_backoff.saturating_mul(factor).min(MAX_BACKOFF)
+    }
+}
+
+/// Whether a request may be sent twice without changing its effect.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Idempotency {
+    /// Reads: retried after any transport error and every transient status.
+    Idempotent,
+    /// Submissions: retried only when the server cannot have acted on the
+    /// request, i.e. the connection failed or the server answered 429 or 503.
+    NotIdempotent,
+}
+
+/// HTTP statuses worth retrying: rate limiting and transient server errors.
+pub fn is_transient_status(status: u16) -> bool {
+    matches!(status, 429 | 500 | 502 | 503 | 504)
+}
+
+/// The delay requested by a `Retry-After` header in seconds, capped at two minutes.
+///
+/// The HTTP-date form is not supported and falls back to the backoff.
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let seconds: u64 = headers.get(RETRY_AFTER)?.to_str().ok()?.trim().parse().ok()?;
+    Some(Duration::from_secs(seconds).min(MAX_RETRY_AFTER))
+}
+
+/// Send the request built by `build`, retrying transient failures under `policy`.
+///
+/// Returns the first response that is not transient, or the last one once
+/// attempts run out, so callers map error statuses as before. Each retry is
+/// logged through `verbose::emit`, naming the request as `what`.
+pub async fn send_with_retry(
+    policy: &RetryPolicy,
+    idempotency: Idempotency,
+    verbose: bool,
+    what: &str,
+    build: impl Fn() -> RequestBuilder,
+) -> Result<Response, reqwest::Error> {
+    let max_attempts = policy.max_attempts.max(1);
+    let mut attempt = 1;
+
+    loop {
+        let (reason, delay) = match build().send().await {
+            Ok(resp) => {
+                let status = resp.status().as_u16();
+                let retryable = match idempotency {
+                    Idempotency::Idempotent => is_transient_status(status),
+                    Idempotency::NotIdempotent => matches!(status, 429 | 503),
+                };
+                if !retryable || attempt >= max_attempts {
+                    return Ok(resp);
+                }
+                let delay = retry_after(resp.headers()).unwrap_or_else(|| policy.backoff(attempt));
+                (format!("HTTP {}", status), delay)
+            }
+            Err(e) => {
+                let retryable = match idempotency {
+                    Idempotency::Idempotent => e.is_connect() || e.is_timeout() || e.is_request(),
+                    Idempotency::NotIdempotent => e.is_connect(),
+                };
+                if !retryable || attempt >= max_attempts {
+                    return Err(e);
+                }
+                (e.to_string(), policy.backoff(attempt))
+            }
+        };
+
+        verbose::emit(
+            verbose,
+            &format!(
+                "{} failed ({}), retrying in {}ms (attempt {}/{})",
+                what,
+                reason,
+                delay.as_millis(),
+                attempt + 1,
+                max_attempts
+            ),
+        );
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
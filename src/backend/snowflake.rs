@@ -0,0 +1,633 @@
+//! Snowflake backend over the SQL REST API (`/api/v2/statements`) with key-pair JWT auth.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use base64::Engine;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use reqwest::{Client, RequestBuilder, Response};
+use rsa::RsaPrivateKey;
+use rsa::pkcs1::DecodeRsaPrivateKey;
+use rsa::pkcs1v15::SigningKey;
+use rsa::pkcs8::{DecodePrivateKey, EncodePublicKey};
+use rsa::signature::{SignatureEncoding, Signer};
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::backend::databricks::host_url;
+use crate::backend::retry::{Idempotency, RetryPolicy, send_with_retry};
+use crate::backend::value::{hex_decode, parse_bool, parse_decimal, rescale_decimal};
+use crate::backend::{
+    Backend, CellValue, ColumnMeta, QueryResult, ResultSink, StreamSummary, http_client,
+};
+use crate::cancel::CancelToken;
+use crate::error::DbtoonError;
+use crate::params::{QueryParam, to_positional};
+
+/// How long a key-pair JWT is valid; Snowflake rejects tokens living over an hour.
+/// First and longest wait between polls of a running statement.
+const POLL_FIRST: Duration = Duration::from_millis(100);
+const POLL_MAX: Duration = Duration::from_secs(2);
+
+const JWT_LIFETIME: Duration = Duration::from_secs(59 * 60);
+
+/// Age at which a cached JWT is replaced, leaving room for clock skew.
+const JWT_REFRESH_AFTER: Duration = Duration::from_secs(50 * 60);
+
+pub struct SnowflakeBackend {
+    account: String,
+    host: Option<String>,
+    user: String,
+    private_key: RsaPrivateKey,
+    warehouse: Option<String>,
+    database: Option<String>,
+    schema: Option<String>,
+    role: Option<String>,
+    cancel: CancelToken,
+    retry: RetryPolicy,
+    verbose: bool,
+    client: Client,
+    token: Mutex<Option<(Instant, String)>>,
+}
+
+#[derive(Serialize)]
+struct StatementRequest {
+    statement: String,
+    timeout: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    warehouse: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    database: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    schema: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    role: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    bindings: BTreeMap<String, Binding>,
+}
+
+/// A value bound to the `?` marker at the position of its key ("1", "2", ...).
+#[derive(Serialize)]
+struct Binding {
+    #[serde(rename = "type")]
+    type_name: &'static str,
+    value: Option<String>,
+}
+
+/// The body of every statement endpoint: a result set (200), a status while
+/// the statement runs (202) or a failure (422 and other errors).
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct StatementResponse {
+    code: Option<String>,
+    message: Option<String>,
+    statement_handle: Option<String>,
+    result_set_meta_data: Option<ResultSetMetaData>,
+    data: Option<Vec<Vec<Option<String>>>>,
+}
+
+impl StatementResponse {
+    fn format_message(self) -> String {
+        match (self.code, self.message) {
+            (Some(code), Some(msg)) => format!("[{}] {}", code, msg),
+            (Some(code), None) => format!("[{}]", code),
+            (None, Some(msg)) => msg,
+            (None, None) => "unknown error".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct ResultSetMetaData {
+    num_rows: Option<usize>,
+    /// One entry per result partition; only the count is used.
+    #[serde(default)]
+    partition_info: Vec<serde::de::IgnoredAny>,
+    #[serde(default)]
+    row_type: Vec<RowType>,
+}
+
+#[derive(Deserialize)]
+struct RowType {
+    name: String,
+    #[serde(rename = "type")]
+    type_name: String,
+    precision: Option<u32>,
+    scale: Option<i8>,
+}
+
+impl SnowflakeBackend {
+    pub fn new(
+        account: String,
+        user: String,
+        private_key: RsaPrivateKey,
+        warehouse: Option<String>,
+        database: Option<String>,
+        schema: Option<String>,
+        role: Option<String>,
+    ) -> Self {
+        Self {
+            account,
+            host: None,
+            user,
+            private_key,
+            warehouse,
+            database,
+            schema,
+            role,
+            cancel: CancelToken::default(),
+            retry: RetryPolicy::default(),
+            verbose: false,
+            client: http_client(),
+            token: Mutex::new(None),
+        }
+    }
+
+    /// Connect to `host` instead of `<account>.snowflakecomputing.com`, e.g. for
+    /// a private-link endpoint.
+    pub fn with_host(mut self, host: Option<String>) -> Self {
+        self.host = host;
+        self
+    }
+
+    /// Retry transient HTTP failures under `retry`.
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Log retries to stderr.
+    pub fn with_verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    /// Cancel the statement in Snowflake when `cancel` fires.
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    fn base_url(&self) -> String {
+        let host = match &self.host {
+            Some(host) => host.clone(),
+            None => format!("{}.snowflakecomputing.com", self.account.to_lowercase()),
+        };
+        format!("{}/api/v2/statements", host_url(&host))
+    }
+
+    /// A key-pair JWT for the configured user, signed again once the cached one ages.
+    fn token(&self) -> Result<String, DbtoonError> {
+        let mut cached = self.token.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((issued, token)) = cached.as_ref()
+            && issued.elapsed() < JWT_REFRESH_AFTER
+        {
+            return Ok(token.clone());
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let token = key_pair_jwt(&self.account, &self.user, &self.private_key, now)?;
+        *cached = Some((Instant::now(), token.expose_secret().to_string()));
+        Ok(token.expose_secret().to_string())
+    }
+
+    /// `request` with the SQL API's authentication and content headers.
+    fn authorized(&self, request: RequestBuilder, token: &str) -> RequestBuilder {
+        request
+            .bearer_auth(token)
+            .header("X-Snowflake-Authorization-Token-Type", "KEYPAIR_JWT")
+            .header("Accept", "application/json")
+            .header("User-Agent", concat!("dbtoon/", env!("CARGO_PKG_VERSION")))
+    }
+
+    async fn get(&self, url: &str, what: &str) -> Result<Response, DbtoonError> {
+        let token = self.token()?;
+        send_with_retry(&self.retry, Idempotency::Idempotent, self.verbose, what, || {
+            self.authorized(self.client.get(url), &token)
+        })
+        .await
+        .map_err(|e| DbtoonError::Connection {
+            message: format!("{} failed: {}", what, e),
+        })
+    }
+
+    async fn poll_statement(&self, handle: &str, timeout_secs: u64) -> Result<StatementResponse, DbtoonError> {
+        let start = Instant::now();
+        let url = format!("{}/{}", self.base_url(), handle);
+        let mut delay = POLL_FIRST;
+
+        loop {
+            if start.elapsed().as_secs() >= timeout_secs {
+                self.cancel_statement(handle).await;
+                return Err(DbtoonError::Timeout {
+                    seconds: timeout_secs,
+                });
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = self.cancel.cancelled() => {
+                    self.cancel_statement(handle).await;
+                    return Err(DbtoonError::Cancelled);
+                }
+            }
+
+            // Short statements finish within the first polls; long ones back off
+            delay = (delay * 2).min(POLL_MAX);
+            let resp = self.get(&url, "statement poll").await?;
+            match read_response(resp, timeout_secs).await? {
+                (202, _) => continue,
+                (_, response) => return Ok(response),
+            }
+        }
+    }
+
+    /// Ask Snowflake to stop a statement. Best effort: failures are ignored.
+    async fn cancel_statement(&self, handle: &str) {
+        let cancel_url = format!("{}/{}/cancel", self.base_url(), handle);
+        let Ok(token) = self.token() else {
+            return;
+        };
+        let _ = self
+            .authorized(self.client.post(&cancel_url), &token)
+            .send()
+            .await;
+    }
+
+    /// Fetch the rows of result partition `partition` (1 or later; partition 0
+    /// arrives with the statement response).
+    async fn fetch_partition(
+        &self,
+        handle: &str,
+        partition: usize,
+    ) -> Result<Vec<Vec<Option<String>>>, DbtoonError> {
+        let url = format!("{}/{}?partition={}", self.base_url(), handle, partition);
+        let resp = self.get(&url, "partition fetch").await?;
+        let (_, response) = read_response(resp, 0).await?;
+        Ok(response.data.unwrap_or_default())
+    }
+
+    /// Feed a finished statement's result to `sink`, fetching partitions in
+    /// order until the result is exhausted or `limit` rows have been written.
+    async fn stream_response(
+        &self,
+        response: StatementResponse,
+        limit: Option<usize>,
+        sink: &mut dyn ResultSink,
+    ) -> Result<StreamSummary, DbtoonError> {
+        let meta = response.result_set_meta_data.unwrap_or_default();
+
+        let columns = meta
+            .row_type
+            .iter()
+            .map(|c| ColumnMeta {
+                name: c.name.clone(),
+                type_name: normalize_snowflake_type(&c.type_name, c.precision, c.scale),
+            })
+            .collect::<Vec<_>>();
+        sink.begin(&columns)?;
+
+        let handle = response.statement_handle.unwrap_or_default();
+        let partitions = meta.partition_info.len().max(1);
+        let mut truncated = false;
+        let mut written = 0usize;
+        let mut data = response.data.unwrap_or_default();
+
+        for partition in 0..partitions {
+            if partition > 0 {
+                data = self.fetch_partition(&handle, partition).await?;
+            }
+
+            let mut rows: Vec<Vec<CellValue>> = data
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .enumerate()
+                        .map(|(i, cell)| match (cell, columns.get(i)) {
+                            (Some(s), Some(col)) => snowflake_cell(s, &col.type_name),
+                            (Some(s), None) => CellValue::Text(s.clone()),
+                            (None, _) => CellValue::Null,
+                        })
+                        .collect()
+                })
+                .collect();
+
+            let has_more = partition + 1 < partitions;
+
+            if let Some(lim) = limit {
+                let remaining = lim.saturating_sub(written);
+                if rows.len() > remaining || (rows.len() == remaining && has_more) {
+                    rows.truncate(remaining);
+                    truncated = true;
+                }
+            }
+
+            if !rows.is_empty() {
+                sink.write_batch(&rows)?;
+                written += rows.len();
+            }
+
+            if limit.is_some_and(|lim| written >= lim) {
+                break;
+            }
+        }
+
+        Ok(StreamSummary {
+            rows: written,
+            truncated,
+            total_rows: meta.num_rows,
+            ..StreamSummary::default()
+        })
+    }
+
+    /// Submit a statement and wait for it to finish.
+    ///
+    /// The statement is submitted asynchronously, so Snowflake answers at once
+    /// with a handle to poll and a cancel is acted on while it runs;
+    /// `timeout_secs` also bounds the run on the warehouse itself.
+    async fn run_statement(
+        &self,
+        sql: &str,
+        params: &[QueryParam],
+        timeout_secs: u64,
+    ) -> Result<StatementResponse, DbtoonError> {
+        let (sql, bound) = to_positional(sql, params)?;
+        let request = StatementRequest {
+            statement: sql,
+            timeout: timeout_secs,
+            warehouse: self.warehouse.clone(),
+            database: self.database.clone(),
+            schema: self.schema.clone(),
+            role: self.role.clone(),
+            bindings: bound
+                .iter()
+                .enumerate()
+                .map(|(i, param)| ((i + 1).to_string(), binding(param)))
+                .collect(),
+        };
+
+        // A submission that reached Snowflake may have run, so only retry rejections
+        let url = self.base_url();
+        let token = self.token()?;
+        let resp = send_with_retry(&self.retry, Idempotency::NotIdempotent, self.verbose, "statement submit", || {
+            self.authorized(self.client.post(&url), &token)
+                .query(&[("async", "true")])
+                .json(&request)
+        })
+        .await
+        .map_err(|e| DbtoonError::Connection {
+            message: format!("failed to execute statement: {}", e),
+        })?;
+
+        let (status, response) = read_response(resp, timeout_secs).await?;
+
+        // Cancelled while waiting for the submission: the handle is only known now
+        if self.cancel.is_cancelled() {
+            if let (202, Some(handle)) = (status, &response.statement_handle) {
+                self.cancel_statement(handle).await;
+            }
+            return Err(DbtoonError::Cancelled);
+        }
+
+        if status != 202 {
+            return Ok(response);
+        }
+        let handle = response.statement_handle.ok_or_else(|| DbtoonError::Query {
+            message: "no statementHandle in running statement response".to_string(),
+        })?;
+        self.poll_statement(&handle, timeout_secs).await
+    }
+}
+
+impl Backend for SnowflakeBackend {
+    async fn execute(
+        &self,
+        sql: &str,
+        params: &[QueryParam],
+        limit: Option<usize>,
+        timeout_secs: u64,
+    ) -> Result<QueryResult, DbtoonError> {
+        let (mut result, summary) = self
+            .execute_streaming(sql, params, limit, timeout_secs, QueryResult::default())
+            .await?;
+        result.truncated = summary.truncated;
+        result.total_rows = summary.total_rows;
+        Ok(result)
+    }
+
+    async fn execute_streaming<S: ResultSink + 'static>(
+        &self,
+        sql: &str,
+        params: &[QueryParam],
+        limit: Option<usize>,
+        timeout_secs: u64,
+        mut sink: S,
+    ) -> Result<(S, StreamSummary), DbtoonError> {
+        let response = self.run_statement(sql, params, timeout_secs).await?;
+        // The statement has finished; cancelling only stops the download
+        let summary = tokio::select! {
+            summary = self.stream_response(response, limit, &mut sink) => summary?,
+            _ = self.cancel.cancelled() => return Err(DbtoonError::Cancelled),
+        };
+        Ok((sink, summary))
+    }
+}
+
+/// Parse a statement endpoint response, returning 200 and 202 bodies with their
+/// status and mapping every other status to an error.
+async fn read_response(resp: Response, timeout_secs: u64) -> Result<(u16, StatementResponse), DbtoonError> {
+    let status = resp.status().as_u16();
+    let body = resp.bytes().await.map_err(|e| DbtoonError::Connection {
+        message: format!("failed to read response: {}", e),
+    })?;
+
+    if matches!(status, 200 | 202) {
+        let response = serde_json::from_slice(&body).map_err(|e| DbtoonError::Query {
+            message: format!("failed to parse response: {}", e),
+        })?;
+        return Ok((status, response));
+    }
+
+    // Error bodies are JSON with a code and message, when Snowflake produced them
+    let response: StatementResponse = serde_json::from_slice(&body).unwrap_or_default();
+    let detail = response.message.clone();
+    Err(match status {
+        422 => DbtoonError::Query {
+            message: response.format_message(),
+        },
+        408 => DbtoonError::Timeout {
+            seconds: timeout_secs,
+        },
+        401 => DbtoonError::Auth {
+            message: format!(
+                "key-pair authentication failed: {}",
+                detail.unwrap_or_else(|| "JWT rejected".to_string())
+            ),
+        },
+        403 => DbtoonError::Auth {
+            message: detail.unwrap_or_else(|| "insufficient privileges".to_string()),
+        },
+        404 => DbtoonError::Config {
+            message: "statement endpoint not found (check the account identifier)".to_string(),
+        },
+        _ => DbtoonError::Connection {
+            message: match detail {
+                Some(msg) => format!("HTTP error: {}: {}", status, msg),
+                None => format!("HTTP error: {}", status),
+            },
+        },
+    })
+}
+
+/// The SQL API binding for a parameter: numbers and booleans keep their type,
+/// everything else is sent as TEXT for Snowflake to cast.
+fn binding(param: &QueryParam) -> Binding {
+    let base = param
+        .type_name
+        .as_deref()
+        .and_then(|t| t.split('(').next())
+        .map(|t| t.trim().to_uppercase());
+    let type_name = match base.as_deref() {
+        Some("TINYINT" | "SMALLINT" | "INT" | "INTEGER" | "BIGINT" | "DECIMAL" | "NUMERIC") => "FIXED",
+        Some("FLOAT" | "REAL" | "DOUBLE") => "REAL",
+        Some("BOOLEAN" | "BIT") => "BOOLEAN",
+        _ => "TEXT",
+    };
+    Binding {
+        type_name,
+        value: param.typed_value().to_text().map(|v| v.into_owned()),
+    }
+}
+
+/// Normalize a Snowflake `rowType` type to the SQL type names
+/// `format_columnar::sql_type_to_arrow` understands.
+///
+/// `NUMBER` columns of scale 0 fitting in 64 bits become `BIGINT`, other
+/// `NUMBER`s `DECIMAL(p,s)`. All timestamp kinds become `TIMESTAMP` (values
+/// are in UTC). Semi-structured and spatial types keep their upper-case name.
+pub fn normalize_snowflake_type(type_name: &str, precision: Option<u32>, scale: Option<i8>) -> String {
+    match type_name.to_lowercase().as_str() {
+        "fixed" => match (precision, scale.unwrap_or(0)) {
+            (Some(p), 0) if p <= 18 => "BIGINT".to_string(),
+            (Some(p), s) => format!("DECIMAL({},{})", p, s),
+            (None, 0) => "BIGINT".to_string(),
+            (None, s) => format!("DECIMAL(38,{})", s),
+        },
+        "real" => "DOUBLE".to_string(),
+        "text" => "VARCHAR".to_string(),
+        "boolean" => "BOOLEAN".to_string(),
+        "date" => "DATE".to_string(),
+        "time" => "TIME".to_string(),
+        "timestamp_ntz" | "timestamp_ltz" | "timestamp_tz" => "TIMESTAMP".to_string(),
+        "binary" => "VARBINARY".to_string(),
+        other => other.to_uppercase(),
+    }
+}
+
+/// Decode one value of the SQL API's JSON result format, given its normalized type.
+///
+/// Dates arrive as days since the epoch, times as seconds since midnight and
+/// timestamps as seconds since the epoch (followed by an offset for
+/// `TIMESTAMP_TZ`, which is dropped: the seconds are already UTC). Values that
+/// do not parse stay `Text`.
+pub fn snowflake_cell(value: &str, type_name: &str) -> CellValue {
+    let base = type_name.split('(').next().unwrap_or_default();
+    let parsed = match base {
+        "BIGINT" => value.parse().ok().map(CellValue::Int64),
+        "DECIMAL" => parse_decimal(value).map(|(value, scale)| CellValue::Decimal { value, scale }),
+        "DOUBLE" => value.parse().ok().map(CellValue::Float64),
+        "BOOLEAN" => parse_bool(value).map(CellValue::Bool),
+        "DATE" => value.parse().ok().map(CellValue::Date),
+        "TIME" => seconds_to_micros(value).map(CellValue::Time),
+        "TIMESTAMP" => value
+            .split_whitespace()
+            .next()
+            .and_then(seconds_to_micros)
+            .map(CellValue::Timestamp),
+        "VARBINARY" => hex_decode(value).map(CellValue::Bytes),
+        _ => None,
+    };
+    parsed.unwrap_or_else(|| CellValue::Text(value.to_string()))
+}
+
+/// Microseconds in a decimal number of seconds like `1616173619.123456789`.
+fn seconds_to_micros(value: &str) -> Option<i64> {
+    let (seconds, scale) = parse_decimal(value)?;
+    rescale_decimal(seconds, scale, 6)?.try_into().ok()
+}
+
+/// Read an RSA private key from a PEM file: PKCS#8, encrypted PKCS#8 (with
+/// `passphrase`) or PKCS#1.
+pub fn load_private_key(path: &Path, passphrase: Option<&SecretString>) -> Result<RsaPrivateKey, DbtoonError> {
+    let pem = std::fs::read_to_string(path).map_err(|e| DbtoonError::Config {
+        message: format!("cannot read private key '{}': {}", path.display(), e),
+    })?;
+
+    let key = if pem.contains("BEGIN ENCRYPTED PRIVATE KEY") {
+        let passphrase = passphrase.ok_or_else(|| DbtoonError::Config {
+            message: format!(
+                "private key '{}' is encrypted but no 'private_key_passphrase' is set",
+                path.display()
+            ),
+        })?;
+        RsaPrivateKey::from_pkcs8_encrypted_pem(&pem, passphrase.expose_secret().as_bytes())
+            .map_err(|e| e.to_string())
+    } else if pem.contains("BEGIN RSA PRIVATE KEY") {
+        RsaPrivateKey::from_pkcs1_pem(&pem).map_err(|e| e.to_string())
+    } else {
+        RsaPrivateKey::from_pkcs8_pem(&pem).map_err(|e| e.to_string())
+    };
+
+    key.map_err(|e| DbtoonError::Config {
+        message: format!("invalid private key '{}': {}", path.display(), e),
+    })
+}
+
+/// `SHA256:<base64>` fingerprint of the public half of `key`, as Snowflake
+/// shows it in `RSA_PUBLIC_KEY_FP`.
+pub fn public_key_fingerprint(key: &RsaPrivateKey) -> Result<String, DbtoonError> {
+    let der = key.to_public_key().to_public_key_der().map_err(|e| DbtoonError::Auth {
+        message: format!("cannot encode public key: {}", e),
+    })?;
+    Ok(format!("SHA256:{}", STANDARD.encode(Sha256::digest(der.as_bytes()))))
+}
+
+/// A key-pair authentication JWT for `user` in `account`, issued at `issued_at`
+/// (seconds since the epoch) and signed with RS256.
+///
+/// The account is upper-cased and loses any `.region` suffix, as Snowflake expects.
+pub fn key_pair_jwt(
+    account: &str,
+    user: &str,
+    key: &RsaPrivateKey,
+    issued_at: u64,
+) -> Result<SecretString, DbtoonError> {
+    let account = account.split('.').next().unwrap_or_default().to_uppercase();
+    let qualified_user = format!("{}.{}", account, user.to_uppercase());
+
+    let header = serde_json::json!({ "alg": "RS256", "typ": "JWT" });
+    let claims = serde_json::json!({
+        "iss": format!("{}.{}", qualified_user, public_key_fingerprint(key)?),
+        "sub": qualified_user,
+        "iat": issued_at,
+        "exp": issued_at + JWT_LIFETIME.as_secs(),
+    });
+    Ok(SecretString::from(rs256_jwt(&header, &claims, key)))
+}
+
+/// A compact JWT of `header` and `claims`, signed with RS256 under `key`.
+pub(crate) fn rs256_jwt(header: &serde_json::Value, claims: &serde_json::Value, key: &RsaPrivateKey) -> String {
+    let signing_input = format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(header.to_string()),
+        URL_SAFE_NO_PAD.encode(claims.to_string())
+    );
+    let signature = SigningKey::<Sha256>::new(key.clone()).sign(signing_input.as_bytes());
+    format!("{}.{}", signing_input, URL_SAFE_NO_PAD.encode(signature.to_bytes()))
+}
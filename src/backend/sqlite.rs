@@ -0,0 +1,719 @@
+//! Local SQLite backend (rusqlite, bundled SQLite) for database files, `:memory:`,
+//! and the Parquet, Arrow and CSV files dbtoon writes.
+//!
+//! A quoted file name in table position (`FROM 'out.parquet'`) is loaded into a
+//! temporary table of the same name before the query runs, which SQLite then
+//! resolves since it accepts a string literal as a table name. A cached
+//! connection reloads the file only once it has been modified.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Seek;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use arrow::array::{Array, AsArray};
+use arrow::datatypes::{
+    DataType, Date32Type, Date64Type, Decimal128Type, Float16Type, Float32Type, Float64Type,
+    Int8Type, Int16Type, Int32Type, Int64Type, Time32MillisecondType, Time32SecondType,
+    Time64MicrosecondType, Time64NanosecondType, TimeUnit, TimestampMicrosecondType,
+    TimestampMillisecondType, TimestampNanosecondType, TimestampSecondType, UInt8Type,
+    UInt16Type, UInt32Type, UInt64Type,
+};
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatchReader;
+use arrow::util::display::ArrayFormatter;
+use rusqlite::types::{Value, ValueRef};
+use rusqlite::{Connection, OpenFlags, Statement};
+
+use crate::backend::idle::IdleConnections;
+use crate::backend::{
+    Backend, CellValue, ColumnMeta, QueryResult, ResultSink, STREAM_BATCH_SIZE, SessionReuse,
+    StreamSummary,
+};
+use crate::cancel::CancelToken;
+use crate::error::DbtoonError;
+use crate::params::{QueryParam, to_numbered};
+
+/// The in-memory database, as accepted by SQLite itself.
+pub const MEMORY_PATH: &str = ":memory:";
+
+/// SQLite virtual machine steps between cancellation and timeout checks.
+const PROGRESS_STEPS: i32 = 10_000;
+
+pub struct SqliteBackend {
+    path: String,
+    read_only: bool,
+    working_dir: Option<PathBuf>,
+    session: SessionReuse,
+    cancel: CancelToken,
+}
+
+impl SqliteBackend {
+    pub fn new(path: String) -> Self {
+        Self {
+            path,
+            read_only: false,
+            working_dir: None,
+            session: SessionReuse::default(),
+            cancel: CancelToken::default(),
+        }
+    }
+
+    /// Open the database file read-only, as a second line of defense behind
+    /// query validation. Loaded files still go to writable temporary tables.
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Resolve relative file names in queries against `working_dir` rather than
+    /// the process's working directory.
+    pub fn with_working_dir(mut self, working_dir: Option<PathBuf>) -> Self {
+        self.working_dir = working_dir;
+        self
+    }
+
+    /// Whether the connection is cached for the next query once this one is done.
+    pub fn with_session(mut self, session: SessionReuse) -> Self {
+        self.session = session;
+        self
+    }
+
+    /// Interrupt the running statement when `cancel` fires.
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// A cached connection to the database (see `backend::reuse_connections`), or a new one.
+    fn checkout(&self) -> Result<Session, DbtoonError> {
+        match IDLE.take(&self.key()) {
+            Some(session) => Ok(session),
+            None => Ok(Session {
+                conn: self.open()?,
+                loaded: HashMap::new(),
+            }),
+        }
+    }
+
+    /// The path, and whether it is opened read-only.
+    fn key(&self) -> (String, bool) {
+        (self.path.clone(), self.read_only && self.path != MEMORY_PATH)
+    }
+
+    fn open(&self) -> Result<Connection, DbtoonError> {
+        let mut flags = OpenFlags::SQLITE_OPEN_URI | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+        if self.key().1 {
+            flags |= OpenFlags::SQLITE_OPEN_READ_ONLY;
+        } else {
+            flags |= OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE;
+        }
+        Connection::open_with_flags(&self.path, flags).map_err(|e| DbtoonError::Connection {
+            message: format!("failed to open '{}': {}", self.path, e),
+        })
+    }
+}
+
+impl Backend for SqliteBackend {
+    async fn execute(
+        &self,
+        sql: &str,
+        params: &[QueryParam],
+        limit: Option<usize>,
+        timeout_secs: u64,
+    ) -> Result<QueryResult, DbtoonError> {
+        let (mut result, summary) = self
+            .execute_streaming(sql, params, limit, timeout_secs, QueryResult::default())
+            .await?;
+        result.truncated = summary.truncated;
+        result.rows_affected = summary.rows_affected;
+        Ok(result)
+    }
+
+    async fn execute_streaming<S: ResultSink + 'static>(
+        &self,
+        sql: &str,
+        params: &[QueryParam],
+        limit: Option<usize>,
+        timeout_secs: u64,
+        mut sink: S,
+    ) -> Result<(S, StreamSummary), DbtoonError> {
+        let mut session = self.checkout()?;
+        let key = self.key();
+        let sql = sql.to_string();
+        let params = params.to_vec();
+        let working_dir = self.working_dir.clone();
+        let reuse = self.session;
+        let cancel = self.cancel.clone();
+        let timeout = Duration::from_secs(timeout_secs);
+
+        // rusqlite calls block, so the query runs on the blocking pool; the
+        // progress handler stops it once `cancel` fires or the timeout passes
+        let task = tokio::task::spawn_blocking(move || -> Result<(S, StreamSummary), DbtoonError> {
+            let started = Instant::now();
+            let watched = cancel.clone();
+            session
+                .conn
+                .progress_handler(
+                    PROGRESS_STEPS,
+                    Some(move || watched.is_cancelled() || started.elapsed() > timeout),
+                )
+                .map_err(|e| sqlite_error("failed to set progress handler", e))?;
+
+            let outcome = run_statement(&mut session, working_dir.as_deref(), &sql, &params, limit, &mut sink);
+            let _ = session.conn.progress_handler(0, None::<fn() -> bool>);
+            // An interrupted statement is rolled back, so a shared session is kept either way.
+            // Loaded files stay, since a plain query cannot change their tables.
+            if reuse == SessionReuse::Keep || (reuse == SessionReuse::ReadOnly && outcome.is_ok()) {
+                drop(IDLE.put(key, session));
+            }
+            match outcome {
+                Ok(summary) => Ok((sink, summary)),
+                Err(_) if cancel.is_cancelled() => Err(DbtoonError::Cancelled),
+                Err(_) if started.elapsed() > timeout => Err(DbtoonError::Query {
+                    message: format!("query timed out after {} seconds", timeout.as_secs()),
+                }),
+                Err(e) => Err(e),
+            }
+        });
+
+        task.await.map_err(|e| DbtoonError::Query {
+            message: format!("task join error: {}", e),
+        })?
+    }
+}
+
+/// An open database and the files loaded into its temporary tables.
+struct Session {
+    conn: Connection,
+    /// The file behind each loaded table, by table name, and its modification time when loaded.
+    loaded: HashMap<String, (PathBuf, SystemTime)>,
+}
+
+/// Open databases kept between queries, by path and read-only flag.
+static IDLE: IdleConnections<(String, bool), Session> = IdleConnections::new();
+
+pub(crate) fn close_idle(idle_timeout: Duration) {
+    drop(IDLE.expired(idle_timeout));
+}
+
+pub(crate) fn idle_count() -> usize {
+    IDLE.len()
+}
+
+/// Load referenced files, relative to `working_dir` if set, then run `sql`,
+/// streaming its rows into `sink` in `STREAM_BATCH_SIZE` batches and stopping
+/// after `limit` rows.
+fn run_statement(
+    session: &mut Session,
+    working_dir: Option<&Path>,
+    sql: &str,
+    params: &[QueryParam],
+    limit: Option<usize>,
+    sink: &mut dyn ResultSink,
+) -> Result<StreamSummary, DbtoonError> {
+    for file in file_references(sql) {
+        let path = working_dir.map_or_else(|| PathBuf::from(&file), |dir| dir.join(&file));
+        load_file(session, &file, path)?;
+    }
+    let conn = &session.conn;
+
+    let (sql, bound) = to_numbered(sql, params)?;
+    let mut stmt = conn.prepare(&sql).map_err(|e| sqlite_error("query preparation failed", e))?;
+    for (i, param) in bound.iter().enumerate() {
+        stmt.raw_bind_parameter(i + 1, param_value(param.typed_value()))
+            .map_err(|e| sqlite_error(&format!("failed to bind parameter '{}'", param.name), e))?;
+    }
+
+    if stmt.column_count() == 0 {
+        let changed = stmt.raw_execute().map_err(|e| sqlite_error("query execution failed", e))?;
+        sink.begin(&[])?;
+        return Ok(StreamSummary {
+            rows_affected: Some(changed),
+            ..StreamSummary::default()
+        });
+    }
+
+    let declared = declared_columns(&stmt);
+    let mut rows = stmt.raw_query();
+    let mut columns: Option<Vec<ColumnMeta>> = None;
+    let mut total = 0usize;
+    let mut truncated = false;
+    let mut batch = Vec::with_capacity(STREAM_BATCH_SIZE);
+
+    while let Some(row) = rows.next().map_err(|e| sqlite_error("fetch error", e))? {
+        if limit.is_some_and(|limit| total + batch.len() >= limit) {
+            truncated = true;
+            break;
+        }
+        let values: Vec<ValueRef<'_>> = (0..declared.len())
+            .map(|i| row.get_ref(i))
+            .collect::<Result<_, _>>()
+            .map_err(|e| sqlite_error("fetch error", e))?;
+
+        // Expressions have no declared type, so the first row decides theirs
+        let columns = match &mut columns {
+            Some(columns) => columns,
+            None => {
+                let inferred = infer_columns(&declared, &values);
+                sink.begin(&inferred)?;
+                columns.insert(inferred)
+            }
+        };
+        batch.push(
+            values
+                .into_iter()
+                .zip(columns.iter())
+                .map(|(value, column)| sqlite_cell(value, &column.type_name))
+                .collect(),
+        );
+        if batch.len() == STREAM_BATCH_SIZE {
+            sink.write_batch(&batch)?;
+            total += batch.len();
+            batch.clear();
+        }
+    }
+
+    if columns.is_none() {
+        sink.begin(&infer_columns(&declared, &[]))?;
+    }
+    if !batch.is_empty() {
+        sink.write_batch(&batch)?;
+        total += batch.len();
+    }
+
+    Ok(StreamSummary {
+        rows: total,
+        truncated,
+        ..StreamSummary::default()
+    })
+}
+
+fn sqlite_error(context: &str, e: rusqlite::Error) -> DbtoonError {
+    DbtoonError::Query {
+        message: format!("{}: {}", context, e),
+    }
+}
+
+/// Column names with their normalized declared types, `None` for expressions.
+fn declared_columns(stmt: &Statement<'_>) -> Vec<(String, Option<String>)> {
+    stmt.columns()
+        .iter()
+        .map(|c| (c.name().to_string(), c.decl_type().map(normalize_sqlite_type)))
+        .collect()
+}
+
+fn infer_columns(declared: &[(String, Option<String>)], first_row: &[ValueRef<'_>]) -> Vec<ColumnMeta> {
+    declared
+        .iter()
+        .enumerate()
+        .map(|(i, (name, type_name))| {
+            let type_name = type_name.clone().unwrap_or_else(|| {
+                match first_row.get(i) {
+                    Some(ValueRef::Integer(_)) => "BIGINT",
+                    Some(ValueRef::Real(_)) => "DOUBLE",
+                    Some(ValueRef::Blob(_)) => "VARBINARY",
+                    _ => "VARCHAR",
+                }
+                .to_string()
+            });
+            ColumnMeta { name: name.clone(), type_name }
+        })
+        .collect()
+}
+
+/// Normalize a declared SQLite column type to the SQL type names
+/// `format_columnar::sql_type_to_arrow` understands.
+///
+/// Types dbtoon's own outputs declare (`DECIMAL(p,s)`, `DATE`, `TIMESTAMP`, ...)
+/// are kept; anything else follows SQLite's type affinity rules. Integers are
+/// always `BIGINT`, since SQLite stores every integer in up to 64 bits.
+pub fn normalize_sqlite_type(declared: &str) -> String {
+    let upper = declared.trim().to_uppercase();
+    let base = upper.split('(').next().unwrap_or_default().trim();
+    match base {
+        "BOOLEAN" | "BOOL" => return "BOOLEAN".to_string(),
+        "DECIMAL" | "NUMERIC" if upper.contains('(') => return upper.replace(' ', ""),
+        "DECIMAL" | "NUMERIC" => return "DECIMAL".to_string(),
+        "DATE" => return "DATE".to_string(),
+        "TIME" => return "TIME".to_string(),
+        "DATETIME" | "TIMESTAMP" => return "TIMESTAMP".to_string(),
+        "VARCHAR" | "NVARCHAR" | "CHAR" | "NCHAR" if upper.contains('(') => {
+            return upper.replace(' ', "");
+        }
+        "VARBINARY" | "BINARY" => return "VARBINARY".to_string(),
+        _ => {}
+    }
+    // https://www.sqlite.org/datatype3.html#determination_of_column_affinity
+    if base.contains("INT") {
+        "BIGINT".to_string()
+    } else if base.contains("CHAR") || base.contains("CLOB") || base.contains("TEXT") {
+        "VARCHAR".to_string()
+    } else if base.contains("BLOB") || base.is_empty() {
+        if base.is_empty() { "VARCHAR" } else { "VARBINARY" }.to_string()
+    } else if base.contains("REAL") || base.contains("FLOA") || base.contains("DOUB") {
+        "DOUBLE".to_string()
+    } else {
+        base.to_string()
+    }
+}
+
+/// Convert a SQLite value to a cell of the column's normalized type.
+///
+/// SQLite types values, not columns, so a value that does not fit its
+/// column (text in an integer column, say) keeps its own kind.
+pub fn sqlite_cell(value: ValueRef<'_>, type_name: &str) -> CellValue {
+    match value {
+        ValueRef::Null => CellValue::Null,
+        ValueRef::Integer(i) => match type_name {
+            "BOOLEAN" => CellValue::Bool(i != 0),
+            "DOUBLE" => CellValue::Float64(i as f64),
+            t if t.starts_with("DECIMAL") => CellValue::Decimal { value: i.into(), scale: 0 },
+            _ => CellValue::Int64(i),
+        },
+        ValueRef::Real(f) if type_name.starts_with("DECIMAL") => {
+            CellValue::from_typed_text(&f.to_string(), type_name)
+        }
+        ValueRef::Real(f) => CellValue::Float64(f),
+        ValueRef::Text(bytes) => {
+            CellValue::from_typed_text(&String::from_utf8_lossy(bytes), type_name)
+        }
+        ValueRef::Blob(bytes) => CellValue::Bytes(bytes.to_vec()),
+    }
+}
+
+fn param_value(cell: CellValue) -> Value {
+    match cell {
+        CellValue::Null => Value::Null,
+        CellValue::Int64(i) => Value::Integer(i),
+        CellValue::Float64(f) => Value::Real(f),
+        CellValue::Bool(b) => Value::Integer(b.into()),
+        CellValue::Text(s) => Value::Text(s),
+        CellValue::Bytes(b) => Value::Blob(b),
+        other => Value::Text(other.to_text().unwrap_or_default().into_owned()),
+    }
+}
+
+// --- Files queried as tables ---
+
+/// File extensions loaded when quoted in table position.
+const FILE_EXTENSIONS: &[&str] = &["parquet", "arrow", "feather", "ipc", "csv"];
+
+/// Quoted file names that follow `FROM`, `JOIN` or a comma in `sql`, in order,
+/// without duplicates. Literals elsewhere, comments and identifiers are skipped.
+pub fn file_references(sql: &str) -> Vec<String> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut files: Vec<String> = Vec::new();
+    let mut previous_word = String::new();
+    let mut previous_char = ' ';
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '-' if chars.get(i + 1) == Some(&'-') => {
+                i = (i..chars.len()).find(|&j| chars[j] == '\n').unwrap_or(chars.len());
+            }
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                i = (i + 2..chars.len().saturating_sub(1))
+                    .find(|&j| chars[j] == '*' && chars[j + 1] == '/')
+                    .map_or(chars.len(), |j| j + 2);
+            }
+            '\'' | '"' | '`' | '[' => {
+                let close = if c == '[' { ']' } else { c };
+                let mut text = String::new();
+                let mut j = i + 1;
+                while j < chars.len() {
+                    if chars[j] == close {
+                        if close != ']' && chars.get(j + 1) == Some(&close) {
+                            text.push(close);
+                            j += 2;
+                            continue;
+                        }
+                        break;
+                    }
+                    text.push(chars[j]);
+                    j += 1;
+                }
+                let in_table_position = previous_char == ','
+                    || previous_word.eq_ignore_ascii_case("from")
+                    || previous_word.eq_ignore_ascii_case("join");
+                if c == '\'' && in_table_position && is_data_file(&text) && !files.contains(&text) {
+                    files.push(text);
+                }
+                previous_word.clear();
+                previous_char = close;
+                i = j + 1;
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                previous_word = chars[start..i].iter().collect();
+                previous_char = chars[i - 1];
+            }
+            c if c.is_whitespace() => i += 1,
+            c => {
+                previous_word.clear();
+                previous_char = c;
+                i += 1;
+            }
+        }
+    }
+    files
+}
+
+fn extension(path: &str) -> Option<String> {
+    Path::new(path).extension().map(|e| e.to_string_lossy().to_lowercase())
+}
+
+fn is_data_file(path: &str) -> bool {
+    extension(path).is_some_and(|e| FILE_EXTENSIONS.contains(&e.as_str()))
+}
+
+/// Load the file at `path` into a temporary table named `name`, as quoted in
+/// the query, unless this connection already loaded it and it has not been
+/// modified since.
+fn load_file(session: &mut Session, name: &str, path: PathBuf) -> Result<(), DbtoonError> {
+    let modified = std::fs::metadata(&path)
+        .and_then(|m| m.modified())
+        .map_err(|e| read_error(name, &e))?;
+    let loaded = (path, modified);
+    if session.loaded.get(name) == Some(&loaded) {
+        return Ok(());
+    }
+    let file = File::open(&loaded.0).map_err(|e| read_error(name, &e))?;
+
+    // Rows are inserted as each batch is read, and only a complete load replaces the
+    // table. A savepoint nests inside a transaction the shell's session left open.
+    let tx = session.conn.savepoint().map_err(|e| load_error(name, e))?;
+    match extension(name).as_deref() {
+        Some("parquet") => {
+            let reader = parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(file)
+                .and_then(|builder| builder.build())
+                .map_err(|e| read_error(name, &e))?;
+            load_batches(&tx, name, reader)?;
+        }
+        Some("csv") => load_csv(&tx, name, file)?,
+        _ => {
+            let reader = arrow::ipc::reader::FileReader::try_new(file, None).map_err(|e| read_error(name, &e))?;
+            load_batches(&tx, name, reader)?;
+        }
+    }
+    tx.commit().map_err(|e| load_error(name, e))?;
+
+    // Inside an open transaction a later ROLLBACK can undo the load, so it is not remembered
+    if session.conn.is_autocommit() {
+        session.loaded.insert(name.to_string(), loaded);
+    } else {
+        session.loaded.remove(name);
+    }
+    Ok(())
+}
+
+fn read_error(path: &str, e: &dyn std::fmt::Display) -> DbtoonError {
+    DbtoonError::Query {
+        message: format!("cannot read '{}': {}", path, e),
+    }
+}
+
+fn load_error(path: &str, e: rusqlite::Error) -> DbtoonError {
+    sqlite_error(&format!("cannot load '{}'", path), e)
+}
+
+/// A temporary table being filled from a file, replacing any earlier one of the same name.
+struct TempTable<'conn> {
+    insert: Statement<'conn>,
+}
+
+impl<'conn> TempTable<'conn> {
+    /// `columns` are names with declared types.
+    fn create(conn: &'conn Connection, name: &str, columns: &[(String, String)]) -> rusqlite::Result<Self> {
+        let quoted = quote_identifier(name);
+        let definitions: Vec<String> = columns
+            .iter()
+            .map(|(column, type_name)| format!("{} {}", quote_identifier(column), type_name))
+            .collect();
+        conn.execute_batch(&format!(
+            "DROP TABLE IF EXISTS temp.{quoted}; CREATE TEMP TABLE {quoted} ({});",
+            definitions.join(", ")
+        ))?;
+
+        let markers = vec!["?"; columns.len()].join(", ");
+        let insert = conn.prepare(&format!("INSERT INTO temp.{quoted} VALUES ({markers})"))?;
+        Ok(Self { insert })
+    }
+
+    fn insert<'v>(&mut self, row: impl IntoIterator<Item = &'v Value>) -> rusqlite::Result<()> {
+        self.insert.execute(rusqlite::params_from_iter(row)).map(drop)
+    }
+}
+
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// A CSV file with a header row, as written by dbtoon. Empty fields are NULL,
+/// and columns whose values all parse as integers or numbers get that type.
+///
+/// The file is read twice, first for the column types and then for the rows.
+fn load_csv(conn: &Connection, path: &str, file: File) -> Result<(), DbtoonError> {
+    let failed = |e: csv::Error| read_error(path, &e);
+    let mut reader = csv::Reader::from_reader(&file);
+    let names: Vec<String> = reader.headers().map_err(failed)?.iter().map(str::to_string).collect();
+    let mut record = csv::StringRecord::new();
+
+    let mut types = vec!["BIGINT"; names.len()];
+    while reader.read_record(&mut record).map_err(failed)? {
+        for (type_name, value) in types.iter_mut().zip(record.iter()) {
+            if value.is_empty() {
+                continue;
+            }
+            if *type_name == "BIGINT" && value.parse::<i64>().is_err() {
+                *type_name = "DOUBLE";
+            }
+            if *type_name == "DOUBLE" && value.parse::<f64>().is_err() {
+                *type_name = "VARCHAR";
+            }
+        }
+    }
+
+    let columns: Vec<(String, String)> = names.into_iter().zip(types.iter().map(|t| t.to_string())).collect();
+    let mut table = TempTable::create(conn, path, &columns).map_err(|e| load_error(path, e))?;
+    (&file).rewind().map_err(|e| read_error(path, &e))?;
+    let mut reader = csv::Reader::from_reader(&file);
+    while reader.read_record(&mut record).map_err(failed)? {
+        let row: Vec<Value> = types
+            .iter()
+            .enumerate()
+            .map(|(i, type_name)| match (record.get(i).unwrap_or_default(), *type_name) {
+                ("", _) => Value::Null,
+                (v, "BIGINT") => v.parse().map_or(Value::Null, Value::Integer),
+                (v, "DOUBLE") => v.parse().map_or(Value::Null, Value::Real),
+                (v, _) => Value::Text(v.to_string()),
+            })
+            .collect();
+        table.insert(&row).map_err(|e| load_error(path, e))?;
+    }
+    Ok(())
+}
+
+/// The record batches of a Parquet or Arrow IPC file, inserted as they are read.
+fn load_batches(
+    conn: &Connection,
+    path: &str,
+    reader: impl RecordBatchReader,
+) -> Result<(), DbtoonError> {
+    let columns: Vec<(String, String)> = reader
+        .schema()
+        .fields()
+        .iter()
+        .map(|f| (f.name().clone(), declared_type(f.data_type())))
+        .collect();
+    let mut table = TempTable::create(conn, path, &columns).map_err(|e| load_error(path, e))?;
+
+    for batch in reader {
+        let batch = batch.map_err(|e| read_error(path, &e))?;
+        let converted: Vec<Vec<Value>> = batch
+            .columns()
+            .iter()
+            .map(|array| arrow_values(array.as_ref()))
+            .collect::<Result<_, ArrowError>>()
+            .map_err(|e| read_error(path, &e))?;
+        for row in 0..batch.num_rows() {
+            table
+                .insert(converted.iter().map(|column| &column[row]))
+                .map_err(|e| load_error(path, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Declared type for an Arrow column, chosen so `normalize_sqlite_type` maps it
+/// back to a matching Arrow type.
+fn declared_type(data_type: &DataType) -> String {
+    let name = match data_type {
+        DataType::Boolean => "BOOLEAN",
+        DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Int64 => "BIGINT",
+        DataType::UInt8 | DataType::UInt16 | DataType::UInt32 | DataType::UInt64 => "BIGINT",
+        DataType::Float16 | DataType::Float32 | DataType::Float64 => "DOUBLE",
+        DataType::Decimal128(precision, scale) => return format!("DECIMAL({},{})", precision, scale),
+        DataType::Date32 | DataType::Date64 => "DATE",
+        DataType::Time32(_) | DataType::Time64(_) => "TIME",
+        DataType::Timestamp(..) => "TIMESTAMP",
+        DataType::Binary | DataType::LargeBinary | DataType::FixedSizeBinary(_) => "VARBINARY",
+        _ => "VARCHAR",
+    };
+    name.to_string()
+}
+
+/// Every value of `array` as a SQLite value. Dates and times become ISO 8601
+/// text, decimals their exact text; types without a SQLite counterpart use
+/// Arrow's display format.
+fn arrow_values(array: &dyn Array) -> Result<Vec<Value>, ArrowError> {
+    let len = array.len();
+    let each = |f: &dyn Fn(usize) -> Value| -> Vec<Value> {
+        (0..len).map(|i| if array.is_null(i) { Value::Null } else { f(i) }).collect()
+    };
+    let text = |cell: CellValue| Value::Text(cell.to_text().unwrap_or_default().into_owned());
+
+    let values = match array.data_type() {
+        DataType::Boolean => each(&|i| Value::Integer(array.as_boolean().value(i).into())),
+        DataType::Int8 => each(&|i| Value::Integer(array.as_primitive::<Int8Type>().value(i).into())),
+        DataType::Int16 => each(&|i| Value::Integer(array.as_primitive::<Int16Type>().value(i).into())),
+        DataType::Int32 => each(&|i| Value::Integer(array.as_primitive::<Int32Type>().value(i).into())),
+        DataType::Int64 => each(&|i| Value::Integer(array.as_primitive::<Int64Type>().value(i))),
+        DataType::UInt8 => each(&|i| Value::Integer(array.as_primitive::<UInt8Type>().value(i).into())),
+        DataType::UInt16 => each(&|i| Value::Integer(array.as_primitive::<UInt16Type>().value(i).into())),
+        DataType::UInt32 => each(&|i| Value::Integer(array.as_primitive::<UInt32Type>().value(i).into())),
+        DataType::UInt64 => each(&|i| {
+            let v = array.as_primitive::<UInt64Type>().value(i);
+            i64::try_from(v).map_or_else(|_| Value::Text(v.to_string()), Value::Integer)
+        }),
+        DataType::Float16 => each(&|i| Value::Real(array.as_primitive::<Float16Type>().value(i).to_f64())),
+        DataType::Float32 => each(&|i| param_value(CellValue::from_f32(array.as_primitive::<Float32Type>().value(i)))),
+        DataType::Float64 => each(&|i| Value::Real(array.as_primitive::<Float64Type>().value(i))),
+        DataType::Decimal128(_, scale) => each(&|i| {
+            text(CellValue::Decimal { value: array.as_primitive::<Decimal128Type>().value(i), scale: *scale })
+        }),
+        DataType::Date32 => each(&|i| text(CellValue::Date(array.as_primitive::<Date32Type>().value(i)))),
+        DataType::Date64 => each(&|i| {
+            let millis = array.as_primitive::<Date64Type>().value(i);
+            text(CellValue::Date(millis.div_euclid(86_400_000) as i32))
+        }),
+        DataType::Time32(TimeUnit::Second) => each(&|i| {
+            text(CellValue::Time(i64::from(array.as_primitive::<Time32SecondType>().value(i)) * 1_000_000))
+        }),
+        DataType::Time32(_) => each(&|i| {
+            text(CellValue::Time(i64::from(array.as_primitive::<Time32MillisecondType>().value(i)) * 1_000))
+        }),
+        DataType::Time64(TimeUnit::Nanosecond) => each(&|i| {
+            text(CellValue::Time(array.as_primitive::<Time64NanosecondType>().value(i) / 1_000))
+        }),
+        DataType::Time64(_) => each(&|i| {
+            text(CellValue::Time(array.as_primitive::<Time64MicrosecondType>().value(i)))
+        }),
+        DataType::Timestamp(unit, _) => each(&|i| {
+            let micros = match unit {
+                TimeUnit::Second => array.as_primitive::<TimestampSecondType>().value(i) * 1_000_000,
+                TimeUnit::Millisecond => array.as_primitive::<TimestampMillisecondType>().value(i) * 1_000,
+                TimeUnit::Microsecond => array.as_primitive::<TimestampMicrosecondType>().value(i),
+                TimeUnit::Nanosecond => array.as_primitive::<TimestampNanosecondType>().value(i) / 1_000,
+            };
+            text(CellValue::Timestamp(micros))
+        }),
+        DataType::Utf8 => each(&|i| Value::Text(array.as_string::<i32>().value(i).to_string())),
+        DataType::LargeUtf8 => each(&|i| Value::Text(array.as_string::<i64>().value(i).to_string())),
+        DataType::Utf8View => each(&|i| Value::Text(array.as_string_view().value(i).to_string())),
+        DataType::Binary => each(&|i| Value::Blob(array.as_binary::<i32>().value(i).to_vec())),
+        DataType::LargeBinary => each(&|i| Value::Blob(array.as_binary::<i64>().value(i).to_vec())),
+        DataType::FixedSizeBinary(_) => each(&|i| Value::Blob(array.as_fixed_size_binary().value(i).to_vec())),
+        _ => {
+            let formatter = ArrayFormatter::try_new(array, &Default::default())?;
+            each(&|i| Value::Text(formatter.value(i).to_string()))
+        }
+    };
+    Ok(values)
+}
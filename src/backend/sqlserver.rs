@@ -1,55 +1,23 @@
-use crate::backend::{Backend, CellValue, ColumnMeta, QueryResult};
-use crate::config::SqlServerAuth;
+use crate::backend::odbc::{self, ConnectTarget, PreConnectAttribute};
+use crate::backend::{Backend, QueryResult, ResultSink, SessionReuse, StreamSummary};
+use crate::cancel::CancelToken;
+use crate::config::{SqlServerAuth, SqlServerOptions};
 use crate::error::DbtoonError;
-use odbc_api::buffers::{BufferDesc, ColumnarAnyBuffer};
-use odbc_api::{
-    ColumnDescription, ConnectionOptions, Cursor, DataType, Environment, Nullability,
-    ResultSetMetadata,
-};
-
-/// Normalize an ODBC `DataType` enum value to a standard SQL type string.
-pub fn normalize_odbc_type(data_type: &DataType) -> String {
-    match data_type {
-        DataType::Unknown => "UNKNOWN".to_string(),
-        DataType::Char { length: Some(n) } => format!("CHAR({})", n),
-        DataType::Char { length: None } => "CHAR".to_string(),
-        DataType::WChar { length: Some(n) } => format!("NCHAR({})", n),
-        DataType::WChar { length: None } => "NCHAR".to_string(),
-        DataType::Varchar { length: Some(n) } => format!("VARCHAR({})", n),
-        DataType::Varchar { length: None } => "VARCHAR(MAX)".to_string(),
-        DataType::WVarchar { length: Some(n) } => format!("NVARCHAR({})", n),
-        DataType::WVarchar { length: None } => "NVARCHAR(MAX)".to_string(),
-        DataType::LongVarchar { .. } => "VARCHAR(MAX)".to_string(),
-        DataType::WLongVarchar { .. } => "NVARCHAR(MAX)".to_string(),
-        DataType::Integer => "INT".to_string(),
-        DataType::SmallInt => "SMALLINT".to_string(),
-        DataType::BigInt => "BIGINT".to_string(),
-        DataType::TinyInt => "TINYINT".to_string(),
-        DataType::Float { precision } => format!("FLOAT({})", precision),
-        DataType::Real => "REAL".to_string(),
-        DataType::Double => "FLOAT".to_string(),
-        DataType::Numeric { precision, scale } => format!("NUMERIC({},{})", precision, scale),
-        DataType::Decimal { precision, scale } => format!("DECIMAL({},{})", precision, scale),
-        DataType::Date => "DATE".to_string(),
-        DataType::Time { precision } => format!("TIME({})", precision),
-        DataType::Timestamp { precision } => format!("DATETIME2({})", precision),
-        DataType::Bit => "BIT".to_string(),
-        DataType::Binary { length: Some(n) } => format!("BINARY({})", n),
-        DataType::Binary { length: None } => "BINARY".to_string(),
-        DataType::Varbinary { length: Some(n) } => format!("VARBINARY({})", n),
-        DataType::Varbinary { length: None } => "VARBINARY(MAX)".to_string(),
-        DataType::LongVarbinary { .. } => "VARBINARY(MAX)".to_string(),
-        DataType::Other { .. } => "UNKNOWN".to_string(),
-    }
-}
+use crate::params::QueryParam;
 
 pub struct SqlServerBackend {
     server: String,
     database: Option<String>,
     auth: SqlServerAuth,
     trust_server_certificate: bool,
+    options: SqlServerOptions,
+    session: SessionReuse,
+    cancel: CancelToken,
 }
 
+/// Driver used when the profile does not name one.
+pub const DEFAULT_DRIVER: &str = "ODBC Driver 18 for SQL Server";
+
 impl SqlServerBackend {
     pub fn new(
         server: String,
@@ -62,17 +30,54 @@ impl SqlServerBackend {
             database,
             auth,
             trust_server_certificate,
+            options: SqlServerOptions::default(),
+            session: SessionReuse::default(),
+            cancel: CancelToken::default(),
         }
     }
 
-    fn connection_string(&self) -> String {
+    /// Use `options` for the driver, server address and extra connection attributes.
+    pub fn with_options(mut self, options: SqlServerOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Whether the connection is cached for the next query once this one is done.
+    pub fn with_session(mut self, session: SessionReuse) -> Self {
+        self.session = session;
+        self
+    }
+
+    /// Cancel the running statement with `SQLCancel` when `cancel` fires.
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// ODBC connection string for this server and credentials.
+    ///
+    /// Access-token auth adds nothing here; the token is set as a connection
+    /// attribute instead (see `access_token_attribute`).
+    pub fn connection_string(&self) -> String {
+        use secrecy::ExposeSecret;
+
+        let options = &self.options;
+        let driver = options.driver.as_deref().unwrap_or(DEFAULT_DRIVER);
+        let mut server = self.server.clone();
+        if let Some(ref instance) = options.instance {
+            server = format!("{}\\{}", server, instance);
+        }
+        if let Some(port) = options.port {
+            server = format!("{},{}", server, port);
+        }
+
         let mut parts = vec![
-            "Driver={ODBC Driver 18 for SQL Server}".to_string(),
-            format!("Server={}", self.server),
+            format!("Driver={{{}}}", driver.trim_start_matches('{').trim_end_matches('}')),
+            attribute("Server", &server),
         ];
 
         if let Some(ref db) = self.database {
-            parts.push(format!("Database={}", db));
+            parts.push(attribute("Database", db));
         }
 
         match &self.auth {
@@ -80,159 +85,115 @@ impl SqlServerBackend {
                 parts.push("Trusted_Connection=yes".to_string());
             }
             SqlServerAuth::SqlLogin { username, password } => {
-                use secrecy::ExposeSecret;
-                parts.push(format!("UID={}", username));
-                parts.push(format!(
-                    "PWD={}",
-                    odbc_api::escape_attribute_value(password.expose_secret())
-                ));
+                parts.push(attribute("UID", username));
+                parts.push(attribute("PWD", password.expose_secret()));
+            }
+            SqlServerAuth::ActiveDirectoryServicePrincipal {
+                client_id,
+                client_secret,
+            } => {
+                parts.push("Authentication=ActiveDirectoryServicePrincipal".to_string());
+                parts.push(attribute("UID", client_id));
+                parts.push(attribute("PWD", client_secret.expose_secret()));
+            }
+            SqlServerAuth::ActiveDirectoryPassword { username, password } => {
+                parts.push("Authentication=ActiveDirectoryPassword".to_string());
+                parts.push(attribute("UID", username));
+                parts.push(attribute("PWD", password.expose_secret()));
+            }
+            SqlServerAuth::ActiveDirectoryInteractive { username } => {
+                parts.push("Authentication=ActiveDirectoryInteractive".to_string());
+                if let Some(username) = username {
+                    parts.push(attribute("UID", username));
+                }
             }
+            SqlServerAuth::ActiveDirectoryDefault => {
+                parts.push("Authentication=ActiveDirectoryDefault".to_string());
+            }
+            SqlServerAuth::AccessToken(_) => {}
         }
 
+        if let Some(ref encrypt) = options.encrypt {
+            parts.push(attribute("Encrypt", encrypt));
+        }
         if self.trust_server_certificate {
             parts.push("TrustServerCertificate=yes".to_string());
         }
+        if let Some(ref intent) = options.application_intent {
+            parts.push(attribute("ApplicationIntent", intent));
+        }
+        for (key, value) in &options.extra_attributes {
+            parts.push(attribute(key, value));
+        }
 
         parts.join(";") + ";"
     }
+
+    fn connect_target(&self) -> ConnectTarget {
+        use secrecy::ExposeSecret;
+
+        let attributes = match &self.auth {
+            SqlServerAuth::AccessToken(token) => vec![PreConnectAttribute {
+                attribute: SQL_COPT_SS_ACCESS_TOKEN,
+                value: access_token_attribute(token.expose_secret()),
+                description: "access token",
+            }],
+            _ => Vec::new(),
+        };
+        ConnectTarget {
+            connection_string: self.connection_string(),
+            attributes,
+        }
+    }
+}
+
+/// One `key=value` pair of a connection string, with the value braced if it
+/// contains a separator.
+fn attribute(key: &str, value: &str) -> String {
+    format!("{}={}", key, odbc_api::escape_attribute_value(value))
+}
+
+/// Driver-specific connection attribute taking an Entra ID access token.
+const SQL_COPT_SS_ACCESS_TOKEN: i32 = 1256;
+
+/// The `SQL_COPT_SS_ACCESS_TOKEN` value for `token`: a little-endian byte
+/// count followed by the token with each byte widened to UTF-16LE.
+pub fn access_token_attribute(token: &str) -> Vec<u8> {
+    let data: Vec<u8> = token.bytes().flat_map(|b| [b, 0]).collect();
+    let mut value = Vec::with_capacity(4 + data.len());
+    value.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    value.extend_from_slice(&data);
+    value
 }
 
 impl Backend for SqlServerBackend {
     async fn execute(
         &self,
         sql: &str,
+        params: &[QueryParam],
         limit: Option<usize>,
         timeout_secs: u64,
     ) -> Result<QueryResult, DbtoonError> {
-        let conn_str = self.connection_string();
-
-        // odbc-api Environment and connection are not Send, so we run in spawn_blocking
-        let sql = sql.to_string();
-        let result = tokio::task::spawn_blocking(move || -> Result<QueryResult, DbtoonError> {
-            let env = Environment::new().map_err(|e| DbtoonError::Connection {
-                message: format!("ODBC environment error: {}", e),
-            })?;
-
-            let conn = env
-                .connect_with_connection_string(
-                    &conn_str,
-                    ConnectionOptions {
-                        login_timeout_sec: Some(30),
-                        ..Default::default()
-                    },
-                )
-                .map_err(|e| DbtoonError::Connection {
-                    message: format!("connection failed: {}", e),
-                })?;
-
-            let cursor = conn
-                .execute(&sql, (), Some(timeout_secs as usize))
-                .map_err(|e| DbtoonError::Query {
-                    message: format!("query execution failed: {}", e),
-                })?;
-
-            let Some(mut cursor) = cursor else {
-                return Ok(QueryResult {
-                    columns: vec![],
-                    rows: vec![],
-                    total_rows: None,
-                    truncated: false,
-                });
-            };
-
-            // Extract column metadata
-            let num_cols = cursor.num_result_cols().map_err(|e| DbtoonError::Query {
-                message: format!("failed to get column count: {}", e),
-            })? as usize;
-
-            let mut columns = Vec::with_capacity(num_cols);
-            let mut buffer_descs = Vec::with_capacity(num_cols);
-
-            for i in 1..=num_cols as u16 {
-                let mut col_desc = ColumnDescription::default();
-                cursor
-                    .describe_col(i, &mut col_desc)
-                    .map_err(|e| DbtoonError::Query {
-                        message: format!("failed to describe column {}: {}", i, e),
-                    })?;
-
-                let name = col_desc.name_to_string().map_err(|e| DbtoonError::Query {
-                    message: format!("failed to decode column name {}: {}", i, e),
-                })?;
-
-                columns.push(ColumnMeta {
-                    name,
-                    type_name: normalize_odbc_type(&col_desc.data_type),
-                });
-
-                let nullable = col_desc.nullability != Nullability::NoNulls;
-                let desc = BufferDesc::from_data_type(col_desc.data_type, nullable)
-                    .unwrap_or(BufferDesc::Text { max_str_len: 255 });
-                buffer_descs.push(desc);
-            }
-
-            let batch_size = 5000;
-            let buffer = ColumnarAnyBuffer::try_from_descs(batch_size, buffer_descs)
-                .map_err(|e| DbtoonError::Query {
-                    message: format!("failed to create buffer: {}", e),
-                })?;
-
-            let mut row_set_cursor =
-                cursor.bind_buffer(buffer).map_err(|e| DbtoonError::Query {
-                    message: format!("failed to bind buffer: {}", e),
-                })?;
-
-            let mut rows: Vec<Vec<CellValue>> = Vec::new();
-            let mut truncated = false;
-
-            while let Some(batch) = row_set_cursor.fetch().map_err(|e| DbtoonError::Query {
-                message: format!("fetch error: {}", e),
-            })? {
-                let num_rows_in_batch = batch.num_rows();
-                for row_idx in 0..num_rows_in_batch {
-                    if let Some(lim) = limit
-                        && rows.len() >= lim {
-                            truncated = true;
-                            break;
-                        }
-
-                    let mut row = Vec::with_capacity(num_cols);
-                    for col_idx in 0..num_cols {
-                        let col = batch.column(col_idx);
-                        let text_col = col.as_text_view();
-                        match text_col {
-                            Some(text_view) => match text_view.get(row_idx) {
-                                Some(bytes) => {
-                                    let s = String::from_utf8_lossy(bytes).to_string();
-                                    row.push(CellValue::Text(s));
-                                }
-                                None => row.push(CellValue::Null),
-                            },
-                            None => {
-                                row.push(CellValue::Null);
-                            }
-                        }
-                    }
-                    rows.push(row);
-                }
-
-                if truncated {
-                    break;
-                }
-            }
+        let (mut result, summary) = self
+            .execute_streaming(sql, params, limit, timeout_secs, QueryResult::default())
+            .await?;
+        result.truncated = summary.truncated;
+        result.messages = summary.messages;
+        result.rows_affected = summary.rows_affected;
+        Ok(result)
+    }
 
-            Ok(QueryResult {
-                columns,
-                rows,
-                total_rows: None,
-                truncated,
-            })
-        })
+    async fn execute_streaming<S: ResultSink + 'static>(
+        &self,
+        sql: &str,
+        params: &[QueryParam],
+        limit: Option<usize>,
+        timeout_secs: u64,
+        sink: S,
+    ) -> Result<(S, StreamSummary), DbtoonError> {
+        odbc::execute_streaming(
+            self.connect_target(), self.session, &self.cancel, sql, params, limit, timeout_secs, sink,
+        )
         .await
-        .map_err(|e| DbtoonError::Query {
-            message: format!("task join error: {}", e),
-        })??;
-
-        Ok(result)
     }
 }
@@ -0,0 +1,493 @@
+//! Trino (and Presto) backend over the HTTP client protocol (`/v1/statement`).
+
+use std::time::Instant;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use reqwest::{Client, RequestBuilder, Response};
+use secrecy::ExposeSecret;
+use serde::Deserialize;
+
+use crate::backend::databricks::host_url;
+use crate::backend::retry::{Idempotency, RetryPolicy, send_with_retry};
+use crate::backend::value::{parse_date32, parse_decimal, parse_time_micros, parse_timestamp_micros};
+use crate::backend::{
+    Backend, CellValue, ColumnMeta, QueryResult, ResultSink, StreamSummary, http_client,
+};
+use crate::cancel::CancelToken;
+use crate::config::TrinoAuth;
+use crate::error::DbtoonError;
+use crate::params::{QueryParam, to_positional};
+
+/// Name under which parameterized statements are prepared for `EXECUTE ... USING`.
+const PREPARED_NAME: &str = "dbtoon_statement";
+
+pub struct TrinoBackend {
+    host: String,
+    port: Option<u16>,
+    user: String,
+    auth: TrinoAuth,
+    catalog: Option<String>,
+    schema: Option<String>,
+    /// Send `X-Presto-*` headers instead of `X-Trino-*`, for Presto coordinators.
+    presto_headers: bool,
+    cancel: CancelToken,
+    retry: RetryPolicy,
+    verbose: bool,
+    client: Client,
+}
+
+/// One page of query results; every page names the next one until the query ends.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QueryResults {
+    next_uri: Option<String>,
+    columns: Option<Vec<Column>>,
+    data: Option<Vec<Vec<serde_json::Value>>>,
+    error: Option<QueryError>,
+    update_count: Option<u64>,
+    #[serde(default)]
+    warnings: Vec<Warning>,
+}
+
+#[derive(Deserialize)]
+struct Column {
+    name: String,
+    #[serde(rename = "type")]
+    type_name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QueryError {
+    message: Option<String>,
+    error_name: Option<String>,
+}
+
+impl QueryError {
+    fn format_message(self) -> String {
+        match (self.error_name, self.message) {
+            (Some(name), Some(msg)) => format!("[{}] {}", name, msg),
+            (Some(name), None) => format!("[{}]", name),
+            (None, Some(msg)) => msg,
+            (None, None) => "unknown error".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Warning {
+    message: Option<String>,
+}
+
+impl TrinoBackend {
+    pub fn new(
+        host: String,
+        port: Option<u16>,
+        user: String,
+        auth: TrinoAuth,
+        catalog: Option<String>,
+        schema: Option<String>,
+    ) -> Self {
+        Self {
+            host,
+            port,
+            user,
+            auth,
+            catalog,
+            schema,
+            presto_headers: false,
+            cancel: CancelToken::default(),
+            retry: RetryPolicy::default(),
+            verbose: false,
+            client: http_client(),
+        }
+    }
+
+    /// Speak to a Presto coordinator, which expects `X-Presto-*` protocol headers.
+    pub fn with_presto_headers(mut self, presto_headers: bool) -> Self {
+        self.presto_headers = presto_headers;
+        self
+    }
+
+    /// The protocol header `X-Trino-<name>`, or `X-Presto-<name>` for Presto.
+    fn header(&self, name: &str) -> String {
+        let product = if self.presto_headers { "Presto" } else { "Trino" };
+        format!("X-{}-{}", product, name)
+    }
+
+    /// Retry transient HTTP failures under `retry`.
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Log retries to stderr.
+    pub fn with_verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    /// Cancel the query on the coordinator when `cancel` fires.
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// `request` with the user and credentials every protocol request carries.
+    fn authorized(&self, request: RequestBuilder) -> RequestBuilder {
+        let request = request
+            .header(self.header("User"), &self.user)
+            .header("User-Agent", concat!("dbtoon/", env!("CARGO_PKG_VERSION")));
+        match &self.auth {
+            TrinoAuth::None => request,
+            TrinoAuth::Password(password) => request.header(
+                "Authorization",
+                format!(
+                    "Basic {}",
+                    STANDARD.encode(format!("{}:{}", self.user, password.expose_secret()))
+                ),
+            ),
+            TrinoAuth::Jwt(token) => request.bearer_auth(token.expose_secret()),
+        }
+    }
+
+    /// Submit the statement and return the first page of its results.
+    async fn submit(
+        &self,
+        sql: &str,
+        params: &[QueryParam],
+        timeout_secs: u64,
+    ) -> Result<QueryResults, DbtoonError> {
+        let url = format!("{}/v1/statement", server_url(&self.host, self.port));
+        let (body, prepared) = statement_text(sql, params)?;
+
+        // A submission that reached the coordinator may have run, so only retry rejections
+        let resp = send_with_retry(&self.retry, Idempotency::NotIdempotent, self.verbose, "statement submit", || {
+            let mut request = self
+                .authorized(self.client.post(&url))
+                .header(self.header("Source"), "dbtoon")
+                .header(self.header("Session"), format!("query_max_run_time={}s", timeout_secs))
+                .body(body.clone());
+            if let Some(catalog) = &self.catalog {
+                request = request.header(self.header("Catalog"), catalog);
+            }
+            if let Some(schema) = &self.schema {
+                request = request.header(self.header("Schema"), schema);
+            }
+            if let Some(prepared) = &prepared {
+                request = request.header(self.header("Prepared-Statement"), prepared);
+            }
+            request
+        })
+        .await
+        .map_err(|e| DbtoonError::Connection {
+            message: format!("failed to execute statement: {}", e),
+        })?;
+
+        read_page(resp).await
+    }
+
+    /// Follow a `nextUri`, which long-polls until the query has progressed.
+    async fn fetch_page(&self, next_uri: &str) -> Result<QueryResults, DbtoonError> {
+        let resp = send_with_retry(&self.retry, Idempotency::Idempotent, self.verbose, "result page fetch", || {
+            self.authorized(self.client.get(next_uri))
+        })
+        .await
+        .map_err(|e| DbtoonError::Connection {
+            message: format!("failed to fetch result page: {}", e),
+        })?;
+        read_page(resp).await
+    }
+
+    /// Ask the coordinator to stop the query behind `next_uri`. Best effort: failures are ignored.
+    async fn cancel_query(&self, next_uri: &str) {
+        let _ = self.authorized(self.client.delete(next_uri)).send().await;
+    }
+
+    /// Feed the query's pages to `sink` until the last one, cancelling the rest
+    /// of the query once `limit` rows have been written and another row arrives.
+    async fn stream_pages(
+        &self,
+        mut page: QueryResults,
+        limit: Option<usize>,
+        timeout_secs: u64,
+        sink: &mut dyn ResultSink,
+    ) -> Result<StreamSummary, DbtoonError> {
+        let start = Instant::now();
+        let mut columns: Option<Vec<ColumnMeta>> = None;
+        let mut summary = StreamSummary::default();
+
+        loop {
+            if let Some(error) = page.error {
+                return Err(DbtoonError::Query {
+                    message: error.format_message(),
+                });
+            }
+            // Each page repeats the warnings raised so far
+            for message in page.warnings.into_iter().filter_map(|w| w.message) {
+                if !summary.messages.contains(&message) {
+                    summary.messages.push(message);
+                }
+            }
+
+            if columns.is_none()
+                && let Some(cols) = page.columns
+            {
+                let meta: Vec<ColumnMeta> = cols
+                    .into_iter()
+                    .map(|c| ColumnMeta {
+                        name: c.name,
+                        type_name: normalize_trino_type(&c.type_name),
+                    })
+                    .collect();
+                sink.begin(&meta)?;
+                columns = Some(meta);
+            }
+
+            let cols = columns.as_deref().unwrap_or_default();
+            let mut rows: Vec<Vec<CellValue>> = page
+                .data
+                .unwrap_or_default()
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .enumerate()
+                        .map(|(i, cell)| match cols.get(i) {
+                            Some(col) => trino_cell(cell, &col.type_name),
+                            None => trino_cell(cell, ""),
+                        })
+                        .collect()
+                })
+                .collect();
+
+            if let Some(lim) = limit {
+                let remaining = lim.saturating_sub(summary.rows);
+                if rows.len() > remaining {
+                    rows.truncate(remaining);
+                    summary.truncated = true;
+                }
+            }
+
+            if !rows.is_empty() {
+                sink.write_batch(&rows)?;
+                summary.rows += rows.len();
+            }
+
+            if let Some(count) = page.update_count {
+                summary.rows_affected = usize::try_from(count).ok();
+            }
+
+            let Some(next_uri) = page.next_uri else {
+                break;
+            };
+            if summary.truncated {
+                self.cancel_query(&next_uri).await;
+                break;
+            }
+            if start.elapsed().as_secs() >= timeout_secs {
+                self.cancel_query(&next_uri).await;
+                return Err(DbtoonError::Timeout {
+                    seconds: timeout_secs,
+                });
+            }
+
+            page = tokio::select! {
+                page = self.fetch_page(&next_uri) => page?,
+                _ = self.cancel.cancelled() => {
+                    self.cancel_query(&next_uri).await;
+                    return Err(DbtoonError::Cancelled);
+                }
+            };
+        }
+
+        if columns.is_none() {
+            sink.begin(&[])?;
+        }
+        Ok(summary)
+    }
+}
+
+impl Backend for TrinoBackend {
+    async fn execute(
+        &self,
+        sql: &str,
+        params: &[QueryParam],
+        limit: Option<usize>,
+        timeout_secs: u64,
+    ) -> Result<QueryResult, DbtoonError> {
+        let (mut result, summary) = self
+            .execute_streaming(sql, params, limit, timeout_secs, QueryResult::default())
+            .await?;
+        result.truncated = summary.truncated;
+        result.messages = summary.messages;
+        result.rows_affected = summary.rows_affected;
+        Ok(result)
+    }
+
+    async fn execute_streaming<S: ResultSink + 'static>(
+        &self,
+        sql: &str,
+        params: &[QueryParam],
+        limit: Option<usize>,
+        timeout_secs: u64,
+        mut sink: S,
+    ) -> Result<(S, StreamSummary), DbtoonError> {
+        let page = tokio::select! {
+            page = self.submit(sql, params, timeout_secs) => page?,
+            _ = self.cancel.cancelled() => return Err(DbtoonError::Cancelled),
+        };
+        let summary = self.stream_pages(page, limit, timeout_secs, &mut sink).await?;
+        Ok((sink, summary))
+    }
+}
+
+/// Base URL of the coordinator. A bare hostname gets `https://`; `port`, when
+/// set, replaces any port in `host`.
+pub(crate) fn server_url(host: &str, port: Option<u16>) -> String {
+    let base = host_url(host);
+    let Some(port) = port else {
+        return base;
+    };
+    let (scheme, authority) = base.split_once("://").unwrap_or(("https", &base));
+    let hostname = match authority.rsplit_once(':') {
+        Some((name, p)) if p.bytes().all(|b| b.is_ascii_digit()) => name,
+        _ => authority,
+    };
+    format!("{}://{}:{}", scheme, hostname, port)
+}
+
+/// Parse a protocol response, mapping HTTP error statuses to errors.
+async fn read_page(resp: Response) -> Result<QueryResults, DbtoonError> {
+    let status = resp.status().as_u16();
+    if !resp.status().is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        let detail = body.trim();
+        return Err(match status {
+            401 => DbtoonError::Auth {
+                message: "authentication failed (check user, password or token)".to_string(),
+            },
+            403 => DbtoonError::Auth {
+                message: if detail.is_empty() { "access denied".to_string() } else { detail.to_string() },
+            },
+            404 => DbtoonError::Config {
+                message: "statement endpoint not found (is this a Trino coordinator?)".to_string(),
+            },
+            _ if detail.is_empty() => DbtoonError::Connection {
+                message: format!("HTTP error: {}", status),
+            },
+            _ => DbtoonError::Connection {
+                message: format!("HTTP error: {}: {}", status, detail),
+            },
+        });
+    }
+    resp.json().await.map_err(|e| DbtoonError::Query {
+        message: format!("failed to parse response: {}", e),
+    })
+}
+
+/// The statement to send, and the `X-Trino-Prepared-Statement` header value
+/// when `params` are bound.
+///
+/// The protocol has no bind values, so a parameterized statement is prepared
+/// under a fixed name (with `:name` markers rewritten to `?`) and run as
+/// `EXECUTE ... USING` with the values as literals, as Trino's JDBC driver does.
+fn statement_text(sql: &str, params: &[QueryParam]) -> Result<(String, Option<String>), DbtoonError> {
+    if params.is_empty() {
+        return Ok((sql.to_string(), None));
+    }
+    let (prepared, bound) = to_positional(sql, params)?;
+    let values = bound.iter().map(|p| literal(p)).collect::<Vec<_>>().join(", ");
+    let header = format!(
+        "{}={}",
+        PREPARED_NAME,
+        url::form_urlencoded::byte_serialize(prepared.as_bytes()).collect::<String>()
+    );
+    Ok((format!("EXECUTE {} USING {}", PREPARED_NAME, values), Some(header)))
+}
+
+/// A SQL literal for a parameter value according to its type; untyped values are strings.
+fn literal(param: &QueryParam) -> String {
+    let quoted = || format!("'{}'", param.value.replace('\'', "''"));
+    let base = param
+        .type_name
+        .as_deref()
+        .and_then(|t| t.split('(').next())
+        .map(|t| t.trim().to_uppercase());
+    match (base.as_deref(), param.typed_value()) {
+        (_, CellValue::Int64(n)) => n.to_string(),
+        (_, CellValue::Bool(b)) => b.to_string(),
+        (Some("DECIMAL" | "NUMERIC"), _) => format!("DECIMAL {}", quoted()),
+        (Some("FLOAT" | "DOUBLE"), _) => format!("DOUBLE {}", quoted()),
+        (Some("REAL"), _) => format!("REAL {}", quoted()),
+        (Some("DATE"), _) => format!("DATE {}", quoted()),
+        (Some("TIME"), _) => format!("TIME {}", quoted()),
+        (Some("TIMESTAMP" | "TIMESTAMP_NTZ" | "DATETIME2"), _) => {
+            format!("TIMESTAMP '{}'", param.value.replace('T', " ").replace('\'', "''"))
+        }
+        _ => quoted(),
+    }
+}
+
+/// Normalize a Trino column type to the SQL type names
+/// `format_columnar::sql_type_to_arrow` understands.
+///
+/// `tinyint` widens to `SMALLINT` (it is signed in Trino). Types with a time
+/// zone, and types without a columnar counterpart (`json`, `uuid`, `array(...)`,
+/// `row(...)`, ...), keep their upper-case Trino name and are shown as text.
+pub fn normalize_trino_type(type_name: &str) -> String {
+    let lower = type_name.trim().to_lowercase();
+    if lower.contains("with time zone") {
+        return lower.to_uppercase();
+    }
+    let base = lower.split('(').next().unwrap_or_default().trim();
+    match base {
+        "boolean" => "BOOLEAN".to_string(),
+        "tinyint" | "smallint" => "SMALLINT".to_string(),
+        "integer" => "INT".to_string(),
+        "bigint" => "BIGINT".to_string(),
+        "real" => "REAL".to_string(),
+        "double" => "DOUBLE".to_string(),
+        "decimal" | "varchar" | "char" => lower.to_uppercase(),
+        "varbinary" => "VARBINARY".to_string(),
+        "date" => "DATE".to_string(),
+        "time" => "TIME".to_string(),
+        "timestamp" => "TIMESTAMP".to_string(),
+        _ => lower.to_uppercase(),
+    }
+}
+
+/// Decode one JSON result value, given its normalized column type.
+///
+/// Integers, floats and booleans arrive as JSON scalars; decimals, dates and
+/// times as strings; `varbinary` as base64. Arrays, maps and rows become JSON
+/// text, and anything that does not parse as its type stays `Text`.
+pub fn trino_cell(value: &serde_json::Value, type_name: &str) -> CellValue {
+    use serde_json::Value;
+
+    let base = type_name.split('(').next().unwrap_or_default();
+    match value {
+        Value::Null => CellValue::Null,
+        Value::Bool(b) => CellValue::Bool(*b),
+        Value::Number(n) => match (base, n.as_i64()) {
+            ("SMALLINT" | "INT" | "BIGINT", Some(i)) => CellValue::Int64(i),
+            ("REAL" | "DOUBLE", _) => n.as_f64().map_or_else(|| CellValue::Text(n.to_string()), CellValue::Float64),
+            _ => CellValue::Text(n.to_string()),
+        },
+        Value::String(s) => {
+            let parsed = match base {
+                // NaN and infinities arrive as strings
+                "REAL" | "DOUBLE" => s.parse().ok().map(CellValue::Float64),
+                "DECIMAL" => parse_decimal(s).map(|(value, scale)| CellValue::Decimal { value, scale }),
+                "DATE" => parse_date32(s).map(CellValue::Date),
+                "TIME" => parse_time_micros(s).map(CellValue::Time),
+                "TIMESTAMP" => parse_timestamp_micros(s).map(CellValue::Timestamp),
+                "VARBINARY" => STANDARD.decode(s).ok().map(CellValue::Bytes),
+                _ => None,
+            };
+            parsed.unwrap_or_else(|| CellValue::Text(s.clone()))
+        }
+        Value::Array(_) | Value::Object(_) => CellValue::Text(value.to_string()),
+    }
+}
+
@@ -0,0 +1,261 @@
+use std::borrow::Cow;
+
+/// A single cell value from a query result.
+///
+/// Backends produce native variants wherever the driver or API reports the
+/// column type; `Text` carries everything else verbatim.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellValue {
+    Text(String),
+    Int64(i64),
+    Float64(f64),
+    /// Fixed-point number equal to `value * 10^-scale`.
+    Decimal { value: i128, scale: i8 },
+    Bool(bool),
+    /// Days since 1970-01-01.
+    Date(i32),
+    /// Microseconds since midnight.
+    Time(i64),
+    /// Microseconds since 1970-01-01T00:00:00, without a time zone.
+    Timestamp(i64),
+    Bytes(Vec<u8>),
+    Null,
+}
+
+impl CellValue {
+    /// Parse a textual value into the native variant for a SQL `type_name`.
+    ///
+    /// Unknown types, and values that do not parse as their declared type, stay `Text`.
+    pub fn from_typed_text(text: &str, type_name: &str) -> CellValue {
+        let normalized = type_name.trim().to_uppercase();
+        let base = normalized.split('(').next().unwrap_or_default();
+
+        let parsed = match base {
+            "TINYINT" | "BYTE" | "SMALLINT" | "SHORT" | "INT" | "INTEGER" | "BIGINT" | "LONG" => {
+                text.trim().parse().ok().map(CellValue::Int64)
+            }
+            "REAL" | "FLOAT" | "DOUBLE" => text.trim().parse().ok().map(CellValue::Float64),
+            "DECIMAL" | "NUMERIC" => {
+                parse_decimal(text).map(|(value, scale)| CellValue::Decimal { value, scale })
+            }
+            "BIT" | "BOOLEAN" => parse_bool(text).map(CellValue::Bool),
+            "DATE" => parse_date32(text.trim()).map(CellValue::Date),
+            "TIME" => parse_time_micros(text.trim()).map(CellValue::Time),
+            "DATETIME2" | "TIMESTAMP" | "TIMESTAMP_NTZ" => {
+                parse_timestamp_micros(text).map(CellValue::Timestamp)
+            }
+            _ => None,
+        };
+
+        parsed.unwrap_or_else(|| CellValue::Text(text.to_string()))
+    }
+
+    /// A `Float64` widened from `f` via its shortest decimal form, so `0.1f32`
+    /// stays 0.1 rather than becoming 0.10000000149011612.
+    pub fn from_f32(f: f32) -> CellValue {
+        CellValue::Float64(f.to_string().parse().unwrap_or(f64::from(f)))
+    }
+
+    /// The value as text, or `None` for NULL.
+    ///
+    /// Dates and times use ISO 8601; bytes are `0x`-prefixed hex.
+    pub fn to_text(&self) -> Option<Cow<'_, str>> {
+        let text = match self {
+            CellValue::Null => return None,
+            CellValue::Text(s) => return Some(Cow::Borrowed(s)),
+            CellValue::Int64(v) => v.to_string(),
+            CellValue::Float64(v) => v.to_string(),
+            CellValue::Decimal { value, scale } => format_decimal(*value, *scale),
+            CellValue::Bool(b) => b.to_string(),
+            CellValue::Date(days) => format_date(*days),
+            CellValue::Time(micros) => format_time(*micros),
+            CellValue::Timestamp(micros) => {
+                let days = micros.div_euclid(MICROS_PER_DAY);
+                let time = micros.rem_euclid(MICROS_PER_DAY);
+                format!("{}T{}", format_date(days as i32), format_time(time))
+            }
+            CellValue::Bytes(bytes) => {
+                let mut hex = String::with_capacity(2 + bytes.len() * 2);
+                hex.push_str("0x");
+                for b in bytes {
+                    hex.push_str(&format!("{b:02X}"));
+                }
+                hex
+            }
+        };
+        Some(Cow::Owned(text))
+    }
+}
+
+const MICROS_PER_DAY: i64 = 86_400_000_000;
+
+pub(crate) fn parse_bool(s: &str) -> Option<bool> {
+    match s.trim().to_lowercase().as_str() {
+        "1" | "true" => Some(true),
+        "0" | "false" => Some(false),
+        _ => None,
+    }
+}
+
+pub(crate) fn parse_date32(s: &str) -> Option<i32> {
+    // Parse YYYY-MM-DD to days since Unix epoch
+    let parts: Vec<&str> = s.split('-').collect();
+    if parts.len() != 3 {
+        return None;
+    }
+    let y: i32 = parts[0].parse().ok()?;
+    let m: u32 = parts[1].parse().ok()?;
+    let d: u32 = parts[2].parse().ok()?;
+
+    // Days from civil date to Unix epoch using a simplified algorithm
+    days_from_civil(y, m, d)
+}
+
+pub(crate) fn days_from_civil(y: i32, m: u32, d: u32) -> Option<i32> {
+    if !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+        return None;
+    }
+    // Algorithm from http://howardhinnant.github.io/date_algorithms.html
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400) as u32;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe as i32 - 719468;
+    Some(days)
+}
+
+/// Inverse of `days_from_civil`: (year, month, day) for days since Unix epoch.
+fn civil_from_days(days: i32) -> (i32, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097) as u32;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe as i32 + era * 400 + i32::from(m <= 2);
+    (y, m, d)
+}
+
+pub(crate) fn parse_timestamp_micros(s: &str) -> Option<i64> {
+    // Parse ISO 8601: YYYY-MM-DDThh:mm:ss[.ffffff][Z] or YYYY-MM-DD hh:mm:ss[.ffffff]
+    let s = s.trim();
+    let s = s.strip_suffix('Z').unwrap_or(s);
+    let (date_part, time_part) = if let Some(pos) = s.find('T') {
+        (&s[..pos], &s[pos + 1..])
+    } else if let Some(pos) = s.find(' ') {
+        (&s[..pos], &s[pos + 1..])
+    } else {
+        return None;
+    };
+
+    let days = parse_date32(date_part)?;
+    let micros = parse_time_micros(time_part)?;
+
+    Some(days as i64 * MICROS_PER_DAY + micros)
+}
+
+pub(crate) fn parse_time_micros(s: &str) -> Option<i64> {
+    // Parse HH:MM:SS[.ffffff]
+    let parts: Vec<&str> = s.splitn(2, '.').collect();
+    let hms: Vec<&str> = parts[0].split(':').collect();
+    if hms.len() != 3 {
+        return None;
+    }
+    let h: i64 = hms[0].parse().ok()?;
+    let m: i64 = hms[1].parse().ok()?;
+    let sec: i64 = hms[2].parse().ok()?;
+
+    let mut micros = (h * 3600 + m * 60 + sec) * 1_000_000;
+
+    if parts.len() == 2 {
+        let frac = parts[1];
+        if !frac.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        // Pad or truncate to 6 digits
+        let padded = format!("{:0<6}", &frac[..frac.len().min(6)]);
+        let frac_micros: i64 = padded.parse().ok()?;
+        micros += frac_micros;
+    }
+
+    Some(micros)
+}
+
+pub(crate) fn hex_decode(s: &str) -> Option<Vec<u8>> {
+    let s = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")).unwrap_or(s);
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    s.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let high = char::from(pair[0]).to_digit(16)?;
+            let low = char::from(pair[1]).to_digit(16)?;
+            u8::try_from((high << 4) | low).ok()
+        })
+        .collect()
+}
+
+/// Parse a decimal string like "-123.45" into its unscaled value and scale.
+pub(crate) fn parse_decimal(s: &str) -> Option<(i128, i8)> {
+    let s = s.trim();
+    let digits = s.strip_prefix(['-', '+']).unwrap_or(s);
+    let (integer_part, frac_part) = digits.split_once('.').unwrap_or((digits, ""));
+    if integer_part.is_empty() && frac_part.is_empty() {
+        return None;
+    }
+    if !integer_part.bytes().chain(frac_part.bytes()).all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let scale = i8::try_from(frac_part.len()).ok()?;
+    let mut value: i128 = format!("{integer_part}{frac_part}").parse().ok()?;
+    if s.starts_with('-') {
+        value = -value;
+    }
+    Some((value, scale))
+}
+
+/// Change a decimal's scale, truncating extra fractional digits.
+/// Returns `None` on overflow.
+pub(crate) fn rescale_decimal(value: i128, from: i8, to: i8) -> Option<i128> {
+    let diff = i32::from(to) - i32::from(from);
+    let factor = 10i128.checked_pow(diff.unsigned_abs())?;
+    if diff >= 0 {
+        value.checked_mul(factor)
+    } else {
+        Some(value / factor)
+    }
+}
+
+fn format_decimal(value: i128, scale: i8) -> String {
+    if scale <= 0 {
+        let factor = 10i128.pow(u32::from(scale.unsigned_abs()));
+        return value.saturating_mul(factor).to_string();
+    }
+    let scale = scale as usize;
+    let digits = format!("{:0>width$}", value.unsigned_abs(), width = scale + 1);
+    let (integer_part, frac_part) = digits.split_at(digits.len() - scale);
+    let sign = if value < 0 { "-" } else { "" };
+    format!("{sign}{integer_part}.{frac_part}")
+}
+
+fn format_date(days: i32) -> String {
+    let (y, m, d) = civil_from_days(days);
+    format!("{y:04}-{m:02}-{d:02}")
+}
+
+/// `HH:MM:SS`, with a `.ffffff` fraction only when it is non-zero.
+fn format_time(micros: i64) -> String {
+    let secs = micros / 1_000_000;
+    let frac = micros % 1_000_000;
+    let hms = format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60);
+    if frac == 0 {
+        hms
+    } else {
+        format!("{hms}.{frac:06}")
+    }
+}
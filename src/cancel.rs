@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use tokio::sync::watch;
+
+use crate::error::DbtoonError;
+
+/// Shared flag telling a running query to stop, e.g. on Ctrl-C.
+///
+/// Clones share the same flag. A default token is never cancelled unless
+/// `cancel` is called on it or one of its clones.
+#[derive(Clone)]
+pub struct CancelToken {
+    sender: Arc<watch::Sender<bool>>,
+}
+
+impl Default for CancelToken {
+    fn default() -> Self {
+        Self {
+            sender: Arc::new(watch::channel(false).0),
+        }
+    }
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.sender.send_replace(true);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.sender.borrow()
+    }
+
+    /// Resolves once the token is cancelled.
+    pub async fn cancelled(&self) {
+        let mut receiver = self.sender.subscribe();
+        // The sender lives as long as `self`, so this only returns once cancelled
+        let _ = receiver.wait_for(|cancelled| *cancelled).await;
+    }
+
+    /// `Err(Cancelled)` if the token has been cancelled.
+    pub fn check(&self) -> Result<(), DbtoonError> {
+        if self.is_cancelled() {
+            Err(DbtoonError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Cancel `token` on the first Ctrl-C (or SIGTERM on Unix).
+///
+/// Backends then stop the running statement and return `DbtoonError::Cancelled`.
+/// A second signal exits immediately, for when the server does not respond.
+pub fn cancel_on_signal(token: CancelToken, verbose: bool) {
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        crate::verbose::emit(verbose, "interrupted — cancelling query...");
+        token.cancel();
+
+        shutdown_signal().await;
+        crate::output::print_error(&DbtoonError::Cancelled);
+        std::process::exit(DbtoonError::Cancelled.exit_code());
+    });
+}
+
+/// Resolves on the next Ctrl-C or SIGTERM; never, if no handler could be installed.
+pub(crate) async fn shutdown_signal() {
+    let ctrl_c = async {
+        if tokio::signal::ctrl_c().await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{SignalKind, signal};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
@@ -1,4 +1,5 @@
 use clap::{Parser, Subcommand};
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
@@ -28,6 +29,9 @@ pub enum Command {
     /// Execute a SQL query against a profile
     Query(QueryArgs),
 
+    /// Interactive SQL shell on a profile
+    Shell(ShellArgs),
+
     /// Manage connection profiles
     #[command(subcommand)]
     Profile(ProfileCommand),
@@ -35,11 +39,22 @@ pub enum Command {
     /// Databricks warehouse operations
     Warehouse(WarehouseArgs),
 
+    /// Sign in to a Databricks workspace with OAuth
+    #[command(subcommand)]
+    Auth(AuthCommand),
+
+    /// Serve queries to agents over the Model Context Protocol (stdio)
+    Mcp,
+
     /// Update dbtoon to the latest release
     Update,
+
+    /// Keep connections open between queries in a background process (Unix only)
+    #[command(subcommand)]
+    Daemon(DaemonCommand),
 }
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone, Serialize, Deserialize)]
 pub struct QueryArgs {
     /// SQL query text
     #[arg(conflicts_with = "file")]
@@ -84,6 +99,53 @@ pub struct QueryArgs {
     /// Bypass read-only safety validation
     #[arg(long)]
     pub allow_write: bool,
+
+    /// Bind a value to the :name marker in the query (repeatable, e.g., --param id=42:INT)
+    #[arg(long = "param", value_name = "NAME=VALUE[:TYPE]")]
+    pub params: Vec<String>,
+
+    /// Estimate the bytes a BigQuery query would process without running it
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Run the query in this process even if a daemon is running
+    #[arg(long)]
+    pub no_daemon: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct ShellArgs {
+    /// Profile name
+    #[arg(short = 'P', long, required = true)]
+    pub profile: String,
+
+    /// Override database/catalog
+    #[arg(short = 'd', long, conflicts_with = "catalog")]
+    pub database: Option<String>,
+
+    /// Override catalog (alias for --database)
+    #[arg(long, conflicts_with = "database")]
+    pub catalog: Option<String>,
+
+    /// Override schema
+    #[arg(short = 's', long)]
+    pub schema: Option<String>,
+
+    /// Override row limit
+    #[arg(short = 'l', long)]
+    pub limit: Option<usize>,
+
+    /// Disable row limit
+    #[arg(long)]
+    pub no_limit: bool,
+
+    /// Override timeout in seconds
+    #[arg(short = 't', long)]
+    pub timeout: Option<u64>,
+
+    /// Bypass read-only safety validation
+    #[arg(long)]
+    pub allow_write: bool,
 }
 
 #[derive(Subcommand, Debug)]
@@ -115,7 +177,7 @@ pub struct ProfileCreateArgs {
     /// Profile name
     pub name: String,
 
-    /// Backend type: databricks or sqlserver
+    /// Backend type: databricks, sqlserver, odbc, postgres, mysql, clickhouse, bigquery, sqlite, snowflake, trino or presto
     #[arg(long, required = true)]
     pub backend: String,
 
@@ -171,3 +233,47 @@ pub struct WarehouseListArgs {
     #[arg(short = 'P', long, required = true)]
     pub profile: String,
 }
+
+#[derive(Subcommand, Debug)]
+pub enum AuthCommand {
+    /// Log in through the browser and cache OAuth tokens for an `auth = "oauth-u2m"` profile
+    Login(AuthLoginArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct AuthLoginArgs {
+    /// Databricks profile name
+    #[arg(short = 'P', long, required = true)]
+    pub profile: String,
+
+    /// Use the device code flow instead of a browser redirect (for headless machines)
+    #[arg(long)]
+    pub device_code: bool,
+
+    /// Localhost port for the browser redirect
+    #[arg(long, default_value_t = crate::backend::oauth::DEFAULT_REDIRECT_PORT)]
+    pub port: u16,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DaemonCommand {
+    /// Run the daemon in the foreground until stopped
+    Run(DaemonRunArgs),
+
+    /// Stop the running daemon
+    Stop,
+
+    /// Show whether a daemon is running and what it is doing
+    Status,
+}
+
+#[derive(Parser, Debug)]
+pub struct DaemonRunArgs {
+    /// Close connections unused for this many seconds
+    #[arg(long, default_value_t = 300)]
+    pub idle_timeout: u64,
+
+    /// Exit after this many seconds without a query (0 = never)
+    #[arg(long, default_value_t = 0)]
+    pub exit_after: u64,
+}
@@ -1,8 +1,11 @@
+use crate::backend::retry::{DEFAULT_RETRY_ATTEMPTS, DEFAULT_RETRY_BACKOFF_MS, RetryPolicy};
 use crate::cli::{QueryArgs, WarehouseListArgs};
 use crate::error::DbtoonError;
-use secrecy::SecretString;
-use serde::Deserialize;
-use std::collections::HashMap;
+use crate::validation::BackendDialect;
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 
 /// Top-level application configuration.
@@ -15,6 +18,16 @@ pub struct AppConfig {
     pub verbose: bool,
     pub show_secrets: bool,
     pub output_file: Option<PathBuf>,
+    pub download_concurrency: usize,
+    pub retry: RetryPolicy,
+    /// Estimate a query's cost instead of running it (BigQuery only).
+    pub dry_run: bool,
+    /// Directory that relative file names in queries resolve against, when not
+    /// this process's own (see `with_environment`).
+    pub working_dir: Option<PathBuf>,
+    /// Queries continue one session on a reused connection, as in the shell
+    /// (see `backend::SessionReuse`).
+    pub shared_session: bool,
 }
 
 /// Configured database connection target.
@@ -25,14 +38,88 @@ pub enum BackendConfig {
         database: Option<String>,
         auth: SqlServerAuth,
         trust_server_certificate: bool,
+        options: SqlServerOptions,
     },
     Databricks {
         host: String,
-        token: SecretString,
+        auth: DatabricksAuth,
         warehouse_id: String,
         catalog: Option<String>,
         schema: Option<String>,
     },
+    /// Any ODBC driver, reached through a DSN or a full connection string.
+    Odbc {
+        connection_string: SecretString,
+        dialect: BackendDialect,
+    },
+    /// A local SQLite database file, or `:memory:`.
+    Sqlite {
+        path: String,
+    },
+    /// PostgreSQL over its native wire protocol.
+    Postgres {
+        host: String,
+        port: u16,
+        database: Option<String>,
+        user: String,
+        password: Option<SecretString>,
+        sslmode: PostgresSslMode,
+    },
+    /// MySQL or MariaDB over the native protocol.
+    MySql {
+        host: String,
+        port: u16,
+        database: Option<String>,
+        user: String,
+        password: Option<SecretString>,
+        ssl_mode: MySqlSslMode,
+    },
+    /// Snowflake over the SQL REST API, signing in with a key pair.
+    Snowflake {
+        account: String,
+        /// Host overriding `<account>.snowflakecomputing.com`.
+        host: Option<String>,
+        user: String,
+        private_key_path: PathBuf,
+        private_key_passphrase: Option<SecretString>,
+        warehouse: Option<String>,
+        database: Option<String>,
+        schema: Option<String>,
+        role: Option<String>,
+    },
+    /// BigQuery over the REST API, signing in with a service account key.
+    BigQuery {
+        /// Project the jobs run (and are billed) in; the key's own project when unset.
+        project: Option<String>,
+        /// Service account JSON key; only optional against an emulator `host`.
+        credentials_path: Option<PathBuf>,
+        /// Default dataset for unqualified table names, as `dataset` or `project.dataset`.
+        dataset: Option<String>,
+        location: Option<String>,
+        /// Host overriding `bigquery.googleapis.com`, e.g. an emulator.
+        host: Option<String>,
+    },
+    /// ClickHouse over its HTTP interface.
+    ClickHouse {
+        host: String,
+        /// Port overriding the one implied by `host` (8443 for a bare hostname).
+        port: Option<u16>,
+        database: Option<String>,
+        user: String,
+        password: Option<SecretString>,
+    },
+    /// Trino or Presto over the HTTP client protocol.
+    Trino {
+        /// Presto coordinator, which uses `X-Presto-*` rather than `X-Trino-*` headers.
+        presto: bool,
+        host: String,
+        /// Port overriding the one implied by `host` (443 for HTTPS).
+        port: Option<u16>,
+        user: String,
+        auth: TrinoAuth,
+        catalog: Option<String>,
+        schema: Option<String>,
+    },
 }
 
 /// Authentication method for SQL Server.
@@ -43,6 +130,120 @@ pub enum SqlServerAuth {
         username: String,
         password: SecretString,
     },
+    /// Entra ID service principal: application (client) id and secret.
+    ActiveDirectoryServicePrincipal {
+        client_id: String,
+        client_secret: SecretString,
+    },
+    /// Entra ID user name and password.
+    ActiveDirectoryPassword {
+        username: String,
+        password: SecretString,
+    },
+    /// Entra ID sign-in through the driver's browser prompt, optionally for `username`.
+    ActiveDirectoryInteractive { username: Option<String> },
+    /// Entra ID credentials the driver finds on its own (environment, managed identity, CLI).
+    ActiveDirectoryDefault,
+    /// A ready-made Entra ID access token, passed to the driver before connecting.
+    AccessToken(SecretString),
+}
+
+/// SQL Server connection-string settings beyond server, database and credentials.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SqlServerOptions {
+    /// ODBC driver name; `ODBC Driver 18 for SQL Server` when unset.
+    pub driver: Option<String>,
+    pub port: Option<u16>,
+    /// Named instance, e.g. `SQLEXPRESS`.
+    pub instance: Option<String>,
+    /// `Encrypt=` value: yes, no, mandatory, optional or strict.
+    pub encrypt: Option<String>,
+    /// `ApplicationIntent=` value: ReadOnly or ReadWrite.
+    pub application_intent: Option<String>,
+    /// Further `Key=Value` attributes, validated and sorted by key.
+    pub extra_attributes: Vec<(String, String)>,
+}
+
+/// TLS policy for PostgreSQL, named after libpq's `sslmode` values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PostgresSslMode {
+    Disable,
+    /// Use TLS when the server offers it, without checking its certificate.
+    Prefer,
+    /// Always use TLS, without checking the certificate.
+    Require,
+    /// Always use TLS and check the certificate chain, but not the host name.
+    VerifyCa,
+    /// Always use TLS and check both the certificate chain and the host name.
+    VerifyFull,
+}
+
+impl PostgresSslMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "disable" => Some(Self::Disable),
+            "prefer" => Some(Self::Prefer),
+            "require" => Some(Self::Require),
+            "verify-ca" => Some(Self::VerifyCa),
+            "verify-full" => Some(Self::VerifyFull),
+            _ => None,
+        }
+    }
+}
+
+/// TLS policy for MySQL, named after the `mysql` client's `--ssl-mode` values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MySqlSslMode {
+    Disabled,
+    /// Use TLS when the server supports it, without checking its certificate.
+    Preferred,
+    /// Always use TLS, without checking the certificate.
+    Required,
+    /// Always use TLS and check the certificate chain, but not the host name.
+    VerifyCa,
+    /// Always use TLS and check both the certificate chain and the host name.
+    VerifyIdentity,
+}
+
+impl MySqlSslMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().replace('-', "_").as_str() {
+            "disabled" => Some(Self::Disabled),
+            "preferred" => Some(Self::Preferred),
+            "required" => Some(Self::Required),
+            "verify_ca" => Some(Self::VerifyCa),
+            "verify_identity" => Some(Self::VerifyIdentity),
+            _ => None,
+        }
+    }
+}
+
+/// Credentials sent to a Trino coordinator along with the user name.
+#[derive(Debug)]
+pub enum TrinoAuth {
+    /// No credentials, for clusters that trust the `X-Trino-User` header.
+    None,
+    /// HTTP basic authentication with the user's password.
+    Password(SecretString),
+    /// A JWT sent as a bearer token.
+    Jwt(SecretString),
+}
+
+/// Authentication method for Databricks.
+#[derive(Debug)]
+pub enum DatabricksAuth {
+    /// Personal access token.
+    Token(SecretString),
+    /// OAuth machine-to-machine: a service principal's client credentials.
+    OAuthM2M {
+        client_id: String,
+        client_secret: SecretString,
+    },
+    /// OAuth user-to-machine: tokens from `dbtoon auth login`, kept in `token_cache`.
+    OAuthU2M {
+        client_id: String,
+        token_cache: PathBuf,
+    },
 }
 
 // --- TOML config file structs ---
@@ -61,6 +262,9 @@ pub struct TomlDefaults {
     pub timeout: Option<u64>,
     pub verbose: Option<bool>,
     pub allow_write: Option<bool>,
+    pub download_concurrency: Option<usize>,
+    pub retry_attempts: Option<u32>,
+    pub retry_backoff_ms: Option<u64>,
 }
 
 #[derive(Debug, Deserialize, Default, Clone)]
@@ -69,14 +273,42 @@ pub struct TomlProfile {
     pub server: Option<String>,
     pub database: Option<String>,
     pub username: Option<String>,
+    pub user: Option<String>,
     pub password: Option<String>,
+    pub account: Option<String>,
+    pub private_key_path: Option<String>,
+    pub private_key_passphrase: Option<String>,
+    pub project: Option<String>,
+    pub dataset: Option<String>,
+    pub location: Option<String>,
+    pub credentials_path: Option<String>,
+    pub warehouse: Option<String>,
+    pub role: Option<String>,
+    pub sslmode: Option<String>,
+    pub ssl_mode: Option<String>,
     pub windows_auth: Option<bool>,
     pub trust_server_certificate: Option<bool>,
+    pub driver: Option<String>,
+    pub port: Option<u16>,
+    pub instance: Option<String>,
+    pub encrypt: Option<String>,
+    pub application_intent: Option<String>,
+    pub extra_attributes: Option<BTreeMap<String, toml::Value>>,
+    pub path: Option<String>,
+    pub dsn: Option<String>,
+    pub connection_string: Option<String>,
+    pub dialect: Option<String>,
     pub host: Option<String>,
     pub token: Option<String>,
+    pub access_token: Option<String>,
+    pub auth: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
     pub warehouse_id: Option<String>,
     pub catalog: Option<String>,
     pub schema: Option<String>,
+    pub retry_attempts: Option<u32>,
+    pub retry_backoff_ms: Option<u64>,
 }
 
 /// Filter `Some("")` to `None`. Passes through `None` and non-empty values.
@@ -84,16 +316,81 @@ pub fn non_empty(s: Option<&str>) -> Option<&str> {
     s.filter(|v| !v.is_empty())
 }
 
+/// Environment variables and working directory of a process, for resolving its
+/// config in another one (see `with_environment`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Environment {
+    pub vars: HashMap<String, String>,
+    pub working_dir: PathBuf,
+}
+
+impl Environment {
+    /// The environment of this process. Variables that are not valid Unicode are left out.
+    pub fn current() -> Result<Self, DbtoonError> {
+        let vars = std::env::vars_os()
+            .filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?)))
+            .collect();
+        Ok(Self { vars, working_dir: std::env::current_dir()? })
+    }
+}
+
+thread_local! {
+    static ENVIRONMENT: RefCell<Option<Environment>> = const { RefCell::new(None) };
+}
+
+/// Resets `ENVIRONMENT` when `with_environment` returns, or unwinds.
+struct RestoreEnvironment(Option<Environment>);
+
+impl Drop for RestoreEnvironment {
+    fn drop(&mut self) {
+        ENVIRONMENT.set(self.0.take());
+    }
+}
+
+/// Run `f` with env vars and relative paths in the config taken from
+/// `environment` rather than this process, as the daemon does for its clients.
+pub fn with_environment<T>(environment: Environment, f: impl FnOnce() -> T) -> T {
+    let _restore = RestoreEnvironment(ENVIRONMENT.replace(Some(environment)));
+    f()
+}
+
+fn env_var(key: &str) -> Option<String> {
+    ENVIRONMENT.with_borrow(|environment| match environment {
+        Some(environment) => environment.vars.get(key).cloned(),
+        None => std::env::var(key).ok(),
+    })
+}
+
+/// `path`, relative to the working directory set by `with_environment`, if any.
+fn resolve_path(path: &str) -> PathBuf {
+    ENVIRONMENT.with_borrow(|environment| match environment {
+        Some(environment) => environment.working_dir.join(path),
+        None => PathBuf::from(path),
+    })
+}
+
+fn working_dir() -> Option<PathBuf> {
+    ENVIRONMENT.with_borrow(|environment| environment.as_ref().map(|e| e.working_dir.clone()))
+}
+
 /// Read an env var, returning `None` if unset or empty.
 pub fn env_non_empty(key: &str) -> Option<String> {
-    std::env::var(key).ok().filter(|v| !v.is_empty())
+    env_var(key).filter(|v| !v.is_empty())
 }
 
 /// Return the default config file path: `$HOME/.config/dbtoon/config.toml`.
 pub fn default_config_path() -> Option<PathBuf> {
-    std::env::var("HOME")
-        .ok()
-        .map(|h| PathBuf::from(h).join(".config/dbtoon/config.toml"))
+    env_var("HOME").map(|h| PathBuf::from(h).join(".config/dbtoon/config.toml"))
+}
+
+/// Return the OAuth token cache path: `$HOME/.config/dbtoon/oauth-tokens.json`.
+pub fn default_token_cache_path() -> Option<PathBuf> {
+    default_config_path().map(|p| p.with_file_name("oauth-tokens.json"))
+}
+
+/// Return the daemon socket path: `$HOME/.config/dbtoon/daemon.sock`.
+pub fn default_daemon_socket_path() -> Option<PathBuf> {
+    default_config_path().map(|p| p.with_file_name("daemon.sock"))
 }
 
 /// Resolve a `$VAR` reference to its env var value.
@@ -105,7 +402,7 @@ pub fn resolve_env_var(value: &str) -> Result<String, DbtoonError> {
     if let Some(rest) = value.strip_prefix("$$") {
         Ok(format!("${}", rest))
     } else if let Some(var_name) = value.strip_prefix('$') {
-        std::env::var(var_name).map_err(|_| DbtoonError::Config {
+        env_var(var_name).ok_or_else(|| DbtoonError::Config {
             message: format!("environment variable '{}' is not set", var_name),
         })
     } else {
@@ -217,8 +514,25 @@ pub fn build_backend_config(
     match backend_str {
         "sqlserver" => build_sqlserver_config(profile, cli_database),
         "databricks" => build_databricks_config(profile, cli_database, cli_schema),
+        "odbc" => build_odbc_config(profile, cli_database),
+        "postgres" => build_postgres_config(profile, cli_database),
+        "mysql" => build_mysql_config(profile, cli_database),
+        "clickhouse" => build_clickhouse_config(profile, cli_database),
+        "bigquery" => build_bigquery_config(profile, cli_database),
+        "sqlite" => build_sqlite_config(profile, cli_database),
+        "snowflake" => build_snowflake_config(profile, cli_database, cli_schema),
+        "trino" => build_trino_config(profile, cli_database, cli_schema, false),
+        "presto" => build_trino_config(profile, cli_database, cli_schema, true),
+        "duckdb" => Err(DbtoonError::Config {
+            message: "the duckdb backend is not available in this build; use backend = \"sqlite\", \
+                      which also queries Parquet, Arrow and CSV files"
+                .to_string(),
+        }),
         other => Err(DbtoonError::Config {
-            message: format!("unknown backend type: '{}' (expected 'sqlserver' or 'databricks')", other),
+            message: format!(
+                "unknown backend type: '{}' (expected 'sqlserver', 'databricks', 'odbc', 'postgres', 'mysql', 'clickhouse', 'bigquery', 'sqlite', 'snowflake', 'trino' or 'presto')",
+                other
+            ),
         }),
     }
 }
@@ -240,7 +554,15 @@ fn build_sqlserver_config(
 
     let windows_auth = profile.windows_auth.unwrap_or(false);
 
-    let auth = if windows_auth {
+    let auth = if let Some(mode) = profile.auth.as_deref() {
+        if windows_auth {
+            return Err(DbtoonError::Config {
+                message: "'windows_auth' and 'auth' cannot both be set for sqlserver backend"
+                    .to_string(),
+            });
+        }
+        build_sqlserver_entra_auth(mode, profile)?
+    } else if windows_auth {
         SqlServerAuth::WindowsIntegrated
     } else {
         let username = resolve_profile_string(profile.username.as_deref())?
@@ -257,15 +579,165 @@ fn build_sqlserver_config(
     };
 
     let trust_server_certificate = profile.trust_server_certificate.unwrap_or(false);
+    let options = build_sqlserver_options(profile, &server)?;
 
     Ok(BackendConfig::SqlServer {
         server,
         database,
         auth,
         trust_server_certificate,
+        options,
+    })
+}
+
+/// Connection-string keywords set by dedicated profile fields, which
+/// `extra_attributes` may not override.
+const RESERVED_SQLSERVER_ATTRIBUTES: &[(&str, &str)] = &[
+    ("driver", "driver"),
+    ("server", "server"),
+    ("addr", "server"),
+    ("address", "server"),
+    ("database", "database"),
+    ("uid", "username"),
+    ("pwd", "password"),
+    ("authentication", "auth"),
+    ("trusted_connection", "windows_auth"),
+    ("trustservercertificate", "trust_server_certificate"),
+    ("encrypt", "encrypt"),
+    ("applicationintent", "application_intent"),
+];
+
+fn build_sqlserver_options(profile: &TomlProfile, server: &str) -> Result<SqlServerOptions, DbtoonError> {
+    let instance = resolve_profile_string(profile.instance.as_deref())?;
+    if instance.is_some() && server.contains('\\') {
+        return Err(DbtoonError::Config {
+            message: "'server' already names an instance; remove it or 'instance'".to_string(),
+        });
+    }
+    if profile.port.is_some() && server.contains(',') {
+        return Err(DbtoonError::Config {
+            message: "'server' already includes a port; remove it or 'port'".to_string(),
+        });
+    }
+
+    let encrypt = resolve_profile_string(profile.encrypt.as_deref())?;
+    if let Some(ref value) = encrypt
+        && !matches!(
+            value.to_ascii_lowercase().as_str(),
+            "yes" | "no" | "true" | "false" | "mandatory" | "optional" | "strict"
+        )
+    {
+        return Err(DbtoonError::Config {
+            message: format!(
+                "invalid 'encrypt' value '{}' (expected yes, no, mandatory, optional or strict)",
+                value
+            ),
+        });
+    }
+
+    let application_intent = resolve_profile_string(profile.application_intent.as_deref())?
+        .map(|value| match value.to_ascii_lowercase().as_str() {
+            "readonly" => Ok("ReadOnly".to_string()),
+            "readwrite" => Ok("ReadWrite".to_string()),
+            _ => Err(DbtoonError::Config {
+                message: format!(
+                    "invalid 'application_intent' value '{}' (expected ReadOnly or ReadWrite)",
+                    value
+                ),
+            }),
+        })
+        .transpose()?;
+
+    let mut extra_attributes = Vec::new();
+    for (key, value) in profile.extra_attributes.iter().flatten() {
+        validate_attribute_key(key)?;
+        extra_attributes.push((key.clone(), extra_attribute_value(key, value)?));
+    }
+
+    Ok(SqlServerOptions {
+        driver: resolve_profile_string(profile.driver.as_deref())?,
+        port: profile.port,
+        instance,
+        encrypt,
+        application_intent,
+        extra_attributes,
     })
 }
 
+/// Reject keys ODBC cannot parse and keys owned by a dedicated profile field.
+fn validate_attribute_key(key: &str) -> Result<(), DbtoonError> {
+    if key.trim().is_empty() || key.trim() != key || key.contains(|c| "[]{}(),;?*=!@".contains(c)) {
+        return Err(DbtoonError::Config {
+            message: format!("invalid extra_attributes key '{}'", key),
+        });
+    }
+    let lower = key.to_ascii_lowercase();
+    if let Some((_, field)) = RESERVED_SQLSERVER_ATTRIBUTES.iter().find(|(k, _)| *k == lower) {
+        return Err(DbtoonError::Config {
+            message: format!(
+                "extra_attributes.{} is set by the '{}' profile field; use that instead",
+                key, field
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// An attribute value as connection-string text: strings (with `$VAR`
+/// resolution), booleans as Yes/No, and numbers.
+fn extra_attribute_value(key: &str, value: &toml::Value) -> Result<String, DbtoonError> {
+    match value {
+        toml::Value::String(s) => resolve_env_var(s),
+        toml::Value::Boolean(b) => Ok(if *b { "Yes" } else { "No" }.to_string()),
+        toml::Value::Integer(n) => Ok(n.to_string()),
+        _ => Err(DbtoonError::Config {
+            message: format!("extra_attributes.{} must be a string, boolean or integer", key),
+        }),
+    }
+}
+
+/// SQL Server Entra ID credentials for `auth = "<mode>"`; modes match the
+/// driver's `Authentication=` values, plus `AccessToken`.
+fn build_sqlserver_entra_auth(mode: &str, profile: &TomlProfile) -> Result<SqlServerAuth, DbtoonError> {
+    let required_string = |value: &Option<String>, field: &str| {
+        resolve_profile_string(value.as_deref())?.ok_or_else(|| DbtoonError::Config {
+            message: format!("no '{}' specified for SQL Server auth '{}'", field, mode),
+        })
+    };
+    let required_secret = |value: &Option<String>, field: &str| {
+        resolve_profile_secret(value.as_deref())?.ok_or_else(|| DbtoonError::Config {
+            message: format!("no '{}' specified for SQL Server auth '{}'", field, mode),
+        })
+    };
+
+    match mode.to_ascii_lowercase().as_str() {
+        "activedirectoryserviceprincipal" => Ok(SqlServerAuth::ActiveDirectoryServicePrincipal {
+            client_id: required_string(&profile.client_id, "client_id")?,
+            client_secret: required_secret(&profile.client_secret, "client_secret")?,
+        }),
+        "activedirectorypassword" => Ok(SqlServerAuth::ActiveDirectoryPassword {
+            username: required_string(&profile.username, "username")?,
+            password: required_secret(&profile.password, "password")?,
+        }),
+        "activedirectoryinteractive" => Ok(SqlServerAuth::ActiveDirectoryInteractive {
+            username: resolve_profile_string(profile.username.as_deref())?,
+        }),
+        "activedirectorydefault" => Ok(SqlServerAuth::ActiveDirectoryDefault),
+        "accesstoken" => Ok(SqlServerAuth::AccessToken(required_secret(
+            &profile.access_token,
+            "access_token",
+        )?)),
+        _ => Err(DbtoonError::Config {
+            message: format!(
+                "unknown sqlserver auth '{}' (expected ActiveDirectoryServicePrincipal, \
+                 ActiveDirectoryPassword, ActiveDirectoryInteractive, ActiveDirectoryDefault \
+                 or AccessToken)",
+                mode
+            ),
+        }),
+    }
+}
+
 fn build_databricks_config(
     profile: &TomlProfile,
     cli_database: Option<&str>,
@@ -273,7 +745,6 @@ fn build_databricks_config(
 ) -> Result<BackendConfig, DbtoonError> {
     // Databricks standard env vars as lowest-priority fallback
     let std_host = env_non_empty("DATABRICKS_HOST");
-    let std_token = env_non_empty("DATABRICKS_TOKEN");
     let std_warehouse = env_non_empty("DATABRICKS_SQL_WAREHOUSE_ID");
     let std_catalog = env_non_empty("DATABRICKS_CATALOG");
     let std_schema = env_non_empty("DATABRICKS_SCHEMA");
@@ -284,11 +755,7 @@ fn build_databricks_config(
             message: "no 'host' specified for databricks backend".to_string(),
         })?;
 
-    let token = resolve_profile_secret(profile.token.as_deref())?
-        .or_else(|| std_token.map(SecretString::from))
-        .ok_or_else(|| DbtoonError::Config {
-            message: "no 'token' specified for databricks backend".to_string(),
-        })?;
+    let auth = build_databricks_auth(profile)?;
 
     let warehouse_id = resolve_profile_string(profile.warehouse_id.as_deref())?
         .or(std_warehouse)
@@ -312,13 +779,428 @@ fn build_databricks_config(
 
     Ok(BackendConfig::Databricks {
         host,
-        token,
+        auth,
         warehouse_id,
         catalog,
         schema,
     })
 }
 
+/// Databricks credentials: profile `client_id` > profile/env token > env client credentials.
+///
+/// A `client_id` selects OAuth M2M and requires a `client_secret` from the
+/// profile or `DATABRICKS_CLIENT_SECRET`. `auth = "oauth-u2m"` instead uses the
+/// tokens saved by `dbtoon auth login`, with `client_id` naming the OAuth app.
+fn build_databricks_auth(profile: &TomlProfile) -> Result<DatabricksAuth, DbtoonError> {
+    match profile.auth.as_deref() {
+        None => {}
+        Some("oauth-u2m") => {
+            let client_id = resolve_profile_string(profile.client_id.as_deref())?
+                .unwrap_or_else(|| crate::backend::oauth::DEFAULT_U2M_CLIENT_ID.to_string());
+            let token_cache = default_token_cache_path().ok_or_else(|| DbtoonError::Config {
+                message: "cannot determine OAuth token cache location (HOME not set)".to_string(),
+            })?;
+            return Ok(DatabricksAuth::OAuthU2M { client_id, token_cache });
+        }
+        Some(other) => {
+            return Err(DbtoonError::Config {
+                message: format!("unknown databricks auth '{}' (expected 'oauth-u2m')", other),
+            });
+        }
+    }
+
+    let std_client_secret = || env_non_empty("DATABRICKS_CLIENT_SECRET").map(SecretString::from);
+    let m2m = |client_id: String, client_secret: Option<SecretString>| {
+        let client_secret = client_secret.ok_or_else(|| DbtoonError::Config {
+            message: "no 'client_secret' specified for databricks 'client_id'".to_string(),
+        })?;
+        Ok(DatabricksAuth::OAuthM2M { client_id, client_secret })
+    };
+
+    if let Some(client_id) = resolve_profile_string(profile.client_id.as_deref())? {
+        let client_secret = resolve_profile_secret(profile.client_secret.as_deref())?
+            .or_else(std_client_secret);
+        return m2m(client_id, client_secret);
+    }
+
+    if let Some(token) = resolve_profile_secret(profile.token.as_deref())?
+        .or_else(|| env_non_empty("DATABRICKS_TOKEN").map(SecretString::from))
+    {
+        return Ok(DatabricksAuth::Token(token));
+    }
+
+    match env_non_empty("DATABRICKS_CLIENT_ID") {
+        Some(client_id) => m2m(client_id, std_client_secret()),
+        None => Err(DbtoonError::Config {
+            message: "no 'token' or 'client_id' specified for databricks backend".to_string(),
+        }),
+    }
+}
+
+/// ODBC connection string from `dsn` or `connection_string`, with `username`,
+/// `password` and a CLI `--database` appended as `UID`, `PWD` and `Database`.
+fn build_odbc_config(
+    profile: &TomlProfile,
+    cli_database: Option<&str>,
+) -> Result<BackendConfig, DbtoonError> {
+    let dsn = resolve_profile_string(profile.dsn.as_deref())?;
+    let connection_string = resolve_profile_secret(profile.connection_string.as_deref())?;
+
+    let mut parts = match (dsn, connection_string) {
+        (Some(_), Some(_)) => {
+            return Err(DbtoonError::Config {
+                message: "'dsn' and 'connection_string' cannot both be set for odbc backend"
+                    .to_string(),
+            });
+        }
+        (Some(dsn), None) => vec![format!("DSN={}", odbc_api::escape_attribute_value(&dsn))],
+        (None, Some(cs)) => {
+            let cs = cs.expose_secret().trim().trim_end_matches(';');
+            vec![cs.to_string()]
+        }
+        (None, None) => {
+            return Err(DbtoonError::Config {
+                message: "no 'dsn' or 'connection_string' specified for odbc backend".to_string(),
+            });
+        }
+    };
+
+    if let Some(username) = resolve_profile_string(profile.username.as_deref())? {
+        parts.push(format!("UID={}", odbc_api::escape_attribute_value(&username)));
+    }
+    if let Some(password) = resolve_profile_secret(profile.password.as_deref())? {
+        parts.push(format!(
+            "PWD={}",
+            odbc_api::escape_attribute_value(password.expose_secret())
+        ));
+    }
+    if let Some(db) = cli_database {
+        parts.push(format!("Database={}", odbc_api::escape_attribute_value(db)));
+    }
+
+    let dialect = match resolve_profile_string(profile.dialect.as_deref())? {
+        None => BackendDialect::Generic,
+        Some(name) => BackendDialect::from_name(&name).ok_or_else(|| DbtoonError::Config {
+            message: format!(
+                "unknown odbc dialect '{}' (expected generic, postgres, mysql, clickhouse, bigquery, snowflake or sqlite)",
+                name
+            ),
+        })?,
+    };
+
+    Ok(BackendConfig::Odbc {
+        connection_string: SecretString::from(parts.join(";") + ";"),
+        dialect,
+    })
+}
+
+/// PostgreSQL connection settings, with libpq's `PGHOST`, `PGPORT`,
+/// `PGDATABASE`, `PGUSER`, `PGPASSWORD` and `PGSSLMODE` as lowest-priority fallbacks.
+fn build_postgres_config(
+    profile: &TomlProfile,
+    cli_database: Option<&str>,
+) -> Result<BackendConfig, DbtoonError> {
+    let host = resolve_profile_string(profile.host.as_deref())?
+        .or_else(|| env_non_empty("PGHOST"))
+        .ok_or_else(|| DbtoonError::Config {
+            message: "no 'host' specified for postgres backend".to_string(),
+        })?;
+
+    let port = match profile.port {
+        Some(port) => port,
+        None => match env_non_empty("PGPORT") {
+            Some(port) => port.parse().map_err(|_| DbtoonError::Config {
+                message: format!("invalid PGPORT '{}'", port),
+            })?,
+            None => 5432,
+        },
+    };
+
+    let database = if let Some(db) = cli_database {
+        Some(db.to_string())
+    } else {
+        resolve_profile_string(profile.database.as_deref())?.or_else(|| env_non_empty("PGDATABASE"))
+    };
+
+    let user = resolve_profile_string(profile.user.as_deref())?
+        .or_else(|| env_non_empty("PGUSER"))
+        .ok_or_else(|| DbtoonError::Config {
+            message: "no 'user' specified for postgres backend".to_string(),
+        })?;
+
+    let password = resolve_profile_secret(profile.password.as_deref())?
+        .or_else(|| env_non_empty("PGPASSWORD").map(SecretString::from));
+
+    let sslmode = match resolve_profile_string(profile.sslmode.as_deref())?
+        .or_else(|| env_non_empty("PGSSLMODE"))
+    {
+        None => PostgresSslMode::Prefer,
+        Some(name) => PostgresSslMode::from_name(&name).ok_or_else(|| DbtoonError::Config {
+            message: format!(
+                "unknown sslmode '{}' (expected disable, prefer, require, verify-ca or verify-full)",
+                name
+            ),
+        })?,
+    };
+
+    Ok(BackendConfig::Postgres {
+        host,
+        port,
+        database,
+        user,
+        password,
+        sslmode,
+    })
+}
+
+/// MySQL connection settings, with the `mysql` client's `MYSQL_HOST`,
+/// `MYSQL_TCP_PORT` and `MYSQL_PWD` as lowest-priority fallbacks.
+fn build_mysql_config(
+    profile: &TomlProfile,
+    cli_database: Option<&str>,
+) -> Result<BackendConfig, DbtoonError> {
+    let host = resolve_profile_string(profile.host.as_deref())?
+        .or_else(|| env_non_empty("MYSQL_HOST"))
+        .ok_or_else(|| DbtoonError::Config {
+            message: "no 'host' specified for mysql backend".to_string(),
+        })?;
+
+    let port = match profile.port {
+        Some(port) => port,
+        None => match env_non_empty("MYSQL_TCP_PORT") {
+            Some(port) => port.parse().map_err(|_| DbtoonError::Config {
+                message: format!("invalid MYSQL_TCP_PORT '{}'", port),
+            })?,
+            None => 3306,
+        },
+    };
+
+    let database = match cli_database {
+        Some(db) => Some(db.to_string()),
+        None => resolve_profile_string(profile.database.as_deref())?,
+    };
+
+    let user = resolve_profile_string(profile.user.as_deref())?.ok_or_else(|| DbtoonError::Config {
+        message: "no 'user' specified for mysql backend".to_string(),
+    })?;
+
+    let password = resolve_profile_secret(profile.password.as_deref())?
+        .or_else(|| env_non_empty("MYSQL_PWD").map(SecretString::from));
+
+    let ssl_mode = match resolve_profile_string(profile.ssl_mode.as_deref())? {
+        None => MySqlSslMode::Preferred,
+        Some(name) => MySqlSslMode::from_name(&name).ok_or_else(|| DbtoonError::Config {
+            message: format!(
+                "unknown ssl_mode '{}' (expected disabled, preferred, required, verify_ca or verify_identity)",
+                name
+            ),
+        })?,
+    };
+
+    Ok(BackendConfig::MySql {
+        host,
+        port,
+        database,
+        user,
+        password,
+        ssl_mode,
+    })
+}
+
+/// ClickHouse HTTP interface settings, with `clickhouse-client`'s
+/// `CLICKHOUSE_HOST`, `CLICKHOUSE_USER` and `CLICKHOUSE_PASSWORD` as
+/// lowest-priority fallbacks and `default` as the user.
+///
+/// A bare `host` is reached over HTTPS, so give `http://host:8123` for an
+/// unsecured server.
+fn build_clickhouse_config(
+    profile: &TomlProfile,
+    cli_database: Option<&str>,
+) -> Result<BackendConfig, DbtoonError> {
+    let host = resolve_profile_string(profile.host.as_deref())?
+        .or_else(|| env_non_empty("CLICKHOUSE_HOST"))
+        .ok_or_else(|| DbtoonError::Config {
+            message: "no 'host' specified for clickhouse backend".to_string(),
+        })?;
+
+    let database = match cli_database {
+        Some(db) => Some(db.to_string()),
+        None => resolve_profile_string(profile.database.as_deref())?,
+    };
+
+    let user = resolve_profile_string(profile.user.as_deref())?
+        .or_else(|| env_non_empty("CLICKHOUSE_USER"))
+        .unwrap_or_else(|| "default".to_string());
+
+    let password = resolve_profile_secret(profile.password.as_deref())?
+        .or_else(|| env_non_empty("CLICKHOUSE_PASSWORD").map(SecretString::from));
+
+    Ok(BackendConfig::ClickHouse {
+        host,
+        port: profile.port,
+        database,
+        user,
+        password,
+    })
+}
+
+/// BigQuery settings, with the Google Cloud SDK's `GOOGLE_CLOUD_PROJECT` and
+/// `GOOGLE_APPLICATION_CREDENTIALS` as lowest-priority fallbacks. A CLI
+/// `--database` overrides the dataset.
+fn build_bigquery_config(
+    profile: &TomlProfile,
+    cli_database: Option<&str>,
+) -> Result<BackendConfig, DbtoonError> {
+    let host = resolve_profile_string(profile.host.as_deref())?;
+    let credentials_path = resolve_profile_string(profile.credentials_path.as_deref())?
+        .or_else(|| env_non_empty("GOOGLE_APPLICATION_CREDENTIALS"))
+        .map(|path| resolve_path(&path));
+    if credentials_path.is_none() && host.is_none() {
+        return Err(DbtoonError::Config {
+            message: "no 'credentials_path' specified for bigquery backend".to_string(),
+        });
+    }
+
+    let project = resolve_profile_string(profile.project.as_deref())?
+        .or_else(|| env_non_empty("GOOGLE_CLOUD_PROJECT"));
+
+    let dataset = match cli_database {
+        Some(db) => Some(db.to_string()),
+        None => resolve_profile_string(profile.dataset.as_deref())?,
+    };
+
+    Ok(BackendConfig::BigQuery {
+        project,
+        credentials_path,
+        dataset,
+        location: resolve_profile_string(profile.location.as_deref())?,
+        host,
+    })
+}
+
+/// SQLite database `path` (or `:memory:`), which a CLI `--database` overrides.
+fn build_sqlite_config(
+    profile: &TomlProfile,
+    cli_database: Option<&str>,
+) -> Result<BackendConfig, DbtoonError> {
+    let path = match cli_database {
+        Some(db) => db.to_string(),
+        None => resolve_profile_string(profile.path.as_deref())?.ok_or_else(|| {
+            DbtoonError::Config {
+                message: "no 'path' specified for sqlite backend (use ':memory:' for an in-memory database)"
+                    .to_string(),
+            }
+        })?,
+    };
+    // `:memory:` and `file:` URIs are not paths
+    let path = if path == crate::backend::sqlite::MEMORY_PATH || path.starts_with("file:") {
+        path
+    } else {
+        resolve_path(&path).to_string_lossy().into_owned()
+    };
+    Ok(BackendConfig::Sqlite { path })
+}
+
+/// Snowflake key-pair settings, with the `SNOWFLAKE_*` variables of Snowflake's
+/// own tools (and `PRIVATE_KEY_PASSPHRASE`) as lowest-priority fallbacks.
+fn build_snowflake_config(
+    profile: &TomlProfile,
+    cli_database: Option<&str>,
+    cli_schema: Option<&str>,
+) -> Result<BackendConfig, DbtoonError> {
+    let setting = |value: Option<&str>, env_key: &str| -> Result<Option<String>, DbtoonError> {
+        Ok(resolve_profile_string(value)?.or_else(|| env_non_empty(env_key)))
+    };
+    let required = |value: Option<String>, field: &str| {
+        value.ok_or_else(|| DbtoonError::Config {
+            message: format!("no '{}' specified for snowflake backend", field),
+        })
+    };
+
+    let account = required(setting(profile.account.as_deref(), "SNOWFLAKE_ACCOUNT")?, "account")?;
+    let user = required(setting(profile.user.as_deref(), "SNOWFLAKE_USER")?, "user")?;
+    let private_key_path = required(
+        setting(profile.private_key_path.as_deref(), "SNOWFLAKE_PRIVATE_KEY_PATH")?,
+        "private_key_path",
+    )?;
+    let private_key_passphrase = resolve_profile_secret(profile.private_key_passphrase.as_deref())?
+        .or_else(|| env_non_empty("PRIVATE_KEY_PASSPHRASE").map(SecretString::from));
+
+    let database = match cli_database {
+        Some(db) => Some(db.to_string()),
+        None => setting(profile.database.as_deref(), "SNOWFLAKE_DATABASE")?,
+    };
+    let schema = match cli_schema {
+        Some(s) => Some(s.to_string()),
+        None => setting(profile.schema.as_deref(), "SNOWFLAKE_SCHEMA")?,
+    };
+
+    Ok(BackendConfig::Snowflake {
+        account,
+        host: resolve_profile_string(profile.host.as_deref())?,
+        user,
+        private_key_path: resolve_path(&private_key_path),
+        private_key_passphrase,
+        warehouse: setting(profile.warehouse.as_deref(), "SNOWFLAKE_WAREHOUSE")?,
+        database,
+        schema,
+        role: setting(profile.role.as_deref(), "SNOWFLAKE_ROLE")?,
+    })
+}
+
+/// Trino (or, with `presto`, Presto) coordinator settings; `-d` overrides
+/// `catalog` and `-s` `schema`.
+///
+/// `password` selects basic authentication and `token` a JWT; a bare `host`
+/// is reached over HTTPS, so give `http://host` for an unsecured cluster.
+fn build_trino_config(
+    profile: &TomlProfile,
+    cli_database: Option<&str>,
+    cli_schema: Option<&str>,
+    presto: bool,
+) -> Result<BackendConfig, DbtoonError> {
+    let backend = if presto { "presto" } else { "trino" };
+    let host = resolve_profile_string(profile.host.as_deref())?.ok_or_else(|| DbtoonError::Config {
+        message: format!("no 'host' specified for {} backend", backend),
+    })?;
+    let user = resolve_profile_string(profile.user.as_deref())?.ok_or_else(|| DbtoonError::Config {
+        message: format!("no 'user' specified for {} backend", backend),
+    })?;
+
+    let auth = match (
+        resolve_profile_secret(profile.password.as_deref())?,
+        resolve_profile_secret(profile.token.as_deref())?,
+    ) {
+        (Some(_), Some(_)) => {
+            return Err(DbtoonError::Config {
+                message: format!("'password' and 'token' cannot both be set for {} backend", backend),
+            });
+        }
+        (Some(password), None) => TrinoAuth::Password(password),
+        (None, Some(token)) => TrinoAuth::Jwt(token),
+        (None, None) => TrinoAuth::None,
+    };
+
+    let catalog = match cli_database {
+        Some(db) => Some(db.to_string()),
+        None => resolve_profile_string(profile.catalog.as_deref())?,
+    };
+    let schema = match cli_schema {
+        Some(s) => Some(s.to_string()),
+        None => resolve_profile_string(profile.schema.as_deref())?,
+    };
+
+    Ok(BackendConfig::Trino {
+        presto,
+        host,
+        port: profile.port,
+        user,
+        auth,
+        catalog,
+        schema,
+    })
+}
+
 /// Build AppConfig from query args.
 pub fn load_from_query_args(
     args: &QueryArgs,
@@ -359,6 +1241,11 @@ pub fn load_from_query_args(
         verbose,
         show_secrets,
         output_file: args.output.clone(),
+        download_concurrency: download_concurrency(toml_config),
+        retry: retry_policy(&profile, toml_config),
+        dry_run: args.dry_run,
+        working_dir: working_dir(),
+        shared_session: false,
     })
 }
 
@@ -382,5 +1269,37 @@ pub fn load_from_warehouse_list_args(
         verbose,
         show_secrets,
         output_file: None,
+        download_concurrency: download_concurrency(toml_config),
+        retry: retry_policy(&profile, toml_config),
+        dry_run: false,
+        working_dir: working_dir(),
+        shared_session: false,
     })
 }
+
+/// Parallel Databricks chunk downloads: defaults > built-in default.
+fn download_concurrency(toml_config: &TomlConfig) -> usize {
+    toml_config
+        .defaults
+        .download_concurrency
+        .unwrap_or(crate::backend::databricks::DEFAULT_DOWNLOAD_CONCURRENCY)
+        .max(1)
+}
+
+/// Retry policy for transient HTTP failures: profile > defaults > built-in default.
+fn retry_policy(profile: &TomlProfile, toml_config: &TomlConfig) -> RetryPolicy {
+    let defaults = &toml_config.defaults;
+    let max_attempts = profile
+        .retry_attempts
+        .or(defaults.retry_attempts)
+        .unwrap_or(DEFAULT_RETRY_ATTEMPTS)
+        .max(1);
+    let backoff_ms = profile
+        .retry_backoff_ms
+        .or(defaults.retry_backoff_ms)
+        .unwrap_or(DEFAULT_RETRY_BACKOFF_MS);
+    RetryPolicy {
+        max_attempts,
+        initial_backoff: std::time::Duration::from_millis(backoff_ms),
+    }
+}
@@ -0,0 +1,535 @@
+//! Connection-keeping daemon (`dbtoon daemon run`) and its client.
+//!
+//! The daemon listens on a Unix socket and runs `dbtoon query` requests in one
+//! long-lived process that reuses connections (see `backend::reuse_connections`),
+//! so ODBC logins and TLS handshakes are paid once per profile rather than per
+//! query. Each request reads the config file afresh, resolving `$VAR`s, env
+//! fallbacks and relative paths against the client's environment, and goes
+//! through `query::run_to_output`, the same validation and output path as a
+//! direct query.
+//!
+//! Every message is a frame: a kind byte, a big-endian `u32` length and the
+//! payload. The client sends one request frame and keeps its end open; the
+//! daemon answers with stdout and stderr frames, then a done frame carrying the
+//! outcome. A client closing its end early cancels the query.
+
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{Notify, mpsc};
+
+use crate::cancel::{self, CancelToken};
+use crate::cli::QueryArgs;
+use crate::error::DbtoonError;
+use crate::{backend, config, params, query, verbose};
+
+const REQUEST: u8 = b'q';
+const STDOUT: u8 = b'o';
+const STDERR: u8 = b'e';
+const DONE: u8 = b'd';
+
+/// Largest request frame the daemon accepts.
+const MAX_REQUEST_BYTES: usize = 64 * 1024 * 1024;
+
+/// Output frames queued for a client before the query waits for it to read them.
+const QUEUED_FRAMES: usize = 64;
+
+/// How often idle connections are swept, at most.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Settings for `serve`.
+#[derive(Debug, Clone)]
+pub struct DaemonOptions {
+    /// Close connections left unused this long.
+    pub idle_timeout: Duration,
+    /// Exit once no query has run for this long.
+    pub exit_after: Option<Duration>,
+    pub verbose: bool,
+}
+
+/// What a client asks the daemon to do.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Request {
+    Query(Box<QueryRequest>),
+    Status,
+    Stop,
+}
+
+/// A `dbtoon query` invocation, with paths made absolute and the SQL already read.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QueryRequest {
+    /// `--config` of the client.
+    pub config: Option<PathBuf>,
+    /// The client's env vars, including those from its `.env`, and working directory.
+    pub environment: config::Environment,
+    pub verbose: bool,
+    pub show_secrets: bool,
+    pub sql: String,
+    pub args: QueryArgs,
+}
+
+impl QueryRequest {
+    /// The request for `args`, resolving relative paths against the client's
+    /// working directory, since the daemon's may differ.
+    pub fn new(
+        args: &QueryArgs,
+        sql: String,
+        config: Option<&PathBuf>,
+        verbose: bool,
+        show_secrets: bool,
+    ) -> Result<Self, DbtoonError> {
+        let mut args = args.clone();
+        args.sql = None;
+        args.file = None;
+        args.output = args.output.as_deref().map(std::path::absolute).transpose()?;
+        Ok(Self {
+            config: config.map(std::path::absolute).transpose()?,
+            environment: config::Environment::current()?,
+            verbose,
+            show_secrets,
+            sql,
+            args,
+        })
+    }
+}
+
+/// A running daemon's activity, as reported by `dbtoon daemon status`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Status {
+    pub pid: u32,
+    pub uptime_secs: u64,
+    /// Queries received since the daemon started.
+    pub queries: u64,
+    /// Queries running now.
+    pub running: usize,
+    /// Open connections waiting for a query.
+    pub idle_connections: usize,
+}
+
+/// How a request ended, sent in the done frame.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Outcome {
+    Ok,
+    Status(Status),
+    Error(RemoteError),
+}
+
+/// A `DbtoonError` in transit, rebuilt on the client so it prints and exits the same way.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum RemoteError {
+    Validation { reason: String },
+    Connection { message: String },
+    Query { message: String },
+    Timeout { seconds: u64 },
+    Config { message: String },
+    Auth { message: String },
+    Io { message: String },
+    Format { message: String },
+    Cancelled,
+}
+
+impl From<&DbtoonError> for RemoteError {
+    fn from(err: &DbtoonError) -> Self {
+        match err {
+            DbtoonError::Validation { reason } => Self::Validation { reason: reason.clone() },
+            DbtoonError::Connection { message } => Self::Connection { message: message.clone() },
+            DbtoonError::Query { message } => Self::Query { message: message.clone() },
+            DbtoonError::Timeout { seconds } => Self::Timeout { seconds: *seconds },
+            DbtoonError::Config { message } => Self::Config { message: message.clone() },
+            DbtoonError::Auth { message } => Self::Auth { message: message.clone() },
+            DbtoonError::Io(e) => Self::Io { message: e.to_string() },
+            DbtoonError::Format { message } => Self::Format { message: message.clone() },
+            DbtoonError::Cancelled => Self::Cancelled,
+        }
+    }
+}
+
+impl From<RemoteError> for DbtoonError {
+    fn from(err: RemoteError) -> Self {
+        match err {
+            RemoteError::Validation { reason } => Self::Validation { reason },
+            RemoteError::Connection { message } => Self::Connection { message },
+            RemoteError::Query { message } => Self::Query { message },
+            RemoteError::Timeout { seconds } => Self::Timeout { seconds },
+            RemoteError::Config { message } => Self::Config { message },
+            RemoteError::Auth { message } => Self::Auth { message },
+            RemoteError::Io { message } => Self::Io(std::io::Error::other(message)),
+            RemoteError::Format { message } => Self::Format { message },
+            RemoteError::Cancelled => Self::Cancelled,
+        }
+    }
+}
+
+// --- Daemon ---
+
+struct State {
+    started: Instant,
+    queries: AtomicU64,
+    running: AtomicUsize,
+    last_active: Mutex<Instant>,
+    stop: Notify,
+}
+
+impl State {
+    fn status(&self) -> Status {
+        Status {
+            pid: std::process::id(),
+            uptime_secs: self.started.elapsed().as_secs(),
+            queries: self.queries.load(Ordering::Relaxed),
+            running: self.running.load(Ordering::Relaxed),
+            idle_connections: backend::idle_connection_count(),
+        }
+    }
+
+    fn touch(&self) {
+        *self.last_active.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+    }
+
+    /// Whether no query has run for `exit_after`.
+    fn idle_for(&self, exit_after: Duration) -> bool {
+        self.running.load(Ordering::Relaxed) == 0
+            && self.last_active.lock().unwrap_or_else(|e| e.into_inner()).elapsed() >= exit_after
+    }
+}
+
+/// Removes the socket file when the daemon stops.
+struct SocketFile<'a>(&'a Path);
+
+impl Drop for SocketFile<'_> {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(self.0);
+    }
+}
+
+/// Serve requests on `socket` until a stop request, Ctrl-C or SIGTERM, or
+/// until idle for `options.exit_after`.
+///
+/// The socket is created readable and writable by the owner only. Output
+/// waiting on a slow client blocks the query's thread, so this must run on a
+/// multi-threaded runtime.
+pub async fn serve(socket: &Path, options: DaemonOptions) -> Result<(), DbtoonError> {
+    let verbose = options.verbose;
+    if UnixStream::connect(socket).await.is_ok() {
+        return Err(DbtoonError::Config {
+            message: format!("a daemon is already running at {}", socket.display()),
+        });
+    }
+    // Left behind by a daemon that did not shut down cleanly
+    let _ = std::fs::remove_file(socket);
+    if let Some(parent) = socket.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let listener = UnixListener::bind(socket)?;
+    let _socket_file = SocketFile(socket);
+    std::fs::set_permissions(socket, std::fs::Permissions::from_mode(0o600))?;
+
+    backend::reuse_connections(options.idle_timeout);
+    verbose::emit(verbose, &format!("daemon listening on {}", socket.display()));
+
+    let state = Arc::new(State {
+        started: Instant::now(),
+        queries: AtomicU64::new(0),
+        running: AtomicUsize::new(0),
+        last_active: Mutex::new(Instant::now()),
+        stop: Notify::new(),
+    });
+    let mut sweep = tokio::time::interval(SWEEP_INTERVAL.min(options.idle_timeout).max(Duration::from_secs(1)));
+    let shutdown = cancel::shutdown_signal();
+    tokio::pin!(shutdown);
+
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => {
+                    tokio::spawn(handle(stream, state.clone()));
+                }
+                Err(e) => verbose::emit(verbose, &format!("accept failed: {}", e)),
+            },
+            _ = sweep.tick() => {
+                backend::close_idle_connections();
+                if let Some(exit_after) = options.exit_after
+                    && state.idle_for(exit_after)
+                {
+                    verbose::emit(verbose, "daemon idle — exiting");
+                    break;
+                }
+            }
+            _ = state.stop.notified() => {
+                verbose::emit(verbose, "daemon stop requested");
+                break;
+            }
+            _ = &mut shutdown => break,
+        }
+    }
+
+    backend::close_all_connections();
+    Ok(())
+}
+
+async fn handle(stream: UnixStream, state: Arc<State>) {
+    let (mut reader, mut writer) = stream.into_split();
+    let request = match read_frame(&mut reader, MAX_REQUEST_BYTES).await {
+        Ok(Some((REQUEST, payload))) => serde_json::from_slice::<Request>(&payload).map_err(|e| e.to_string()),
+        Ok(Some((kind, _))) => Err(format!("unexpected frame kind {:?}", kind as char)),
+        Ok(None) => return,
+        Err(e) => Err(e.to_string()),
+    };
+
+    let outcome = match request {
+        Ok(Request::Status) => Outcome::Status(state.status()),
+        Ok(Request::Stop) => {
+            state.stop.notify_one();
+            Outcome::Ok
+        }
+        Ok(Request::Query(request)) => return run_query(*request, reader, writer, &state).await,
+        Err(message) => Outcome::Error(RemoteError::Config {
+            message: format!("invalid daemon request: {}", message),
+        }),
+    };
+    let _ = write_frame(&mut writer, DONE, &encode(&outcome)).await;
+}
+
+/// Run a query request, forwarding its output to the client as it is produced.
+async fn run_query<R, W>(request: QueryRequest, mut reader: R, mut writer: W, state: &State)
+where
+    R: AsyncRead + Unpin + Send + 'static,
+    W: AsyncWrite + Unpin,
+{
+    state.queries.fetch_add(1, Ordering::Relaxed);
+    state.running.fetch_add(1, Ordering::Relaxed);
+    state.touch();
+
+    let cancel = CancelToken::new();
+    // The client closes its end on Ctrl-C, or when it exits
+    let hangup = {
+        let cancel = cancel.clone();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            while let Ok(n) = reader.read(&mut buf).await {
+                if n == 0 {
+                    break;
+                }
+            }
+            cancel.cancel();
+        })
+    };
+
+    let (frames, mut outgoing) = mpsc::channel::<(u8, Vec<u8>)>(QUEUED_FRAMES);
+    let work = {
+        let cancel = cancel.clone();
+        async move {
+            let stdout = FrameWriter { kind: STDOUT, frames: frames.clone() };
+            let mut stderr = FrameWriter { kind: STDERR, frames: frames.clone() };
+            let outcome = match execute(&request, &cancel, stdout, &mut stderr).await {
+                Ok(()) => Outcome::Ok,
+                Err(e) => Outcome::Error(RemoteError::from(&e)),
+            };
+            let _ = frames.send((DONE, encode(&outcome))).await;
+        }
+    };
+    let forward = async {
+        while let Some((kind, payload)) = outgoing.recv().await {
+            if write_frame(&mut writer, kind, &payload).await.is_err() {
+                // Writes to the dropped channel then fail, stopping the output sinks
+                cancel.cancel();
+                break;
+            }
+        }
+    };
+    tokio::join!(work, forward);
+
+    hangup.abort();
+    state.running.fetch_sub(1, Ordering::Relaxed);
+    state.touch();
+}
+
+async fn execute(
+    request: &QueryRequest,
+    cancel: &CancelToken,
+    stdout: FrameWriter,
+    stderr: &mut FrameWriter,
+) -> Result<(), DbtoonError> {
+    let app_config = config::with_environment(request.environment.clone(), || {
+        let (toml_config, _) = config::load_toml_config_required(request.config.as_ref())?;
+        config::load_from_query_args(&request.args, &toml_config, request.verbose, request.show_secrets)
+    })?;
+    let params = params::parse_params(&request.args.params)?;
+    query::run_to_output(&app_config, &request.sql, &params, cancel, stdout, stderr).await
+}
+
+/// Sends everything written to it to the client as frames of `kind`, blocking
+/// while `QUEUED_FRAMES` are waiting to go out.
+struct FrameWriter {
+    kind: u8,
+    frames: mpsc::Sender<(u8, Vec<u8>)>,
+}
+
+impl Write for FrameWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let sent = match self.frames.try_send((self.kind, buf.to_vec())) {
+            Err(TrySendError::Full(frame)) => {
+                tokio::task::block_in_place(|| self.frames.blocking_send(frame)).is_ok()
+            }
+            sent => sent.is_ok(),
+        };
+        if !sent {
+            return Err(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "daemon client disconnected"));
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+// --- Client ---
+
+/// A connection to the daemon listening on `socket`, if one is.
+pub async fn connect(socket: &Path) -> Option<UnixStream> {
+    UnixStream::connect(socket).await.ok()
+}
+
+/// Run `request` on the daemon behind `stream`, copying its output to
+/// `stdout` and `stderr` as it arrives.
+///
+/// Firing `cancel` hangs up on the daemon, which cancels the query and
+/// reports `DbtoonError::Cancelled`.
+pub async fn forward_query(
+    stream: UnixStream,
+    request: QueryRequest,
+    cancel: &CancelToken,
+    stdout: &mut impl Write,
+    stderr: &mut impl Write,
+) -> Result<(), DbtoonError> {
+    let (mut reader, mut writer) = stream.into_split();
+    write_frame(&mut writer, REQUEST, &encode(&Request::Query(Box::new(request)))).await?;
+
+    let mut writer = Some(writer);
+    loop {
+        let frame = tokio::select! {
+            frame = read_frame(&mut reader, usize::MAX) => frame?,
+            _ = cancel.cancelled(), if writer.is_some() => {
+                // Dropping the write half closes it
+                writer = None;
+                continue;
+            }
+        };
+        match frame {
+            Some((STDOUT, bytes)) => {
+                stdout.write_all(&bytes)?;
+                stdout.flush()?;
+            }
+            Some((STDERR, bytes)) => stderr.write_all(&bytes)?,
+            Some((DONE, payload)) => {
+                return match outcome(&payload)? {
+                    Outcome::Error(e) => Err(e.into()),
+                    _ => Ok(()),
+                };
+            }
+            Some((kind, _)) => {
+                return Err(DbtoonError::Connection {
+                    message: format!("unexpected frame kind {:?} from daemon", kind as char),
+                });
+            }
+            None => {
+                return Err(DbtoonError::Connection {
+                    message: "daemon closed the connection before the query finished".to_string(),
+                });
+            }
+        }
+    }
+}
+
+/// The status of the daemon listening on `socket`, or `None` if none is.
+pub async fn status(socket: &Path) -> Result<Option<Status>, DbtoonError> {
+    let Some(stream) = connect(socket).await else {
+        return Ok(None);
+    };
+    match round_trip(stream, &Request::Status).await? {
+        Outcome::Status(status) => Ok(Some(status)),
+        Outcome::Error(e) => Err(e.into()),
+        Outcome::Ok => Err(DbtoonError::Connection {
+            message: "daemon sent no status".to_string(),
+        }),
+    }
+}
+
+/// Ask the daemon listening on `socket` to stop.
+pub async fn stop(socket: &Path) -> Result<(), DbtoonError> {
+    let Some(stream) = connect(socket).await else {
+        return Err(DbtoonError::Config {
+            message: format!("no daemon is running at {}", socket.display()),
+        });
+    };
+    match round_trip(stream, &Request::Stop).await? {
+        Outcome::Error(e) => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+async fn round_trip(stream: UnixStream, request: &Request) -> Result<Outcome, DbtoonError> {
+    let (mut reader, mut writer) = stream.into_split();
+    write_frame(&mut writer, REQUEST, &encode(request)).await?;
+    match read_frame(&mut reader, MAX_REQUEST_BYTES).await? {
+        Some((DONE, payload)) => outcome(&payload),
+        _ => Err(DbtoonError::Connection {
+            message: "daemon closed the connection without answering".to_string(),
+        }),
+    }
+}
+
+fn outcome(payload: &[u8]) -> Result<Outcome, DbtoonError> {
+    serde_json::from_slice(payload).map_err(|e| DbtoonError::Connection {
+        message: format!("invalid reply from daemon: {}", e),
+    })
+}
+
+// --- Framing ---
+
+/// JSON for a frame payload; these types always serialize.
+fn encode(value: &impl Serialize) -> Vec<u8> {
+    serde_json::to_vec(value).unwrap_or_default()
+}
+
+async fn write_frame(writer: &mut (impl AsyncWrite + Unpin), kind: u8, payload: &[u8]) -> std::io::Result<()> {
+    let len = u32::try_from(payload.len())
+        .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidInput, "frame too large"))?;
+    let mut frame = Vec::with_capacity(5 + payload.len());
+    frame.push(kind);
+    frame.extend_from_slice(&len.to_be_bytes());
+    frame.extend_from_slice(payload);
+    writer.write_all(&frame).await?;
+    writer.flush().await
+}
+
+/// The next frame, or `None` if the peer closed the connection between frames.
+async fn read_frame(
+    reader: &mut (impl AsyncRead + Unpin),
+    max_len: usize,
+) -> std::io::Result<Option<(u8, Vec<u8>)>> {
+    let mut head = [0u8; 5];
+    match reader.read_exact(&mut head).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let len = u32::from_be_bytes([head[1], head[2], head[3], head[4]]) as usize;
+    if len > max_len {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "frame too large"));
+    }
+    let mut payload = vec![0u8; len];
+    reader.read_exact(&mut payload).await?;
+    Ok(Some((head[0], payload)))
+}
@@ -25,4 +25,17 @@ pub enum DbtoonError {
 
     #[error("format: {message}")]
     Format { message: String },
+
+    #[error("cancelled: query was cancelled")]
+    Cancelled,
+}
+
+impl DbtoonError {
+    /// Process exit code: 130 for a cancelled query (as after SIGINT), 1 otherwise.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            DbtoonError::Cancelled => 130,
+            _ => 1,
+        }
+    }
 }
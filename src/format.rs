@@ -1,47 +1,368 @@
-use crate::backend::{CellValue, QueryResult};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+
+use crate::backend::{CellValue, ColumnMeta, QueryResult, ResultSink, StreamSummary};
 use crate::error::DbtoonError;
 
 /// Convert a QueryResult to a TOON-formatted string with truncation metadata.
 ///
-/// Output is a root object: `{ "types": [...], "rows": [...], "truncated": bool, "message"?: str }`.
-/// The `"truncated"` key is always present. The `"message"` key is present only when truncated.
+/// Output is a root object: `{ "types": [...], "rows": [...], "truncated": bool, "message"?: str,
+/// "messages"?: [str], "rows_affected"?: number }`.
+/// The `"truncated"` key is always present. The `"message"` key is present only when truncated;
+/// `"messages"` and `"rows_affected"` only when the backend reported them.
 pub fn to_toon(
     result: &QueryResult,
     truncated: bool,
     message: Option<&str>,
 ) -> Result<String, DbtoonError> {
-    let types: Vec<serde_json::Value> = result
-        .columns
-        .iter()
-        .map(|c| serde_json::Value::String(c.type_name.clone()))
-        .collect();
-
     let rows: Vec<serde_json::Value> = result
         .rows
         .iter()
-        .map(|row| {
-            let mut map = serde_json::Map::new();
-            for (i, col) in result.columns.iter().enumerate() {
-                let value = row.get(i).unwrap_or(&CellValue::Null);
-                let json_val = match value {
-                    CellValue::Text(s) => serde_json::Value::String(s.clone()),
-                    CellValue::Null => serde_json::Value::Null,
-                };
-                map.insert(col.name.clone(), json_val);
-            }
-            serde_json::Value::Object(map)
-        })
+        .map(|row| row_to_json(&result.columns, row))
         .collect();
 
     let mut root = serde_json::Map::new();
-    root.insert("types".to_string(), serde_json::Value::Array(types));
+    root.insert("types".to_string(), types_to_json(&result.columns));
     root.insert("rows".to_string(), serde_json::Value::Array(rows));
     root.insert("truncated".to_string(), serde_json::Value::Bool(truncated));
     if let Some(msg) = message {
         root.insert("message".to_string(), serde_json::Value::String(msg.to_string()));
     }
+    insert_server_info(&mut root, &result.messages, result.rows_affected);
+
+    encode(&serde_json::Value::Object(root))
+}
+
+/// One result set of a multi-set batch, as rendered by [`to_toon_results`].
+pub struct ToonResultSet<'a> {
+    pub result: &'a QueryResult,
+    pub truncated: bool,
+    pub message: Option<&'a str>,
+}
+
+/// Convert the result sets of one batch to TOON.
+///
+/// A single set renders exactly like [`to_toon`]. Several sets render as
+/// `{ "results": [{ "truncated", "message"?, "messages"?, "rows_affected"?, "types", "rows" }, ...] }`.
+pub fn to_toon_results(sets: &[ToonResultSet<'_>]) -> Result<String, DbtoonError> {
+    if let [set] = sets {
+        return to_toon(set.result, set.truncated, set.message);
+    }
+
+    let mut out = format!("results[{}]:", sets.len()).into_bytes();
+    for set in sets {
+        let (fields, body) = encode_rows(&set.result.columns, &set.result.rows)?;
+        let rows_header = rows_header(set.result.rows.len(), fields.as_deref());
+        let meta = result_meta(
+            set.truncated,
+            set.message,
+            &set.result.messages,
+            set.result.rows_affected,
+        );
+        write_result_item(&mut out, meta, &set.result.columns, &rows_header, |out| {
+            copy_indented(body.as_bytes(), out, RESULT_ROW_INDENT)
+        })?;
+    }
+    Ok(String::from_utf8(out).expect("TOON output is UTF-8"))
+}
+
+/// Extra indentation of table rows inside a `results` list item.
+const RESULT_ROW_INDENT: &str = "    ";
+
+/// Write one `results` list item, starting with a newline.
+///
+/// Items are assembled by hand with the rows table last: toon-format 0.4 does not
+/// indent tables nested in list items consistently with its own decoder.
+fn write_result_item<W: Write>(
+    out: &mut W,
+    meta: serde_json::Map<String, serde_json::Value>,
+    columns: &[ColumnMeta],
+    rows_header: &str,
+    write_rows: impl FnOnce(&mut W) -> Result<(), DbtoonError>,
+) -> Result<(), DbtoonError> {
+    for (i, line) in encode(&serde_json::Value::Object(meta))?.lines().enumerate() {
+        let prefix = if i == 0 { "\n  - " } else { "\n    " };
+        write!(out, "{prefix}{line}")?;
+    }
+
+    let mut types = serde_json::Map::new();
+    types.insert("types".to_string(), types_to_json(columns));
+    write!(out, "\n    {}", encode(&serde_json::Value::Object(types))?)?;
+    write!(out, "\n    {rows_header}")?;
+    write_rows(out)
+}
+
+/// The keys written after a result set's rows: `truncated`, `message`?, `messages`?, `rows_affected`?.
+fn result_meta(
+    truncated: bool,
+    message: Option<&str>,
+    messages: &[String],
+    rows_affected: Option<usize>,
+) -> serde_json::Map<String, serde_json::Value> {
+    let mut meta = serde_json::Map::new();
+    meta.insert("truncated".to_string(), serde_json::Value::Bool(truncated));
+    if let Some(msg) = message {
+        meta.insert("message".to_string(), serde_json::Value::String(msg.to_string()));
+    }
+    insert_server_info(&mut meta, messages, rows_affected);
+    meta
+}
+
+/// Add `messages` and `rows_affected`, each only when the backend reported it.
+fn insert_server_info(
+    map: &mut serde_json::Map<String, serde_json::Value>,
+    messages: &[String],
+    rows_affected: Option<usize>,
+) {
+    if !messages.is_empty() {
+        let messages = messages.iter().cloned().map(serde_json::Value::String).collect();
+        map.insert("messages".to_string(), serde_json::Value::Array(messages));
+    }
+    if let Some(count) = rows_affected {
+        map.insert("rows_affected".to_string(), serde_json::Value::from(count));
+    }
+}
+
+/// Copy newline-led lines from `lines` to `out`, prefixing each with `indent`.
+fn copy_indented(mut lines: impl BufRead, out: &mut impl Write, indent: &str) -> Result<(), DbtoonError> {
+    let mut line = Vec::new();
+    let mut first = true;
+    while lines.read_until(b'\n', &mut line)? > 0 {
+        if !first {
+            out.write_all(b"\n")?;
+            out.write_all(indent.as_bytes())?;
+            out.write_all(line.strip_suffix(b"\n").unwrap_or(&line))?;
+        }
+        first = false;
+        line.clear();
+    }
+    Ok(())
+}
+
+/// `rows[N]{fields}:`, or `rows[0]:` when no rows were encoded.
+fn rows_header(rows: usize, fields: Option<&str>) -> String {
+    match fields {
+        Some(fields) => format!("rows[{rows}]{fields}"),
+        None => "rows[0]:".to_string(),
+    }
+}
+
+/// Encode rows as a TOON table, returning the field list of its header (`{a,b}:`)
+/// and the indented row lines, each led by a newline. Empty input has no header.
+fn encode_rows(
+    columns: &[ColumnMeta],
+    rows: &[Vec<CellValue>],
+) -> Result<(Option<String>, String), DbtoonError> {
+    if rows.is_empty() {
+        return Ok((None, String::new()));
+    }
+    let rows_json: Vec<serde_json::Value> =
+        rows.iter().map(|row| row_to_json(columns, row)).collect();
+    let mut root = serde_json::Map::new();
+    root.insert("rows".to_string(), serde_json::Value::Array(rows_json));
+    let encoded = encode(&serde_json::Value::Object(root))?;
+
+    let (header, body) = encoded.split_once('\n').unwrap_or((encoded.as_str(), ""));
+    let fields = header
+        .find(']')
+        .map(|pos| header[pos + 1..].to_string())
+        .ok_or_else(|| DbtoonError::Format {
+            message: format!("unexpected TOON table header: {header}"),
+        })?;
+    Ok((Some(fields), format!("\n{body}")))
+}
+
+fn types_to_json(columns: &[ColumnMeta]) -> serde_json::Value {
+    serde_json::Value::Array(
+        columns
+            .iter()
+            .map(|c| serde_json::Value::String(c.type_name.clone()))
+            .collect(),
+    )
+}
+
+fn row_to_json(columns: &[ColumnMeta], row: &[CellValue]) -> serde_json::Value {
+    let mut map = serde_json::Map::new();
+    for (i, col) in columns.iter().enumerate() {
+        let value = row.get(i).unwrap_or(&CellValue::Null);
+        map.insert(col.name.clone(), cell_to_json(value, &col.type_name));
+    }
+    serde_json::Value::Object(map)
+}
 
-    toon_format::encode_default(&serde_json::Value::Object(root))
+/// Numbers and booleans become native JSON values; everything else is a string.
+///
+/// Text cells are parsed per the column `type_name` (INT, DECIMAL, FLOAT, BIT, ...)
+/// and keep their original text when they do not parse.
+fn cell_to_json(value: &CellValue, type_name: &str) -> serde_json::Value {
+    match value {
+        CellValue::Null => serde_json::Value::Null,
+        CellValue::Text(s) => native_json(&CellValue::from_typed_text(s, type_name))
+            .unwrap_or_else(|| serde_json::Value::String(s.clone())),
+        other => native_json(other).unwrap_or_else(|| {
+            serde_json::Value::String(other.to_text().unwrap_or_default().into_owned())
+        }),
+    }
+}
+
+/// The JSON number or boolean for a value, if it has an exact one.
+fn native_json(value: &CellValue) -> Option<serde_json::Value> {
+    match value {
+        CellValue::Int64(n) => Some(serde_json::Value::from(*n)),
+        CellValue::Bool(b) => Some(serde_json::Value::Bool(*b)),
+        CellValue::Float64(f) => serde_json::Number::from_f64(*f).map(serde_json::Value::Number),
+        // Beyond 15 significant digits an f64 may not reproduce the decimal exactly
+        CellValue::Decimal { value: unscaled, .. } if unscaled.unsigned_abs() < 10u128.pow(15) => {
+            value
+                .to_text()?
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(serde_json::Value::Number)
+        }
+        _ => None,
+    }
+}
+
+fn encode(value: &serde_json::Value) -> Result<String, DbtoonError> {
+    toon_format::encode_default(value)
         .map_err(|e| DbtoonError::Format { message: e.to_string() })
 }
 
+/// Streaming TOON writer producing the same document as [`to_toon_results`].
+///
+/// The tabular `rows[N]{...}:` header carries the row count, so encoded rows are
+/// spilled to a temporary file and copied after the header once the stream ends.
+pub struct ToonSink<W: Write> {
+    out: W,
+    current: SpilledSet,
+    /// Result sets closed by `next_result`, alongside their summaries in `summaries`.
+    finished: Vec<SpilledSet>,
+    summaries: Vec<StreamSummary>,
+}
+
+impl<W: Write> ToonSink<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            current: SpilledSet::default(),
+            finished: Vec::new(),
+            summaries: Vec::new(),
+        }
+    }
+
+    /// Summaries of the result sets closed before the current one.
+    pub fn earlier_results(&self) -> &[StreamSummary] {
+        &self.summaries
+    }
+
+    /// Write the complete document to the output.
+    ///
+    /// `last` describes the last (or only) result set.
+    pub fn finish(mut self, last: &StreamSummary) -> Result<(), DbtoonError> {
+        let mut current = std::mem::take(&mut self.current);
+        let mut out = BufWriter::new(&mut self.out);
+
+        if self.finished.is_empty() {
+            let mut head = serde_json::Map::new();
+            head.insert("types".to_string(), types_to_json(&current.columns));
+            let mut head = encode(&serde_json::Value::Object(head))?;
+            head.push('\n');
+            head.push_str(&current.rows_header());
+            out.write_all(head.as_bytes())?;
+
+            current.copy_rows(&mut out, "")?;
+
+            let tail = summary_meta(last);
+            let tail = encode(&serde_json::Value::Object(tail))?;
+            write!(out, "\n{}", tail)?;
+        } else {
+            self.finished.push(current);
+            self.summaries.push(last.clone());
+            write!(out, "results[{}]:", self.finished.len())?;
+            for (set, summary) in self.finished.iter_mut().zip(&self.summaries) {
+                let rows_header = set.rows_header();
+                let columns = std::mem::take(&mut set.columns);
+                write_result_item(&mut out, summary_meta(summary), &columns, &rows_header, |out| {
+                    set.copy_rows(out, RESULT_ROW_INDENT)
+                })?;
+            }
+        }
+
+        out.flush()?;
+        Ok(())
+    }
+}
+
+impl<W: Write + Send> ResultSink for ToonSink<W> {
+    fn begin(&mut self, columns: &[ColumnMeta]) -> Result<(), DbtoonError> {
+        self.current.columns = columns.to_vec();
+        Ok(())
+    }
+
+    fn write_batch(&mut self, rows: &[Vec<CellValue>]) -> Result<(), DbtoonError> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        // Encode the batch as its own `rows` table and keep the indented row lines
+        let (fields, body) = encode_rows(&self.current.columns, rows)?;
+        if self.current.fields_header.is_none() {
+            self.current.fields_header = fields;
+        }
+
+        self.current.spill()?.write_all(body.as_bytes())?;
+        self.current.rows += rows.len();
+        Ok(())
+    }
+
+    fn next_result(&mut self, previous: StreamSummary) -> Result<(), DbtoonError> {
+        self.finished.push(std::mem::take(&mut self.current));
+        self.summaries.push(previous);
+        Ok(())
+    }
+}
+
+fn summary_meta(summary: &StreamSummary) -> serde_json::Map<String, serde_json::Value> {
+    result_meta(
+        summary.truncated,
+        summary.truncation_message().as_deref(),
+        &summary.messages,
+        summary.rows_affected,
+    )
+}
+
+/// Encoded rows of one result set, spilled to an unnamed temporary file.
+#[derive(Default)]
+struct SpilledSet {
+    columns: Vec<ColumnMeta>,
+    spill: Option<BufWriter<File>>,
+    /// Field list of the tabular header (`{a,b}:`), taken from the first batch.
+    fields_header: Option<String>,
+    rows: usize,
+}
+
+impl SpilledSet {
+    fn spill(&mut self) -> Result<&mut BufWriter<File>, DbtoonError> {
+        if self.spill.is_none() {
+            // Created with mode 0600 and already unlinked, so no other user can
+            // open it and it disappears with the process
+            self.spill = Some(BufWriter::new(tempfile::tempfile()?));
+        }
+        Ok(self.spill.as_mut().expect("spill initialized above"))
+    }
+
+    fn rows_header(&self) -> String {
+        rows_header(self.rows, self.fields_header.as_deref())
+    }
+
+    /// Copy the spilled row lines to `out` with `indent` added to each, then drop the spill.
+    fn copy_rows(&mut self, out: &mut impl Write, indent: &str) -> Result<(), DbtoonError> {
+        let Some(spill) = self.spill.take() else {
+            return Ok(());
+        };
+        let mut file = spill.into_inner().map_err(|e| e.into_error())?;
+        file.seek(SeekFrom::Start(0))?;
+        copy_indented(BufReader::new(file), out, indent)
+    }
+}
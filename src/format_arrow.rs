@@ -1,11 +1,135 @@
 use std::fs::File;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+use arrow::ipc::reader::FileReader;
 use arrow::ipc::writer::FileWriter;
+use arrow::datatypes::Schema;
+use arrow::record_batch::RecordBatch;
 
-use crate::backend::QueryResult;
+use crate::backend::{CellValue, ColumnMeta, QueryResult, ResultSink};
 use crate::error::DbtoonError;
-use crate::format_columnar;
+use crate::format_columnar::{self, BatchEncoder};
+
+/// Streaming Arrow IPC file writer that writes each row batch as it arrives.
+///
+/// IPC schema metadata is written with the file header, before the truncation
+/// state is known, so a truncated file is copied batch by batch into one whose
+/// schema records it on `finish`.
+pub struct ArrowSink {
+    path: PathBuf,
+    file: Option<File>,
+    encoder: BatchEncoder,
+    writer: Option<FileWriter<File>>,
+}
+
+impl ArrowSink {
+    /// Create the output file up front so path errors surface before the query runs.
+    pub fn create(path: &Path) -> Result<Self, DbtoonError> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            file: Some(File::create(path)?),
+            encoder: BatchEncoder::new(&[]),
+            writer: None,
+        })
+    }
+
+    fn writer(&mut self) -> Result<&mut FileWriter<File>, DbtoonError> {
+        if self.writer.is_none() {
+            let file = self.file.take().ok_or_else(|| DbtoonError::Format {
+                message: "Arrow IPC output file already consumed".to_string(),
+            })?;
+            self.writer = Some(ipc_writer(file, &self.encoder.schema())?);
+        }
+        Ok(self.writer.as_mut().expect("writer initialized above"))
+    }
+
+    /// Finalize the file. When truncated, schema metadata includes
+    /// `dbtoon:truncated` and `dbtoon:message` keys.
+    pub fn finish(mut self, truncated: bool, message: Option<&str>) -> Result<(), DbtoonError> {
+        finish_ipc(self.writer()?)?;
+        if truncated {
+            add_truncation_metadata(&self.path, message)?;
+        }
+        Ok(())
+    }
+}
+
+impl ResultSink for ArrowSink {
+    fn begin(&mut self, columns: &[ColumnMeta]) -> Result<(), DbtoonError> {
+        self.encoder = BatchEncoder::new(columns);
+        Ok(())
+    }
+
+    fn write_batch(&mut self, rows: &[Vec<CellValue>]) -> Result<(), DbtoonError> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+        let batch = self.encoder.encode(rows)?;
+        self.write_arrow_batch(&batch)
+    }
+
+    fn accepts_arrow(&self) -> bool {
+        true
+    }
+
+    fn write_arrow_batch(&mut self, batch: &RecordBatch) -> Result<(), DbtoonError> {
+        if self.writer.is_none() {
+            self.encoder.set_schema(batch.schema());
+        }
+        write_ipc_batch(self.writer()?, batch)
+    }
+}
+
+fn ipc_writer(file: File, schema: &Schema) -> Result<FileWriter<File>, DbtoonError> {
+    FileWriter::try_new(file, schema).map_err(|e| DbtoonError::Format {
+        message: format!("failed to create Arrow IPC writer: {e}"),
+    })
+}
+
+fn finish_ipc(writer: &mut FileWriter<File>) -> Result<(), DbtoonError> {
+    writer.finish().map_err(|e| DbtoonError::Format {
+        message: format!("failed to finalize Arrow IPC file: {e}"),
+    })
+}
+
+/// Rewrite the finished file at `path` with truncation metadata in its schema,
+/// one batch at a time.
+fn add_truncation_metadata(path: &Path, message: Option<&str>) -> Result<(), DbtoonError> {
+    let read_error = |e: arrow::error::ArrowError| DbtoonError::Format {
+        message: format!("failed to read back Arrow IPC file: {e}"),
+    };
+    let reader = FileReader::try_new(File::open(path)?, None).map_err(read_error)?;
+    let schema = format_columnar::with_truncation_metadata(reader.schema(), true, message);
+
+    let mut copy = path.as_os_str().to_owned();
+    copy.push(".tmp");
+    let copy = PathBuf::from(copy);
+    let result = (|| {
+        let mut writer = ipc_writer(File::create(&copy)?, &schema)?;
+        for batch in reader {
+            let batch = batch.map_err(read_error)?;
+            let batch = batch.with_schema(schema.clone()).map_err(|e| DbtoonError::Format {
+                message: format!("failed to rebuild record batch: {e}"),
+            })?;
+            write_ipc_batch(&mut writer, &batch)?;
+        }
+        finish_ipc(&mut writer)?;
+        std::fs::rename(&copy, path)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&copy);
+    }
+    result
+}
+
+fn write_ipc_batch(writer: &mut FileWriter<File>, batch: &RecordBatch) -> Result<(), DbtoonError> {
+    writer
+        .write(batch)
+        .map_err(|e| DbtoonError::Format {
+            message: format!("failed to write Arrow IPC data: {e}"),
+        })
+}
 
 /// Write query results as an Arrow IPC file with typed columns and optional truncation metadata.
 ///
@@ -16,26 +140,12 @@ pub fn write_arrow(
     truncated: bool,
     message: Option<&str>,
 ) -> Result<(), DbtoonError> {
-    let (schema, batch) = format_columnar::build_record_batch(result)?;
-    let schema = format_columnar::with_truncation_metadata(schema, truncated, message);
-
-    let file = File::create(path)?;
-    let mut writer =
-        FileWriter::try_new(file, &schema).map_err(|e| DbtoonError::Format {
-            message: format!("failed to create Arrow IPC writer: {e}"),
-        })?;
-
-    if batch.num_rows() > 0 {
-        writer
-            .write(&batch)
-            .map_err(|e| DbtoonError::Format {
-                message: format!("failed to write Arrow IPC data: {e}"),
-            })?;
+    let mut sink = ArrowSink::create(path)?;
+    sink.begin(&result.columns)?;
+    if !result.rows.is_empty() {
+        // Every row is in memory, so the column types can come from all of them
+        let (_, batch) = format_columnar::build_record_batch(result)?;
+        sink.write_arrow_batch(&batch)?;
     }
-
-    writer.finish().map_err(|e| DbtoonError::Format {
-        message: format!("failed to finalize Arrow IPC file: {e}"),
-    })?;
-
-    Ok(())
+    sink.finish(truncated, message)
 }
@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::sync::Arc;
 
@@ -9,7 +10,11 @@ use arrow::array::{
 use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
 use arrow::record_batch::RecordBatch;
 
-use crate::backend::{CellValue, QueryResult};
+use crate::backend::value::{
+    hex_decode, parse_bool, parse_date32, parse_decimal, parse_time_micros,
+    parse_timestamp_micros, rescale_decimal,
+};
+use crate::backend::{CellValue, ColumnMeta, QueryResult};
 use crate::error::DbtoonError;
 
 /// Add truncation metadata to an Arrow schema.
@@ -46,8 +51,8 @@ pub fn sql_type_to_arrow(type_name: &str) -> DataType {
     match base {
         // Integer types
         "INT" | "INTEGER" => DataType::Int32,
-        "SMALLINT" => DataType::Int16,
-        "BIGINT" => DataType::Int64,
+        "SMALLINT" | "SHORT" => DataType::Int16,
+        "BIGINT" | "LONG" => DataType::Int64,
         "TINYINT" => DataType::UInt8,
 
         // Boolean
@@ -55,7 +60,7 @@ pub fn sql_type_to_arrow(type_name: &str) -> DataType {
 
         // Floating point
         "REAL" => DataType::Float32,
-        "FLOAT" => DataType::Float64,
+        "FLOAT" | "DOUBLE" => DataType::Float64,
 
         // String types
         "VARCHAR" | "NVARCHAR" | "CHAR" | "NCHAR" | "STRING" => DataType::Utf8,
@@ -65,7 +70,7 @@ pub fn sql_type_to_arrow(type_name: &str) -> DataType {
 
         // Date/Time types
         "DATE" => DataType::Date32,
-        "DATETIME2" | "TIMESTAMP" => DataType::Timestamp(TimeUnit::Microsecond, None),
+        "DATETIME2" | "TIMESTAMP" | "TIMESTAMP_NTZ" => DataType::Timestamp(TimeUnit::Microsecond, None),
         "TIME" => DataType::Time64(TimeUnit::Microsecond),
 
         // Binary types
@@ -100,32 +105,14 @@ fn parse_decimal_params(normalized: &str) -> DataType {
 pub fn build_record_batch(
     result: &QueryResult,
 ) -> Result<(Arc<Schema>, RecordBatch), DbtoonError> {
-    let num_rows = result.rows.len();
-    let num_cols = result.columns.len();
-
-    // Build schema and arrays
-    let mut fields = Vec::with_capacity(num_cols);
-    let mut arrays: Vec<ArrayRef> = Vec::with_capacity(num_cols);
-
+    // Every row is here, so text values can be parsed: no later row can contradict the type
+    let mut fields = Vec::with_capacity(result.columns.len());
+    let mut arrays: Vec<ArrayRef> = Vec::with_capacity(result.columns.len());
     for (col_idx, col_meta) in result.columns.iter().enumerate() {
+        let col_values: Vec<&CellValue> = result.rows.iter().map(|row| &row[col_idx]).collect();
         let target_type = sql_type_to_arrow(&col_meta.type_name);
-
-        // Collect column values
-        let col_values: Vec<Option<&str>> = (0..num_rows)
-            .map(|row_idx| match &result.rows[row_idx][col_idx] {
-                CellValue::Text(s) => Some(s.as_str()),
-                CellValue::Null => None,
-            })
-            .collect();
-
-        // Try to build a typed array; on failure, fall back to StringArray
-        let (final_type, array) =
-            build_typed_array(&target_type, &col_values).unwrap_or_else(|| {
-                let fallback: ArrayRef =
-                    Arc::new(StringArray::from(col_values.to_vec()));
-                (DataType::Utf8, fallback)
-            });
-
+        let (final_type, array) = build_typed_array(&target_type, &col_values)
+            .unwrap_or_else(|| (DataType::Utf8, string_array(&col_values)));
         fields.push(Field::new(&col_meta.name, final_type, true));
         arrays.push(array);
     }
@@ -134,142 +121,176 @@ pub fn build_record_batch(
     let batch = RecordBatch::try_new(schema.clone(), arrays).map_err(|e| DbtoonError::Format {
         message: format!("failed to build record batch: {e}"),
     })?;
-
     Ok((schema, batch))
 }
 
-/// Attempt to build a typed Arrow array from string values.
-/// Returns None if any non-null value fails to parse (triggering column-level fallback).
+/// Converts successive row batches into Arrow record batches sharing one schema.
+///
+/// The schema is fixed by the first encoded batch, so it is chosen to hold any
+/// later value: typed columns only for native cells (with integers widened to
+/// Int64), and Utf8 for text, which a later row might not parse. A later value
+/// that still does not fit its column fails the batch rather than being written
+/// as something else.
+pub struct BatchEncoder {
+    columns: Vec<ColumnMeta>,
+    schema: Option<Arc<Schema>>,
+}
+
+impl BatchEncoder {
+    pub fn new(columns: &[ColumnMeta]) -> Self {
+        Self {
+            columns: columns.to_vec(),
+            schema: None,
+        }
+    }
+
+    /// The fixed schema, or the declared column types if nothing was encoded yet.
+    pub fn schema(&self) -> Arc<Schema> {
+        match &self.schema {
+            Some(schema) => schema.clone(),
+            None => {
+                let fields: Vec<Field> = self
+                    .columns
+                    .iter()
+                    .map(|c| Field::new(&c.name, sql_type_to_arrow(&c.type_name), true))
+                    .collect();
+                Arc::new(Schema::new(fields))
+            }
+        }
+    }
+
+    /// Fix the schema up front, e.g. from natively typed Arrow batches.
+    pub fn set_schema(&mut self, schema: Arc<Schema>) {
+        self.schema = Some(schema);
+    }
+
+    pub fn encode(&mut self, rows: &[Vec<CellValue>]) -> Result<RecordBatch, DbtoonError> {
+        let num_cols = self.columns.len();
+
+        let mut fields = Vec::with_capacity(num_cols);
+        let mut arrays: Vec<ArrayRef> = Vec::with_capacity(num_cols);
+
+        for (col_idx, col_meta) in self.columns.iter().enumerate() {
+            // Collect column values
+            let col_values: Vec<&CellValue> = rows.iter().map(|row| &row[col_idx]).collect();
+
+            let (final_type, array) = match &self.schema {
+                Some(schema) => {
+                    let fixed_type = schema.field(col_idx).data_type();
+                    build_typed_array(fixed_type, &col_values).ok_or_else(|| DbtoonError::Format {
+                        message: format!(
+                            "column '{}' has a value that does not fit type {fixed_type}, fixed by the first rows written; \
+                             cast the column in the query, or write CSV or TOON instead",
+                            col_meta.name
+                        ),
+                    })?
+                }
+                None => {
+                    let target_type = stream_type(&sql_type_to_arrow(&col_meta.type_name), &col_values);
+                    // Try to build a typed array; on failure, fall back to StringArray
+                    build_typed_array(&target_type, &col_values).unwrap_or_else(|| {
+                        (DataType::Utf8, string_array(&col_values))
+                    })
+                }
+            };
+
+            fields.push(Field::new(&col_meta.name, final_type, true));
+            arrays.push(array);
+        }
+
+        let schema = self
+            .schema
+            .get_or_insert_with(|| Arc::new(Schema::new(fields)))
+            .clone();
+        RecordBatch::try_new(schema, arrays).map_err(|e| DbtoonError::Format {
+            message: format!("failed to build record batch: {e}"),
+        })
+    }
+}
+
+/// The type a streamed column is fixed to, given its declared type and first values.
+fn stream_type(declared: &DataType, values: &[&CellValue]) -> DataType {
+    if values.iter().any(|v| matches!(v, CellValue::Text(_))) {
+        return DataType::Utf8;
+    }
+    match declared {
+        // A later native integer may exceed the declared width
+        DataType::Int16 | DataType::Int32 | DataType::UInt8 => DataType::Int64,
+        other => other.clone(),
+    }
+}
+
+/// Attempt to build a typed Arrow array from cell values.
+///
+/// Native values convert directly; `Text` values are parsed. Returns None if any
+/// non-null value does not fit the type (triggering column-level fallback).
 fn build_typed_array(
     target_type: &DataType,
-    values: &[Option<&str>],
+    values: &[&CellValue],
 ) -> Option<(DataType, ArrayRef)> {
     let array: ArrayRef = match target_type {
-        DataType::Int16 => {
-            let parsed: Option<Vec<Option<i16>>> = values
-                .iter()
-                .map(|v| match v {
-                    None => Some(None),
-                    Some(s) => s.parse::<i16>().ok().map(Some),
-                })
-                .collect();
-            Arc::new(Int16Array::from(parsed?))
-        }
-        DataType::Int32 => {
-            let parsed: Option<Vec<Option<i32>>> = values
-                .iter()
-                .map(|v| match v {
-                    None => Some(None),
-                    Some(s) => s.parse::<i32>().ok().map(Some),
-                })
-                .collect();
-            Arc::new(Int32Array::from(parsed?))
-        }
-        DataType::Int64 => {
-            let parsed: Option<Vec<Option<i64>>> = values
-                .iter()
-                .map(|v| match v {
-                    None => Some(None),
-                    Some(s) => s.parse::<i64>().ok().map(Some),
-                })
-                .collect();
-            Arc::new(Int64Array::from(parsed?))
-        }
-        DataType::UInt8 => {
-            let parsed: Option<Vec<Option<u8>>> = values
-                .iter()
-                .map(|v| match v {
-                    None => Some(None),
-                    Some(s) => s.parse::<u8>().ok().map(Some),
-                })
-                .collect();
-            Arc::new(UInt8Array::from(parsed?))
-        }
-        DataType::Boolean => {
-            let parsed: Option<Vec<Option<bool>>> = values
-                .iter()
-                .map(|v| match v {
-                    None => Some(None),
-                    Some(s) => parse_bool(s).map(Some),
-                })
-                .collect();
-            Arc::new(BooleanArray::from(parsed?))
-        }
-        DataType::Float32 => {
-            let parsed: Option<Vec<Option<f32>>> = values
-                .iter()
-                .map(|v| match v {
-                    None => Some(None),
-                    Some(s) => s.parse::<f32>().ok().map(Some),
-                })
-                .collect();
-            Arc::new(Float32Array::from(parsed?))
-        }
-        DataType::Float64 => {
-            let parsed: Option<Vec<Option<f64>>> = values
-                .iter()
-                .map(|v| match v {
-                    None => Some(None),
-                    Some(s) => s.parse::<f64>().ok().map(Some),
-                })
-                .collect();
-            Arc::new(Float64Array::from(parsed?))
-        }
-        DataType::Utf8 => {
-            Arc::new(StringArray::from(values.to_vec()))
-        }
-        DataType::Date32 => {
-            let parsed: Option<Vec<Option<i32>>> = values
-                .iter()
-                .map(|v| match v {
-                    None => Some(None),
-                    Some(s) => parse_date32(s).map(Some),
-                })
-                .collect();
-            Arc::new(Date32Array::from(parsed?))
-        }
+        DataType::Int16 => Arc::new(Int16Array::from(convert(values, |v| {
+            to_i64(v).and_then(|n| i16::try_from(n).ok())
+        })?)),
+        DataType::Int32 => Arc::new(Int32Array::from(convert(values, |v| {
+            to_i64(v).and_then(|n| i32::try_from(n).ok())
+        })?)),
+        DataType::Int64 => Arc::new(Int64Array::from(convert(values, to_i64)?)),
+        DataType::UInt8 => Arc::new(UInt8Array::from(convert(values, |v| {
+            to_i64(v).and_then(|n| u8::try_from(n).ok())
+        })?)),
+        DataType::Boolean => Arc::new(BooleanArray::from(convert(values, |v| match v {
+            CellValue::Bool(b) => Some(*b),
+            CellValue::Int64(0) => Some(false),
+            CellValue::Int64(1) => Some(true),
+            CellValue::Text(s) => parse_bool(s),
+            _ => None,
+        })?)),
+        DataType::Float32 => Arc::new(Float32Array::from(convert(values, |v| match v {
+            CellValue::Text(s) => s.parse::<f32>().ok(),
+            other => to_f64(other).map(|f| f as f32),
+        })?)),
+        DataType::Float64 => Arc::new(Float64Array::from(convert(values, to_f64)?)),
+        DataType::Utf8 => string_array(values),
+        DataType::Date32 => Arc::new(Date32Array::from(convert(values, |v| match v {
+            CellValue::Date(d) => Some(*d),
+            CellValue::Text(s) => parse_date32(s),
+            _ => None,
+        })?)),
         DataType::Timestamp(TimeUnit::Microsecond, None) => {
-            let parsed: Option<Vec<Option<i64>>> = values
-                .iter()
-                .map(|v| match v {
-                    None => Some(None),
-                    Some(s) => parse_timestamp_micros(s).map(Some),
-                })
-                .collect();
-            Arc::new(TimestampMicrosecondArray::from(parsed?))
+            Arc::new(TimestampMicrosecondArray::from(convert(values, |v| match v {
+                CellValue::Timestamp(t) => Some(*t),
+                CellValue::Date(d) => Some(*d as i64 * 86_400_000_000),
+                CellValue::Text(s) => parse_timestamp_micros(s),
+                _ => None,
+            })?))
         }
         DataType::Time64(TimeUnit::Microsecond) => {
-            let parsed: Option<Vec<Option<i64>>> = values
-                .iter()
-                .map(|v| match v {
-                    None => Some(None),
-                    Some(s) => parse_time_micros(s).map(Some),
-                })
-                .collect();
-            Arc::new(Time64MicrosecondArray::from(parsed?))
+            Arc::new(Time64MicrosecondArray::from(convert(values, |v| match v {
+                CellValue::Time(t) => Some(*t),
+                CellValue::Text(s) => parse_time_micros(s),
+                _ => None,
+            })?))
         }
         DataType::Binary => {
-            let parsed: Option<Vec<Option<Vec<u8>>>> = values
-                .iter()
-                .map(|v| match v {
-                    None => Some(None),
-                    Some(s) => hex_decode(s).map(Some),
-                })
-                .collect();
-            let parsed = parsed?;
+            let parsed = convert(values, |v| match v {
+                CellValue::Bytes(b) => Some(b.clone()),
+                CellValue::Text(s) => hex_decode(s),
+                _ => None,
+            })?;
             let refs: Vec<Option<&[u8]>> = parsed.iter().map(|v| v.as_deref()).collect();
             Arc::new(BinaryArray::from(refs))
         }
         DataType::Decimal128(p, s) => {
             let scale = *s;
-            let parsed: Option<Vec<Option<i128>>> = values
-                .iter()
-                .map(|v| match v {
-                    None => Some(None),
-                    Some(sv) => parse_decimal_i128(sv, scale).map(Some),
-                })
-                .collect();
-            let arr = arrow::array::Decimal128Array::from(parsed?)
+            let parsed = convert(values, |v| match v {
+                CellValue::Decimal { value, scale: from } => rescale_decimal(*value, *from, scale),
+                CellValue::Int64(n) => rescale_decimal(i128::from(*n), 0, scale),
+                CellValue::Text(sv) => parse_decimal(sv)
+                    .and_then(|(value, from)| rescale_decimal(value, from, scale)),
+                _ => None,
+            })?;
+            let arr = arrow::array::Decimal128Array::from(parsed)
                 .with_precision_and_scale(*p, scale)
                 .map_err(|_| ())
                 .ok()?;
@@ -277,132 +298,49 @@ fn build_typed_array(
         }
         // Any other type → treat as string (shouldn't normally reach here since
         // sql_type_to_arrow already maps unknowns to Utf8)
-        _ => {
-            Arc::new(StringArray::from(values.to_vec()))
-        }
+        _ => string_array(values),
     };
 
     Some((target_type.clone(), array))
 }
 
-fn parse_bool(s: &str) -> Option<bool> {
-    match s.trim().to_lowercase().as_str() {
-        "1" | "true" => Some(true),
-        "0" | "false" => Some(false),
-        _ => None,
-    }
-}
-
-fn parse_date32(s: &str) -> Option<i32> {
-    // Parse YYYY-MM-DD to days since Unix epoch
-    let parts: Vec<&str> = s.split('-').collect();
-    if parts.len() != 3 {
-        return None;
-    }
-    let y: i32 = parts[0].parse().ok()?;
-    let m: u32 = parts[1].parse().ok()?;
-    let d: u32 = parts[2].parse().ok()?;
-
-    // Days from civil date to Unix epoch using a simplified algorithm
-    days_from_civil(y, m, d)
-}
-
-fn days_from_civil(y: i32, m: u32, d: u32) -> Option<i32> {
-    if !(1..=12).contains(&m) || !(1..=31).contains(&d) {
-        return None;
-    }
-    // Algorithm from http://howardhinnant.github.io/date_algorithms.html
-    let y = if m <= 2 { y - 1 } else { y };
-    let era = y.div_euclid(400);
-    let yoe = y.rem_euclid(400) as u32;
-    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + d - 1;
-    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
-    let days = era * 146097 + doe as i32 - 719468;
-    Some(days)
-}
-
-fn parse_timestamp_micros(s: &str) -> Option<i64> {
-    // Parse ISO 8601: YYYY-MM-DDThh:mm:ss[.ffffff] or YYYY-MM-DD hh:mm:ss[.ffffff]
-    let s = s.trim();
-    let (date_part, time_part) = if let Some(pos) = s.find('T') {
-        (&s[..pos], &s[pos + 1..])
-    } else if let Some(pos) = s.find(' ') {
-        (&s[..pos], &s[pos + 1..])
-    } else {
-        return None;
-    };
-
-    let days = parse_date32(date_part)?;
-    let micros = parse_time_micros(time_part)?;
-
-    Some(days as i64 * 86_400_000_000 + micros)
+/// Convert every non-null value with `f`, or `None` if any of them fails.
+fn convert<T>(values: &[&CellValue], f: impl Fn(&CellValue) -> Option<T>) -> Option<Vec<Option<T>>> {
+    values
+        .iter()
+        .map(|v| match v {
+            CellValue::Null => Some(None),
+            other => f(other).map(Some),
+        })
+        .collect()
 }
 
-fn parse_time_micros(s: &str) -> Option<i64> {
-    // Parse HH:MM:SS[.ffffff]
-    let parts: Vec<&str> = s.splitn(2, '.').collect();
-    let hms: Vec<&str> = parts[0].split(':').collect();
-    if hms.len() != 3 {
-        return None;
-    }
-    let h: i64 = hms[0].parse().ok()?;
-    let m: i64 = hms[1].parse().ok()?;
-    let sec: i64 = hms[2].parse().ok()?;
-
-    let mut micros = (h * 3600 + m * 60 + sec) * 1_000_000;
-
-    if parts.len() == 2 {
-        let frac = parts[1];
-        // Pad or truncate to 6 digits
-        let padded = format!("{:0<6}", &frac[..frac.len().min(6)]);
-        let frac_micros: i64 = padded.parse().ok()?;
-        micros += frac_micros;
-    }
-
-    Some(micros)
+fn string_array(values: &[&CellValue]) -> ArrayRef {
+    let texts: Vec<Option<Cow<'_, str>>> = values.iter().map(|v| v.to_text()).collect();
+    Arc::new(StringArray::from_iter(texts))
 }
 
-fn hex_decode(s: &str) -> Option<Vec<u8>> {
-    let s = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")).unwrap_or(s);
-    if !s.len().is_multiple_of(2) {
-        return None;
+fn to_i64(value: &CellValue) -> Option<i64> {
+    match value {
+        CellValue::Int64(n) => Some(*n),
+        CellValue::Decimal { value, scale } => {
+            // Only whole values fit an integer column
+            let whole = rescale_decimal(*value, *scale, 0)?;
+            if rescale_decimal(whole, 0, *scale)? != *value {
+                return None;
+            }
+            i64::try_from(whole).ok()
+        }
+        CellValue::Text(s) => s.parse().ok(),
+        _ => None,
     }
-    (0..s.len())
-        .step_by(2)
-        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok())
-        .collect()
 }
 
-fn parse_decimal_i128(s: &str, scale: i8) -> Option<i128> {
-    // Parse a decimal string like "123.45" into a scaled i128
-    let s = s.trim();
-    let negative = s.starts_with('-');
-    let s = s.strip_prefix('-').unwrap_or(s).strip_prefix('+').unwrap_or(s);
-
-    let (integer_part, frac_part) = match s.find('.') {
-        Some(pos) => (&s[..pos], &s[pos + 1..]),
-        None => (s, ""),
-    };
-
-    let scale = scale as usize;
-
-    // Pad or truncate fractional part to match scale
-    let frac_str = if frac_part.len() >= scale {
-        &frac_part[..scale]
-    } else {
-        // Need to pad — build a new string
-        &format!("{:0<width$}", frac_part, width = scale)
-    };
-
-    let combined = if scale > 0 {
-        format!("{integer_part}{frac_str}")
-    } else {
-        integer_part.to_string()
-    };
-
-    let mut val: i128 = combined.parse().ok()?;
-    if negative {
-        val = -val;
+fn to_f64(value: &CellValue) -> Option<f64> {
+    match value {
+        CellValue::Float64(f) => Some(*f),
+        CellValue::Int64(n) => Some(*n as f64),
+        CellValue::Decimal { .. } | CellValue::Text(_) => value.to_text()?.parse().ok(),
+        _ => None,
     }
-    Some(val)
 }
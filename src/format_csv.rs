@@ -1,45 +1,66 @@
+use std::borrow::Cow;
 use std::fs::File;
 use std::io::Write;
 use std::path::Path;
 
 use csv::WriterBuilder;
 
-use crate::backend::{CellValue, QueryResult};
+use crate::backend::{CellValue, ColumnMeta, QueryResult, ResultSink};
 use crate::error::DbtoonError;
 
-/// Write query results as RFC 4180 CSV to a generic writer.
-pub fn write_csv_to_writer<W: Write>(result: &QueryResult, writer: W) -> Result<(), DbtoonError> {
-    let mut wtr = WriterBuilder::new()
-        .terminator(csv::Terminator::CRLF)
-        .from_writer(writer);
-
-    // Header row from column names
-    let headers: Vec<&str> = result.columns.iter().map(|c| c.name.as_str()).collect();
-    wtr.write_record(&headers)
-        .map_err(|e| DbtoonError::Format {
-            message: format!("failed to write CSV header: {e}"),
-        })?;
-
-    // Data rows
-    for row in &result.rows {
-        let fields: Vec<&str> = row
-            .iter()
-            .map(|cell| match cell {
-                CellValue::Text(s) => s.as_str(),
-                CellValue::Null => "",
-            })
-            .collect();
-        wtr.write_record(&fields)
+/// Streaming RFC 4180 CSV writer: the header on `begin`, rows as batches arrive.
+pub struct CsvSink<W: Write> {
+    wtr: csv::Writer<W>,
+}
+
+impl<W: Write> CsvSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            wtr: WriterBuilder::new()
+                .terminator(csv::Terminator::CRLF)
+                .from_writer(writer),
+        }
+    }
+
+    /// Flush any buffered rows to the underlying writer.
+    pub fn finish(mut self) -> Result<(), DbtoonError> {
+        self.wtr.flush().map_err(|e| DbtoonError::Format {
+            message: format!("failed to flush CSV output: {e}"),
+        })
+    }
+}
+
+impl<W: Write + Send> ResultSink for CsvSink<W> {
+    fn begin(&mut self, columns: &[ColumnMeta]) -> Result<(), DbtoonError> {
+        // Header row from column names
+        let headers: Vec<&str> = columns.iter().map(|c| c.name.as_str()).collect();
+        self.wtr.write_record(&headers)
             .map_err(|e| DbtoonError::Format {
-                message: format!("failed to write CSV row: {e}"),
-            })?;
+                message: format!("failed to write CSV header: {e}"),
+            })
     }
 
-    wtr.flush().map_err(|e| DbtoonError::Format {
-        message: format!("failed to flush CSV output: {e}"),
-    })?;
+    fn write_batch(&mut self, rows: &[Vec<CellValue>]) -> Result<(), DbtoonError> {
+        for row in rows {
+            let fields: Vec<Cow<'_, str>> = row
+                .iter()
+                .map(|cell| cell.to_text().unwrap_or_default())
+                .collect();
+            self.wtr.write_record(fields.iter().map(|f| f.as_bytes()))
+                .map_err(|e| DbtoonError::Format {
+                    message: format!("failed to write CSV row: {e}"),
+                })?;
+        }
+        Ok(())
+    }
+}
 
-    Ok(())
+/// Write query results as RFC 4180 CSV to a generic writer.
+pub fn write_csv_to_writer<W: Write + Send>(result: &QueryResult, writer: W) -> Result<(), DbtoonError> {
+    let mut sink = CsvSink::new(writer);
+    sink.begin(&result.columns)?;
+    sink.write_batch(&result.rows)?;
+    sink.finish()
 }
 
 /// Write query results as RFC 4180 CSV to a file.
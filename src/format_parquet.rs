@@ -1,62 +1,118 @@
 use std::fs::File;
 use std::path::Path;
+use arrow::record_batch::RecordBatch;
 use parquet::arrow::ArrowWriter;
-use parquet::file::properties::WriterProperties;
+use parquet::file::metadata::KeyValue;
 
-use crate::backend::QueryResult;
+use crate::backend::{CellValue, ColumnMeta, QueryResult, ResultSink};
 use crate::error::DbtoonError;
-use crate::format_columnar;
+use crate::format_columnar::{self, BatchEncoder};
 
-/// Write query results as a Parquet file with typed columns and optional truncation metadata.
+/// Streaming Parquet writer that encodes each row batch as it arrives.
 ///
-/// When truncated, file metadata includes `dbtoon:truncated` and `dbtoon:message` keys.
-pub fn write_parquet(
-    result: &QueryResult,
-    path: &Path,
-    truncated: bool,
-    message: Option<&str>,
-) -> Result<(), DbtoonError> {
-    let (schema, batch) = format_columnar::build_record_batch(result)?;
+/// The Arrow writer is opened on the first non-empty batch, once the column
+/// types are inferred. Truncation metadata is appended to the footer on `finish`.
+pub struct ParquetSink {
+    file: Option<File>,
+    encoder: BatchEncoder,
+    writer: Option<ArrowWriter<File>>,
+}
+
+impl ParquetSink {
+    /// Create the output file up front so path errors surface before the query runs.
+    pub fn create(path: &Path) -> Result<Self, DbtoonError> {
+        Ok(Self {
+            file: Some(File::create(path)?),
+            encoder: BatchEncoder::new(&[]),
+            writer: None,
+        })
+    }
 
-    let props = if truncated {
-        let mut kv = vec![
-            parquet::file::metadata::KeyValue {
+    fn writer(&mut self) -> Result<&mut ArrowWriter<File>, DbtoonError> {
+        if self.writer.is_none() {
+            let file = self.file.take().ok_or_else(|| DbtoonError::Format {
+                message: "Parquet output file already consumed".to_string(),
+            })?;
+            let writer = ArrowWriter::try_new(file, self.encoder.schema(), None)
+                .map_err(|e| DbtoonError::Format {
+                    message: format!("failed to create Parquet writer: {e}"),
+                })?;
+            self.writer = Some(writer);
+        }
+        Ok(self.writer.as_mut().expect("writer initialized above"))
+    }
+
+    /// Finalize the file, recording `dbtoon:truncated` and `dbtoon:message` when truncated.
+    pub fn finish(mut self, truncated: bool, message: Option<&str>) -> Result<(), DbtoonError> {
+        let writer = self.writer()?;
+
+        if truncated {
+            writer.append_key_value_metadata(KeyValue {
                 key: "dbtoon:truncated".to_string(),
                 value: Some("true".to_string()),
-            },
-        ];
-        if let Some(msg) = message {
-            kv.push(parquet::file::metadata::KeyValue {
-                key: "dbtoon:message".to_string(),
-                value: Some(msg.to_string()),
             });
+            if let Some(msg) = message {
+                writer.append_key_value_metadata(KeyValue {
+                    key: "dbtoon:message".to_string(),
+                    value: Some(msg.to_string()),
+                });
+            }
         }
-        Some(
-            WriterProperties::builder()
-                .set_key_value_metadata(Some(kv))
-                .build(),
-        )
-    } else {
-        None
-    };
-
-    let file = File::create(path)?;
-    let mut writer =
-        ArrowWriter::try_new(file, schema, props).map_err(|e| DbtoonError::Format {
-            message: format!("failed to create Parquet writer: {e}"),
+
+        let writer = self.writer.take().expect("writer initialized above");
+        writer.close().map_err(|e| DbtoonError::Format {
+            message: format!("failed to finalize Parquet file: {e}"),
         })?;
 
-    if batch.num_rows() > 0 {
-        writer
-            .write(&batch)
+        Ok(())
+    }
+}
+
+impl ResultSink for ParquetSink {
+    fn begin(&mut self, columns: &[ColumnMeta]) -> Result<(), DbtoonError> {
+        self.encoder = BatchEncoder::new(columns);
+        Ok(())
+    }
+
+    fn write_batch(&mut self, rows: &[Vec<CellValue>]) -> Result<(), DbtoonError> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+        let batch = self.encoder.encode(rows)?;
+        self.write_arrow_batch(&batch)
+    }
+
+    fn accepts_arrow(&self) -> bool {
+        true
+    }
+
+    fn write_arrow_batch(&mut self, batch: &RecordBatch) -> Result<(), DbtoonError> {
+        if self.writer.is_none() {
+            self.encoder.set_schema(batch.schema());
+        }
+        self.writer()?
+            .write(batch)
             .map_err(|e| DbtoonError::Format {
                 message: format!("failed to write Parquet data: {e}"),
-            })?;
+            })
     }
+}
 
-    writer.close().map_err(|e| DbtoonError::Format {
-        message: format!("failed to finalize Parquet file: {e}"),
-    })?;
-
-    Ok(())
+/// Write query results as a Parquet file with typed columns and optional truncation metadata.
+///
+/// When truncated, file metadata includes `dbtoon:truncated` and `dbtoon:message` keys.
+pub fn write_parquet(
+    result: &QueryResult,
+    path: &Path,
+    truncated: bool,
+    message: Option<&str>,
+) -> Result<(), DbtoonError> {
+    let mut sink = ParquetSink::create(path)?;
+    sink.begin(&result.columns)?;
+    if !result.rows.is_empty() {
+        // Every row is in memory, so the column types can come from all of them
+        let (_, batch) = format_columnar::build_record_batch(result)?;
+        sink.write_arrow_batch(&batch)?;
+    }
+    sink.finish(truncated, message)
 }
@@ -0,0 +1,81 @@
+use crate::backend::{CellValue, QueryResult};
+
+/// Shown for SQL NULL.
+const NULL: &str = "NULL";
+
+/// Render `result` as an aligned text table for the terminal, `psql` style:
+/// a header, a rule, one line per row with numbers right-aligned, and a
+/// `(N rows)` footer.
+///
+/// Line breaks and tabs inside values are shown as `\n`, `\r` and `\t` so
+/// every row stays on one line.
+pub fn to_table(result: &QueryResult) -> String {
+    let header: Vec<String> = result.columns.iter().map(|c| escape(&c.name)).collect();
+    let rows: Vec<Vec<(String, bool)>> = result
+        .rows
+        .iter()
+        .map(|row| row.iter().map(|cell| (cell_text(cell), is_numeric(cell))).collect())
+        .collect();
+
+    let mut widths: Vec<usize> = header.iter().map(|h| h.chars().count()).collect();
+    for row in &rows {
+        for (width, (text, _)) in widths.iter_mut().zip(row) {
+            *width = (*width).max(text.chars().count());
+        }
+    }
+
+    let mut out = String::new();
+    let centered: Vec<String> = header
+        .iter()
+        .zip(&widths)
+        .map(|(name, &width)| {
+            let left = (width - name.chars().count()) / 2;
+            pad(&format!("{}{}", " ".repeat(left), name), width, false)
+        })
+        .collect();
+    push_line(&mut out, &centered);
+    let rule: Vec<String> = widths.iter().map(|&w| "-".repeat(w + 2)).collect();
+    out.push_str(&rule.join("+"));
+    out.push('\n');
+    for row in &rows {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|((text, right), &width)| pad(text, width, *right))
+            .collect();
+        push_line(&mut out, &cells);
+    }
+
+    let count = result.rows.len();
+    out.push_str(&format!("({} {})\n", count, if count == 1 { "row" } else { "rows" }));
+    out
+}
+
+fn push_line(out: &mut String, cells: &[String]) {
+    let line = cells.iter().map(|c| format!(" {} ", c)).collect::<Vec<_>>().join("|");
+    out.push_str(line.trim_end());
+    out.push('\n');
+}
+
+fn pad(text: &str, width: usize, right: bool) -> String {
+    let fill = " ".repeat(width.saturating_sub(text.chars().count()));
+    if right { format!("{}{}", fill, text) } else { format!("{}{}", text, fill) }
+}
+
+fn cell_text(cell: &CellValue) -> String {
+    match cell.to_text() {
+        Some(text) => escape(&text),
+        None => NULL.to_string(),
+    }
+}
+
+fn is_numeric(cell: &CellValue) -> bool {
+    matches!(cell, CellValue::Int64(_) | CellValue::Float64(_) | CellValue::Decimal { .. })
+}
+
+fn escape(text: &str) -> String {
+    if !text.contains(['\n', '\r', '\t']) {
+        return text.to_string();
+    }
+    text.replace('\n', "\\n").replace('\r', "\\r").replace('\t', "\\t")
+}
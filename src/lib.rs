@@ -1,6 +1,10 @@
+pub mod auth;
 pub mod backend;
+pub mod cancel;
 pub mod cli;
 pub mod config;
+#[cfg(unix)]
+pub mod daemon;
 pub mod error;
 pub mod init;
 pub mod profile;
@@ -10,8 +14,14 @@ pub mod format_columnar;
 pub mod format_csv;
 pub mod format_detect;
 pub mod format_parquet;
+pub mod format_table;
+pub mod line_editor;
 pub mod masking;
+pub mod mcp;
 pub mod output;
+pub mod params;
+pub mod query;
+pub mod shell;
 pub mod update;
 pub mod validation;
 pub mod verbose;
@@ -0,0 +1,77 @@
+//! Line editing for the interactive shell, on `rustyline`: cursor movement,
+//! Emacs-style keys and history recall on a terminal. Input that is not a
+//! terminal, such as a piped script, is read line by line as it is.
+
+use std::io::{self, IsTerminal};
+use std::path::PathBuf;
+
+use rustyline::error::ReadlineError;
+use rustyline::history::FileHistory;
+use rustyline::{Config, Editor};
+
+/// Most history entries kept, in memory and in the history file.
+const HISTORY_LIMIT: usize = 1000;
+
+/// What reading a line produced.
+#[derive(Debug, PartialEq)]
+pub enum ReadLine {
+    Line(String),
+    /// Ctrl-C: the line was abandoned.
+    Interrupted,
+    Eof,
+}
+
+pub struct LineEditor {
+    editor: Editor<(), FileHistory>,
+    history_file: Option<PathBuf>,
+}
+
+impl LineEditor {
+    /// An editor for stdin, with history loaded from, and appended to, `history_file`.
+    pub fn new(history_file: Option<PathBuf>) -> io::Result<Self> {
+        let config = Config::builder()
+            .max_history_size(HISTORY_LIMIT)
+            .and_then(|builder| builder.history_ignore_dups(true))
+            .map_err(io::Error::other)?
+            .auto_add_history(false)
+            .build();
+        let mut editor = Editor::with_config(config).map_err(io::Error::other)?;
+        if let Some(path) = &history_file {
+            // A missing or unreadable history file starts an empty history
+            let _ = editor.load_history(path);
+        }
+        Ok(Self { editor, history_file })
+    }
+
+    /// Whether input comes from a terminal, so prompts and editing apply.
+    pub fn is_terminal(&self) -> bool {
+        io::stdin().is_terminal() && io::stdout().is_terminal()
+    }
+
+    pub fn history(&self) -> Vec<&str> {
+        self.editor.history().iter().map(String::as_str).collect()
+    }
+
+    /// Read a line from stdin, showing `prompt` and allowing editing on a terminal.
+    pub fn read_line(&mut self, prompt: &str) -> io::Result<ReadLine> {
+        match self.editor.readline(prompt) {
+            Ok(line) => Ok(ReadLine::Line(line)),
+            Err(ReadlineError::Interrupted) => Ok(ReadLine::Interrupted),
+            Err(ReadlineError::Eof) => Ok(ReadLine::Eof),
+            Err(ReadlineError::Io(e)) => Err(e),
+            Err(e) => Err(io::Error::other(e)),
+        }
+    }
+
+    /// Remember `line` and append it to the history file, skipping blank
+    /// lines and repeats of the previous entry.
+    pub fn add_history(&mut self, line: &str) {
+        if line.trim().is_empty() || !self.editor.add_history_entry(line).unwrap_or(false) {
+            return;
+        }
+        // History is a convenience; failing to save it does not stop the shell
+        if let Some(path) = &self.history_file {
+            let _ = self.editor.append_history(path);
+        }
+    }
+}
@@ -1,9 +1,9 @@
 use clap::Parser;
 use dbtoon::cli::{self, Cli, Command, ProfileCommand};
 use dbtoon::error::DbtoonError;
-use dbtoon::verbose::{self, Timer};
-use dbtoon::format_detect::{self, OutputFormat};
-use dbtoon::{backend, config, format, output, validation};
+use dbtoon::verbose;
+use dbtoon::cancel::{self, CancelToken};
+use dbtoon::{config, format, output, query};
 use std::process;
 
 #[tokio::main]
@@ -18,12 +18,18 @@ async fn main() {
         Command::Query(ref args) => {
             run_query(args, cli.verbose, cli.show_secrets, cli.config.as_ref()).await
         }
+        Command::Shell(ref args) => {
+            run_shell(args, cli.verbose, cli.show_secrets, cli.config.as_ref()).await
+        }
         Command::Profile(ref cmd) => {
             run_profile(cmd, cli.verbose, cli.show_secrets, cli.config.as_ref())
         }
         Command::Warehouse(ref args) => {
             run_warehouse(args, cli.verbose, cli.show_secrets, cli.config.as_ref()).await
         }
+        Command::Auth(ref cmd) => run_auth(cmd, cli.config.as_ref()).await,
+        Command::Mcp => run_mcp(cli.verbose, cli.config.as_ref()).await,
+        Command::Daemon(ref cmd) => run_daemon(cmd, cli.verbose).await,
         Command::Update => dbtoon::update::run_update().map_err(|e| DbtoonError::Config {
             message: e.to_string(),
         }),
@@ -31,7 +37,7 @@ async fn main() {
 
     if let Err(err) = result {
         output::print_error(&err);
-        process::exit(1);
+        process::exit(err.exit_code());
     }
 }
 
@@ -51,66 +57,36 @@ async fn run_query(
     show_secrets: bool,
     config_path: Option<&std::path::PathBuf>,
 ) -> Result<(), DbtoonError> {
-    let (toml_config, _config_file_path) = config::load_toml_config_required(config_path)?;
-    let app_config = config::load_from_query_args(args, &toml_config, verbose, show_secrets)?;
-    let verbose = app_config.verbose;
-
     // Resolve SQL input
     let sql = resolve_sql(&args.sql, &args.file)?;
 
-    // Validation: block write queries unless --allow-write
-    let dialect = match &app_config.backend {
-        config::BackendConfig::SqlServer { .. } => validation::BackendDialect::SqlServer,
-        config::BackendConfig::Databricks { .. } => validation::BackendDialect::Databricks,
-    };
-
-    if !app_config.allow_write {
-        verbose::emit(verbose, "validating query (read-only mode)...");
-        let vtimer = Timer::start();
-        let validation_result = validation::validate(&sql, dialect);
-        match validation_result {
-            validation::ValidationResult::Safe => {
-                verbose::emit(
-                    verbose,
-                    &format!("validation passed ({}ms)", vtimer.elapsed_ms()),
-                );
-            }
-            validation::ValidationResult::Denied { reasons } => {
-                verbose::emit(verbose, "validation failed");
-                let detail = reasons
-                    .iter()
-                    .map(|r| r.detail.clone())
-                    .collect::<Vec<_>>()
-                    .join("; ");
-                return Err(DbtoonError::Validation { reason: detail });
-            }
-        }
-    } else {
-        verbose::emit(verbose, "write mode enabled — skipping validation");
+    // Ctrl-C / SIGTERM cancel the statement on the server instead of abandoning it
+    let cancel = CancelToken::new();
+
+    #[cfg(unix)]
+    if !args.no_daemon
+        && let Some(socket) = config::default_daemon_socket_path()
+        && let Some(stream) = dbtoon::daemon::connect(&socket).await
+    {
+        verbose::emit(verbose, &format!("running query in daemon at {}...", socket.display()));
+        let request = dbtoon::daemon::QueryRequest::new(args, sql, config_path, verbose, show_secrets)?;
+        cancel::cancel_on_signal(cancel.clone(), verbose);
+        return dbtoon::daemon::forward_query(
+            stream,
+            request,
+            &cancel,
+            &mut std::io::stdout(),
+            &mut std::io::stderr(),
+        )
+        .await;
     }
 
-    // Detect output format before query (fail-fast on bad extension)
-    let format_info = if let Some(ref path) = app_config.output_file {
-        Some(format_detect::detect_format(path)?)
-    } else {
-        None
-    };
-
-    // Execute query
-    let result = execute_query(&app_config, &sql, verbose).await?;
-
-    // Format and output
-    let format_label = match &format_info {
-        Some((OutputFormat::Toon, _)) => "TOON",
-        Some((OutputFormat::Csv, _)) => "CSV",
-        Some((OutputFormat::Parquet, _)) => "Parquet",
-        Some((OutputFormat::Arrow, _)) => "Arrow IPC",
-        None => "TOON",
-    };
-    verbose::emit(verbose, &format!("formatting {format_label} output..."));
-    output_result(&app_config, &result, format_info)?;
+    let (toml_config, _config_file_path) = config::load_toml_config_required(config_path)?;
+    let app_config = config::load_from_query_args(args, &toml_config, verbose, show_secrets)?;
+    let params = dbtoon::params::parse_params(&args.params)?;
 
-    Ok(())
+    cancel::cancel_on_signal(cancel.clone(), app_config.verbose);
+    query::run_to_output(&app_config, &sql, &params, &cancel, std::io::stdout(), &mut std::io::stderr()).await
 }
 
 fn run_profile(
@@ -164,59 +140,8 @@ async fn run_warehouse(
             let app_config = config::load_from_warehouse_list_args(
                 list_args, &toml_config, verbose, show_secrets,
             )?;
-            let verbose = app_config.verbose;
-
-            let (host, token) = match &app_config.backend {
-                config::BackendConfig::Databricks { host, token, .. } => (host.clone(), token.clone()),
-                _ => {
-                    return Err(DbtoonError::Config {
-                        message: "warehouse list requires a databricks profile".to_string(),
-                    });
-                }
-            };
-
-            verbose::emit(verbose, &format!("listing warehouses on {}...", host));
-            let timer = Timer::start();
-            let warehouses = backend::databricks::list_warehouses(&host, &token).await?;
-            verbose::emit(
-                verbose,
-                &format!(
-                    "warehouse list retrieved ({}ms, {} warehouses)",
-                    timer.elapsed_ms(),
-                    warehouses.len()
-                ),
-            );
-
-            let columns = vec![
-                backend::ColumnMeta { name: "id".to_string(), type_name: "STRING".to_string() },
-                backend::ColumnMeta { name: "name".to_string(), type_name: "STRING".to_string() },
-                backend::ColumnMeta { name: "state".to_string(), type_name: "STRING".to_string() },
-                backend::ColumnMeta { name: "cluster_size".to_string(), type_name: "STRING".to_string() },
-                backend::ColumnMeta { name: "type".to_string(), type_name: "STRING".to_string() },
-            ];
 
-            let rows = warehouses
-                .into_iter()
-                .map(|w| {
-                    vec![
-                        backend::CellValue::Text(w.id),
-                        backend::CellValue::Text(w.name),
-                        backend::CellValue::Text(w.state),
-                        backend::CellValue::Text(w.cluster_size),
-                        match w.warehouse_type {
-                            Some(t) => backend::CellValue::Text(t),
-                            None => backend::CellValue::Null,
-                        },
-                    ]
-                })
-                .collect();
-
-            let query_result = backend::QueryResult {
-                columns,
-                rows,
-                total_rows: None,
-                truncated: false,
-            };
+            let query_result = query::list_warehouses(&app_config).await?;
 
             let toon = format::to_toon(&query_result, false, None)?;
             output::print_result(&toon);
@@ -226,159 +151,82 @@ async fn run_warehouse(
     }
 }
 
-// --- Helpers ---
-
-fn resolve_sql(sql: &Option<String>, file: &Option<std::path::PathBuf>) -> Result<String, DbtoonError> {
-    if let Some(sql) = sql {
-        return Ok(sql.clone());
-    }
-    if let Some(path) = file {
-        let content = std::fs::read_to_string(path).map_err(|e| DbtoonError::Config {
-            message: format!("cannot read SQL file {}: {}", path.display(), e),
-        })?;
-        return Ok(content);
+async fn run_auth(
+    cmd: &cli::AuthCommand,
+    config_path: Option<&std::path::PathBuf>,
+) -> Result<(), DbtoonError> {
+    match cmd {
+        cli::AuthCommand::Login(args) => {
+            let (toml_config, _) = config::load_toml_config_required(config_path)?;
+            dbtoon::auth::login(args, &toml_config).await
+        }
     }
-    Err(DbtoonError::Config {
-        message: "no SQL provided — use positional argument or --file".to_string(),
-    })
 }
 
-async fn execute_query(
-    app_config: &config::AppConfig,
-    sql: &str,
+async fn run_shell(
+    args: &cli::ShellArgs,
     verbose: bool,
-) -> Result<backend::QueryResult, DbtoonError> {
-    use backend::Backend;
+    show_secrets: bool,
+    config_path: Option<&std::path::PathBuf>,
+) -> Result<(), DbtoonError> {
+    dbtoon::shell::Shell::new(args, config_path, verbose, show_secrets)?.run().await
+}
 
-    match &app_config.backend {
-        config::BackendConfig::SqlServer {
-            server,
-            database,
-            auth,
-            trust_server_certificate,
-        } => {
-            verbose::emit(verbose, &format!("connecting to sqlserver at {}...", server));
-            let timer = Timer::start();
-            let backend_impl = backend::sqlserver::SqlServerBackend::new(
-                server.clone(),
-                database.clone(),
-                clone_auth(auth),
-                *trust_server_certificate,
-            );
-            verbose::emit(verbose, "executing query...");
-            let result = backend_impl
-                .execute(sql, app_config.default_row_limit, app_config.query_timeout_secs)
-                .await?;
-            verbose::emit(
-                verbose,
-                &format!(
-                    "query complete ({}ms, {} rows)",
-                    timer.elapsed_ms(),
-                    result.rows.len()
-                ),
-            );
-            Ok(result)
-        }
-        config::BackendConfig::Databricks {
-            host,
-            token,
-            warehouse_id,
-            catalog,
-            schema,
-        } => {
-            verbose::emit(verbose, &format!("connecting to databricks at {}...", host));
-            let timer = Timer::start();
-            let backend_impl = backend::databricks::DatabricksBackend::new(
-                host.clone(),
-                clone_secret(token),
-                warehouse_id.clone(),
-                catalog.clone(),
-                schema.clone(),
-            );
-            verbose::emit(verbose, "executing query...");
-            let result = backend_impl
-                .execute(sql, app_config.default_row_limit, app_config.query_timeout_secs)
-                .await?;
-            verbose::emit(
-                verbose,
-                &format!(
-                    "query complete ({}ms, {} rows)",
-                    timer.elapsed_ms(),
-                    result.rows.len()
-                ),
-            );
-            Ok(result)
-        }
-    }
+async fn run_mcp(verbose: bool, config_path: Option<&std::path::PathBuf>) -> Result<(), DbtoonError> {
+    let server = dbtoon::mcp::McpServer::new(config_path.cloned(), verbose);
+    server.serve(std::io::stdin().lock(), std::io::stdout().lock()).await
 }
 
-fn clone_auth(auth: &config::SqlServerAuth) -> config::SqlServerAuth {
-    match auth {
-        config::SqlServerAuth::WindowsIntegrated => config::SqlServerAuth::WindowsIntegrated,
-        config::SqlServerAuth::SqlLogin { username, password } => {
-            use secrecy::ExposeSecret;
-            config::SqlServerAuth::SqlLogin {
-                username: username.clone(),
-                password: secrecy::SecretString::from(password.expose_secret().to_string()),
+#[cfg(unix)]
+async fn run_daemon(cmd: &cli::DaemonCommand, verbose: bool) -> Result<(), DbtoonError> {
+    use std::time::Duration;
+
+    let socket = config::default_daemon_socket_path().ok_or_else(|| DbtoonError::Config {
+        message: "cannot determine daemon socket location (HOME not set)".to_string(),
+    })?;
+    match cmd {
+        cli::DaemonCommand::Run(args) => {
+            let options = dbtoon::daemon::DaemonOptions {
+                idle_timeout: Duration::from_secs(args.idle_timeout),
+                exit_after: (args.exit_after > 0).then(|| Duration::from_secs(args.exit_after)),
+                verbose,
+            };
+            dbtoon::daemon::serve(&socket, options).await
+        }
+        cli::DaemonCommand::Stop => dbtoon::daemon::stop(&socket).await,
+        cli::DaemonCommand::Status => {
+            match dbtoon::daemon::status(&socket).await? {
+                Some(status) => println!(
+                    "running (pid {}, up {}s, {} queries, {} running, {} idle connections)",
+                    status.pid, status.uptime_secs, status.queries, status.running, status.idle_connections
+                ),
+                None => println!("not running"),
             }
+            Ok(())
         }
     }
 }
 
-fn clone_secret(secret: &secrecy::SecretString) -> secrecy::SecretString {
-    use secrecy::ExposeSecret;
-    secrecy::SecretString::from(secret.expose_secret().to_string())
+#[cfg(not(unix))]
+async fn run_daemon(_cmd: &cli::DaemonCommand, _verbose: bool) -> Result<(), DbtoonError> {
+    Err(DbtoonError::Config {
+        message: "the daemon needs Unix domain sockets and is not available on this platform".to_string(),
+    })
 }
 
-fn output_result(
-    app_config: &config::AppConfig,
-    result: &backend::QueryResult,
-    format_info: Option<(OutputFormat, std::path::PathBuf)>,
-) -> Result<(), DbtoonError> {
-    let message = if result.truncated {
-        Some(format!(
-            "Showing {} rows. Use --no-limit to return all rows.",
-            result.rows.len()
-        ))
-    } else {
-        None
-    };
+// --- Helpers ---
 
-    if let Some((format, path)) = format_info {
-        verbose::emit(
-            app_config.verbose,
-            &format!("writing output to {}...", path.display()),
-        );
-        match format {
-            OutputFormat::Toon => {
-                let toon = format::to_toon(result, result.truncated, message.as_deref())?;
-                output::write_file(&toon, &path)?;
-            }
-            OutputFormat::Csv => {
-                dbtoon::format_csv::write_csv(result, &path)?;
-            }
-            OutputFormat::Parquet => {
-                dbtoon::format_parquet::write_parquet(
-                    result, &path, result.truncated, message.as_deref(),
-                )?;
-            }
-            OutputFormat::Arrow => {
-                dbtoon::format_arrow::write_arrow(
-                    result, &path, result.truncated, message.as_deref(),
-                )?;
-            }
-        }
-        output::print_summary(
-            result.rows.len(), &path, result.truncated, message.as_deref(),
-        )?;
-    } else {
-        let toon = format::to_toon(result, result.truncated, message.as_deref())?;
-        output::print_result(&toon);
+fn resolve_sql(sql: &Option<String>, file: &Option<std::path::PathBuf>) -> Result<String, DbtoonError> {
+    if let Some(sql) = sql {
+        return Ok(sql.clone());
     }
-
-    if let Some(ref msg) = message {
-        output::print_truncation_warning(msg);
+    if let Some(path) = file {
+        let content = std::fs::read_to_string(path).map_err(|e| DbtoonError::Config {
+            message: format!("cannot read SQL file {}: {}", path.display(), e),
+        })?;
+        return Ok(content);
     }
-
-    Ok(())
+    Err(DbtoonError::Config {
+        message: "no SQL provided — use positional argument or --file".to_string(),
+    })
 }
@@ -16,3 +16,46 @@ pub fn format_optional_secret(secret: Option<&SecretString>, show_secrets: bool)
         None => "(not set)".to_string(),
     }
 }
+
+/// Whether a free-form connection attribute likely holds a credential.
+pub fn is_sensitive_attribute(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    key == "pwd" || ["password", "secret", "token"].iter().any(|s| key.contains(s))
+}
+
+/// `connection_string` with the values of credential attributes (see
+/// `is_sensitive_attribute`) replaced by `****`.
+///
+/// Braced values may contain `;`, with `}}` standing for a literal `}`.
+pub fn mask_connection_string(connection_string: &str) -> String {
+    let mut attributes = Vec::new();
+    let mut current = String::new();
+    let mut in_braces = false;
+    let mut chars = connection_string.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_braces {
+            current.push(c);
+            if c == '}' {
+                match chars.peek() {
+                    Some('}') => current.push(chars.next().unwrap_or('}')),
+                    _ => in_braces = false,
+                }
+            }
+        } else if c == ';' {
+            attributes.push(std::mem::take(&mut current));
+        } else {
+            in_braces = c == '{' && current.ends_with('=');
+            current.push(c);
+        }
+    }
+    attributes.push(current);
+
+    attributes
+        .into_iter()
+        .map(|attribute| match attribute.split_once('=') {
+            Some((key, _)) if is_sensitive_attribute(key.trim()) => format!("{}=****", key),
+            _ => attribute,
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
@@ -0,0 +1,302 @@
+//! Model Context Protocol server over stdio (`dbtoon mcp`).
+//!
+//! Speaks newline-delimited JSON-RPC 2.0 and exposes the `query`,
+//! `list_profiles` and `list_warehouses` tools. Tools answer in TOON, like the
+//! CLI. The config file is re-read on every call, and `query` never takes an
+//! `allow_write` argument: writes are allowed only when the config says so.
+
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+use serde_json::{Value, json};
+
+use crate::backend::{CellValue, ColumnMeta, QueryResult, ResultSink, StreamSummary};
+use crate::cancel::CancelToken;
+use crate::cli::{QueryArgs, WarehouseListArgs};
+use crate::error::DbtoonError;
+use crate::format::{self, ToonResultSet};
+use crate::{config, params, profile, query};
+
+/// Protocol revisions this server understands, newest first.
+const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+pub struct McpServer {
+    config_path: Option<PathBuf>,
+    verbose: bool,
+}
+
+impl McpServer {
+    pub fn new(config_path: Option<PathBuf>, verbose: bool) -> Self {
+        Self { config_path, verbose }
+    }
+
+    /// Answer each request line from `input` on `output` until `input` closes.
+    pub async fn serve(&self, input: impl BufRead, mut output: impl Write) -> Result<(), DbtoonError> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = self.handle_message(&line).await {
+                writeln!(output, "{}", response)?;
+                output.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle one JSON-RPC message, returning the response (none for notifications).
+    pub async fn handle_message(&self, line: &str) -> Option<Value> {
+        let message: Value = match serde_json::from_str(line) {
+            Ok(message) => message,
+            Err(e) => return Some(error_response(Value::Null, PARSE_ERROR, &e.to_string())),
+        };
+
+        let Some(method) = message.get("method").and_then(Value::as_str) else {
+            return Some(error_response(
+                message.get("id").cloned().unwrap_or(Value::Null),
+                INVALID_REQUEST,
+                "missing method",
+            ));
+        };
+        // Notifications carry no id and get no response
+        let id = message.get("id").cloned()?;
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+        let result = match method {
+            "initialize" => Ok(initialize(&params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": tool_definitions() })),
+            "tools/call" => self.call_tool(&params).await,
+            other => Err((METHOD_NOT_FOUND, format!("method not found: {}", other))),
+        };
+
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => error_response(id, code, &message),
+        })
+    }
+
+    async fn call_tool(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let name = params.get("name").and_then(Value::as_str).unwrap_or_default();
+        let arguments = params.get("arguments").cloned().unwrap_or_else(|| json!({}));
+
+        let output = match name {
+            "query" => self.query(&arguments).await,
+            "list_profiles" => self.list_profiles(),
+            "list_warehouses" => self.list_warehouses(&arguments).await,
+            other => return Err((INVALID_PARAMS, format!("unknown tool: {}", other))),
+        };
+
+        // Tool failures are results, so the agent can read and correct them
+        Ok(match output {
+            Ok(text) => tool_result(&text, false),
+            Err(err) => tool_result(&format!("error: {}", err), true),
+        })
+    }
+
+    async fn query(&self, arguments: &Value) -> Result<String, DbtoonError> {
+        let args = QueryArgs {
+            sql: Some(required_string(arguments, "sql")?),
+            file: None,
+            profile: required_string(arguments, "profile")?,
+            database: optional_string(arguments, "database")?,
+            catalog: None,
+            schema: optional_string(arguments, "schema")?,
+            limit: optional_usize(arguments, "limit")?,
+            no_limit: false,
+            timeout: None,
+            output: None,
+            allow_write: false,
+            params: optional_strings(arguments, "params")?,
+            dry_run: false,
+            no_daemon: false,
+        };
+
+        let (toml_config, _) = config::load_toml_config_required(self.config_path.as_ref())?;
+        let app_config = config::load_from_query_args(&args, &toml_config, self.verbose, false)?;
+        let sql = args.sql.unwrap_or_default();
+        let params = params::parse_params(&args.params)?;
+
+        query::check_read_only(&app_config, &sql)?;
+
+        // Signals keep their default meaning here: they stop the server, not one query
+        let cancel = CancelToken::default();
+        let (mut results, summary) =
+            query::execute_query(&app_config, &sql, &params, &cancel, Vec::<QueryResult>::new())
+                .await?;
+        results.next_result(summary)?;
+
+        let messages: Vec<Option<String>> = results
+            .iter()
+            .map(|r| {
+                StreamSummary { rows: r.rows.len(), truncated: r.truncated, ..StreamSummary::default() }
+                    .truncation_message()
+            })
+            .collect();
+        let sets: Vec<ToonResultSet<'_>> = results
+            .iter()
+            .zip(&messages)
+            .map(|(result, message)| ToonResultSet {
+                result,
+                truncated: result.truncated,
+                message: message.as_deref(),
+            })
+            .collect();
+        format::to_toon_results(&sets)
+    }
+
+    fn list_profiles(&self) -> Result<String, DbtoonError> {
+        let (toml_config, config_file_path) =
+            config::load_toml_config_required(self.config_path.as_ref())?;
+        let names = profile::list_profiles(&config_file_path)?;
+
+        let columns = ["name", "backend"]
+            .into_iter()
+            .map(|name| ColumnMeta { name: name.to_string(), type_name: "STRING".to_string() })
+            .collect();
+        let rows = names
+            .into_iter()
+            .map(|name| {
+                let backend = toml_config
+                    .profiles
+                    .get(&name)
+                    .and_then(|p| p.backend.clone())
+                    .map_or(CellValue::Null, CellValue::Text);
+                vec![CellValue::Text(name), backend]
+            })
+            .collect();
+
+        let result = QueryResult { columns, rows, ..QueryResult::default() };
+        format::to_toon(&result, false, None)
+    }
+
+    async fn list_warehouses(&self, arguments: &Value) -> Result<String, DbtoonError> {
+        let args = WarehouseListArgs {
+            profile: required_string(arguments, "profile")?,
+        };
+        let (toml_config, _) = config::load_toml_config_required(self.config_path.as_ref())?;
+        let app_config =
+            config::load_from_warehouse_list_args(&args, &toml_config, self.verbose, false)?;
+
+        let result = query::list_warehouses(&app_config).await?;
+        format::to_toon(&result, false, None)
+    }
+}
+
+fn initialize(params: &Value) -> Value {
+    let requested = params.get("protocolVersion").and_then(Value::as_str);
+    let version = requested
+        .filter(|v| PROTOCOL_VERSIONS.contains(v))
+        .unwrap_or(PROTOCOL_VERSIONS[0]);
+
+    json!({
+        "protocolVersion": version,
+        "capabilities": { "tools": {} },
+        "serverInfo": { "name": "dbtoon", "version": env!("CARGO_PKG_VERSION") },
+    })
+}
+
+fn tool_definitions() -> Value {
+    json!([
+        {
+            "name": "query",
+            "description": "Run a SQL query against a dbtoon profile and return the rows as TOON. \
+                Queries are validated as read-only unless the config file sets allow_write.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "profile": { "type": "string", "description": "Profile name from the dbtoon config file" },
+                    "sql": { "type": "string", "description": "SQL query text" },
+                    "params": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Values for :name markers, as name=value[:TYPE] (e.g. id=42:INT)",
+                    },
+                    "limit": { "type": "integer", "minimum": 0, "description": "Override the row limit" },
+                    "database": { "type": "string", "description": "Override the database/catalog" },
+                    "schema": { "type": "string", "description": "Override the schema" },
+                },
+                "required": ["profile", "sql"],
+            },
+        },
+        {
+            "name": "list_profiles",
+            "description": "List the connection profiles in the dbtoon config file.",
+            "inputSchema": { "type": "object", "properties": {} },
+        },
+        {
+            "name": "list_warehouses",
+            "description": "List the SQL warehouses available to a Databricks profile.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "profile": { "type": "string", "description": "Databricks profile name" },
+                },
+                "required": ["profile"],
+            },
+        },
+    ])
+}
+
+fn tool_result(text: &str, is_error: bool) -> Value {
+    json!({
+        "content": [{ "type": "text", "text": text }],
+        "isError": is_error,
+    })
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+fn required_string(arguments: &Value, key: &str) -> Result<String, DbtoonError> {
+    optional_string(arguments, key)?.ok_or_else(|| DbtoonError::Config {
+        message: format!("missing required argument '{}'", key),
+    })
+}
+
+fn optional_string(arguments: &Value, key: &str) -> Result<Option<String>, DbtoonError> {
+    match arguments.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s.clone())),
+        Some(_) => Err(invalid_argument(key, "a string")),
+    }
+}
+
+fn optional_usize(arguments: &Value, key: &str) -> Result<Option<usize>, DbtoonError> {
+    match arguments.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => value
+            .as_u64()
+            .and_then(|n| usize::try_from(n).ok())
+            .map(Some)
+            .ok_or_else(|| invalid_argument(key, "a non-negative integer")),
+    }
+}
+
+fn optional_strings(arguments: &Value, key: &str) -> Result<Vec<String>, DbtoonError> {
+    match arguments.get(key) {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|item| {
+                item.as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| invalid_argument(key, "an array of strings"))
+            })
+            .collect(),
+        Some(_) => Err(invalid_argument(key, "an array of strings")),
+    }
+}
+
+fn invalid_argument(key: &str, expected: &str) -> DbtoonError {
+    DbtoonError::Config {
+        message: format!("argument '{}' must be {}", key, expected),
+    }
+}
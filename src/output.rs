@@ -1,5 +1,14 @@
+use crate::backend::{CellValue, ColumnMeta, ResultSink, StreamSummary};
 use crate::error::DbtoonError;
-use std::path::Path;
+use crate::format::ToonSink;
+use crate::format_arrow::ArrowSink;
+use crate::format_csv::CsvSink;
+use crate::format_detect::OutputFormat;
+use crate::format_parquet::ParquetSink;
+use arrow::record_batch::RecordBatch;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
 /// Print TOON result to stdout.
 pub fn print_result(toon_string: &str) {
@@ -12,19 +21,36 @@ pub fn print_error(err: &DbtoonError) {
 }
 
 /// Print file output summary to stdout as a valid TOON object.
+pub fn print_summary(path: &Path, summary: &StreamSummary) -> Result<(), DbtoonError> {
+    print!("{}", summary_toon(path, summary)?);
+    Ok(())
+}
+
+/// The TOON summary of a written file.
 ///
 /// Summary includes: rows_written (number), file (string), truncated (bool),
-/// and message (string, only when truncated).
-pub fn print_summary(
-    rows: usize,
-    path: &Path,
-    truncated: bool,
-    message: Option<&str>,
-) -> Result<(), DbtoonError> {
+/// message (string, only when truncated), and messages (strings) and
+/// rows_affected (number), only when the backend reported them.
+pub fn summary_toon(path: &Path, summary: &StreamSummary) -> Result<String, DbtoonError> {
+    encode_toon(&summary_json(path, summary))
+}
+
+/// The TOON summary of the files written for several result sets.
+pub fn results_summary_toon(files: &[(PathBuf, StreamSummary)]) -> Result<String, DbtoonError> {
+    let results = files
+        .iter()
+        .map(|(path, summary)| summary_json(path, summary))
+        .collect();
+    let mut map = serde_json::Map::new();
+    map.insert("results".to_string(), serde_json::Value::Array(results));
+    encode_toon(&serde_json::Value::Object(map))
+}
+
+fn summary_json(path: &Path, summary: &StreamSummary) -> serde_json::Value {
     let mut map = serde_json::Map::new();
     map.insert(
         "rows_written".to_string(),
-        serde_json::Value::Number(serde_json::Number::from(rows)),
+        serde_json::Value::Number(serde_json::Number::from(summary.rows)),
     );
     map.insert(
         "file".to_string(),
@@ -32,21 +58,36 @@ pub fn print_summary(
     );
     map.insert(
         "truncated".to_string(),
-        serde_json::Value::Bool(truncated),
+        serde_json::Value::Bool(summary.truncated),
     );
-    if let Some(msg) = message {
+    if let Some(msg) = summary.truncation_message() {
         map.insert(
             "message".to_string(),
-            serde_json::Value::String(msg.to_string()),
+            serde_json::Value::String(msg),
         );
     }
+    if !summary.messages.is_empty() {
+        map.insert(
+            "messages".to_string(),
+            serde_json::Value::Array(
+                summary.messages.iter().cloned().map(serde_json::Value::String).collect(),
+            ),
+        );
+    }
+    if let Some(count) = summary.rows_affected {
+        map.insert(
+            "rows_affected".to_string(),
+            serde_json::Value::Number(serde_json::Number::from(count)),
+        );
+    }
+    serde_json::Value::Object(map)
+}
 
-    let toon = toon_format::encode_default(&serde_json::Value::Object(map))
+fn encode_toon(value: &serde_json::Value) -> Result<String, DbtoonError> {
+    toon_format::encode_default(value)
         .map_err(|e| DbtoonError::Format {
             message: e.to_string(),
-        })?;
-    print!("{}", toon);
-    Ok(())
+        })
 }
 
 /// Print a truncation warning to stderr for interactive visibility.
@@ -57,6 +98,13 @@ pub fn print_truncation_warning(message: &str) {
 
 /// Write TOON string to a file.
 pub fn write_file(toon_string: &str, path: &Path) -> Result<(), DbtoonError> {
+    let mut file = create_file(path)?;
+    file.write_all(toon_string.as_bytes())?;
+    Ok(())
+}
+
+/// Create an output file, failing with a clear error if its parent directory is missing.
+pub fn create_file(path: &Path) -> Result<File, DbtoonError> {
     if let Some(parent) = path.parent()
         && !parent.exists() {
             return Err(DbtoonError::Io(std::io::Error::new(
@@ -64,6 +112,170 @@ pub fn write_file(toon_string: &str, path: &Path) -> Result<(), DbtoonError> {
                 format!("parent directory does not exist: {}", parent.display()),
             )));
         }
-    std::fs::write(path, toon_string)?;
-    Ok(())
+    Ok(File::create(path)?)
+}
+
+/// An output file of any supported format.
+pub enum FileSink {
+    Toon(ToonSink<File>),
+    Csv(CsvSink<File>),
+    Parquet(ParquetSink),
+    Arrow(ArrowSink),
+}
+
+impl FileSink {
+    /// Create the output file up front so path errors surface before the query runs.
+    pub fn create(format: OutputFormat, path: &Path) -> Result<Self, DbtoonError> {
+        Ok(match format {
+            OutputFormat::Toon => FileSink::Toon(ToonSink::new(create_file(path)?)),
+            OutputFormat::Csv => FileSink::Csv(CsvSink::new(create_file(path)?)),
+            OutputFormat::Parquet => FileSink::Parquet(ParquetSink::create(path)?),
+            OutputFormat::Arrow => FileSink::Arrow(ArrowSink::create(path)?),
+        })
+    }
+
+    /// Finalize the file, recording truncation where the format supports it.
+    pub fn finish(self, summary: &StreamSummary) -> Result<(), DbtoonError> {
+        let message = summary.truncation_message();
+        match self {
+            FileSink::Toon(sink) => sink.finish(summary),
+            FileSink::Csv(sink) => sink.finish(),
+            FileSink::Parquet(sink) => sink.finish(summary.truncated, message.as_deref()),
+            FileSink::Arrow(sink) => sink.finish(summary.truncated, message.as_deref()),
+        }
+    }
+
+    fn sink(&mut self) -> &mut dyn ResultSink {
+        match self {
+            FileSink::Toon(sink) => sink,
+            FileSink::Csv(sink) => sink,
+            FileSink::Parquet(sink) => sink,
+            FileSink::Arrow(sink) => sink,
+        }
+    }
+}
+
+/// Placeholder in an output path replaced by the 1-based result set number.
+pub const RESULT_SET_PLACEHOLDER: &str = "{n}";
+
+/// Writes the result sets of a query to files, one per set.
+///
+/// `{n}` in the path is replaced by the set number. Without it, a query returning
+/// several sets is an error, except for TOON, which holds every set in one document.
+/// Files are removed again unless `finish` succeeds.
+pub struct ResultFiles {
+    format: OutputFormat,
+    template: PathBuf,
+    current: Option<(PathBuf, FileSink)>,
+    written: Vec<(PathBuf, StreamSummary)>,
+    created: Vec<PathBuf>,
+    keep: bool,
+}
+
+impl ResultFiles {
+    /// Create the file for the first result set.
+    pub fn create(format: OutputFormat, template: &Path) -> Result<Self, DbtoonError> {
+        let mut files = Self {
+            format,
+            template: template.to_path_buf(),
+            current: None,
+            written: Vec::new(),
+            created: Vec::new(),
+            keep: false,
+        };
+        files.open_next()?;
+        Ok(files)
+    }
+
+    fn is_pattern(&self) -> bool {
+        self.template.to_string_lossy().contains(RESULT_SET_PLACEHOLDER)
+    }
+
+    fn open_next(&mut self) -> Result<(), DbtoonError> {
+        let n = self.written.len() + 1;
+        let path = PathBuf::from(
+            self.template
+                .to_string_lossy()
+                .replace(RESULT_SET_PLACEHOLDER, &n.to_string()),
+        );
+        let sink = FileSink::create(self.format, &path)?;
+        self.created.push(path.clone());
+        self.current = Some((path, sink));
+        Ok(())
+    }
+
+    fn current(&mut self) -> Result<&mut dyn ResultSink, DbtoonError> {
+        match &mut self.current {
+            Some((_, sink)) => Ok(sink.sink()),
+            None => Err(DbtoonError::Format {
+                message: "output file already finished".to_string(),
+            }),
+        }
+    }
+
+    /// Finalize the last file. Returns each result set's file and summary, in order.
+    pub fn finish(mut self, last: StreamSummary) -> Result<Vec<(PathBuf, StreamSummary)>, DbtoonError> {
+        if let Some((path, sink)) = self.current.take() {
+            sink.finish(&last)?;
+            self.written.push((path, last));
+        }
+        self.keep = true;
+        Ok(std::mem::take(&mut self.written))
+    }
+}
+
+impl Drop for ResultFiles {
+    fn drop(&mut self) {
+        if !self.keep {
+            // Don't leave partial files behind
+            self.current = None;
+            for path in &self.created {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+}
+
+impl ResultSink for ResultFiles {
+    fn begin(&mut self, columns: &[ColumnMeta]) -> Result<(), DbtoonError> {
+        self.current()?.begin(columns)
+    }
+
+    fn write_batch(&mut self, rows: &[Vec<CellValue>]) -> Result<(), DbtoonError> {
+        self.current()?.write_batch(rows)
+    }
+
+    fn accepts_arrow(&self) -> bool {
+        matches!(self.format, OutputFormat::Parquet | OutputFormat::Arrow)
+    }
+
+    fn write_arrow_batch(&mut self, batch: &RecordBatch) -> Result<(), DbtoonError> {
+        self.current()?.write_arrow_batch(batch)
+    }
+
+    fn next_result(&mut self, previous: StreamSummary) -> Result<(), DbtoonError> {
+        if !self.is_pattern() {
+            if let Some((path, FileSink::Toon(sink))) = &mut self.current {
+                self.written.push((path.clone(), previous.clone()));
+                return sink.next_result(previous);
+            }
+            let example = match self.format {
+                OutputFormat::Toon => "out_{n}.toon",
+                OutputFormat::Csv => "out_{n}.csv",
+                OutputFormat::Parquet => "out_{n}.parquet",
+                OutputFormat::Arrow => "out_{n}.arrow",
+            };
+            return Err(DbtoonError::Config {
+                message: format!(
+                    "query returned more than one result set; use an output path such as {example} to write one file per set"
+                ),
+            });
+        }
+
+        if let Some((path, sink)) = self.current.take() {
+            sink.finish(&previous)?;
+            self.written.push((path, previous));
+        }
+        self.open_next()
+    }
 }
@@ -0,0 +1,283 @@
+use crate::backend::CellValue;
+use crate::error::DbtoonError;
+
+/// A named value bound to a `:name` parameter marker, from `--param name=value[:type]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryParam {
+    pub name: String,
+    pub value: String,
+    /// SQL type of the value (e.g. `INT`, `DATE`, `DECIMAL(10,2)`); untyped values are strings.
+    pub type_name: Option<String>,
+}
+
+impl QueryParam {
+    /// The value as a native cell according to its type; untyped values stay `Text`.
+    pub fn typed_value(&self) -> CellValue {
+        match &self.type_name {
+            Some(type_name) => CellValue::from_typed_text(&self.value, type_name),
+            None => CellValue::Text(self.value.clone()),
+        }
+    }
+}
+
+/// Type names accepted after the last `:` of a `--param` value.
+///
+/// Anything else is part of the value, so times like `12:30:00` need no quoting.
+const PARAM_TYPES: &[&str] = &[
+    "STRING", "VARCHAR", "NVARCHAR", "CHAR", "NCHAR",
+    "TINYINT", "SMALLINT", "INT", "INTEGER", "BIGINT",
+    "FLOAT", "REAL", "DOUBLE", "DECIMAL", "NUMERIC",
+    "BOOLEAN", "BIT",
+    "DATE", "TIME", "TIMESTAMP", "TIMESTAMP_NTZ", "DATETIME2",
+    "BINARY",
+];
+
+/// Types whose values must parse as the native kind `CellValue::from_typed_text` produces.
+const CHECKED_TYPES: &[&str] = &[
+    "TINYINT", "SMALLINT", "INT", "INTEGER", "BIGINT",
+    "FLOAT", "REAL", "DOUBLE", "DECIMAL", "NUMERIC",
+    "BOOLEAN", "BIT",
+    "DATE", "TIME", "TIMESTAMP", "TIMESTAMP_NTZ", "DATETIME2",
+];
+
+/// Parse one `name=value[:type]` specification.
+pub fn parse_param(spec: &str) -> Result<QueryParam, DbtoonError> {
+    let Some((name, rest)) = spec.split_once('=') else {
+        return Err(DbtoonError::Config {
+            message: format!("invalid --param syntax: '{}' (expected name=value[:type])", spec),
+        });
+    };
+
+    let name = name.trim().trim_start_matches(':');
+    if !is_identifier(name) {
+        return Err(DbtoonError::Config {
+            message: format!(
+                "invalid parameter name '{}' (use letters, digits and underscores)",
+                name
+            ),
+        });
+    }
+
+    let (value, type_name) = match rest.rsplit_once(':') {
+        Some((value, suffix)) if is_param_type(suffix) => {
+            (value, Some(suffix.trim().to_uppercase()))
+        }
+        _ => (rest, None),
+    };
+
+    let param = QueryParam {
+        name: name.to_string(),
+        value: value.to_string(),
+        type_name,
+    };
+
+    if let Some(type_name) = &param.type_name
+        && CHECKED_TYPES.contains(&base_type(type_name).as_str())
+        && matches!(param.typed_value(), CellValue::Text(_))
+    {
+        return Err(DbtoonError::Config {
+            message: format!(
+                "parameter '{}': '{}' is not a valid {}",
+                param.name, param.value, type_name
+            ),
+        });
+    }
+
+    Ok(param)
+}
+
+/// Parse every `--param` value, rejecting names given more than once.
+pub fn parse_params(specs: &[String]) -> Result<Vec<QueryParam>, DbtoonError> {
+    let mut params: Vec<QueryParam> = Vec::with_capacity(specs.len());
+    for spec in specs {
+        let param = parse_param(spec)?;
+        if params.iter().any(|p| p.name.eq_ignore_ascii_case(&param.name)) {
+            return Err(DbtoonError::Config {
+                message: format!("parameter '{}' given more than once", param.name),
+            });
+        }
+        params.push(param);
+    }
+    Ok(params)
+}
+
+/// Rewrite `:name` markers to positional `?` markers for ODBC.
+///
+/// Returns the rewritten SQL and the parameters in marker order (a name used twice
+/// is bound twice). Markers inside string literals, quoted identifiers and comments
+/// are left alone, as are `::` casts and names with no matching parameter.
+/// SQL without any `:name` marker is returned unchanged with `params` in the order
+/// given, for queries written with `?` markers.
+pub fn to_positional<'a>(
+    sql: &str,
+    params: &'a [QueryParam],
+) -> Result<(String, Vec<&'a QueryParam>), DbtoonError> {
+    rewrite_markers(sql, params, |_, _| "?".to_string())
+}
+
+/// Rewrite `:name` markers to numbered `$1`, `$2`, ... markers for PostgreSQL.
+///
+/// Like `to_positional`, except that a name used twice shares one number, so
+/// each parameter appears once in the returned list, in number order.
+pub fn to_numbered<'a>(
+    sql: &str,
+    params: &'a [QueryParam],
+) -> Result<(String, Vec<&'a QueryParam>), DbtoonError> {
+    let (sql, bound) = rewrite_markers(sql, params, |bound, param| {
+        let mut names: Vec<&str> = Vec::new();
+        for b in bound {
+            if !names.contains(&b.name.as_str()) {
+                names.push(&b.name);
+            }
+        }
+        let number = names.iter().position(|&n| n == param.name).unwrap_or(names.len()) + 1;
+        format!("${}", number)
+    })?;
+    Ok((sql, unique(bound)))
+}
+
+/// Rewrite `:name` markers to ClickHouse `{name:Type}` query parameters, with
+/// `Type` given by `type_of`.
+///
+/// Like `to_numbered`, each parameter appears once in the returned list.
+pub fn to_braced<'a>(
+    sql: &str,
+    params: &'a [QueryParam],
+    type_of: impl Fn(&QueryParam) -> String,
+) -> Result<(String, Vec<&'a QueryParam>), DbtoonError> {
+    let (sql, bound) = rewrite_markers(sql, params, |_, param| {
+        format!("{{{}:{}}}", param.name, type_of(param))
+    })?;
+    Ok((sql, unique(bound)))
+}
+
+/// Rewrite `:name` markers to `@name` query parameters for BigQuery.
+///
+/// Like `to_numbered`, each parameter appears once in the returned list.
+pub fn to_at_named<'a>(
+    sql: &str,
+    params: &'a [QueryParam],
+) -> Result<(String, Vec<&'a QueryParam>), DbtoonError> {
+    let (sql, bound) = rewrite_markers(sql, params, |_, param| format!("@{}", param.name))?;
+    Ok((sql, unique(bound)))
+}
+
+/// `bound` without repeats, keeping the first occurrence of each name.
+fn unique(bound: Vec<&QueryParam>) -> Vec<&QueryParam> {
+    let mut unique: Vec<&QueryParam> = Vec::with_capacity(bound.len());
+    for param in bound {
+        if !unique.iter().any(|u| u.name == param.name) {
+            unique.push(param);
+        }
+    }
+    unique
+}
+
+/// Replace each `:name` marker with `marker(bound so far, param)`, recording the
+/// parameters in marker order.
+fn rewrite_markers<'a>(
+    sql: &str,
+    params: &'a [QueryParam],
+    marker: impl Fn(&[&'a QueryParam], &'a QueryParam) -> String,
+) -> Result<(String, Vec<&'a QueryParam>), DbtoonError> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut out = String::with_capacity(sql.len());
+    let mut bound = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let skip_to = match c {
+            '\'' | '"' => Some(closing_quote(&chars, i, c)),
+            '[' => Some(closing_quote(&chars, i, ']')),
+            '-' if chars.get(i + 1) == Some(&'-') => {
+                Some(chars[i..].iter().position(|&c| c == '\n').map_or(chars.len(), |p| i + p))
+            }
+            '/' if chars.get(i + 1) == Some(&'*') => Some(
+                (i + 2..chars.len().saturating_sub(1))
+                    .find(|&j| chars[j] == '*' && chars[j + 1] == '/')
+                    .map_or(chars.len(), |j| j + 2),
+            ),
+            _ => None,
+        };
+        if let Some(end) = skip_to {
+            out.extend(&chars[i..end]);
+            i = end;
+            continue;
+        }
+
+        if c == ':' && chars.get(i + 1) == Some(&':') {
+            out.push_str("::");
+            i += 2;
+            continue;
+        }
+
+        if c == ':' {
+            let start = i + 1;
+            let end = (start..chars.len())
+                .find(|&j| !(chars[j].is_ascii_alphanumeric() || chars[j] == '_'))
+                .unwrap_or(chars.len());
+            let name: String = chars[start..end].iter().collect();
+            if let Some(param) = params.iter().find(|p| p.name.eq_ignore_ascii_case(&name)) {
+                out.push_str(&marker(&bound, param));
+                bound.push(param);
+                i = end;
+                continue;
+            }
+        }
+
+        out.push(c);
+        i += 1;
+    }
+
+    if bound.is_empty() {
+        return Ok((sql.to_string(), params.iter().collect()));
+    }
+    if let Some(unused) = params.iter().find(|p| !bound.iter().any(|b| b.name == p.name)) {
+        return Err(DbtoonError::Config {
+            message: format!(
+                "parameter '{}' is not used in the query (expected :{})",
+                unused.name, unused.name
+            ),
+        });
+    }
+    Ok((out, bound))
+}
+
+/// Index just past the quote closing the one at `start`, treating a doubled quote as an escape.
+fn closing_quote(chars: &[char], start: usize, quote: char) -> usize {
+    let mut j = start + 1;
+    while j < chars.len() {
+        if chars[j] == quote {
+            if chars.get(j + 1) == Some(&quote) {
+                j += 2;
+                continue;
+            }
+            return j + 1;
+        }
+        j += 1;
+    }
+    chars.len()
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn is_param_type(suffix: &str) -> bool {
+    let suffix = suffix.trim();
+    let base = base_type(suffix);
+    let params_ok = match suffix.find('(') {
+        Some(pos) => suffix.ends_with(')') && suffix[pos + 1..suffix.len() - 1]
+            .split(',')
+            .all(|p| p.trim().parse::<u32>().is_ok()),
+        None => true,
+    };
+    params_ok && PARAM_TYPES.contains(&base.as_str())
+}
+
+fn base_type(type_name: &str) -> String {
+    type_name.split('(').next().unwrap_or_default().trim().to_uppercase()
+}
@@ -2,34 +2,96 @@
 
 use crate::config::resolve_env_var;
 use crate::error::DbtoonError;
+use crate::masking::{is_sensitive_attribute, mask_connection_string};
 use std::path::Path;
-use toml_edit::{DocumentMut, Item, Table, value};
+use toml_edit::{DocumentMut, InlineTable, Item, Table, value};
 
 /// Valid fields per backend (excluding `backend` which is always set at creation).
 const SQLSERVER_FIELDS: &[&str] = &[
     "server", "database", "username", "password",
     "windows_auth", "trust_server_certificate",
+    "auth", "client_id", "client_secret", "access_token",
+    "driver", "port", "instance", "encrypt", "application_intent", "extra_attributes",
+];
+
+const ODBC_FIELDS: &[&str] = &["dsn", "connection_string", "username", "password", "dialect"];
+
+const POSTGRES_FIELDS: &[&str] = &["host", "port", "database", "user", "password", "sslmode"];
+
+const MYSQL_FIELDS: &[&str] = &["host", "port", "database", "user", "password", "ssl_mode"];
+
+const CLICKHOUSE_FIELDS: &[&str] = &[
+    "host", "port", "database", "user", "password", "retry_attempts", "retry_backoff_ms",
+];
+
+const BIGQUERY_FIELDS: &[&str] = &[
+    "project", "dataset", "location", "credentials_path", "host", "retry_attempts", "retry_backoff_ms",
+];
+
+const SQLITE_FIELDS: &[&str] = &["path"];
+
+const SNOWFLAKE_FIELDS: &[&str] = &[
+    "account", "host", "user", "private_key_path", "private_key_passphrase",
+    "warehouse", "database", "schema", "role", "retry_attempts", "retry_backoff_ms",
+];
+
+const TRINO_FIELDS: &[&str] = &[
+    "host", "port", "user", "password", "token", "catalog", "schema",
+    "retry_attempts", "retry_backoff_ms",
 ];
 
 const DATABRICKS_FIELDS: &[&str] = &[
-    "host", "token", "warehouse_id", "catalog", "schema",
+    "host", "token", "auth", "client_id", "client_secret", "warehouse_id", "catalog", "schema",
+    "retry_attempts", "retry_backoff_ms",
 ];
 
+/// Fields stored as TOML booleans rather than strings.
+const BOOL_FIELDS: &[&str] = &["windows_auth", "trust_server_certificate"];
+
+/// Fields stored as TOML integers rather than strings.
+const INTEGER_FIELDS: &[&str] = &["retry_attempts", "retry_backoff_ms", "port"];
+
+/// Table of free-form connection attributes, set as `extra_attributes.<Key>=<value>`.
+const ATTRIBUTES_TABLE: &str = "extra_attributes";
+
 /// Secret fields that should be masked in `profile show`.
-const SECRET_FIELDS: &[&str] = &["password", "token"];
+const SECRET_FIELDS: &[&str] = &[
+    "password", "token", "client_secret", "access_token", "private_key_passphrase",
+];
 
 fn valid_fields(backend: &str) -> Result<&'static [&'static str], DbtoonError> {
     match backend {
         "sqlserver" => Ok(SQLSERVER_FIELDS),
         "databricks" => Ok(DATABRICKS_FIELDS),
+        "odbc" => Ok(ODBC_FIELDS),
+        "postgres" => Ok(POSTGRES_FIELDS),
+        "mysql" => Ok(MYSQL_FIELDS),
+        "clickhouse" => Ok(CLICKHOUSE_FIELDS),
+        "bigquery" => Ok(BIGQUERY_FIELDS),
+        "sqlite" => Ok(SQLITE_FIELDS),
+        "snowflake" => Ok(SNOWFLAKE_FIELDS),
+        "trino" | "presto" => Ok(TRINO_FIELDS),
         other => Err(DbtoonError::Config {
-            message: format!("unknown backend type: '{}' (expected 'sqlserver' or 'databricks')", other),
+            message: format!(
+                "unknown backend type: '{}' (expected 'sqlserver', 'databricks', 'odbc', 'postgres', 'mysql', 'clickhouse', 'bigquery', 'sqlite', 'snowflake', 'trino' or 'presto')",
+                other
+            ),
         }),
     }
 }
 
 fn validate_field(backend: &str, field: &str) -> Result<(), DbtoonError> {
     let fields = valid_fields(backend)?;
+    if let Some(attribute) = attribute_key(field)
+        && fields.contains(&ATTRIBUTES_TABLE)
+    {
+        if attribute.is_empty() {
+            return Err(DbtoonError::Config {
+                message: format!("missing attribute name in '{}'", field),
+            });
+        }
+        return Ok(());
+    }
     if !fields.contains(&field) && field != "backend" {
         return Err(DbtoonError::Config {
             message: format!(
@@ -63,6 +125,61 @@ fn ensure_profiles_table(doc: &mut DocumentMut) {
     }
 }
 
+/// The TOML value for a `--set key=value`, typed by the field.
+fn field_value(key: &str, val: &str) -> Result<Item, DbtoonError> {
+    if BOOL_FIELDS.contains(&key) {
+        return match val {
+            "true" => Ok(value(true)),
+            "false" => Ok(value(false)),
+            _ => Err(DbtoonError::Config {
+                message: format!("field '{}' must be 'true' or 'false', got '{}'", key, val),
+            }),
+        };
+    }
+    if INTEGER_FIELDS.contains(&key) {
+        return val.parse::<u32>().map(|n| value(i64::from(n))).map_err(|_| DbtoonError::Config {
+            message: format!("field '{}' must be a non-negative integer, got '{}'", key, val),
+        });
+    }
+    Ok(value(val))
+}
+
+/// The attribute name of an `extra_attributes.<Key>` field.
+fn attribute_key(field: &str) -> Option<&str> {
+    field.strip_prefix(ATTRIBUTES_TABLE)?.strip_prefix('.')
+}
+
+/// Set `key` in `profile`, creating the attributes table for `extra_attributes.<Key>`.
+fn set_field(profile: &mut Table, key: &str, val: &str) -> Result<(), DbtoonError> {
+    let Some(attribute) = attribute_key(key) else {
+        profile[key] = field_value(key, val)?;
+        return Ok(());
+    };
+    let attributes = profile
+        .entry(ATTRIBUTES_TABLE)
+        .or_insert_with(|| value(InlineTable::new()))
+        .as_table_like_mut()
+        .ok_or_else(|| DbtoonError::Config {
+            message: format!("'{}' is not a table", ATTRIBUTES_TABLE),
+        })?;
+    attributes.insert(attribute, value(val));
+    Ok(())
+}
+
+/// Remove `key` from `profile`, dropping the attributes table once it is empty.
+fn remove_field(profile: &mut Table, key: &str) {
+    let Some(attribute) = attribute_key(key) else {
+        profile.remove(key);
+        return;
+    };
+    if let Some(attributes) = profile.get_mut(ATTRIBUTES_TABLE).and_then(Item::as_table_like_mut) {
+        attributes.remove(attribute);
+        if attributes.is_empty() {
+            profile.remove(ATTRIBUTES_TABLE);
+        }
+    }
+}
+
 fn parse_set_field(kv: &str) -> Result<(&str, &str), DbtoonError> {
     let Some((key, val)) = kv.split_once('=') else {
         return Err(DbtoonError::Config {
@@ -117,6 +234,45 @@ pub fn create_profile(
             profile["username"] = value("sa");
             profile["password"] = value("$SA_PASSWORD");
         }
+        "odbc" => {
+            profile["dsn"] = value("mydsn");
+            profile["dialect"] = value("generic");
+        }
+        "postgres" => {
+            profile["host"] = value("localhost");
+            profile["database"] = value("postgres");
+            profile["user"] = value("postgres");
+            profile["password"] = value("$PGPASSWORD");
+        }
+        "mysql" => {
+            profile["host"] = value("localhost");
+            profile["database"] = value("mysql");
+            profile["user"] = value("root");
+            profile["password"] = value("$MYSQL_PWD");
+        }
+        "clickhouse" => {
+            profile["host"] = value("http://localhost:8123");
+            profile["database"] = value("default");
+            profile["user"] = value("default");
+            profile["password"] = value("$CLICKHOUSE_PASSWORD");
+        }
+        "bigquery" => {
+            profile["project"] = value("$GOOGLE_CLOUD_PROJECT");
+            profile["credentials_path"] = value("$GOOGLE_APPLICATION_CREDENTIALS");
+        }
+        "sqlite" => {
+            profile["path"] = value(":memory:");
+        }
+        "snowflake" => {
+            profile["account"] = value("$SNOWFLAKE_ACCOUNT");
+            profile["user"] = value("$SNOWFLAKE_USER");
+            profile["private_key_path"] = value("$SNOWFLAKE_PRIVATE_KEY_PATH");
+            profile["warehouse"] = value("$SNOWFLAKE_WAREHOUSE");
+        }
+        "trino" | "presto" => {
+            profile["host"] = value("http://localhost:8080");
+            profile["user"] = value("$USER");
+        }
         _ => {} // Already validated above
     }
 
@@ -124,22 +280,9 @@ pub fn create_profile(
     for kv in set_fields {
         let (key, val) = parse_set_field(kv)?;
         if val.is_empty() {
-            profile.remove(key);
+            remove_field(&mut profile, key);
         } else {
-            // Bool fields
-            if key == "windows_auth" || key == "trust_server_certificate" {
-                match val {
-                    "true" => { profile[key] = value(true); }
-                    "false" => { profile[key] = value(false); }
-                    _ => {
-                        return Err(DbtoonError::Config {
-                            message: format!("field '{}' must be 'true' or 'false', got '{}'", key, val),
-                        });
-                    }
-                }
-            } else {
-                profile[key] = value(val);
-            }
+            set_field(&mut profile, key, val)?;
         }
     }
 
@@ -190,34 +333,18 @@ pub fn edit_profile(
     }
 
     // Apply changes
-    for kv in set_fields {
-        let (key, val) = parse_set_field(kv)?;
-        if val.is_empty() {
-            // Remove field
-            if let Some(profile) = doc["profiles"][name].as_table_mut() {
-                profile.remove(key);
-            }
-        } else {
-            // Bool fields
-            if key == "windows_auth" || key == "trust_server_certificate" {
-                match val {
-                    "true" => { doc["profiles"][name][key] = value(true); }
-                    "false" => { doc["profiles"][name][key] = value(false); }
-                    _ => {
-                        return Err(DbtoonError::Config {
-                            message: format!("field '{}' must be 'true' or 'false', got '{}'", key, val),
-                        });
-                    }
-                }
+    if let Some(profile) = doc["profiles"][name].as_table_mut() {
+        for kv in set_fields {
+            let (key, val) = parse_set_field(kv)?;
+            if val.is_empty() {
+                remove_field(profile, key);
             } else {
-                doc["profiles"][name][key] = value(val);
+                set_field(profile, key, val)?;
             }
         }
-    }
 
-    for key in unset_fields {
-        if let Some(profile) = doc["profiles"][name].as_table_mut() {
-            profile.remove(key.as_str());
+        for key in unset_fields {
+            remove_field(profile, key);
         }
     }
 
@@ -247,49 +374,69 @@ pub fn show_profile(
     let mut output = format!("[profiles.{}]\n", name);
 
     for (key, item) in profile_table.iter() {
-        let raw_value = item.as_str().unwrap_or_default();
-        let is_secret = SECRET_FIELDS.contains(&key);
-
-        if let Some(var_name) = raw_value.strip_prefix('$')
-            && !var_name.starts_with('$') {
-                // It's a $VAR reference
-                match resolve_env_var(raw_value) {
-                    Ok(resolved) => {
-                        let display = if is_secret && !show_secrets {
-                            "****".to_string()
-                        } else {
-                            resolved
-                        };
-                        output.push_str(&format!(
-                            "{} = \"{}\" (${} = \"{}\")\n",
-                            key, raw_value, var_name, display
-                        ));
-                    }
-                    Err(_) => {
-                        output.push_str(&format!(
-                            "{} = \"{}\" (WARNING: ${} is not set)\n",
-                            key, raw_value, var_name
-                        ));
-                    }
-                }
-                continue;
-            }
-
-        // Literal value or bool
-        if is_secret && !show_secrets {
-            if item.as_str().is_some() {
-                output.push_str(&format!("{} = \"****\"\n", key));
-            } else {
-                output.push_str(&format!("{} = {}\n", key, item));
+        if key == ATTRIBUTES_TABLE
+            && let Some(attributes) = item.as_table_like()
+        {
+            for (attribute, item) in attributes.iter() {
+                let key = format!("{}.{}", ATTRIBUTES_TABLE, attribute);
+                let mask = (is_sensitive_attribute(attribute) && !show_secrets).then_some(hide as Mask);
+                show_field(&mut output, &key, item, mask);
             }
-        } else {
-            output.push_str(&format!("{} = {}\n", key, item));
+            continue;
         }
+        let mask: Option<Mask> = match key {
+            _ if show_secrets => None,
+            "connection_string" => Some(mask_connection_string),
+            _ if SECRET_FIELDS.contains(&key) => Some(hide),
+            _ => None,
+        };
+        show_field(&mut output, key, item, mask);
     }
 
     Ok(output)
 }
 
+/// How `profile show` displays a secret string value.
+type Mask = fn(&str) -> String;
+
+fn hide(_: &str) -> String {
+    "****".to_string()
+}
+
+/// Append one `key = value` line, resolving `$VAR` references and applying `mask`.
+fn show_field(output: &mut String, key: &str, item: &Item, mask: Option<Mask>) {
+    let raw_value = item.as_str().unwrap_or_default();
+    if let Some(var_name) = raw_value.strip_prefix('$')
+        && !var_name.starts_with('$') {
+            // It's a $VAR reference
+            match resolve_env_var(raw_value) {
+                Ok(resolved) => {
+                    let display = match mask {
+                        Some(mask) => mask(&resolved),
+                        None => resolved,
+                    };
+                    output.push_str(&format!(
+                        "{} = \"{}\" (${} = \"{}\")\n",
+                        key, raw_value, var_name, display
+                    ));
+                }
+                Err(_) => {
+                    output.push_str(&format!(
+                        "{} = \"{}\" (WARNING: ${} is not set)\n",
+                        key, raw_value, var_name
+                    ));
+                }
+            }
+            return;
+        }
+
+    // Literal value or bool
+    match (mask, item.as_str()) {
+        (Some(mask), Some(text)) => output.push_str(&format!("{} = \"{}\"\n", key, mask(text))),
+        _ => output.push_str(&format!("{} = {}\n", key, item)),
+    }
+}
+
 /// List all profile names.
 pub fn list_profiles(config_path: &Path) -> Result<Vec<String>, DbtoonError> {
     let doc = read_doc(config_path)?;
@@ -347,10 +494,27 @@ pub fn test_profile(config_path: &Path, name: &str) -> Result<(), DbtoonError> {
             message: format!("profile '{}' has no 'backend' field", name),
         })?;
 
-    // Check required fields
+    // Check required fields; a databricks `client_id` or OAuth login stands in for `token`,
+    // an odbc `connection_string` for `dsn`, and a bigquery emulator `host` needs no key
     let required: &[&str] = match backend {
         "sqlserver" => &["server"],
+        "databricks" if profile_table.get("auth").and_then(|a| a.as_str()) == Some("oauth-u2m") => {
+            &["host", "warehouse_id"]
+        }
+        "databricks" if profile_table.contains_key("client_id") => {
+            &["host", "client_id", "warehouse_id"]
+        }
         "databricks" => &["host", "token", "warehouse_id"],
+        "odbc" if profile_table.contains_key("connection_string") => &["connection_string"],
+        "odbc" => &["dsn"],
+        "postgres" => &["host", "user"],
+        "mysql" => &["host", "user"],
+        "clickhouse" => &["host"],
+        "bigquery" if profile_table.contains_key("host") => &[],
+        "bigquery" => &["credentials_path"],
+        "sqlite" => &["path"],
+        "snowflake" => &["account", "user", "private_key_path"],
+        "trino" | "presto" => &["host", "user"],
         other => return Err(DbtoonError::Config {
             message: format!("unknown backend type: '{}'", other),
         }),
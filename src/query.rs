@@ -0,0 +1,528 @@
+use std::io::Write;
+use std::path::Path;
+
+use crate::backend::{
+    self, Backend, CellValue, ColumnMeta, QueryResult, ResultSink, SessionReuse, StreamSummary,
+};
+use crate::cancel::CancelToken;
+use crate::config::{self, AppConfig, BackendConfig};
+use crate::error::DbtoonError;
+use crate::format::ToonSink;
+use crate::format_detect::{self, OutputFormat};
+use crate::{masking, output};
+use crate::params::QueryParam;
+use crate::validation::{self, BackendDialect};
+use secrecy::ExposeSecret;
+use crate::verbose::{self, Timer};
+
+/// Reject write queries unless the config allows them.
+pub fn check_read_only(app_config: &AppConfig, sql: &str) -> Result<(), DbtoonError> {
+    let verbose = app_config.verbose;
+    if app_config.allow_write {
+        verbose::emit(verbose, "write mode enabled — skipping validation");
+        return Ok(());
+    }
+
+    verbose::emit(verbose, "validating query (read-only mode)...");
+    let timer = Timer::start();
+    match validation::validate(sql, dialect(&app_config.backend)) {
+        validation::ValidationResult::Safe => {
+            verbose::emit(
+                verbose,
+                &format!("validation passed ({}ms)", timer.elapsed_ms()),
+            );
+            Ok(())
+        }
+        validation::ValidationResult::Denied { reasons } => {
+            verbose::emit(verbose, "validation failed");
+            let detail = reasons
+                .iter()
+                .map(|r| r.detail.clone())
+                .collect::<Vec<_>>()
+                .join("; ");
+            Err(DbtoonError::Validation { reason: detail })
+        }
+    }
+}
+
+/// What running `sql` leaves of a reused connection's session.
+fn session_reuse(app_config: &AppConfig, sql: &str) -> SessionReuse {
+    if app_config.shared_session {
+        SessionReuse::Keep
+    } else if validation::is_plain_query(sql, dialect(&app_config.backend)) {
+        SessionReuse::ReadOnly
+    } else {
+        SessionReuse::Reset
+    }
+}
+
+pub(crate) fn dialect(backend: &BackendConfig) -> BackendDialect {
+    match backend {
+        BackendConfig::SqlServer { .. } => BackendDialect::SqlServer,
+        BackendConfig::Databricks { .. } => BackendDialect::Databricks,
+        BackendConfig::Odbc { dialect, .. } => *dialect,
+        BackendConfig::Postgres { .. } => BackendDialect::Postgres,
+        BackendConfig::MySql { .. } => BackendDialect::MySql,
+        BackendConfig::ClickHouse { .. } => BackendDialect::ClickHouse,
+        BackendConfig::BigQuery { .. } => BackendDialect::BigQuery,
+        BackendConfig::Sqlite { .. } => BackendDialect::Sqlite,
+        BackendConfig::Snowflake { .. } => BackendDialect::Snowflake,
+        BackendConfig::Trino { .. } => BackendDialect::Generic,
+    }
+}
+
+/// Run `sql` on `backend`, streaming its rows into `sink`, with verbose progress
+/// starting from `connecting` (e.g. "connecting to postgres at db:5432").
+async fn run_on<B: Backend + Sync, S: ResultSink + 'static>(
+    app_config: &AppConfig,
+    backend: B,
+    connecting: &str,
+    sql: &str,
+    params: &[QueryParam],
+    sink: S,
+) -> Result<(S, StreamSummary), DbtoonError> {
+    let verbose = app_config.verbose;
+    verbose::emit(verbose, &format!("{}...", connecting));
+    let timer = Timer::start();
+    verbose::emit(verbose, "executing query...");
+    let (sink, summary) = backend
+        .execute_streaming(sql, params, app_config.default_row_limit, app_config.query_timeout_secs, sink)
+        .await?;
+    verbose::emit(
+        verbose,
+        &format!(
+            "query complete ({}ms, {} rows)",
+            timer.elapsed_ms(),
+            summary.rows
+        ),
+    );
+    Ok((sink, summary))
+}
+
+/// Run `sql` against the configured backend, streaming its rows into `sink`.
+///
+/// Firing `cancel` stops the statement on the server and returns `DbtoonError::Cancelled`.
+pub async fn execute_query<S: ResultSink + 'static>(
+    app_config: &AppConfig,
+    sql: &str,
+    params: &[QueryParam],
+    cancel: &CancelToken,
+    sink: S,
+) -> Result<(S, StreamSummary), DbtoonError> {
+    let verbose = app_config.verbose;
+
+    if app_config.dry_run && !matches!(app_config.backend, BackendConfig::BigQuery { .. }) {
+        return Err(DbtoonError::Config {
+            message: "--dry-run is only supported by the bigquery backend".to_string(),
+        });
+    }
+
+    match &app_config.backend {
+        BackendConfig::SqlServer {
+            server,
+            database,
+            auth,
+            trust_server_certificate,
+            options,
+        } => {
+            let backend_impl = backend::sqlserver::SqlServerBackend::new(
+                server.clone(),
+                database.clone(),
+                clone_auth(auth),
+                *trust_server_certificate,
+            )
+            .with_options(options.clone())
+            .with_session(session_reuse(app_config, sql))
+            .with_cancel(cancel.clone());
+            let connecting = format!("connecting to sqlserver at {}", server);
+            run_on(app_config, backend_impl, &connecting, sql, params, sink).await
+        }
+        BackendConfig::Databricks {
+            host,
+            auth,
+            warehouse_id,
+            catalog,
+            schema,
+        } => {
+            let backend_impl = backend::databricks::DatabricksBackend::new(
+                host.clone(),
+                clone_databricks_auth(auth),
+                warehouse_id.clone(),
+                catalog.clone(),
+                schema.clone(),
+            )
+            .with_download_concurrency(app_config.download_concurrency)
+            .with_retry(app_config.retry)
+            .with_verbose(verbose)
+            .with_cancel(cancel.clone());
+            let connecting = format!("connecting to databricks at {}", host);
+            run_on(app_config, backend_impl, &connecting, sql, params, sink).await
+        }
+        BackendConfig::Odbc { connection_string, .. } => {
+            let target = masking::mask_connection_string(connection_string.expose_secret());
+            let backend_impl = backend::odbc::OdbcBackend::new(clone_secret(connection_string))
+                .with_session(session_reuse(app_config, sql))
+                .with_cancel(cancel.clone());
+            let connecting = format!("connecting to odbc with {}", target);
+            run_on(app_config, backend_impl, &connecting, sql, params, sink).await
+        }
+        BackendConfig::Sqlite { path } => {
+            let backend_impl = backend::sqlite::SqliteBackend::new(path.clone())
+                .with_read_only(!app_config.allow_write)
+                .with_working_dir(app_config.working_dir.clone())
+                .with_session(session_reuse(app_config, sql))
+                .with_cancel(cancel.clone());
+            let connecting = format!("opening sqlite database {}", path);
+            run_on(app_config, backend_impl, &connecting, sql, params, sink).await
+        }
+        BackendConfig::Postgres {
+            host,
+            port,
+            database,
+            user,
+            password,
+            sslmode,
+        } => {
+            let backend_impl = backend::postgres::PostgresBackend::new(
+                host.clone(),
+                *port,
+                database.clone(),
+                user.clone(),
+                password.as_ref().map(clone_secret),
+                *sslmode,
+            )
+            .with_session(session_reuse(app_config, sql))
+            .with_cancel(cancel.clone());
+            let connecting = format!("connecting to postgres at {}:{}", host, port);
+            run_on(app_config, backend_impl, &connecting, sql, params, sink).await
+        }
+        BackendConfig::MySql {
+            host,
+            port,
+            database,
+            user,
+            password,
+            ssl_mode,
+        } => {
+            let backend_impl = backend::mysql::MySqlBackend::new(
+                host.clone(),
+                *port,
+                database.clone(),
+                user.clone(),
+                password.as_ref().map(clone_secret),
+                *ssl_mode,
+            )
+            .with_session(session_reuse(app_config, sql))
+            .with_cancel(cancel.clone());
+            let connecting = format!("connecting to mysql at {}:{}", host, port);
+            run_on(app_config, backend_impl, &connecting, sql, params, sink).await
+        }
+        BackendConfig::ClickHouse {
+            host,
+            port,
+            database,
+            user,
+            password,
+        } => {
+            let backend_impl = backend::clickhouse::ClickHouseBackend::new(
+                host.clone(),
+                *port,
+                database.clone(),
+                user.clone(),
+                password.as_ref().map(clone_secret),
+            )
+            .with_retry(app_config.retry)
+            .with_verbose(verbose)
+            .with_cancel(cancel.clone());
+            let connecting = format!("connecting to clickhouse at {}", host);
+            run_on(app_config, backend_impl, &connecting, sql, params, sink).await
+        }
+        BackendConfig::BigQuery {
+            project,
+            credentials_path,
+            dataset,
+            location,
+            host,
+        } => {
+            let account = credentials_path
+                .as_deref()
+                .map(backend::bigquery::load_service_account)
+                .transpose()?;
+            let project = project
+                .clone()
+                .or_else(|| account.as_ref().and_then(|a| a.project_id.clone()))
+                .ok_or_else(|| DbtoonError::Config {
+                    message: "no 'project' specified for bigquery backend".to_string(),
+                })?;
+            let connecting = format!("connecting to bigquery project {}", project);
+            let backend_impl = backend::bigquery::BigQueryBackend::new(
+                project,
+                account,
+                dataset.clone(),
+                location.clone(),
+            )
+            .with_host(host.clone())
+            .with_dry_run(app_config.dry_run)
+            .with_retry(app_config.retry)
+            .with_verbose(verbose)
+            .with_cancel(cancel.clone());
+            run_on(app_config, backend_impl, &connecting, sql, params, sink).await
+        }
+        BackendConfig::Snowflake {
+            account,
+            host,
+            user,
+            private_key_path,
+            private_key_passphrase,
+            warehouse,
+            database,
+            schema,
+            role,
+        } => {
+            let private_key = backend::snowflake::load_private_key(
+                private_key_path,
+                private_key_passphrase.as_ref(),
+            )?;
+            let backend_impl = backend::snowflake::SnowflakeBackend::new(
+                account.clone(),
+                user.clone(),
+                private_key,
+                warehouse.clone(),
+                database.clone(),
+                schema.clone(),
+                role.clone(),
+            )
+            .with_host(host.clone())
+            .with_retry(app_config.retry)
+            .with_verbose(verbose)
+            .with_cancel(cancel.clone());
+            let connecting = format!("connecting to snowflake account {}", account);
+            run_on(app_config, backend_impl, &connecting, sql, params, sink).await
+        }
+        BackendConfig::Trino {
+            presto,
+            host,
+            port,
+            user,
+            auth,
+            catalog,
+            schema,
+        } => {
+            let product = if *presto { "presto" } else { "trino" };
+            let backend_impl = backend::trino::TrinoBackend::new(
+                host.clone(),
+                *port,
+                user.clone(),
+                clone_trino_auth(auth),
+                catalog.clone(),
+                schema.clone(),
+            )
+            .with_presto_headers(*presto)
+            .with_retry(app_config.retry)
+            .with_verbose(verbose)
+            .with_cancel(cancel.clone());
+            let connecting = format!("connecting to {} at {}", product, host);
+            run_on(app_config, backend_impl, &connecting, sql, params, sink).await
+        }
+    }
+}
+
+/// Validate and run `sql` the way `dbtoon query` does: TOON results (or, with
+/// an output file, the file summary) go to `stdout`, truncation warnings to `stderr`.
+pub async fn run_to_output<W: Write + Send + 'static>(
+    app_config: &AppConfig,
+    sql: &str,
+    params: &[QueryParam],
+    cancel: &CancelToken,
+    stdout: W,
+    stderr: &mut (dyn Write + Send),
+) -> Result<(), DbtoonError> {
+    let verbose = app_config.verbose;
+
+    // Validation: block write queries unless --allow-write
+    check_read_only(app_config, sql)?;
+
+    let Some(path) = &app_config.output_file else {
+        verbose::emit(verbose, "formatting TOON output...");
+        let sink = ToonSink::new(stdout);
+        let (sink, summary) = execute_query(app_config, sql, params, cancel, sink).await?;
+        let mut messages: Vec<String> = sink
+            .earlier_results()
+            .iter()
+            .filter_map(StreamSummary::truncation_message)
+            .collect();
+        sink.finish(&summary)?;
+        messages.extend(summary.truncation_message());
+        for msg in &messages {
+            writeln!(stderr, "warning: {}", msg)?;
+        }
+        return Ok(());
+    };
+
+    let mut stdout = stdout;
+    run_to_files(app_config, sql, params, cancel, path, &mut stdout, stderr).await
+}
+
+/// Run `sql` into the output file(s) at `path`, in the format its extension
+/// names, then write the TOON file summary to `stdout` and truncation warnings
+/// to `stderr`. `sql` is not validated here; see `check_read_only`.
+pub async fn run_to_files(
+    app_config: &AppConfig,
+    sql: &str,
+    params: &[QueryParam],
+    cancel: &CancelToken,
+    path: &Path,
+    stdout: &mut (dyn Write + Send),
+    stderr: &mut (dyn Write + Send),
+) -> Result<(), DbtoonError> {
+    let verbose = app_config.verbose;
+
+    // Detect output format before query (fail-fast on bad extension)
+    let (format, path) = format_detect::detect_format(path)?;
+    let format_label = match format {
+        OutputFormat::Toon => "TOON",
+        OutputFormat::Csv => "CSV",
+        OutputFormat::Parquet => "Parquet",
+        OutputFormat::Arrow => "Arrow IPC",
+    };
+    verbose::emit(verbose, &format!("formatting {format_label} output..."));
+
+    verbose::emit(verbose, &format!("writing output to {}...", path.display()));
+    // Partial files are removed if the query or a writer fails
+    let sink = output::ResultFiles::create(format, &path)?;
+    let (sink, summary) = execute_query(app_config, sql, params, cancel, sink).await?;
+    let files = sink.finish(summary)?;
+
+    match files.as_slice() {
+        [(file, summary)] => write!(stdout, "{}", output::summary_toon(file, summary)?)?,
+        _ => write!(stdout, "{}", output::results_summary_toon(&files)?)?,
+    }
+    stdout.flush()?;
+
+    for msg in files.iter().filter_map(|(_, summary)| summary.truncation_message()) {
+        writeln!(stderr, "warning: {}", msg)?;
+    }
+
+    Ok(())
+}
+
+/// List the SQL warehouses of a Databricks profile as a result table.
+pub async fn list_warehouses(app_config: &AppConfig) -> Result<QueryResult, DbtoonError> {
+    let verbose = app_config.verbose;
+    let (host, auth) = match &app_config.backend {
+        BackendConfig::Databricks { host, auth, .. } => (host, auth),
+        _ => {
+            return Err(DbtoonError::Config {
+                message: "warehouse list requires a databricks profile".to_string(),
+            });
+        }
+    };
+
+    verbose::emit(verbose, &format!("listing warehouses on {}...", host));
+    let timer = Timer::start();
+    let warehouses =
+        backend::databricks::list_warehouses(host, auth, &app_config.retry, verbose).await?;
+    verbose::emit(
+        verbose,
+        &format!(
+            "warehouse list retrieved ({}ms, {} warehouses)",
+            timer.elapsed_ms(),
+            warehouses.len()
+        ),
+    );
+
+    let columns = ["id", "name", "state", "cluster_size", "type"]
+        .into_iter()
+        .map(|name| ColumnMeta { name: name.to_string(), type_name: "STRING".to_string() })
+        .collect();
+
+    let rows = warehouses
+        .into_iter()
+        .map(|w| {
+            vec![
+                CellValue::Text(w.id),
+                CellValue::Text(w.name),
+                CellValue::Text(w.state),
+                CellValue::Text(w.cluster_size),
+                match w.warehouse_type {
+                    Some(t) => CellValue::Text(t),
+                    None => CellValue::Null,
+                },
+            ]
+        })
+        .collect();
+
+    Ok(QueryResult {
+        columns,
+        rows,
+        ..QueryResult::default()
+    })
+}
+
+fn clone_auth(auth: &config::SqlServerAuth) -> config::SqlServerAuth {
+    match auth {
+        config::SqlServerAuth::WindowsIntegrated => config::SqlServerAuth::WindowsIntegrated,
+        config::SqlServerAuth::SqlLogin { username, password } => {
+            config::SqlServerAuth::SqlLogin {
+                username: username.clone(),
+                password: clone_secret(password),
+            }
+        }
+        config::SqlServerAuth::ActiveDirectoryServicePrincipal {
+            client_id,
+            client_secret,
+        } => config::SqlServerAuth::ActiveDirectoryServicePrincipal {
+            client_id: client_id.clone(),
+            client_secret: clone_secret(client_secret),
+        },
+        config::SqlServerAuth::ActiveDirectoryPassword { username, password } => {
+            config::SqlServerAuth::ActiveDirectoryPassword {
+                username: username.clone(),
+                password: clone_secret(password),
+            }
+        }
+        config::SqlServerAuth::ActiveDirectoryInteractive { username } => {
+            config::SqlServerAuth::ActiveDirectoryInteractive {
+                username: username.clone(),
+            }
+        }
+        config::SqlServerAuth::ActiveDirectoryDefault => {
+            config::SqlServerAuth::ActiveDirectoryDefault
+        }
+        config::SqlServerAuth::AccessToken(token) => {
+            config::SqlServerAuth::AccessToken(clone_secret(token))
+        }
+    }
+}
+
+fn clone_databricks_auth(auth: &config::DatabricksAuth) -> config::DatabricksAuth {
+    match auth {
+        config::DatabricksAuth::Token(token) => config::DatabricksAuth::Token(clone_secret(token)),
+        config::DatabricksAuth::OAuthM2M {
+            client_id,
+            client_secret,
+        } => config::DatabricksAuth::OAuthM2M {
+            client_id: client_id.clone(),
+            client_secret: clone_secret(client_secret),
+        },
+        config::DatabricksAuth::OAuthU2M {
+            client_id,
+            token_cache,
+        } => config::DatabricksAuth::OAuthU2M {
+            client_id: client_id.clone(),
+            token_cache: token_cache.clone(),
+        },
+    }
+}
+
+fn clone_trino_auth(auth: &config::TrinoAuth) -> config::TrinoAuth {
+    match auth {
+        config::TrinoAuth::None => config::TrinoAuth::None,
+        config::TrinoAuth::Password(password) => config::TrinoAuth::Password(clone_secret(password)),
+        config::TrinoAuth::Jwt(token) => config::TrinoAuth::Jwt(clone_secret(token)),
+    }
+}
+
+fn clone_secret(secret: &secrecy::SecretString) -> secrecy::SecretString {
+    secrecy::SecretString::from(secret.expose_secret().to_string())
+}
@@ -0,0 +1,639 @@
+//! Interactive shell (`dbtoon shell`): statements typed over one or more lines,
+//! each ended by `;` or a `GO` line, run against a profile over a connection
+//! kept open for the session.
+//!
+//! Statements go through the same read-only validation and backends as
+//! `dbtoon query`. Lines starting with `\` are meta-commands that change the
+//! session's profile, row limit, output and write permission.
+
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::backend::{self, QueryResult, ResultSink};
+use crate::cancel::CancelToken;
+use crate::cli::{QueryArgs, ShellArgs};
+use crate::config::{self, AppConfig, TomlConfig};
+use crate::error::DbtoonError;
+use crate::format::{self, ToonResultSet};
+use crate::format_detect;
+use crate::format_table;
+use crate::line_editor::{LineEditor, ReadLine};
+use crate::query;
+use crate::validation::BackendDialect;
+
+/// How long connections stay open between statements.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(3600);
+
+const HELP: &str = "\
+Statements end with ; or with a line containing only GO. On SQL Server a ;
+inside BEGIN ... END does not, and procedure, function and trigger
+definitions end only at GO.
+
+  \\profile [NAME]            show or switch the profile
+  \\limit [N|off]             show or set the row limit
+  \\output [table|toon|FILE]  show or set where results go (FILE by extension)
+  \\allow-write [on|off]      show, set or toggle permission to write
+  \\timing [on|off]           show, set or toggle statement timing
+  \\help                      show this help
+  \\quit                      leave the shell (also Ctrl-D)
+";
+
+/// How results are shown when they are not written to a file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Display {
+    Table,
+    Toon,
+}
+
+/// Whether the shell keeps reading after a line.
+#[derive(Debug, PartialEq)]
+pub enum Flow {
+    Continue,
+    Quit,
+}
+
+pub struct Shell {
+    config_path: Option<PathBuf>,
+    history_file: PathBuf,
+    args: QueryArgs,
+    verbose: bool,
+    show_secrets: bool,
+    app_config: AppConfig,
+    display: Display,
+    timing: bool,
+    /// Lines of a statement not yet ended.
+    buffer: String,
+}
+
+impl Shell {
+    pub fn new(
+        args: &ShellArgs,
+        config_path: Option<&PathBuf>,
+        verbose: bool,
+        show_secrets: bool,
+    ) -> Result<Self, DbtoonError> {
+        let args = QueryArgs {
+            sql: None,
+            file: None,
+            profile: args.profile.clone(),
+            database: args.database.clone(),
+            catalog: args.catalog.clone(),
+            schema: args.schema.clone(),
+            limit: args.limit,
+            no_limit: args.no_limit,
+            timeout: args.timeout,
+            output: None,
+            allow_write: args.allow_write,
+            params: Vec::new(),
+            dry_run: false,
+            no_daemon: true,
+        };
+        let (toml_config, config_file_path) = config::load_toml_config_required(config_path)?;
+        let mut app_config = config::load_from_query_args(&args, &toml_config, verbose, show_secrets)?;
+        app_config.shared_session = true;
+        Ok(Self {
+            config_path: config_path.cloned(),
+            history_file: config_file_path.with_file_name("shell-history"),
+            args,
+            verbose,
+            show_secrets,
+            app_config,
+            display: Display::Table,
+            timing: false,
+            buffer: String::new(),
+        })
+    }
+
+    /// The history file, next to the config file.
+    pub fn history_file(&self) -> &Path {
+        &self.history_file
+    }
+
+    pub fn app_config(&self) -> &AppConfig {
+        &self.app_config
+    }
+
+    pub fn display(&self) -> Display {
+        self.display
+    }
+
+    /// `profile> ` for a new statement, `      -> ` while one continues.
+    pub fn prompt(&self) -> String {
+        let profile = &self.args.profile;
+        if self.buffer.trim().is_empty() {
+            format!("{}> ", profile)
+        } else {
+            format!("{}-> ", " ".repeat(profile.chars().count().saturating_sub(1)))
+        }
+    }
+
+    /// Read and run lines from stdin until `\quit` or end of input.
+    pub async fn run(mut self) -> Result<(), DbtoonError> {
+        backend::reuse_connections(IDLE_TIMEOUT);
+        let mut editor = LineEditor::new(Some(self.history_file.clone()))?;
+        let interactive = editor.is_terminal();
+        if interactive {
+            println!(
+                "dbtoon shell: profile {}. Type \\help for commands, \\quit to leave.",
+                self.args.profile
+            );
+        }
+
+        let (mut stdout, mut stderr) = (io::stdout(), io::stderr());
+        loop {
+            let prompt = if interactive { self.prompt() } else { String::new() };
+            // Reading blocks, so it runs off the async workers; the editor travels with it
+            let (returned, read) = tokio::task::spawn_blocking(move || {
+                let read = editor.read_line(&prompt);
+                (editor, read)
+            })
+            .await
+            .map_err(|e| DbtoonError::Config {
+                message: format!("input task failed: {}", e),
+            })?;
+            editor = returned;
+
+            match read? {
+                ReadLine::Line(line) => {
+                    if interactive {
+                        editor.add_history(&line);
+                    }
+                    if self.handle_line(&line, &mut stdout, &mut stderr).await? == Flow::Quit {
+                        break;
+                    }
+                }
+                ReadLine::Interrupted => self.interrupt(),
+                ReadLine::Eof => break,
+            }
+        }
+
+        backend::close_all_connections();
+        Ok(())
+    }
+
+    /// Handle one line of input: run the statements it ends, or the meta-command it is.
+    ///
+    /// Errors from statements and meta-commands are written to `stderr`; only
+    /// failing to write output is returned.
+    pub async fn handle_line(
+        &mut self,
+        line: &str,
+        stdout: &mut (dyn Write + Send),
+        stderr: &mut (dyn Write + Send),
+    ) -> io::Result<Flow> {
+        let trimmed = line.trim();
+        if self.buffer.trim().is_empty() {
+            if trimmed.starts_with('\\') {
+                self.buffer.clear();
+                return self.meta_command(trimmed, stdout, stderr);
+            }
+            if trimmed.eq_ignore_ascii_case("quit") || trimmed.eq_ignore_ascii_case("exit") {
+                return Ok(Flow::Quit);
+            }
+        }
+
+        let dialect = query::dialect(&self.app_config.backend);
+        if trimmed.eq_ignore_ascii_case("go") && scan(&self.buffer, dialect).state == Lexical::Code {
+            let batch = std::mem::take(&mut self.buffer);
+            let batch = batch.trim().trim_end_matches(';').trim_end();
+            if !batch.is_empty() {
+                self.execute(batch, stdout, stderr).await?;
+            }
+            return Ok(Flow::Continue);
+        }
+
+        self.buffer.push_str(line);
+        self.buffer.push('\n');
+        let (statements, rest) = split_statements(&self.buffer, dialect);
+        self.buffer = rest;
+        for statement in statements {
+            self.execute(&statement, stdout, stderr).await?;
+        }
+        Ok(Flow::Continue)
+    }
+
+    /// Forget a partly typed statement, as on Ctrl-C.
+    pub fn interrupt(&mut self) {
+        self.buffer.clear();
+    }
+
+    /// Run `sql`, cancelling it on Ctrl-C, and report the outcome.
+    async fn execute(
+        &mut self,
+        sql: &str,
+        stdout: &mut (dyn Write + Send),
+        stderr: &mut (dyn Write + Send),
+    ) -> io::Result<()> {
+        let cancel = CancelToken::new();
+        let started = Instant::now();
+        let result = {
+            let work = self.run_statement(sql, &cancel, stdout, stderr);
+            tokio::pin!(work);
+            tokio::select! {
+                result = &mut work => result,
+                // The terminal is out of raw mode while a statement runs, so Ctrl-C is a signal
+                Ok(()) = tokio::signal::ctrl_c() => {
+                    cancel.cancel();
+                    work.await
+                }
+            }
+        };
+        match result {
+            Ok(()) if self.timing => {
+                writeln!(stdout, "Time: {:.3} ms", started.elapsed().as_secs_f64() * 1000.0)?;
+            }
+            Ok(()) => {}
+            Err(DbtoonError::Io(e)) => return Err(e),
+            Err(e) => writeln!(stderr, "error: {}", e)?,
+        }
+        stdout.flush()
+    }
+
+    async fn run_statement(
+        &self,
+        sql: &str,
+        cancel: &CancelToken,
+        stdout: &mut (dyn Write + Send),
+        stderr: &mut (dyn Write + Send),
+    ) -> Result<(), DbtoonError> {
+        let app_config = &self.app_config;
+        query::check_read_only(app_config, sql)?;
+        if let Some(path) = &app_config.output_file {
+            query::run_to_files(app_config, sql, &[], cancel, path, stdout, stderr).await?;
+            // The file summary is written without a final line break
+            writeln!(stdout)?;
+            return Ok(());
+        }
+
+        let (mut results, summary) =
+            query::execute_query(app_config, sql, &[], cancel, Vec::<QueryResult>::new()).await?;
+        if results.is_empty() {
+            // A statement without a result set still reports rows affected and messages
+            results.push(QueryResult::default());
+        }
+        results.next_result(summary)?;
+
+        let messages: Vec<Option<String>> = results.iter().map(truncation_message).collect();
+        match self.display {
+            Display::Toon => {
+                let sets: Vec<ToonResultSet<'_>> = results
+                    .iter()
+                    .zip(&messages)
+                    .map(|(result, message)| ToonResultSet {
+                        result,
+                        truncated: result.truncated,
+                        message: message.as_deref(),
+                    })
+                    .collect();
+                writeln!(stdout, "{}", format::to_toon_results(&sets)?)?;
+            }
+            Display::Table => {
+                for (i, result) in results.iter().enumerate() {
+                    if i > 0 {
+                        writeln!(stdout)?;
+                    }
+                    for message in &result.messages {
+                        writeln!(stderr, "{}", message)?;
+                    }
+                    if !result.columns.is_empty() {
+                        write!(stdout, "{}", format_table::to_table(result))?;
+                    } else if let Some(count) = result.rows_affected {
+                        writeln!(stdout, "({} {} affected)", count, if count == 1 { "row" } else { "rows" })?;
+                    } else {
+                        writeln!(stdout, "OK")?;
+                    }
+                }
+            }
+        }
+        for message in messages.iter().flatten() {
+            writeln!(stderr, "warning: {}", message)?;
+        }
+        Ok(())
+    }
+
+    fn meta_command(
+        &mut self,
+        line: &str,
+        stdout: &mut (dyn Write + Send),
+        stderr: &mut (dyn Write + Send),
+    ) -> io::Result<Flow> {
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap_or_default();
+        let argument = words.collect::<Vec<_>>().join(" ");
+        let argument = argument.as_str();
+
+        let outcome = match command {
+            "\\q" | "\\quit" => return Ok(Flow::Quit),
+            "\\?" | "\\h" | "\\help" => Ok(HELP.to_string()),
+            "\\profile" => self.profile_command(argument),
+            "\\limit" => self.limit_command(argument),
+            "\\output" => self.output_command(argument),
+            "\\allow-write" => on_off(argument, self.app_config.allow_write).map(|on| {
+                self.app_config.allow_write = on;
+                format!("allow-write is {}\n", if on { "on" } else { "off" })
+            }),
+            "\\timing" => on_off(argument, self.timing).map(|on| {
+                self.timing = on;
+                format!("timing is {}\n", if on { "on" } else { "off" })
+            }),
+            _ => Err(DbtoonError::Config {
+                message: format!("unknown command {} — try \\help", command),
+            }),
+        };
+        match outcome {
+            Ok(text) => write!(stdout, "{}", text)?,
+            Err(e) => writeln!(stderr, "error: {}", e)?,
+        }
+        stdout.flush()?;
+        Ok(Flow::Continue)
+    }
+
+    /// `\profile [NAME]`: switch to another profile from a freshly read config
+    /// file, keeping the session's limit, output and write permission.
+    fn profile_command(&mut self, name: &str) -> Result<String, DbtoonError> {
+        if name.is_empty() {
+            return Ok(format!("profile is {}\n", self.args.profile));
+        }
+        let (toml_config, _) = config::load_toml_config_required(self.config_path.as_ref())?;
+        let mut args = self.args.clone();
+        args.profile = name.to_string();
+        let mut app_config = config::load_from_query_args(&args, &toml_config, self.verbose, self.show_secrets)?;
+        app_config.default_row_limit = self.app_config.default_row_limit;
+        app_config.allow_write = self.app_config.allow_write;
+        app_config.output_file = self.app_config.output_file.take();
+        app_config.shared_session = true;
+
+        // The previous profile's connection is not needed any more
+        backend::close_all_connections();
+        self.args = args;
+        self.app_config = app_config;
+        Ok(format!("profile is {}{}\n", name, backend_label(&toml_config, name)))
+    }
+
+    /// `\limit [N|off]`.
+    fn limit_command(&mut self, argument: &str) -> Result<String, DbtoonError> {
+        match argument {
+            "" => {}
+            "off" | "none" => self.app_config.default_row_limit = None,
+            n => {
+                let limit = n.parse::<usize>().map_err(|_| DbtoonError::Config {
+                    message: format!("invalid row limit '{}' — use a number or off", n),
+                })?;
+                self.app_config.default_row_limit = Some(limit);
+            }
+        }
+        Ok(match self.app_config.default_row_limit {
+            Some(limit) => format!("row limit is {}\n", limit),
+            None => "row limit is off\n".to_string(),
+        })
+    }
+
+    /// `\output [table|toon|FILE]`.
+    fn output_command(&mut self, argument: &str) -> Result<String, DbtoonError> {
+        match argument {
+            "" => {}
+            "table" => {
+                self.display = Display::Table;
+                self.app_config.output_file = None;
+            }
+            "toon" => {
+                self.display = Display::Toon;
+                self.app_config.output_file = None;
+            }
+            path => {
+                // Rejects an unsupported extension now rather than at the next statement
+                format_detect::detect_format(Path::new(path))?;
+                self.app_config.output_file = Some(PathBuf::from(path));
+            }
+        }
+        Ok(match (&self.app_config.output_file, self.display) {
+            (Some(path), _) => format!("output goes to {}\n", path.display()),
+            (None, Display::Table) => "output is table\n".to_string(),
+            (None, Display::Toon) => "output is toon\n".to_string(),
+        })
+    }
+}
+
+/// ` (backend)` for profile `name`, if the config names its backend.
+fn backend_label(toml_config: &TomlConfig, name: &str) -> String {
+    toml_config
+        .profiles
+        .get(name)
+        .and_then(|p| p.backend.as_deref())
+        .map(|backend| format!(" ({})", backend))
+        .unwrap_or_default()
+}
+
+/// The setting `on`/`off` asks for, or the opposite of `current` when empty.
+fn on_off(argument: &str, current: bool) -> Result<bool, DbtoonError> {
+    match argument {
+        "" => Ok(!current),
+        "on" => Ok(true),
+        "off" => Ok(false),
+        other => Err(DbtoonError::Config {
+            message: format!("expected on or off, got '{}'", other),
+        }),
+    }
+}
+
+/// The truncation notice for a result set, pointing at `\limit`.
+fn truncation_message(result: &QueryResult) -> Option<String> {
+    result
+        .truncated
+        .then(|| format!("Showing {} rows. Use \\limit off to return all rows.", result.rows.len()))
+}
+
+/// Where the scanner is at the end of some SQL text.
+#[derive(Debug, Clone, PartialEq)]
+enum Lexical {
+    Code,
+    /// Inside a string or quoted identifier opened by this character.
+    Quoted(char),
+    LineComment,
+    BlockComment,
+    /// Inside a PostgreSQL dollar-quoted string with this tag, e.g. `$body$`.
+    Dollar(String),
+}
+
+struct Scan {
+    /// Byte offsets of the `;` outside quotes, comments and, when scanning
+    /// T-SQL, `BEGIN … END` blocks.
+    terminators: Vec<usize>,
+    state: Lexical,
+}
+
+fn scan(sql: &str, dialect: BackendDialect) -> Scan {
+    let blocks = dialect == BackendDialect::SqlServer;
+    let mut terminators = Vec::new();
+    let mut state = Lexical::Code;
+    // Open BEGIN and CASE keywords not yet closed by END
+    let mut depth = 0usize;
+    let mut chars = sql.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let next = chars.peek().map(|&(_, c)| c);
+        match &state {
+            Lexical::Code => match c {
+                ';' if depth == 0 => terminators.push(i),
+                c if blocks && (c.is_ascii_alphabetic() || c == '_') && !sql[..i].ends_with(is_word_char) => {
+                    let word = leading_word(&sql[i..]);
+                    for _ in 1..word.len() {
+                        chars.next();
+                    }
+                    if word.eq_ignore_ascii_case("case") || opens_block(word, &sql[i + word.len()..]) {
+                        depth += 1;
+                    } else if word.eq_ignore_ascii_case("end") {
+                        depth = depth.saturating_sub(1);
+                    }
+                }
+                '\'' | '"' | '`' => state = Lexical::Quoted(c),
+                '-' if next == Some('-') => {
+                    chars.next();
+                    state = Lexical::LineComment;
+                }
+                '/' if next == Some('*') => {
+                    chars.next();
+                    state = Lexical::BlockComment;
+                }
+                '$' => {
+                    if let Some(tag) = dollar_tag(&sql[i..]) {
+                        for _ in 1..tag.len() {
+                            chars.next();
+                        }
+                        state = Lexical::Dollar(tag.to_string());
+                    }
+                }
+                _ => {}
+            },
+            Lexical::Quoted(quote) => {
+                if c == *quote {
+                    // A doubled quote is an escaped one
+                    if next == Some(*quote) {
+                        chars.next();
+                    } else {
+                        state = Lexical::Code;
+                    }
+                }
+            }
+            Lexical::LineComment => {
+                if c == '\n' {
+                    state = Lexical::Code;
+                }
+            }
+            Lexical::BlockComment => {
+                if c == '*' && next == Some('/') {
+                    chars.next();
+                    state = Lexical::Code;
+                }
+            }
+            Lexical::Dollar(tag) => {
+                if sql[i..].starts_with(tag.as_str()) {
+                    for _ in 1..tag.len() {
+                        chars.next();
+                    }
+                    state = Lexical::Code;
+                }
+            }
+        }
+    }
+    Scan { terminators, state }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '@' | '#' | '$')
+}
+
+/// The identifier or keyword at the start of `sql`.
+fn leading_word(sql: &str) -> &str {
+    let end = sql.find(|c: char| !is_word_char(c)).unwrap_or(sql.len());
+    &sql[..end]
+}
+
+/// Whether `word`, followed by `rest`, opens a T-SQL block that END closes:
+/// `BEGIN` and `BEGIN TRY`/`CATCH`, but not `BEGIN TRAN` and its kin.
+fn opens_block(word: &str, rest: &str) -> bool {
+    if !word.eq_ignore_ascii_case("begin") {
+        return false;
+    }
+    let next = leading_word(rest.trim_start()).to_ascii_uppercase();
+    !matches!(
+        next.as_str(),
+        "TRAN" | "TRANSACTION" | "DISTRIBUTED" | "DIALOG" | "CONVERSATION"
+    )
+}
+
+/// Whether `statement` creates or alters a T-SQL procedure, function or
+/// trigger, whose body runs to the end of the batch.
+fn starts_routine(statement: &str) -> bool {
+    let Some(code) = skip_comments(statement) else {
+        return false;
+    };
+    let words: Vec<String> = code
+        .split_whitespace()
+        .take(4)
+        .map(str::to_ascii_uppercase)
+        .collect();
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let rest = match words.as_slice() {
+        ["CREATE", "OR", "ALTER", rest @ ..] | ["CREATE" | "ALTER", rest @ ..] => rest,
+        _ => return false,
+    };
+    matches!(rest.first(), Some(&("PROC" | "PROCEDURE" | "FUNCTION" | "TRIGGER")))
+}
+
+/// The `$tag$` opening a dollar-quoted string at the start of `sql`, if any.
+fn dollar_tag(sql: &str) -> Option<&str> {
+    let end = sql[1..].find('$')? + 1;
+    let tag = &sql[1..end];
+    let valid = tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !tag.starts_with(|c: char| c.is_ascii_digit());
+    valid.then(|| &sql[..=end])
+}
+
+/// Split the statements ended by `;` off the front of `buffer`: returns them,
+/// trimmed and without the `;`, and the unfinished rest. A rest holding only
+/// whitespace and comments is dropped.
+///
+/// In T-SQL a `;` inside `BEGIN … END` does not end the statement, and a
+/// procedure, function or trigger definition is only ended by `GO`.
+pub fn split_statements(buffer: &str, dialect: BackendDialect) -> (Vec<String>, String) {
+    let scanned = scan(buffer, dialect);
+    let mut statements = Vec::new();
+    let mut start = 0;
+    for end in scanned.terminators {
+        if dialect == BackendDialect::SqlServer && starts_routine(&buffer[start..end]) {
+            break;
+        }
+        let statement = buffer[start..end].trim();
+        if !statement.is_empty() {
+            statements.push(statement.to_string());
+        }
+        start = end + 1;
+    }
+    let rest = &buffer[start..];
+    let rest = if is_blank(rest) { String::new() } else { rest.to_string() };
+    (statements, rest)
+}
+
+/// Whether `sql` has nothing but whitespace and complete comments.
+fn is_blank(sql: &str) -> bool {
+    skip_comments(sql).is_some_and(str::is_empty)
+}
+
+/// `sql` after its leading whitespace and comments, or `None` if a block
+/// comment is left open.
+fn skip_comments(sql: &str) -> Option<&str> {
+    let mut rest = sql.trim_start();
+    loop {
+        if let Some(after) = rest.strip_prefix("--") {
+            match after.find('\n') {
+                Some(end) => rest = after[end..].trim_start(),
+                None => return Some(""),
+            }
+        } else if let Some(after) = rest.strip_prefix("/*") {
+            rest = after[after.find("*/")? + 2..].trim_start();
+        } else {
+            return Some(rest);
+        }
+    }
+}
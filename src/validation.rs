@@ -1,12 +1,39 @@
-use sqlparser::dialect::{DatabricksDialect, MsSqlDialect};
-use sqlparser::parser::Parser;
+use sqlparser::dialect::{
+    BigQueryDialect, ClickHouseDialect, DatabricksDialect, GenericDialect, MsSqlDialect, MySqlDialect, PostgreSqlDialect,
+    SQLiteDialect, SnowflakeDialect,
+};
+use sqlparser::parser::{Parser, ParserError};
 use sqlparser::ast::{ObjectName, ObjectNamePart, SetExpr, Statement};
 
 /// Which backend dialect to use for SQL parsing.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum BackendDialect {
     SqlServer,
     Databricks,
+    /// ANSI-leaning SQL for Trino and for ODBC sources without a dedicated dialect.
+    Generic,
+    Postgres,
+    MySql,
+    ClickHouse,
+    BigQuery,
+    Snowflake,
+    Sqlite,
+}
+
+impl BackendDialect {
+    /// The dialect for an ODBC profile's `dialect` field, if recognized.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "generic" => Some(Self::Generic),
+            "postgres" | "postgresql" => Some(Self::Postgres),
+            "mysql" | "mariadb" => Some(Self::MySql),
+            "clickhouse" => Some(Self::ClickHouse),
+            "bigquery" => Some(Self::BigQuery),
+            "snowflake" => Some(Self::Snowflake),
+            "sqlite" => Some(Self::Sqlite),
+            _ => None,
+        }
+    }
 }
 
 /// Why a statement was denied.
@@ -68,16 +95,7 @@ pub enum ValidationResult {
 
 /// Validate that all SQL statements are read-only.
 pub fn validate(sql: &str, dialect: BackendDialect) -> ValidationResult {
-    let statements = match dialect {
-        BackendDialect::SqlServer => {
-            Parser::parse_sql(&MsSqlDialect {}, sql)
-        }
-        BackendDialect::Databricks => {
-            Parser::parse_sql(&DatabricksDialect {}, sql)
-        }
-    };
-
-    let statements = match statements {
+    let statements = match parse(sql, dialect) {
         Ok(stmts) => stmts,
         Err(e) => {
             return ValidationResult::Denied {
@@ -109,6 +127,35 @@ pub fn validate(sql: &str, dialect: BackendDialect) -> ValidationResult {
     }
 }
 
+/// Whether every statement in `sql` is a query (`SELECT`, `WITH`, `VALUES`)
+/// without `INTO`, so it cannot change the session it runs in the way `USE`,
+/// `SET` or a temporary table would.
+pub fn is_plain_query(sql: &str, dialect: BackendDialect) -> bool {
+    parse(sql, dialect).is_ok_and(|statements| {
+        statements
+            .iter()
+            .all(|stmt| matches!(stmt, Statement::Query(query) if check_query_denial(&query.body).is_none()))
+    })
+}
+
+fn parse(sql: &str, dialect: BackendDialect) -> Result<Vec<Statement>, ParserError> {
+    match dialect {
+        BackendDialect::SqlServer => {
+            Parser::parse_sql(&MsSqlDialect {}, sql)
+        }
+        BackendDialect::Databricks => {
+            Parser::parse_sql(&DatabricksDialect {}, sql)
+        }
+        BackendDialect::Generic => Parser::parse_sql(&GenericDialect {}, sql),
+        BackendDialect::Postgres => Parser::parse_sql(&PostgreSqlDialect {}, sql),
+        BackendDialect::MySql => Parser::parse_sql(&MySqlDialect {}, sql),
+        BackendDialect::ClickHouse => Parser::parse_sql(&ClickHouseDialect {}, sql),
+        BackendDialect::BigQuery => Parser::parse_sql(&BigQueryDialect {}, sql),
+        BackendDialect::Snowflake => Parser::parse_sql(&SnowflakeDialect {}, sql),
+        BackendDialect::Sqlite => Parser::parse_sql(&SQLiteDialect {}, sql),
+    }
+}
+
 /// Check if a statement is denied. Returns `Some((kind, detail))` if denied, `None` if allowed.
 /// This is the deny-list core: unknown statement types fall through to `_ => None` (allowed).
 fn is_denied_statement(stmt: &Statement, dialect: BackendDialect) -> Option<(DenialKind, String)> {
@@ -187,8 +234,13 @@ fn is_denied_statement(stmt: &Statement, dialect: BackendDialect) -> Option<(Den
         Statement::LoadData { .. } => Some((DenialKind::Operational, "Denied: operational statement (LOAD DATA)".into())),
         Statement::Unload { .. } => Some((DenialKind::Operational, "Denied: operational statement (UNLOAD)".into())),
         Statement::Kill { .. } => Some((DenialKind::Operational, "Denied: operational statement (KILL)".into())),
+        Statement::LockTables { .. } => Some((DenialKind::Operational, "Denied: operational statement (LOCK TABLES)".into())),
+        Statement::OptimizeTable { .. } => Some((DenialKind::Operational, "Denied: operational statement (OPTIMIZE)".into())),
+        Statement::ExportData(_) => Some((DenialKind::Operational, "Denied: operational statement (EXPORT DATA)".into())),
         Statement::Flush { .. } => Some((DenialKind::Operational, "Denied: operational statement (FLUSH)".into())),
         Statement::Install { .. } => Some((DenialKind::Operational, "Denied: operational statement (INSTALL)".into())),
+        // `PRAGMA x = v` and `PRAGMA x(v)` both set; read with the pragma_x() table functions instead
+        Statement::Pragma { value: Some(_), .. } => Some((DenialKind::Operational, "Denied: operational statement (PRAGMA with a value)".into())),
         Statement::AttachDatabase { .. } => Some((DenialKind::Operational, "Denied: operational statement (ATTACH)".into())),
         Statement::AttachDuckDBDatabase { .. } => Some((DenialKind::Operational, "Denied: operational statement (ATTACH)".into())),
         Statement::DetachDuckDBDatabase { .. } => Some((DenialKind::Operational, "Denied: operational statement (DETACH)".into())),
@@ -0,0 +1,364 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+
+use dbtoon::backend::bigquery::{
+    BigQueryBackend, Field, bigquery_cell, format_bytes, load_service_account, normalize_bigquery_type,
+    query_parameter,
+};
+use dbtoon::backend::{Backend, CellValue};
+use dbtoon::cancel::CancelToken;
+use dbtoon::error::DbtoonError;
+use dbtoon::params::parse_param;
+
+use super::mock_http::{MockResponse, MockServer, RecordedRequest};
+use super::snowflake_test::TEST_KEY;
+
+const QUERIES: &str = "/bigquery/v2/projects/p/queries";
+
+const TOKEN: &str = "/token";
+
+fn temp_path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join("dbtoon_test_bigquery");
+    fs::create_dir_all(&dir).unwrap();
+    dir.join(name)
+}
+
+/// A service account key file whose `token_uri` points at `server`.
+fn key_file(server: &MockServer, name: &str) -> PathBuf {
+    let path = temp_path(name);
+    let key = serde_json::json!({
+        "type": "service_account",
+        "project_id": "key-project",
+        "private_key_id": "kid-1",
+        "private_key": TEST_KEY,
+        "client_email": "reader@key-project.iam.gserviceaccount.com",
+        "token_uri": format!("{}{}", server.url(), TOKEN),
+    });
+    fs::write(&path, key.to_string()).unwrap();
+    path
+}
+
+fn backend(server: &MockServer, name: &str) -> BigQueryBackend {
+    server.on(
+        "POST",
+        TOKEN,
+        MockResponse::json(200, r#"{"access_token":"ya29.test","expires_in":3599,"token_type":"Bearer"}"#),
+    );
+    let account = load_service_account(&key_file(server, name)).unwrap();
+    BigQueryBackend::new("p".to_string(), Some(account), Some("analytics".to_string()), Some("EU".to_string()))
+        .with_host(Some(server.url()))
+}
+
+fn body(request: &RecordedRequest) -> serde_json::Value {
+    serde_json::from_str(&request.body).unwrap()
+}
+
+fn query_string(request: &RecordedRequest) -> HashMap<String, String> {
+    url::form_urlencoded::parse(request.query.as_bytes()).into_owned().collect()
+}
+
+fn field(type_name: &str) -> Field {
+    Field {
+        name: "c".to_string(),
+        type_name: type_name.to_string(),
+        ..Field::default()
+    }
+}
+
+const SCHEMA: &str = r#"{"fields":[
+    {"name":"id","type":"INTEGER","mode":"REQUIRED"},
+    {"name":"name","type":"STRING","mode":"NULLABLE"},
+    {"name":"at","type":"TIMESTAMP","mode":"NULLABLE"}
+]}"#;
+
+fn page(rows: &[(&str, &str)], page_token: Option<&str>) -> String {
+    let rows: Vec<String> = rows
+        .iter()
+        .map(|(id, name)| format!(r#"{{"f":[{{"v":"{id}"}},{{"v":"{name}"}},{{"v":"1704067200000000"}}]}}"#))
+        .collect();
+    let token = page_token.map(|t| format!(r#","pageToken":"{t}""#)).unwrap_or_default();
+    format!(
+        r#"{{"jobComplete":true,"jobReference":{{"projectId":"p","jobId":"job-1","location":"EU"}},
+            "schema":{SCHEMA},"totalRows":"3","rows":[{}]{token}}}"#,
+        rows.join(",")
+    )
+}
+
+#[test]
+fn normalizes_types_for_columnar_output() {
+    assert_eq!(normalize_bigquery_type(&field("STRING")), "VARCHAR");
+    assert_eq!(normalize_bigquery_type(&field("INTEGER")), "BIGINT");
+    assert_eq!(normalize_bigquery_type(&field("FLOAT64")), "DOUBLE");
+    assert_eq!(normalize_bigquery_type(&field("BOOL")), "BOOLEAN");
+    assert_eq!(normalize_bigquery_type(&field("NUMERIC")), "DECIMAL(38,9)");
+    let money = Field { precision: Some("12".to_string()), scale: Some("2".to_string()), ..field("NUMERIC") };
+    assert_eq!(normalize_bigquery_type(&money), "DECIMAL(12,2)");
+    assert_eq!(normalize_bigquery_type(&field("BIGNUMERIC")), "BIGNUMERIC");
+    assert_eq!(normalize_bigquery_type(&field("DATETIME")), "TIMESTAMP_NTZ");
+    assert_eq!(normalize_bigquery_type(&field("TIMESTAMP")), "TIMESTAMP");
+    assert_eq!(normalize_bigquery_type(&field("BYTES")), "VARBINARY");
+    let tags = Field { mode: Some("REPEATED".to_string()), ..field("STRING") };
+    assert_eq!(normalize_bigquery_type(&tags), "ARRAY<VARCHAR>");
+    assert_eq!(normalize_bigquery_type(&field("RECORD")), "STRUCT");
+}
+
+#[test]
+fn decodes_row_values() {
+    use serde_json::json;
+    let cell = |v: serde_json::Value, f: &Field| bigquery_cell(&v, f, &normalize_bigquery_type(f));
+    assert_eq!(cell(json!("-42"), &field("INTEGER")), CellValue::Int64(-42));
+    assert_eq!(cell(json!("0.5"), &field("FLOAT")), CellValue::Float64(0.5));
+    assert_eq!(cell(json!("true"), &field("BOOLEAN")), CellValue::Bool(true));
+    assert_eq!(cell(json!("12.5"), &field("NUMERIC")), CellValue::Decimal { value: 125, scale: 1 });
+    assert_eq!(cell(json!("2024-01-01"), &field("DATE")), CellValue::Date(19_723));
+    assert_eq!(
+        cell(json!("1704067201500000"), &field("TIMESTAMP")),
+        CellValue::Timestamp(1_704_067_201_500_000)
+    );
+    assert_eq!(
+        cell(json!("2024-01-01T00:00:01.5"), &field("DATETIME")),
+        CellValue::Timestamp(1_704_067_201_500_000)
+    );
+    assert_eq!(cell(json!("3q0="), &field("BYTES")), CellValue::Bytes(vec![0xde, 0xad]));
+    assert_eq!(cell(json!("POINT(1 2)"), &field("GEOGRAPHY")), CellValue::Text("POINT(1 2)".to_string()));
+    assert_eq!(cell(json!(null), &field("STRING")), CellValue::Null);
+
+    let tags = Field { mode: Some("REPEATED".to_string()), ..field("STRING") };
+    assert_eq!(cell(json!([{"v": "a"}, {"v": "b"}]), &tags), CellValue::Text(r#"["a","b"]"#.to_string()));
+    let point = Field { fields: vec![Field { name: "x".to_string(), ..field("INTEGER") }], ..field("RECORD") };
+    assert_eq!(cell(json!({"f": [{"v": "1"}]}), &point), CellValue::Text(r#"{"x":"1"}"#.to_string()));
+}
+
+#[test]
+fn binds_parameters_as_typed_query_parameters() {
+    let p = |spec: &str| query_parameter(&parse_param(spec).unwrap());
+    let id = p("id=10:INT");
+    assert_eq!(id["name"], "id");
+    assert_eq!(id["parameterType"]["type"], "INT64");
+    assert_eq!(id["parameterValue"]["value"], "10");
+    assert_eq!(p("price=1.50:DECIMAL(10,2)")["parameterType"]["type"], "NUMERIC");
+    assert_eq!(p("on=1:BIT")["parameterValue"]["value"], "true");
+    assert_eq!(p("at=2024-01-01T08:00:00:TIMESTAMP_NTZ")["parameterType"]["type"], "DATETIME");
+    assert_eq!(p("name=x")["parameterType"]["type"], "STRING");
+}
+
+#[test]
+fn formats_bytes_processed() {
+    assert_eq!(format_bytes(512), "512 B");
+    assert_eq!(format_bytes(1536), "1.50 KiB");
+    assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.00 GiB");
+}
+
+#[test]
+fn assertion_is_signed_for_the_token_endpoint() {
+    let server = MockServer::start();
+    let account = load_service_account(&key_file(&server, "assertion.json")).unwrap();
+    assert_eq!(account.project_id.as_deref(), Some("key-project"));
+
+    let token = account.assertion(1_700_000_000);
+    let mut parts = token.split('.');
+    let header: serde_json::Value =
+        serde_json::from_slice(&URL_SAFE_NO_PAD.decode(parts.next().unwrap()).unwrap()).unwrap();
+    let claims: serde_json::Value =
+        serde_json::from_slice(&URL_SAFE_NO_PAD.decode(parts.next().unwrap()).unwrap()).unwrap();
+    assert_eq!(header["alg"], "RS256");
+    assert_eq!(header["kid"], "kid-1");
+    assert_eq!(claims["iss"], "reader@key-project.iam.gserviceaccount.com");
+    assert_eq!(claims["scope"], "https://www.googleapis.com/auth/bigquery");
+    assert_eq!(claims["aud"], format!("{}{}", server.url(), TOKEN));
+    assert_eq!(claims["exp"], 1_700_003_600);
+}
+
+#[test]
+fn rejects_non_service_account_credentials() {
+    let path = temp_path("user.json");
+    fs::write(
+        &path,
+        r#"{"type":"authorized_user","private_key":"","client_email":"me@example.com"}"#,
+    )
+    .unwrap();
+    let err = load_service_account(&path).err().unwrap();
+    assert!(err.to_string().contains("not a service account key"), "{err}");
+}
+
+#[tokio::test]
+async fn pages_through_results_with_bearer_token() {
+    let server = MockServer::start();
+    server.on("POST", QUERIES, MockResponse::json(200, &page(&[("1", "a"), ("2", "b")], Some("t2"))));
+    server.on("GET", &format!("{QUERIES}/job-1"), MockResponse::json(200, &page(&[("3", "c")], None)));
+
+    let params = [parse_param("min=0:INT").unwrap()];
+    let result = backend(&server, "pages.json")
+        .execute("SELECT id, name, at FROM events WHERE id > :min AND id > :min", &params, None, 60)
+        .await
+        .unwrap();
+
+    let types: Vec<&str> = result.columns.iter().map(|c| c.type_name.as_str()).collect();
+    assert_eq!(types, vec!["BIGINT", "VARCHAR", "TIMESTAMP"]);
+    assert_eq!(result.rows.len(), 3);
+    assert_eq!(
+        result.rows[0],
+        vec![
+            CellValue::Int64(1),
+            CellValue::Text("a".to_string()),
+            CellValue::Timestamp(1_704_067_200_000_000),
+        ]
+    );
+    assert_eq!(result.total_rows, Some(3));
+    assert!(!result.truncated);
+
+    let token = &server.requests_to(TOKEN)[0];
+    assert!(token.body.contains("grant_type=urn%3Aietf%3Aparams%3Aoauth%3Agrant-type%3Ajwt-bearer"));
+
+    let submit = &server.requests_to(QUERIES)[0];
+    assert_eq!(submit.headers["authorization"], "Bearer ya29.test");
+    let body = body(submit);
+    assert_eq!(body["query"], "SELECT id, name, at FROM events WHERE id > @min AND id > @min");
+    assert_eq!(body["useLegacySql"], false);
+    assert_eq!(body["location"], "EU");
+    assert_eq!(body["defaultDataset"]["projectId"], "p");
+    assert_eq!(body["defaultDataset"]["datasetId"], "analytics");
+    assert_eq!(body["parameterMode"], "NAMED");
+    assert_eq!(body["queryParameters"].as_array().unwrap().len(), 1);
+    assert_eq!(body["formatOptions"]["useInt64Timestamp"], true);
+    assert!(body["requestId"].as_str().unwrap().starts_with("dbtoon-"));
+
+    let fetch = &server.requests_to(&format!("{QUERIES}/job-1"))[0];
+    let query = query_string(fetch);
+    assert_eq!(query["pageToken"], "t2");
+    assert_eq!(query["location"], "EU");
+    assert_eq!(fetch.headers["authorization"], "Bearer ya29.test");
+    // The access token is cached across requests
+    assert_eq!(server.requests_to(TOKEN).len(), 1);
+}
+
+#[tokio::test]
+async fn polls_until_job_completes() {
+    let server = MockServer::start();
+    let pending = r#"{"jobComplete":false,"jobReference":{"projectId":"p","jobId":"job-1","location":"EU"}}"#;
+    server.on("POST", QUERIES, MockResponse::json(200, pending));
+    let results = format!("{QUERIES}/job-1");
+    server.on("GET", &results, MockResponse::json(200, pending));
+    server.on("GET", &results, MockResponse::json(200, &page(&[("1", "a")], None)));
+
+    let result = backend(&server, "polls.json").execute("SELECT 1", &[], None, 60).await.unwrap();
+    assert_eq!(result.rows.len(), 1);
+    assert_eq!(server.requests_to(&results).len(), 2);
+}
+
+#[tokio::test]
+async fn limit_truncates_without_fetching_further_pages() {
+    let server = MockServer::start();
+    server.on("POST", QUERIES, MockResponse::json(200, &page(&[("1", "a"), ("2", "b")], Some("t2"))));
+
+    let result = backend(&server, "limit.json")
+        .execute("SELECT * FROM events", &[], Some(1), 60)
+        .await
+        .unwrap();
+    assert_eq!(result.rows.len(), 1);
+    assert!(result.truncated);
+    assert_eq!(body(&server.requests_to(QUERIES)[0])["maxResults"], 2);
+    assert!(server.requests_to(&format!("{QUERIES}/job-1")).is_empty());
+}
+
+#[tokio::test]
+async fn dry_run_reports_bytes_processed() {
+    let server = MockServer::start();
+    server.on(
+        "POST",
+        QUERIES,
+        MockResponse::json(
+            200,
+            &format!(r#"{{"jobComplete":true,"schema":{SCHEMA},"totalBytesProcessed":"1610612736"}}"#),
+        ),
+    );
+
+    let result = backend(&server, "dry_run.json")
+        .with_dry_run(true)
+        .execute("SELECT * FROM events", &[], Some(10), 60)
+        .await
+        .unwrap();
+    assert_eq!(result.columns.len(), 3);
+    assert!(result.rows.is_empty());
+    assert_eq!(result.messages, vec!["dry run: query would process 1610612736 bytes (1.50 GiB)"]);
+
+    let body = body(&server.requests_to(QUERIES)[0]);
+    assert_eq!(body["dryRun"], true);
+    assert!(body.get("maxResults").is_none());
+}
+
+#[tokio::test]
+async fn api_errors_carry_reason_and_message() {
+    let server = MockServer::start();
+    server.on(
+        "POST",
+        QUERIES,
+        MockResponse::json(
+            400,
+            r#"{"error":{"code":400,"message":"Unrecognized name: nope at [1:8]",
+                "errors":[{"reason":"invalidQuery","message":"Unrecognized name: nope at [1:8]"}]}}"#,
+        ),
+    );
+    let err = backend(&server, "error.json").execute("SELECT nope", &[], None, 60).await.unwrap_err();
+    assert!(
+        matches!(&err, DbtoonError::Query { message } if message == "[invalidQuery] Unrecognized name: nope at [1:8]"),
+        "{err}"
+    );
+
+    let server = MockServer::start();
+    server.on(
+        "POST",
+        QUERIES,
+        MockResponse::json(
+            403,
+            r#"{"error":{"code":403,"message":"Access Denied: Project p",
+                "errors":[{"reason":"accessDenied","message":"Access Denied: Project p"}]}}"#,
+        ),
+    );
+    let err = backend(&server, "denied.json").execute("SELECT 1", &[], None, 60).await.unwrap_err();
+    assert!(matches!(err, DbtoonError::Auth { .. }), "{err}");
+}
+
+#[tokio::test]
+async fn failed_token_exchange_is_an_auth_error() {
+    let server = MockServer::start();
+    server.on("POST", TOKEN, MockResponse::json(400, r#"{"error":"invalid_grant"}"#));
+    let account = load_service_account(&key_file(&server, "bad_token.json")).unwrap();
+    let err = BigQueryBackend::new("p".to_string(), Some(account), None, None)
+        .with_host(Some(server.url()))
+        .execute("SELECT 1", &[], None, 60)
+        .await
+        .unwrap_err();
+    assert!(matches!(&err, DbtoonError::Auth { message } if message.contains("invalid_grant")), "{err}");
+    assert!(server.requests_to(QUERIES).is_empty());
+}
+
+#[tokio::test]
+async fn cancel_while_polling_cancels_job() {
+    let server = MockServer::start();
+    let pending = r#"{"jobComplete":false,"jobReference":{"projectId":"p","jobId":"job-2","location":"EU"}}"#;
+    server.on("POST", QUERIES, MockResponse::json(200, pending));
+    server.on("GET", &format!("{QUERIES}/job-2"), MockResponse::json(200, pending));
+    server.on("POST", "/bigquery/v2/projects/p/jobs/job-2/cancel", MockResponse::json(200, r#"{}"#));
+
+    let cancel = CancelToken::default();
+    let backend = backend(&server, "cancel.json").with_cancel(cancel.clone());
+    let trigger = cancel.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        trigger.cancel();
+    });
+
+    let start = Instant::now();
+    let err = backend.execute("SELECT 1", &[], None, 60).await.unwrap_err();
+    assert!(matches!(err, DbtoonError::Cancelled), "{err:?}");
+    assert!(start.elapsed() < Duration::from_secs(2));
+    let cancels = server.requests_to("/bigquery/v2/projects/p/jobs/job-2/cancel");
+    assert_eq!(cancels.len(), 1);
+    assert_eq!(query_string(&cancels[0])["location"], "EU");
+}
@@ -152,6 +152,22 @@ fn test_cli_query_with_allow_write() {
     }
 }
 
+#[test]
+fn test_cli_query_with_repeated_params() {
+    let cli = parse_cli(&[
+        "dbtoon", "query", "-P", "dev",
+        "--param", "id=42:INT", "--param", "name=O'Brien",
+        "SELECT * FROM t WHERE id = :id AND name = :name",
+    ])
+    .unwrap();
+    match &cli.command {
+        dbtoon::cli::Command::Query(args) => {
+            assert_eq!(args.params, vec!["id=42:INT", "name=O'Brien"]);
+        }
+        _ => panic!("Expected Query command"),
+    }
+}
+
 // --- Profile ---
 
 #[test]
@@ -362,6 +378,21 @@ fn test_warehouse_list_token_flag_rejected() {
     assert!(result.is_err(), "--token should be unrecognized on warehouse list");
 }
 
+// --- Auth ---
+
+#[test]
+fn test_cli_auth_login() {
+    let cli = parse_cli(&["dbtoon", "auth", "login", "-P", "dbx", "--device-code"]).unwrap();
+    match cli.command {
+        dbtoon::cli::Command::Auth(dbtoon::cli::AuthCommand::Login(args)) => {
+            assert_eq!(args.profile, "dbx");
+            assert!(args.device_code);
+            assert_eq!(args.port, 8020);
+        }
+        other => panic!("expected auth login, got {other:?}"),
+    }
+}
+
 // --- Update ---
 
 #[test]
@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+
+use secrecy::SecretString;
+
+use dbtoon::backend::clickhouse::{
+    ClickHouseBackend, clickhouse_cell, normalize_clickhouse_type, param_type, param_value,
+};
+use dbtoon::backend::{Backend, CellValue};
+use dbtoon::error::DbtoonError;
+use dbtoon::params::parse_param;
+
+use super::mock_http::{MockResponse, MockServer, RecordedRequest};
+
+const FORMAT: &str = "JSONCompactEachRowWithNamesAndTypes";
+
+fn backend(server: &MockServer) -> ClickHouseBackend {
+    ClickHouseBackend::new(
+        server.url(),
+        None,
+        Some("analytics".to_string()),
+        "reader".to_string(),
+        Some(SecretString::from("pw")),
+    )
+}
+
+fn rows(body: &str) -> MockResponse {
+    MockResponse::bytes(200, "application/json; charset=UTF-8", body.as_bytes().to_vec())
+        .with_header("X-ClickHouse-Format", FORMAT)
+}
+
+fn settings(request: &RecordedRequest) -> HashMap<String, String> {
+    url::form_urlencoded::parse(request.query.as_bytes()).into_owned().collect()
+}
+
+const EVENTS: &str = r#"["id","kind","amount","day","at","tags"]
+["UInt64","LowCardinality(String)","Nullable(Decimal(12, 2))","Date","DateTime64(3, 'UTC')","Array(String)"]
+["1","click","1.50","2024-01-01","2024-01-01T00:00:01.500Z",["a"]]
+["2","view",null,"2024-01-02","2024-01-02T00:00:00.000Z",[]]
+["3","click","-2.25","2024-01-03","2024-01-03T00:00:00.000Z",["b","c"]]
+"#;
+
+#[test]
+fn normalizes_types_for_columnar_output() {
+    assert_eq!(normalize_clickhouse_type("UInt8"), "TINYINT");
+    assert_eq!(normalize_clickhouse_type("Int32"), "INT");
+    assert_eq!(normalize_clickhouse_type("Nullable(Int64)"), "BIGINT");
+    assert_eq!(normalize_clickhouse_type("UInt64"), "DECIMAL(20,0)");
+    assert_eq!(normalize_clickhouse_type("Float64"), "DOUBLE");
+    assert_eq!(normalize_clickhouse_type("Decimal(12, 2)"), "DECIMAL(12,2)");
+    assert_eq!(normalize_clickhouse_type("Decimal64(4)"), "DECIMAL(18,4)");
+    assert_eq!(normalize_clickhouse_type("LowCardinality(Nullable(String))"), "VARCHAR");
+    assert_eq!(normalize_clickhouse_type("Enum8('a' = 1, 'b' = 2)"), "VARCHAR");
+    assert_eq!(normalize_clickhouse_type("Date32"), "DATE");
+    assert_eq!(normalize_clickhouse_type("DateTime('Europe/Berlin')"), "TIMESTAMP");
+    assert_eq!(normalize_clickhouse_type("Nullable(DateTime64(3, 'UTC'))"), "TIMESTAMP");
+    assert_eq!(normalize_clickhouse_type("Bool"), "BOOLEAN");
+    assert_eq!(normalize_clickhouse_type("UUID"), "UUID");
+    assert_eq!(normalize_clickhouse_type("Array(Nullable(Int32))"), "Array(Nullable(Int32))");
+}
+
+#[test]
+fn decodes_json_values() {
+    use serde_json::json;
+    assert_eq!(clickhouse_cell(&json!(7), "INT"), CellValue::Int64(7));
+    assert_eq!(clickhouse_cell(&json!("-9000000000"), "BIGINT"), CellValue::Int64(-9_000_000_000));
+    assert_eq!(
+        clickhouse_cell(&json!("18446744073709551615"), "DECIMAL(20,0)"),
+        CellValue::Decimal { value: 18_446_744_073_709_551_615, scale: 0 }
+    );
+    assert_eq!(clickhouse_cell(&json!("-12.50"), "DECIMAL(12,2)"), CellValue::Decimal { value: -1250, scale: 2 });
+    assert_eq!(clickhouse_cell(&json!(0.5), "DOUBLE"), CellValue::Float64(0.5));
+    assert!(matches!(clickhouse_cell(&json!("nan"), "DOUBLE"), CellValue::Float64(f) if f.is_nan()));
+    assert_eq!(clickhouse_cell(&json!("2024-01-01"), "DATE"), CellValue::Date(19_723));
+    assert_eq!(
+        clickhouse_cell(&json!("2024-01-01T00:00:01.500Z"), "TIMESTAMP"),
+        CellValue::Timestamp(1_704_067_201_500_000)
+    );
+    assert_eq!(clickhouse_cell(&json!(true), "BOOLEAN"), CellValue::Bool(true));
+    assert_eq!(clickhouse_cell(&json!({"k": 1}), "Map(String, UInt8)"), CellValue::Text(r#"{"k":1}"#.to_string()));
+    assert_eq!(clickhouse_cell(&json!(null), "VARCHAR"), CellValue::Null);
+}
+
+#[test]
+fn binds_parameters_as_typed_query_parameters() {
+    let p = |spec: &str| parse_param(spec).unwrap();
+    assert_eq!(param_type(&p("id=10:INT")), "Int64");
+    assert_eq!(param_type(&p("price=1.5:DECIMAL(10,2)")), "Decimal(10,2)");
+    assert_eq!(param_type(&p("price=1.25:DECIMAL")), "Decimal(38,2)");
+    assert_eq!(param_type(&p("on=true:BOOLEAN")), "Bool");
+    assert_eq!(param_type(&p("at=2024-01-01T00:00:00:TIMESTAMP")), "DateTime64(6, 'UTC')");
+    assert_eq!(param_type(&p("name=x")), "String");
+
+    assert_eq!(param_value(&p("on=1:BIT")), "true");
+    assert_eq!(param_value(&p("at=2024-01-01T08:00:00:TIMESTAMP")), "2024-01-01 08:00:00");
+    assert_eq!(param_value(&p(r"path=C:\tmp")), r"C:\\tmp");
+}
+
+#[tokio::test]
+async fn streams_rows_with_clickhouse_types() {
+    let server = MockServer::start();
+    server.on("POST", "/", rows(EVENTS));
+
+    let result = backend(&server)
+        .execute("SELECT id, kind, amount, day, at, tags FROM events", &[], None, 60)
+        .await
+        .unwrap();
+
+    let types: Vec<&str> = result.columns.iter().map(|c| c.type_name.as_str()).collect();
+    assert_eq!(
+        types,
+        vec!["DECIMAL(20,0)", "VARCHAR", "DECIMAL(12,2)", "DATE", "TIMESTAMP", "Array(String)"]
+    );
+    assert_eq!(result.rows.len(), 3);
+    assert_eq!(
+        result.rows[0],
+        vec![
+            CellValue::Decimal { value: 1, scale: 0 },
+            CellValue::Text("click".to_string()),
+            CellValue::Decimal { value: 150, scale: 2 },
+            CellValue::Date(19_723),
+            CellValue::Timestamp(1_704_067_201_500_000),
+            CellValue::Text(r#"["a"]"#.to_string()),
+        ]
+    );
+    assert_eq!(result.rows[1][2], CellValue::Null);
+    assert!(!result.truncated);
+
+    let request = &server.requests_to("/")[0];
+    assert_eq!(request.body, "SELECT id, kind, amount, day, at, tags FROM events");
+    assert_eq!(request.headers["x-clickhouse-user"], "reader");
+    assert_eq!(request.headers["x-clickhouse-key"], "pw");
+    assert_eq!(request.headers["x-clickhouse-database"], "analytics");
+    let settings = settings(request);
+    assert_eq!(settings["default_format"], FORMAT);
+    assert_eq!(settings["max_execution_time"], "60");
+    assert!(settings["query_id"].starts_with("dbtoon-"));
+    assert!(!settings.contains_key("max_result_rows"));
+}
+
+#[tokio::test]
+async fn limit_sets_max_result_rows_and_truncates() {
+    let server = MockServer::start();
+    server.on("POST", "/", rows(EVENTS));
+
+    let result = backend(&server).execute("SELECT * FROM events", &[], Some(2), 60).await.unwrap();
+    assert_eq!(result.rows.len(), 2);
+    assert!(result.truncated);
+
+    let settings = settings(&server.requests_to("/")[0]);
+    assert_eq!(settings["max_result_rows"], "3");
+    assert_eq!(settings["result_overflow_mode"], "break");
+
+    let result = backend(&server).execute("SELECT * FROM events", &[], Some(3), 60).await.unwrap();
+    assert_eq!(result.rows.len(), 3);
+    assert!(!result.truncated);
+}
+
+#[tokio::test]
+async fn binds_named_params_as_query_parameters() {
+    let server = MockServer::start();
+    server.on("POST", "/", rows("[\"n\"]\n[\"Int64\"]\n[\"1\"]\n"));
+
+    let params = [parse_param("id=5:INT").unwrap(), parse_param("kind=click").unwrap()];
+    backend(&server)
+        .execute("SELECT count() AS n FROM events WHERE id > :id AND kind = :kind", &params, None, 60)
+        .await
+        .unwrap();
+
+    let request = &server.requests_to("/")[0];
+    assert_eq!(
+        request.body,
+        "SELECT count() AS n FROM events WHERE id > {id:Int64} AND kind = {kind:String}"
+    );
+    let settings = settings(request);
+    assert_eq!(settings["param_id"], "5");
+    assert_eq!(settings["param_kind"], "click");
+}
+
+#[tokio::test]
+async fn statement_without_result_reports_written_rows() {
+    let server = MockServer::start();
+    server.on(
+        "POST",
+        "/",
+        MockResponse::bytes(200, "text/plain", Vec::new())
+            .with_header("X-ClickHouse-Summary", r#"{"read_rows":"3","written_rows":"3"}"#),
+    );
+
+    let result = backend(&server)
+        .execute("INSERT INTO events SELECT * FROM staging", &[], None, 60)
+        .await
+        .unwrap();
+    assert!(result.columns.is_empty());
+    assert_eq!(result.rows_affected, Some(3));
+}
+
+#[tokio::test]
+async fn server_exceptions_become_errors() {
+    let server = MockServer::start();
+    server.on(
+        "POST",
+        "/",
+        MockResponse::bytes(
+            404,
+            "text/plain",
+            b"Code: 60. DB::Exception: Table analytics.nope does not exist. (UNKNOWN_TABLE) (version 24.8.1.1)\n".to_vec(),
+        )
+        .with_header("X-ClickHouse-Exception-Code", "60"),
+    );
+    let err = backend(&server).execute("SELECT * FROM nope", &[], None, 60).await.unwrap_err();
+    assert!(matches!(&err, DbtoonError::Query { message } if message.contains("UNKNOWN_TABLE")), "{err}");
+
+    let server = MockServer::start();
+    server.on(
+        "POST",
+        "/",
+        MockResponse::bytes(
+            403,
+            "text/plain",
+            b"Code: 516. DB::Exception: reader: Authentication failed. (AUTHENTICATION_FAILED)".to_vec(),
+        )
+        .with_header("X-ClickHouse-Exception-Code", "516"),
+    );
+    let err = backend(&server).execute("SELECT 1", &[], None, 60).await.unwrap_err();
+    assert!(matches!(err, DbtoonError::Auth { .. }), "{err}");
+}
+
+#[tokio::test]
+async fn exception_after_rows_began_is_an_error() {
+    let server = MockServer::start();
+    server.on(
+        "POST",
+        "/",
+        rows(
+            "[\"n\"]\n[\"UInt64\"]\n[\"1\"]\nCode: 241. DB::Exception: Memory limit (total) exceeded. (MEMORY_LIMIT_EXCEEDED)\n",
+        ),
+    );
+    let err = backend(&server).execute("SELECT n FROM big", &[], None, 60).await.unwrap_err();
+    assert!(
+        matches!(&err, DbtoonError::Query { message } if message.starts_with("Code: 241.")),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn explicit_format_clause_is_rejected() {
+    let server = MockServer::start();
+    server.on(
+        "POST",
+        "/",
+        MockResponse::bytes(200, "text/csv", b"1\n".to_vec()).with_header("X-ClickHouse-Format", "CSV"),
+    );
+    let err = backend(&server).execute("SELECT 1 FORMAT CSV", &[], None, 60).await.unwrap_err();
+    assert!(err.to_string().contains("FORMAT CSV"), "{err}");
+}
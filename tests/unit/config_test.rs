@@ -1,10 +1,13 @@
 use dbtoon::config::{
     self, default_config_path, env_non_empty, load_toml_config_required,
     non_empty, resolve_env_var, resolve_profile_string, resolve_profile_secret,
-    BackendConfig, SqlServerAuth, TomlConfig, TomlProfile,
+    BackendConfig, DatabricksAuth, MySqlSslMode, PostgresSslMode, SqlServerAuth, TomlConfig, TomlProfile, TrinoAuth,
 };
+use dbtoon::backend::retry::RetryPolicy;
+use dbtoon::validation::BackendDialect;
 use secrecy::ExposeSecret;
 use std::path::PathBuf;
+use std::time::Duration;
 use std::sync::Mutex;
 
 // --- Env var test infrastructure ---
@@ -244,6 +247,569 @@ fn test_sqlserver_sql_auth_config() {
     }
 }
 
+#[test]
+fn test_sqlserver_entra_service_principal_config() {
+    let _guard = EnvGuard::new(&[("SP_SECRET", "sp-secret")]);
+
+    let profile = TomlProfile {
+        backend: Some("sqlserver".to_string()),
+        server: Some("db.database.windows.net".to_string()),
+        auth: Some("ActiveDirectoryServicePrincipal".to_string()),
+        client_id: Some("app-id".to_string()),
+        client_secret: Some("$SP_SECRET".to_string()),
+        ..Default::default()
+    };
+    let backend = config::build_backend_config(&profile, None, None).unwrap();
+    match &backend {
+        BackendConfig::SqlServer {
+            auth: SqlServerAuth::ActiveDirectoryServicePrincipal { client_id, client_secret },
+            ..
+        } => {
+            assert_eq!(client_id, "app-id");
+            assert_eq!(client_secret.expose_secret(), "sp-secret");
+        }
+        other => panic!("Expected service principal auth, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_sqlserver_entra_modes_are_case_insensitive() {
+    let profile = TomlProfile {
+        backend: Some("sqlserver".to_string()),
+        server: Some("db.database.windows.net".to_string()),
+        auth: Some("activedirectorydefault".to_string()),
+        ..Default::default()
+    };
+    let backend = config::build_backend_config(&profile, None, None).unwrap();
+    assert!(matches!(
+        backend,
+        BackendConfig::SqlServer { auth: SqlServerAuth::ActiveDirectoryDefault, .. }
+    ));
+}
+
+#[test]
+fn test_sqlserver_access_token_config() {
+    let profile = TomlProfile {
+        backend: Some("sqlserver".to_string()),
+        server: Some("db.database.windows.net".to_string()),
+        auth: Some("AccessToken".to_string()),
+        access_token: Some("eyJ0eXAi".to_string()),
+        ..Default::default()
+    };
+    let backend = config::build_backend_config(&profile, None, None).unwrap();
+    match &backend {
+        BackendConfig::SqlServer { auth: SqlServerAuth::AccessToken(token), .. } => {
+            assert_eq!(token.expose_secret(), "eyJ0eXAi");
+        }
+        other => panic!("Expected access token auth, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_sqlserver_entra_password_requires_username() {
+    let profile = TomlProfile {
+        backend: Some("sqlserver".to_string()),
+        server: Some("db.database.windows.net".to_string()),
+        auth: Some("ActiveDirectoryPassword".to_string()),
+        password: Some("p".to_string()),
+        ..Default::default()
+    };
+    let err = config::build_backend_config(&profile, None, None).unwrap_err().to_string();
+    assert!(err.contains("'username'"), "Got: {}", err);
+}
+
+#[test]
+fn test_sqlserver_auth_conflicts_with_windows_auth() {
+    let profile = TomlProfile {
+        backend: Some("sqlserver".to_string()),
+        server: Some("localhost".to_string()),
+        auth: Some("ActiveDirectoryDefault".to_string()),
+        windows_auth: Some(true),
+        ..Default::default()
+    };
+    let err = config::build_backend_config(&profile, None, None).unwrap_err().to_string();
+    assert!(err.contains("windows_auth"), "Got: {}", err);
+}
+
+#[test]
+fn test_sqlserver_connection_options_config() {
+    let mut extra_attributes = std::collections::BTreeMap::new();
+    extra_attributes.insert("MultiSubnetFailover".to_string(), toml::Value::Boolean(true));
+    extra_attributes.insert("ConnectRetryCount".to_string(), toml::Value::Integer(3));
+    let profile = TomlProfile {
+        backend: Some("sqlserver".to_string()),
+        server: Some("db01".to_string()),
+        windows_auth: Some(true),
+        port: Some(14330),
+        encrypt: Some("Mandatory".to_string()),
+        application_intent: Some("readonly".to_string()),
+        extra_attributes: Some(extra_attributes),
+        ..Default::default()
+    };
+    let backend = config::build_backend_config(&profile, None, None).unwrap();
+    match &backend {
+        BackendConfig::SqlServer { options, .. } => {
+            assert_eq!(options.port, Some(14330));
+            assert_eq!(options.encrypt.as_deref(), Some("Mandatory"));
+            assert_eq!(options.application_intent.as_deref(), Some("ReadOnly"));
+            assert_eq!(
+                options.extra_attributes,
+                vec![
+                    ("ConnectRetryCount".to_string(), "3".to_string()),
+                    ("MultiSubnetFailover".to_string(), "Yes".to_string()),
+                ]
+            );
+        }
+        other => panic!("Expected SqlServer backend, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_sqlserver_extra_attribute_cannot_override_profile_field() {
+    let mut extra_attributes = std::collections::BTreeMap::new();
+    extra_attributes.insert("PWD".to_string(), toml::Value::String("x".to_string()));
+    let profile = TomlProfile {
+        backend: Some("sqlserver".to_string()),
+        server: Some("db01".to_string()),
+        windows_auth: Some(true),
+        extra_attributes: Some(extra_attributes),
+        ..Default::default()
+    };
+    let err = config::build_backend_config(&profile, None, None).unwrap_err().to_string();
+    assert!(err.contains("'password'"), "Got: {}", err);
+}
+
+#[test]
+fn test_sqlserver_invalid_encrypt_rejected() {
+    let profile = TomlProfile {
+        backend: Some("sqlserver".to_string()),
+        server: Some("db01".to_string()),
+        windows_auth: Some(true),
+        encrypt: Some("maybe".to_string()),
+        ..Default::default()
+    };
+    let err = config::build_backend_config(&profile, None, None).unwrap_err().to_string();
+    assert!(err.contains("encrypt"), "Got: {}", err);
+}
+
+#[test]
+fn test_sqlserver_port_conflicts_with_server_port() {
+    let profile = TomlProfile {
+        backend: Some("sqlserver".to_string()),
+        server: Some("db01,1433".to_string()),
+        windows_auth: Some(true),
+        port: Some(14330),
+        ..Default::default()
+    };
+    let err = config::build_backend_config(&profile, None, None).unwrap_err().to_string();
+    assert!(err.contains("port"), "Got: {}", err);
+}
+
+#[test]
+fn test_odbc_dsn_config() {
+    let profile = TomlProfile {
+        backend: Some("odbc".to_string()),
+        dsn: Some("warehouse".to_string()),
+        username: Some("app".to_string()),
+        password: Some("p;w".to_string()),
+        dialect: Some("postgres".to_string()),
+        ..Default::default()
+    };
+    let backend = config::build_backend_config(&profile, Some("sales"), None).unwrap();
+    match &backend {
+        BackendConfig::Odbc { connection_string, dialect } => {
+            assert_eq!(
+                connection_string.expose_secret(),
+                "DSN=warehouse;UID=app;PWD={p;w};Database=sales;"
+            );
+            assert_eq!(*dialect, BackendDialect::Postgres);
+        }
+        other => panic!("Expected Odbc backend, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_odbc_connection_string_config_defaults_to_generic() {
+    let profile = TomlProfile {
+        backend: Some("odbc".to_string()),
+        connection_string: Some("Driver={SQLite3};Database=/tmp/x.db;".to_string()),
+        ..Default::default()
+    };
+    let backend = config::build_backend_config(&profile, None, None).unwrap();
+    match &backend {
+        BackendConfig::Odbc { connection_string, dialect } => {
+            assert_eq!(connection_string.expose_secret(), "Driver={SQLite3};Database=/tmp/x.db;");
+            assert_eq!(*dialect, BackendDialect::Generic);
+        }
+        other => panic!("Expected Odbc backend, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_odbc_requires_exactly_one_target() {
+    let neither = TomlProfile {
+        backend: Some("odbc".to_string()),
+        ..Default::default()
+    };
+    let err = config::build_backend_config(&neither, None, None).unwrap_err().to_string();
+    assert!(err.contains("'dsn'"), "Got: {}", err);
+
+    let both = TomlProfile {
+        backend: Some("odbc".to_string()),
+        dsn: Some("a".to_string()),
+        connection_string: Some("DSN=b".to_string()),
+        ..Default::default()
+    };
+    let err = config::build_backend_config(&both, None, None).unwrap_err().to_string();
+    assert!(err.contains("cannot both be set"), "Got: {}", err);
+}
+
+#[test]
+fn test_odbc_unknown_dialect_rejected() {
+    let profile = TomlProfile {
+        backend: Some("odbc".to_string()),
+        dsn: Some("a".to_string()),
+        dialect: Some("oracle".to_string()),
+        ..Default::default()
+    };
+    let err = config::build_backend_config(&profile, None, None).unwrap_err().to_string();
+    assert!(err.contains("oracle"), "Got: {}", err);
+}
+
+#[test]
+fn test_postgres_config() {
+    let _guard = EnvGuard::new(&[]);
+    let profile = TomlProfile {
+        backend: Some("postgres".to_string()),
+        host: Some("db.internal".to_string()),
+        port: Some(6543),
+        database: Some("analytics".to_string()),
+        user: Some("reporter".to_string()),
+        password: Some("secret".to_string()),
+        sslmode: Some("verify-full".to_string()),
+        ..Default::default()
+    };
+    let backend = config::build_backend_config(&profile, Some("sales"), None).unwrap();
+    match &backend {
+        BackendConfig::Postgres { host, port, database, user, password, sslmode } => {
+            assert_eq!(host, "db.internal");
+            assert_eq!(*port, 6543);
+            assert_eq!(database.as_deref(), Some("sales"));
+            assert_eq!(user, "reporter");
+            assert_eq!(password.as_ref().unwrap().expose_secret(), "secret");
+            assert_eq!(*sslmode, PostgresSslMode::VerifyFull);
+        }
+        other => panic!("Expected Postgres backend, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_postgres_libpq_env_fallback() {
+    let _guard = EnvGuard::new(&[
+        ("PGHOST", "env-host"),
+        ("PGPORT", "5433"),
+        ("PGDATABASE", "envdb"),
+        ("PGUSER", "env-user"),
+        ("PGPASSWORD", "env-pass"),
+        ("PGSSLMODE", "disable"),
+    ]);
+    let profile = TomlProfile {
+        backend: Some("postgres".to_string()),
+        user: Some("profile-user".to_string()),
+        ..Default::default()
+    };
+    let backend = config::build_backend_config(&profile, None, None).unwrap();
+    match &backend {
+        BackendConfig::Postgres { host, port, database, user, password, sslmode } => {
+            assert_eq!(host, "env-host");
+            assert_eq!(*port, 5433);
+            assert_eq!(database.as_deref(), Some("envdb"));
+            assert_eq!(user, "profile-user");
+            assert_eq!(password.as_ref().unwrap().expose_secret(), "env-pass");
+            assert_eq!(*sslmode, PostgresSslMode::Disable);
+        }
+        other => panic!("Expected Postgres backend, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_postgres_defaults_and_errors() {
+    let _guard = EnvGuard::new(&[]);
+    let profile = TomlProfile {
+        backend: Some("postgres".to_string()),
+        host: Some("localhost".to_string()),
+        user: Some("postgres".to_string()),
+        ..Default::default()
+    };
+    match config::build_backend_config(&profile, None, None).unwrap() {
+        BackendConfig::Postgres { port, password, sslmode, .. } => {
+            assert_eq!(port, 5432);
+            assert!(password.is_none());
+            assert_eq!(sslmode, PostgresSslMode::Prefer);
+        }
+        other => panic!("Expected Postgres backend, got {:?}", other),
+    }
+
+    let no_user = TomlProfile { user: None, ..profile.clone() };
+    let err = config::build_backend_config(&no_user, None, None).unwrap_err().to_string();
+    assert!(err.contains("'user'"), "Got: {}", err);
+
+    let bad_mode = TomlProfile { sslmode: Some("allow".to_string()), ..profile };
+    let err = config::build_backend_config(&bad_mode, None, None).unwrap_err().to_string();
+    assert!(err.contains("unknown sslmode 'allow'"), "Got: {}", err);
+}
+
+#[test]
+fn test_snowflake_config() {
+    let _guard = EnvGuard::new(&[("SNOWFLAKE_ROLE", "ENV_ROLE"), ("PRIVATE_KEY_PASSPHRASE", "env-pass")]);
+    let profile = TomlProfile {
+        backend: Some("snowflake".to_string()),
+        account: Some("myorg-acct".to_string()),
+        user: Some("reporter".to_string()),
+        private_key_path: Some("/keys/rsa_key.p8".to_string()),
+        warehouse: Some("WH".to_string()),
+        database: Some("SALES".to_string()),
+        schema: Some("PUBLIC".to_string()),
+        ..Default::default()
+    };
+    let backend = config::build_backend_config(&profile, None, Some("STAGING")).unwrap();
+    match &backend {
+        BackendConfig::Snowflake {
+            account, host, user, private_key_path, private_key_passphrase,
+            warehouse, database, schema, role,
+        } => {
+            assert_eq!(account, "myorg-acct");
+            assert!(host.is_none());
+            assert_eq!(user, "reporter");
+            assert_eq!(private_key_path, &PathBuf::from("/keys/rsa_key.p8"));
+            assert_eq!(private_key_passphrase.as_ref().unwrap().expose_secret(), "env-pass");
+            assert_eq!(warehouse.as_deref(), Some("WH"));
+            assert_eq!(database.as_deref(), Some("SALES"));
+            assert_eq!(schema.as_deref(), Some("STAGING"));
+            assert_eq!(role.as_deref(), Some("ENV_ROLE"));
+        }
+        other => panic!("Expected Snowflake backend, got {:?}", other),
+    }
+
+    let no_key = TomlProfile { private_key_path: None, ..profile };
+    let err = config::build_backend_config(&no_key, None, None).unwrap_err().to_string();
+    assert!(err.contains("no 'private_key_path' specified for snowflake backend"), "Got: {}", err);
+}
+
+#[test]
+fn test_trino_config() {
+    let _guard = EnvGuard::new(&[]);
+    let profile = TomlProfile {
+        backend: Some("trino".to_string()),
+        host: Some("http://trino.internal".to_string()),
+        port: Some(8080),
+        user: Some("analyst".to_string()),
+        token: Some("jwt-literal".to_string()),
+        catalog: Some("hive".to_string()),
+        schema: Some("sales".to_string()),
+        ..Default::default()
+    };
+    match config::build_backend_config(&profile, Some("iceberg"), None).unwrap() {
+        BackendConfig::Trino { presto, host, port, user, auth, catalog, schema } => {
+            assert!(!presto);
+            assert_eq!(host, "http://trino.internal");
+            assert_eq!(port, Some(8080));
+            assert_eq!(user, "analyst");
+            assert!(matches!(auth, TrinoAuth::Jwt(t) if t.expose_secret() == "jwt-literal"));
+            assert_eq!(catalog.as_deref(), Some("iceberg"));
+            assert_eq!(schema.as_deref(), Some("sales"));
+        }
+        other => panic!("Expected Trino backend, got {:?}", other),
+    }
+
+    let presto = TomlProfile { backend: Some("presto".to_string()), token: None, ..profile.clone() };
+    match config::build_backend_config(&presto, None, None).unwrap() {
+        BackendConfig::Trino { presto, auth, .. } => {
+            assert!(presto);
+            assert!(matches!(auth, TrinoAuth::None));
+        }
+        other => panic!("Expected Trino backend, got {:?}", other),
+    }
+
+    let both = TomlProfile { password: Some("pw".to_string()), ..profile };
+    let err = config::build_backend_config(&both, None, None).unwrap_err().to_string();
+    assert!(err.contains("'password' and 'token' cannot both be set"), "Got: {}", err);
+}
+
+#[test]
+fn test_mysql_config() {
+    let _guard = EnvGuard::new(&[("MYSQL_PWD", "env-pass")]);
+    let profile = TomlProfile {
+        backend: Some("mysql".to_string()),
+        host: Some("db.internal".to_string()),
+        database: Some("app".to_string()),
+        user: Some("reporter".to_string()),
+        ssl_mode: Some("VERIFY_IDENTITY".to_string()),
+        ..Default::default()
+    };
+    match config::build_backend_config(&profile, Some("orders"), None).unwrap() {
+        BackendConfig::MySql { host, port, database, user, password, ssl_mode } => {
+            assert_eq!(host, "db.internal");
+            assert_eq!(port, 3306);
+            assert_eq!(database.as_deref(), Some("orders"));
+            assert_eq!(user, "reporter");
+            assert_eq!(password.unwrap().expose_secret(), "env-pass");
+            assert_eq!(ssl_mode, MySqlSslMode::VerifyIdentity);
+        }
+        other => panic!("Expected MySql backend, got {:?}", other),
+    }
+
+    let defaults = TomlProfile { ssl_mode: None, port: Some(3307), ..profile.clone() };
+    match config::build_backend_config(&defaults, None, None).unwrap() {
+        BackendConfig::MySql { port, database, ssl_mode, .. } => {
+            assert_eq!(port, 3307);
+            assert_eq!(database.as_deref(), Some("app"));
+            assert_eq!(ssl_mode, MySqlSslMode::Preferred);
+        }
+        other => panic!("Expected MySql backend, got {:?}", other),
+    }
+
+    let bad_mode = TomlProfile { ssl_mode: Some("verify-full".to_string()), ..profile.clone() };
+    let err = config::build_backend_config(&bad_mode, None, None).unwrap_err().to_string();
+    assert!(err.contains("unknown ssl_mode 'verify-full'"), "Got: {}", err);
+
+    let no_user = TomlProfile { user: None, ..profile };
+    let err = config::build_backend_config(&no_user, None, None).unwrap_err().to_string();
+    assert!(err.contains("no 'user' specified for mysql backend"), "Got: {}", err);
+}
+
+#[test]
+fn test_clickhouse_config() {
+    let _guard = EnvGuard::new(&[("CLICKHOUSE_PASSWORD", "env-pass")]);
+    let profile = TomlProfile {
+        backend: Some("clickhouse".to_string()),
+        host: Some("http://ch.internal:8123".to_string()),
+        database: Some("analytics".to_string()),
+        ..Default::default()
+    };
+    match config::build_backend_config(&profile, Some("events"), None).unwrap() {
+        BackendConfig::ClickHouse { host, port, database, user, password } => {
+            assert_eq!(host, "http://ch.internal:8123");
+            assert_eq!(port, None);
+            assert_eq!(database.as_deref(), Some("events"));
+            assert_eq!(user, "default");
+            assert_eq!(password.unwrap().expose_secret(), "env-pass");
+        }
+        other => panic!("Expected ClickHouse backend, got {:?}", other),
+    }
+
+    let explicit = TomlProfile {
+        user: Some("reader".to_string()),
+        password: Some("literal".to_string()),
+        port: Some(9440),
+        ..profile.clone()
+    };
+    match config::build_backend_config(&explicit, None, None).unwrap() {
+        BackendConfig::ClickHouse { port, database, user, password, .. } => {
+            assert_eq!(port, Some(9440));
+            assert_eq!(database.as_deref(), Some("analytics"));
+            assert_eq!(user, "reader");
+            assert_eq!(password.unwrap().expose_secret(), "literal");
+        }
+        other => panic!("Expected ClickHouse backend, got {:?}", other),
+    }
+
+    let no_host = TomlProfile { host: None, ..profile };
+    let err = config::build_backend_config(&no_host, None, None).unwrap_err().to_string();
+    assert!(err.contains("no 'host' specified for clickhouse backend"), "Got: {}", err);
+}
+
+#[test]
+fn test_bigquery_config() {
+    let _guard = EnvGuard::new(&[
+        ("GOOGLE_APPLICATION_CREDENTIALS", "/keys/sa.json"),
+        ("GOOGLE_CLOUD_PROJECT", ""),
+    ]);
+    let profile = TomlProfile {
+        backend: Some("bigquery".to_string()),
+        dataset: Some("analytics".to_string()),
+        location: Some("EU".to_string()),
+        ..Default::default()
+    };
+    match config::build_backend_config(&profile, Some("other.events"), None).unwrap() {
+        BackendConfig::BigQuery { project, credentials_path, dataset, location, host } => {
+            assert_eq!(project, None);
+            assert_eq!(credentials_path, Some(PathBuf::from("/keys/sa.json")));
+            assert_eq!(dataset.as_deref(), Some("other.events"));
+            assert_eq!(location.as_deref(), Some("EU"));
+            assert_eq!(host, None);
+        }
+        other => panic!("Expected BigQuery backend, got {:?}", other),
+    }
+
+    let explicit = TomlProfile {
+        project: Some("billing".to_string()),
+        credentials_path: Some("/keys/reader.json".to_string()),
+        ..profile.clone()
+    };
+    match config::build_backend_config(&explicit, None, None).unwrap() {
+        BackendConfig::BigQuery { project, credentials_path, dataset, .. } => {
+            assert_eq!(project.as_deref(), Some("billing"));
+            assert_eq!(credentials_path, Some(PathBuf::from("/keys/reader.json")));
+            assert_eq!(dataset.as_deref(), Some("analytics"));
+        }
+        other => panic!("Expected BigQuery backend, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_bigquery_config_requires_credentials_except_for_emulator() {
+    let _guard = EnvGuard::new(&[("GOOGLE_APPLICATION_CREDENTIALS", "")]);
+    let profile = TomlProfile {
+        backend: Some("bigquery".to_string()),
+        project: Some("test".to_string()),
+        ..Default::default()
+    };
+    let err = config::build_backend_config(&profile, None, None).unwrap_err().to_string();
+    assert!(err.contains("no 'credentials_path' specified for bigquery backend"), "Got: {}", err);
+
+    let emulator = TomlProfile { host: Some("http://localhost:9050".to_string()), ..profile };
+    match config::build_backend_config(&emulator, None, None).unwrap() {
+        BackendConfig::BigQuery { credentials_path, host, .. } => {
+            assert_eq!(credentials_path, None);
+            assert_eq!(host.as_deref(), Some("http://localhost:9050"));
+        }
+        other => panic!("Expected BigQuery backend, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_sqlite_config() {
+    let profile = TomlProfile {
+        backend: Some("sqlite".to_string()),
+        path: Some(":memory:".to_string()),
+        ..Default::default()
+    };
+    match config::build_backend_config(&profile, None, None).unwrap() {
+        BackendConfig::Sqlite { path } => assert_eq!(path, ":memory:"),
+        other => panic!("Expected Sqlite backend, got {:?}", other),
+    }
+    match config::build_backend_config(&profile, Some("extract.db"), None).unwrap() {
+        BackendConfig::Sqlite { path } => assert_eq!(path, "extract.db"),
+        other => panic!("Expected Sqlite backend, got {:?}", other),
+    }
+
+    let no_path = TomlProfile { path: None, ..profile };
+    let err = config::build_backend_config(&no_path, None, None).unwrap_err().to_string();
+    assert!(err.contains("no 'path'"), "Got: {}", err);
+}
+
+#[test]
+fn test_duckdb_backend_points_to_sqlite() {
+    let profile = TomlProfile {
+        backend: Some("duckdb".to_string()),
+        path: Some("local.duckdb".to_string()),
+        ..Default::default()
+    };
+    let err = config::build_backend_config(&profile, None, None).unwrap_err().to_string();
+    assert!(err.contains("backend = \"sqlite\""), "Got: {}", err);
+}
+
 #[test]
 fn test_missing_backend_errors() {
     let profile = TomlProfile::default();
@@ -269,7 +835,7 @@ fn test_databricks_std_env_fallback() {
     };
     let backend = config::build_backend_config(&profile, None, None).unwrap();
     match &backend {
-        BackendConfig::Databricks { host, token, warehouse_id, catalog, schema } => {
+        BackendConfig::Databricks { host, auth: DatabricksAuth::Token(token), warehouse_id, catalog, schema } => {
             assert_eq!(host, "https://std-host.azuredatabricks.net");
             assert_eq!(token.expose_secret(), "dapi-std-token");
             assert_eq!(warehouse_id, "std-warehouse-id");
@@ -299,7 +865,7 @@ fn test_toml_profile_overrides_std_env() {
     };
     let backend = config::build_backend_config(&profile, None, None).unwrap();
     match &backend {
-        BackendConfig::Databricks { host, token, warehouse_id, catalog, schema } => {
+        BackendConfig::Databricks { host, auth: DatabricksAuth::Token(token), warehouse_id, catalog, schema } => {
             assert_eq!(host, "https://toml-host.azuredatabricks.net");
             assert_eq!(token.expose_secret(), "dapi-toml-token");
             assert_eq!(warehouse_id, "toml-warehouse");
@@ -397,7 +963,7 @@ fn test_dollar_var_resolution_in_profile() {
     };
     let backend = config::build_backend_config(&profile, None, None).unwrap();
     match &backend {
-        BackendConfig::Databricks { host, token, .. } => {
+        BackendConfig::Databricks { host, auth: DatabricksAuth::Token(token), .. } => {
             assert_eq!(host, "https://resolved-host.azuredatabricks.net");
             assert_eq!(token.expose_secret(), "dapi-resolved-token");
         }
@@ -405,6 +971,110 @@ fn test_dollar_var_resolution_in_profile() {
     }
 }
 
+#[test]
+fn test_databricks_client_id_selects_oauth_m2m() {
+    let _guard = EnvGuard::new(&[
+        ("DATABRICKS_TOKEN", "dapi-std-token"),
+        ("MY_CLIENT_SECRET", "sp-secret"),
+    ]);
+
+    let profile = TomlProfile {
+        backend: Some("databricks".to_string()),
+        host: Some("h".to_string()),
+        client_id: Some("sp-client".to_string()),
+        client_secret: Some("$MY_CLIENT_SECRET".to_string()),
+        warehouse_id: Some("wh".to_string()),
+        ..Default::default()
+    };
+    let backend = config::build_backend_config(&profile, None, None).unwrap();
+    match &backend {
+        BackendConfig::Databricks { auth: DatabricksAuth::OAuthM2M { client_id, client_secret }, .. } => {
+            assert_eq!(client_id, "sp-client");
+            assert_eq!(client_secret.expose_secret(), "sp-secret");
+        }
+        _ => panic!("Expected Databricks OAuth M2M auth"),
+    }
+}
+
+#[test]
+fn test_databricks_client_credentials_std_env_fallback() {
+    let _guard = EnvGuard::new(&[
+        ("DATABRICKS_CLIENT_ID", "env-client"),
+        ("DATABRICKS_CLIENT_SECRET", "env-secret"),
+    ]);
+    unsafe { std::env::remove_var("DATABRICKS_TOKEN"); }
+
+    let profile = TomlProfile {
+        backend: Some("databricks".to_string()),
+        host: Some("h".to_string()),
+        warehouse_id: Some("wh".to_string()),
+        ..Default::default()
+    };
+    let backend = config::build_backend_config(&profile, None, None).unwrap();
+    match &backend {
+        BackendConfig::Databricks { auth: DatabricksAuth::OAuthM2M { client_id, client_secret }, .. } => {
+            assert_eq!(client_id, "env-client");
+            assert_eq!(client_secret.expose_secret(), "env-secret");
+        }
+        _ => panic!("Expected Databricks OAuth M2M auth"),
+    }
+}
+
+#[test]
+fn test_databricks_client_id_without_secret_errors() {
+    let _guard = EnvGuard::new(&[]);
+    unsafe { std::env::remove_var("DATABRICKS_CLIENT_SECRET"); }
+
+    let profile = TomlProfile {
+        backend: Some("databricks".to_string()),
+        host: Some("h".to_string()),
+        client_id: Some("sp-client".to_string()),
+        warehouse_id: Some("wh".to_string()),
+        ..Default::default()
+    };
+    let err = config::build_backend_config(&profile, None, None).unwrap_err().to_string();
+    assert!(err.contains("client_secret"), "Got: {}", err);
+}
+
+#[test]
+fn test_databricks_oauth_u2m_uses_token_cache() {
+    let _guard = EnvGuard::new(&[
+        ("HOME", "/Users/testuser"),
+        ("DATABRICKS_TOKEN", "dapi-std-token"),
+    ]);
+
+    let profile = TomlProfile {
+        backend: Some("databricks".to_string()),
+        host: Some("h".to_string()),
+        auth: Some("oauth-u2m".to_string()),
+        warehouse_id: Some("wh".to_string()),
+        ..Default::default()
+    };
+    let backend = config::build_backend_config(&profile, None, None).unwrap();
+    match &backend {
+        BackendConfig::Databricks { auth: DatabricksAuth::OAuthU2M { client_id, token_cache }, .. } => {
+            assert_eq!(client_id, "databricks-cli");
+            assert_eq!(token_cache, &PathBuf::from("/Users/testuser/.config/dbtoon/oauth-tokens.json"));
+        }
+        _ => panic!("Expected Databricks OAuth U2M auth"),
+    }
+}
+
+#[test]
+fn test_databricks_unknown_auth_errors() {
+    let _guard = EnvGuard::new(&[]);
+
+    let profile = TomlProfile {
+        backend: Some("databricks".to_string()),
+        host: Some("h".to_string()),
+        auth: Some("kerberos".to_string()),
+        warehouse_id: Some("wh".to_string()),
+        ..Default::default()
+    };
+    let err = config::build_backend_config(&profile, None, None).unwrap_err().to_string();
+    assert!(err.contains("kerberos"), "Got: {}", err);
+}
+
 #[test]
 fn test_dollar_var_unset_error_in_profile() {
     let _guard = EnvGuard::new(&[]);
@@ -450,6 +1120,9 @@ fn test_load_from_query_args_basic() {
         timeout: None,
         output: None,
         allow_write: false,
+        params: vec![],
+        dry_run: false,
+        no_daemon: false,
     };
 
     let app_config = config::load_from_query_args(&args, &toml_config, false, false).unwrap();
@@ -483,6 +1156,9 @@ fn test_load_from_query_args_no_limit() {
         timeout: None,
         output: None,
         allow_write: false,
+        params: vec![],
+        dry_run: false,
+        no_daemon: false,
     };
 
     let app_config = config::load_from_query_args(&args, &toml_config, false, false).unwrap();
@@ -514,6 +1190,9 @@ fn test_load_from_query_args_explicit_limit() {
         timeout: None,
         output: None,
         allow_write: false,
+        params: vec![],
+        dry_run: false,
+        no_daemon: false,
     };
 
     let app_config = config::load_from_query_args(&args, &toml_config, false, false).unwrap();
@@ -545,6 +1224,9 @@ fn test_load_from_query_args_allow_write() {
         timeout: None,
         output: None,
         allow_write: true,
+        params: vec![],
+        dry_run: false,
+        no_daemon: false,
     };
 
     let app_config = config::load_from_query_args(&args, &toml_config, false, false).unwrap();
@@ -582,6 +1264,9 @@ fn test_toml_defaults_apply_when_profile_missing_field() {
         timeout: None,
         output: None,
         allow_write: false,
+        params: vec![],
+        dry_run: false,
+        no_daemon: false,
     };
 
     let app_config = config::load_from_query_args(&args, &toml_config, false, false).unwrap();
@@ -615,6 +1300,9 @@ fn test_cli_limit_overrides_defaults() {
         timeout: None,
         output: None,
         allow_write: false,
+        params: vec![],
+        dry_run: false,
+        no_daemon: false,
     };
 
     let app_config = config::load_from_query_args(&args, &toml_config, false, false).unwrap();
@@ -650,6 +1338,9 @@ fn test_allow_write_false_by_default() {
         timeout: None,
         output: None,
         allow_write: false,
+        params: vec![],
+        dry_run: false,
+        no_daemon: false,
     };
 
     let app_config = config::load_from_query_args(&args, &toml_config, false, false).unwrap();
@@ -682,6 +1373,9 @@ fn test_allow_write_from_defaults() {
         timeout: None,
         output: None,
         allow_write: false,
+        params: vec![],
+        dry_run: false,
+        no_daemon: false,
     };
 
     let app_config = config::load_from_query_args(&args, &toml_config, false, false).unwrap();
@@ -721,6 +1415,9 @@ fn test_full_resolution_hierarchy_cli_wins() {
         timeout: None,
         output: None,
         allow_write: false,
+        params: vec![],
+        dry_run: false,
+        no_daemon: false,
     };
 
     let app_config = config::load_from_query_args(&args, &toml_config, false, false).unwrap();
@@ -760,6 +1457,9 @@ fn test_databricks_env_fallback_lowest_priority() {
         timeout: None,
         output: None,
         allow_write: false,
+        params: vec![],
+        dry_run: false,
+        no_daemon: false,
     };
 
     let app_config = config::load_from_query_args(&args, &toml_config, false, false).unwrap();
@@ -799,6 +1499,9 @@ fn test_dollar_var_unset_is_error_not_fallthrough() {
         timeout: None,
         output: None,
         allow_write: false,
+        params: vec![],
+        dry_run: false,
+        no_daemon: false,
     };
 
     let result = config::load_from_query_args(&args, &toml_config, false, false);
@@ -828,3 +1531,61 @@ fn test_profile_not_found_errors() {
     let err = result.unwrap_err().to_string();
     assert!(err.contains("not found"), "Got: {}", err);
 }
+
+#[test]
+fn test_download_concurrency_from_defaults() {
+    let _guard = EnvGuard::new(&[
+        ("DATABRICKS_HOST", "https://host.azuredatabricks.net"),
+        ("DATABRICKS_TOKEN", "dapi-token"),
+        ("DATABRICKS_SQL_WAREHOUSE_ID", "wh-id"),
+    ]);
+
+    let mut toml_config = make_toml_config("dev", TomlProfile {
+        backend: Some("databricks".to_string()),
+        ..Default::default()
+    });
+    let args = dbtoon::cli::WarehouseListArgs { profile: "dev".to_string() };
+
+    let app_config =
+        config::load_from_warehouse_list_args(&args, &toml_config, false, false).unwrap();
+    assert_eq!(app_config.download_concurrency, 4, "should fall back to the built-in default");
+
+    toml_config.defaults.download_concurrency = Some(8);
+    let app_config =
+        config::load_from_warehouse_list_args(&args, &toml_config, false, false).unwrap();
+    assert_eq!(app_config.download_concurrency, 8, "should use defaults.download_concurrency");
+}
+
+#[test]
+fn test_retry_policy_profile_overrides_defaults() {
+    let _guard = EnvGuard::new(&[
+        ("DATABRICKS_HOST", "https://host.azuredatabricks.net"),
+        ("DATABRICKS_TOKEN", "dapi-token"),
+        ("DATABRICKS_SQL_WAREHOUSE_ID", "wh-id"),
+    ]);
+
+    let mut toml_config = make_toml_config("dev", TomlProfile {
+        backend: Some("databricks".to_string()),
+        ..Default::default()
+    });
+    let args = dbtoon::cli::WarehouseListArgs { profile: "dev".to_string() };
+
+    let app_config =
+        config::load_from_warehouse_list_args(&args, &toml_config, false, false).unwrap();
+    assert_eq!(app_config.retry, RetryPolicy::default(), "should fall back to the built-in default");
+
+    toml_config.defaults.retry_attempts = Some(6);
+    toml_config.defaults.retry_backoff_ms = Some(250);
+    let app_config =
+        config::load_from_warehouse_list_args(&args, &toml_config, false, false).unwrap();
+    assert_eq!(app_config.retry.max_attempts, 6);
+    assert_eq!(app_config.retry.initial_backoff, Duration::from_millis(250));
+
+    let profile = toml_config.profiles.get_mut("dev").unwrap();
+    profile.retry_attempts = Some(0);
+    profile.retry_backoff_ms = Some(2000);
+    let app_config =
+        config::load_from_warehouse_list_args(&args, &toml_config, false, false).unwrap();
+    assert_eq!(app_config.retry.max_attempts, 1, "zero attempts still sends the request once");
+    assert_eq!(app_config.retry.initial_backoff, Duration::from_secs(2));
+}
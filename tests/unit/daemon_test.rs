@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use clap::Parser;
+
+use dbtoon::cancel::CancelToken;
+use dbtoon::cli::{Cli, Command, QueryArgs};
+use dbtoon::config::Environment;
+use dbtoon::daemon::{self, DaemonOptions, QueryRequest};
+use dbtoon::error::DbtoonError;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("dbtoon_test_daemon_{}_{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// A daemon on a fresh socket in `dir`, serving a config with one in-memory SQLite profile.
+async fn start(dir: &Path) -> (PathBuf, tokio::task::JoinHandle<Result<(), DbtoonError>>) {
+    std::fs::write(
+        dir.join("config.toml"),
+        "[defaults]\nrow_limit = 2\n\n[profiles.mem]\nbackend = \"sqlite\"\npath = \":memory:\"\n",
+    )
+    .unwrap();
+    let socket = dir.join("daemon.sock");
+    let options = DaemonOptions { idle_timeout: Duration::from_secs(60), exit_after: None, verbose: false };
+    let server = tokio::spawn({
+        let socket = socket.clone();
+        async move { daemon::serve(&socket, options).await }
+    });
+    for _ in 0..100 {
+        if daemon::connect(&socket).await.is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    (socket, server)
+}
+
+fn query_args(argv: &[&str]) -> QueryArgs {
+    let mut full = vec!["dbtoon", "query", "-P", "mem"];
+    full.extend_from_slice(argv);
+    match Cli::try_parse_from(full).unwrap().command {
+        Command::Query(args) => args,
+        _ => unreachable!(),
+    }
+}
+
+async fn run(dir: &Path, socket: &Path, args: QueryArgs) -> (Result<(), DbtoonError>, String, String) {
+    let sql = args.sql.clone().unwrap();
+    let request = QueryRequest::new(&args, sql, Some(&dir.join("config.toml")), false, false).unwrap();
+    let stream = daemon::connect(socket).await.expect("daemon should be listening");
+    let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+    let result = daemon::forward_query(stream, request, &CancelToken::new(), &mut stdout, &mut stderr).await;
+    (result, String::from_utf8(stdout).unwrap(), String::from_utf8(stderr).unwrap())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn runs_queries_and_forwards_output() {
+    let _cache = super::CONNECTION_CACHE.lock().await;
+    let dir = temp_dir("query");
+    let (socket, server) = start(&dir).await;
+
+    let sql = "SELECT 1 AS n, 'a' AS s UNION ALL SELECT 2, 'b' UNION ALL SELECT 3, 'c'";
+    let (result, stdout, stderr) = run(&dir, &socket, query_args(&[sql])).await;
+    result.unwrap();
+    assert!(stdout.contains("rows[2]{n,s}:\n  1,a\n  2,b\n"), "{stdout}");
+    assert!(stdout.contains("truncated: true"), "{stdout}");
+    assert!(stderr.contains("Showing 2 rows"), "{stderr}");
+
+    let status = daemon::status(&socket).await.unwrap().expect("daemon should report status");
+    assert_eq!(status.pid, std::process::id());
+    assert_eq!(status.queries, 1);
+    assert_eq!(status.running, 0);
+
+    daemon::stop(&socket).await.unwrap();
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn output_larger_than_the_frame_queue_arrives_whole() {
+    let _cache = super::CONNECTION_CACHE.lock().await;
+    let dir = temp_dir("large");
+    let (socket, server) = start(&dir).await;
+
+    let sql = "WITH RECURSIVE c(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM c WHERE n < 20000) SELECT n FROM c";
+    let (result, stdout, _) = run(&dir, &socket, query_args(&[sql, "--no-limit"])).await;
+    result.unwrap();
+    assert!(stdout.contains("rows[20000]{n}:\n  1\n  2\n"), "{}", &stdout[..200.min(stdout.len())]);
+    assert!(stdout.contains("\n  20000\n"));
+
+    daemon::stop(&socket).await.unwrap();
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn relative_output_paths_follow_the_client() {
+    let _cache = super::CONNECTION_CACHE.lock().await;
+    let dir = temp_dir("output");
+    let (socket, server) = start(&dir).await;
+
+    let out = dir.join("rows.csv");
+    let request_args = query_args(&["SELECT 7 AS n", "-o", out.to_str().unwrap()]);
+    let (result, stdout, _) = run(&dir, &socket, request_args).await;
+    result.unwrap();
+    assert_eq!(std::fs::read_to_string(&out).unwrap(), "n\r\n7\r\n");
+    assert!(stdout.contains("rows.csv"), "{stdout}");
+
+    let relative = query_args(&["SELECT 1", "-o", "rows.csv"]);
+    let request = QueryRequest::new(&relative, "SELECT 1".to_string(), None, false, false).unwrap();
+    assert_eq!(request.args.output.unwrap(), std::env::current_dir().unwrap().join("rows.csv"));
+
+    daemon::stop(&socket).await.unwrap();
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn config_resolves_against_the_client_environment() {
+    let _cache = super::CONNECTION_CACHE.lock().await;
+    let dir = temp_dir("environment");
+    let (socket, server) = start(&dir).await;
+    let mut config = std::fs::read_to_string(dir.join("config.toml")).unwrap();
+    config.push_str("\n[profiles.local]\nbackend = \"sqlite\"\npath = \"$DBTOON_TEST_DAEMON_DB\"\n");
+    std::fs::write(dir.join("config.toml"), config).unwrap();
+
+    let client_dir = dir.join("client");
+    std::fs::create_dir_all(&client_dir).unwrap();
+    let db = rusqlite::Connection::open(client_dir.join("local.db")).unwrap();
+    db.execute_batch("CREATE TABLE t (n INTEGER); INSERT INTO t VALUES (1);").unwrap();
+    std::fs::write(client_dir.join("more.csv"), "n\n2\n").unwrap();
+
+    let sql = "SELECT n FROM t UNION ALL SELECT n FROM 'more.csv' ORDER BY n";
+    let mut args = query_args(&[sql]);
+    args.profile = "local".to_string();
+    let mut request = QueryRequest::new(&args, sql.to_string(), Some(&dir.join("config.toml")), false, false).unwrap();
+    // Neither the variable nor the relative paths mean anything to the daemon's own process
+    request.environment = Environment {
+        vars: HashMap::from([("DBTOON_TEST_DAEMON_DB".to_string(), "local.db".to_string())]),
+        working_dir: client_dir,
+    };
+    let stream = daemon::connect(&socket).await.expect("daemon should be listening");
+    let mut stdout = Vec::new();
+    daemon::forward_query(stream, request, &CancelToken::new(), &mut stdout, &mut Vec::new()).await.unwrap();
+    let stdout = String::from_utf8(stdout).unwrap();
+    assert!(stdout.contains("rows[2]{n}:\n  1\n  2\n"), "{stdout}");
+
+    daemon::stop(&socket).await.unwrap();
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn errors_keep_their_kind() {
+    let _cache = super::CONNECTION_CACHE.lock().await;
+    let dir = temp_dir("errors");
+    let (socket, server) = start(&dir).await;
+
+    let (result, stdout, _) = run(&dir, &socket, query_args(&["DELETE FROM t"])).await;
+    assert!(matches!(result, Err(DbtoonError::Validation { .. })), "{result:?}");
+    assert!(stdout.is_empty());
+
+    let mut unknown = query_args(&["SELECT 1"]);
+    unknown.profile = "nope".to_string();
+    let (result, _, _) = run(&dir, &socket, unknown).await;
+    assert!(matches!(&result, Err(DbtoonError::Config { message }) if message.contains("nope")), "{result:?}");
+
+    let (result, _, _) = run(&dir, &socket, query_args(&["SELECT * FROM missing"])).await;
+    let err = result.unwrap_err();
+    assert!(matches!(err, DbtoonError::Query { .. }), "{err}");
+    assert_eq!(err.exit_code(), DbtoonError::Query { message: String::new() }.exit_code());
+
+    daemon::stop(&socket).await.unwrap();
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn one_daemon_per_socket_and_stop_removes_it() {
+    let _cache = super::CONNECTION_CACHE.lock().await;
+    let dir = temp_dir("lifecycle");
+    let (socket, server) = start(&dir).await;
+
+    let options = DaemonOptions { idle_timeout: Duration::from_secs(60), exit_after: None, verbose: false };
+    let err = daemon::serve(&socket, options).await.unwrap_err();
+    assert!(err.to_string().contains("already running"), "{err}");
+
+    daemon::stop(&socket).await.unwrap();
+    server.await.unwrap().unwrap();
+    assert!(!socket.exists());
+    assert!(daemon::status(&socket).await.unwrap().is_none());
+    assert!(daemon::stop(&socket).await.is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn stale_socket_file_is_replaced() {
+    let _cache = super::CONNECTION_CACHE.lock().await;
+    let dir = temp_dir("stale");
+    std::fs::write(dir.join("daemon.sock"), "").unwrap();
+    let (socket, server) = start(&dir).await;
+
+    assert!(daemon::status(&socket).await.unwrap().is_some());
+    daemon::stop(&socket).await.unwrap();
+    server.await.unwrap().unwrap();
+}
@@ -0,0 +1,623 @@
+use std::fs;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use arrow::array::{Int64Array, RecordBatchReader, StringArray};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::ipc::writer::StreamWriter;
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReader;
+
+use dbtoon::backend::databricks::{DatabricksBackend, list_warehouses};
+use dbtoon::backend::retry::RetryPolicy;
+use dbtoon::backend::{Backend, CellValue};
+use dbtoon::cancel::CancelToken;
+use dbtoon::config::DatabricksAuth;
+use dbtoon::error::DbtoonError;
+use dbtoon::format_csv::CsvSink;
+use dbtoon::format_parquet::ParquetSink;
+use dbtoon::params::parse_param;
+use secrecy::SecretString;
+
+use super::mock_http::{MockResponse, MockServer};
+
+const STATEMENTS: &str = "/api/2.0/sql/statements/";
+
+fn backend(server: &MockServer) -> DatabricksBackend {
+    DatabricksBackend::new(
+        server.url(),
+        DatabricksAuth::Token(SecretString::from("test-token")),
+        "wh-1".to_string(),
+        None,
+        None,
+    )
+}
+
+fn int(cell: &CellValue) -> i64 {
+    match cell {
+        CellValue::Int64(n) => *n,
+        other => panic!("expected an INT cell, got {other:?}"),
+    }
+}
+
+/// A SUCCEEDED statement with 5 rows split over three inline chunks.
+fn serve_chunked_result(server: &MockServer, with_links: bool) {
+    let link = |n: u32| {
+        if with_links {
+            format!(r#","next_chunk_internal_link":"/api/2.0/sql/statements/st-1/result/chunks/{n}""#)
+        } else {
+            String::new()
+        }
+    };
+    server.on(
+        "POST",
+        STATEMENTS,
+        MockResponse::json(
+            200,
+            &format!(
+                r#"{{"statement_id":"st-1","status":{{"state":"SUCCEEDED"}},
+                "manifest":{{"schema":{{"columns":[{{"name":"n","type_name":"INT"}}]}},"total_row_count":5,"total_chunk_count":3}},
+                "result":{{"data_array":[["1"],["2"]],"next_chunk_index":1{}}}}}"#,
+                link(1)
+            ),
+        ),
+    );
+    server.on(
+        "GET",
+        "/api/2.0/sql/statements/st-1/result/chunks/1",
+        MockResponse::json(
+            200,
+            &format!(r#"{{"chunk_index":1,"data_array":[["3"],["4"]],"next_chunk_index":2{}}}"#, link(2)),
+        ),
+    );
+    server.on(
+        "GET",
+        "/api/2.0/sql/statements/st-1/result/chunks/2",
+        MockResponse::json(200, r#"{"chunk_index":2,"data_array":[["5"]]}"#),
+    );
+}
+
+#[tokio::test]
+async fn follows_chunk_links_until_exhausted() {
+    let server = MockServer::start();
+    serve_chunked_result(&server, true);
+
+    let result = backend(&server).execute("SELECT n FROM t", &[], None, 60).await.unwrap();
+
+    let values: Vec<i64> = result.rows.iter().map(|r| int(&r[0])).collect();
+    assert_eq!(values, vec![1, 2, 3, 4, 5]);
+    assert_eq!(result.total_rows, Some(5));
+    assert!(!result.truncated);
+}
+
+#[tokio::test]
+async fn falls_back_to_chunk_index_without_internal_link() {
+    let server = MockServer::start();
+    serve_chunked_result(&server, false);
+
+    let result = backend(&server).execute("SELECT n FROM t", &[], None, 60).await.unwrap();
+
+    assert_eq!(result.rows.len(), 5);
+    assert_eq!(server.requests_to("/api/2.0/sql/statements/st-1/result/chunks/2").len(), 1);
+}
+
+#[tokio::test]
+async fn row_limit_applies_across_chunks() {
+    let server = MockServer::start();
+    serve_chunked_result(&server, true);
+
+    let result = backend(&server).execute("SELECT n FROM t", &[], Some(3), 60).await.unwrap();
+
+    let submit = &server.requests_to(STATEMENTS)[0];
+    assert_eq!(submit.method, "POST");
+    assert_eq!(submit.headers.get("authorization").map(String::as_str), Some("Bearer test-token"));
+    assert!(submit.body.contains(r#""row_limit":3"#), "body: {}", submit.body);
+
+    let values: Vec<i64> = result.rows.iter().map(|r| int(&r[0])).collect();
+    assert_eq!(values, vec![1, 2, 3]);
+    assert!(result.truncated, "rows beyond the limit should mark the result truncated");
+    assert!(
+        server.requests_to("/api/2.0/sql/statements/st-1/result/chunks/2").is_empty(),
+        "no chunks should be fetched once the limit is reached"
+    );
+}
+
+#[tokio::test]
+async fn limit_matching_total_is_not_truncated() {
+    let server = MockServer::start();
+    serve_chunked_result(&server, true);
+
+    let result = backend(&server).execute("SELECT n FROM t", &[], Some(5), 60).await.unwrap();
+
+    assert_eq!(result.rows.len(), 5);
+    assert!(!result.truncated);
+}
+
+#[tokio::test]
+async fn inline_values_are_typed_from_manifest() {
+    let server = MockServer::start();
+    server.on(
+        "POST",
+        STATEMENTS,
+        MockResponse::json(
+            200,
+            r#"{"statement_id":"st-t","status":{"state":"SUCCEEDED"},
+            "manifest":{"schema":{"columns":[
+              {"name":"price","type_name":"DECIMAL"},{"name":"ok","type_name":"BOOLEAN"},
+              {"name":"day","type_name":"DATE"},{"name":"at","type_name":"TIMESTAMP"},
+              {"name":"ratio","type_name":"DOUBLE"},{"name":"code","type_name":"INT"}]}},
+            "result":{"data_array":[["12.50","true","2024-03-01","2024-03-01T10:30:00.000Z","0.25","n/a"]]}}"#,
+        ),
+    );
+
+    let result = backend(&server).execute("SELECT 1", &[], None, 60).await.unwrap();
+
+    assert_eq!(
+        result.rows[0],
+        vec![
+            CellValue::Decimal { value: 1250, scale: 2 },
+            CellValue::Bool(true),
+            CellValue::Date(19783),
+            CellValue::Timestamp(19783 * 86_400_000_000 + 37_800_000_000),
+            CellValue::Float64(0.25),
+            CellValue::Text("n/a".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn chunk_fetch_http_error_is_reported() {
+    let server = MockServer::start();
+    server.on(
+        "POST",
+        STATEMENTS,
+        MockResponse::json(
+            200,
+            r#"{"statement_id":"st-2","status":{"state":"SUCCEEDED"},
+            "manifest":{"schema":{"columns":[{"name":"n","type_name":"INT"}]}},
+            "result":{"data_array":[["1"]],"next_chunk_index":1}}"#,
+        ),
+    );
+    server.on(
+        "GET",
+        "/api/2.0/sql/statements/st-2/result/chunks/1",
+        MockResponse::json(403, "{}"),
+    );
+
+    let err = backend(&server).execute("SELECT n FROM t", &[], None, 60).await.unwrap_err();
+    assert!(err.to_string().starts_with("auth:"), "got: {err}");
+}
+
+#[tokio::test]
+async fn params_are_sent_as_statement_parameters() {
+    let server = MockServer::start();
+    serve_chunked_result(&server, false);
+
+    let params = [
+        parse_param("id=42:INT").unwrap(),
+        parse_param("name=a:b").unwrap(),
+    ];
+    backend(&server)
+        .execute("SELECT n FROM t WHERE id = :id AND name = :name", &params, None, 60)
+        .await
+        .unwrap();
+
+    let body: serde_json::Value =
+        serde_json::from_str(&server.requests_to(STATEMENTS)[0].body).unwrap();
+    assert_eq!(body["statement"], "SELECT n FROM t WHERE id = :id AND name = :name");
+    assert_eq!(
+        body["parameters"],
+        serde_json::json!([
+            {"name": "id", "value": "42", "type": "INT"},
+            {"name": "name", "value": "a:b"},
+        ])
+    );
+}
+
+#[tokio::test]
+async fn no_parameters_key_without_params() {
+    let server = MockServer::start();
+    serve_chunked_result(&server, false);
+
+    backend(&server).execute("SELECT n FROM t", &[], None, 60).await.unwrap();
+
+    let submit = &server.requests_to(STATEMENTS)[0];
+    assert!(!submit.body.contains("parameters"), "body: {}", submit.body);
+}
+
+// --- EXTERNAL_LINKS + ARROW_STREAM ---
+
+/// Encode `ids` as an Arrow IPC stream with an Int64 `id` and Utf8 `name` column.
+fn arrow_chunk(ids: &[i64]) -> Vec<u8> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, true),
+        Field::new("name", DataType::Utf8, true),
+    ]));
+    let names: Vec<String> = ids.iter().map(|i| format!("row-{i}")).collect();
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(Int64Array::from(ids.to_vec())),
+            Arc::new(StringArray::from(names)),
+        ],
+    )
+    .unwrap();
+
+    let mut buf = Vec::new();
+    let mut writer = StreamWriter::try_new(&mut buf, &schema).unwrap();
+    writer.write(&batch).unwrap();
+    writer.finish().unwrap();
+    drop(writer);
+    buf
+}
+
+/// A SUCCEEDED ARROW_STREAM statement with three chunks; only chunk 0's link is inline.
+fn serve_arrow_result(server: &MockServer) {
+    let url = server.url();
+    server.on(
+        "POST",
+        STATEMENTS,
+        MockResponse::json(
+            200,
+            &format!(
+                r#"{{"statement_id":"st-a","status":{{"state":"SUCCEEDED"}},
+                "manifest":{{"format":"ARROW_STREAM","total_chunk_count":3,"total_row_count":5,
+                  "schema":{{"columns":[{{"name":"id","type_name":"LONG"}},{{"name":"name","type_name":"STRING"}}]}}}},
+                "result":{{"external_links":[{{"chunk_index":0,"external_link":"{url}/files/0","http_headers":{{"x-ms-blob-type":"BlockBlob"}}}}]}}}}"#
+            ),
+        ),
+    );
+    for n in 1..3 {
+        server.on(
+            "GET",
+            &format!("/api/2.0/sql/statements/st-a/result/chunks/{n}"),
+            MockResponse::json(
+                200,
+                &format!(r#"{{"external_links":[{{"chunk_index":{n},"external_link":"{url}/files/{n}"}}]}}"#),
+            ),
+        );
+    }
+    server.on("GET", "/files/0", MockResponse::bytes(200, "application/octet-stream", arrow_chunk(&[1, 2])));
+    server.on("GET", "/files/1", MockResponse::bytes(200, "application/octet-stream", arrow_chunk(&[3, 4])));
+    server.on("GET", "/files/2", MockResponse::bytes(200, "application/octet-stream", arrow_chunk(&[5])));
+}
+
+fn temp_path(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join("dbtoon_test_databricks");
+    fs::create_dir_all(&dir).unwrap();
+    dir.join(name)
+}
+
+fn read_parquet_ids(path: &std::path::Path) -> (DataType, Vec<i64>) {
+    let reader = ParquetRecordBatchReader::try_new(fs::File::open(path).unwrap(), 1024).unwrap();
+    let data_type = reader.schema().field(0).data_type().clone();
+    let mut ids = Vec::new();
+    for batch in reader {
+        let batch = batch.unwrap();
+        let col = batch.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+        ids.extend(col.values().iter().copied());
+    }
+    (data_type, ids)
+}
+
+#[tokio::test]
+async fn arrow_sink_requests_external_links_and_writes_native_batches() {
+    let server = MockServer::start();
+    serve_arrow_result(&server);
+    let path = temp_path("external_links.parquet");
+
+    let sink = ParquetSink::create(&path).unwrap();
+    let (sink, summary) = backend(&server)
+        .with_download_concurrency(2)
+        .execute_streaming("SELECT id, name FROM t", &[], None, 60, sink)
+        .await
+        .unwrap();
+    sink.finish(summary.truncated, None).unwrap();
+
+    let submit = &server.requests_to(STATEMENTS)[0];
+    assert!(submit.body.contains(r#""disposition":"EXTERNAL_LINKS""#), "body: {}", submit.body);
+    assert!(submit.body.contains(r#""format":"ARROW_STREAM""#), "body: {}", submit.body);
+
+    let download = &server.requests_to("/files/0")[0];
+    assert!(!download.headers.contains_key("authorization"), "presigned downloads must not carry the token");
+    assert_eq!(download.headers.get("x-ms-blob-type").map(String::as_str), Some("BlockBlob"));
+
+    assert_eq!(summary.rows, 5);
+    assert_eq!(summary.total_rows, Some(5));
+    assert!(!summary.truncated);
+    let (data_type, ids) = read_parquet_ids(&path);
+    assert_eq!(data_type, DataType::Int64, "Arrow types should pass through without a text round trip");
+    assert_eq!(ids, vec![1, 2, 3, 4, 5]);
+
+    let _ = fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn arrow_results_honour_row_limit() {
+    let server = MockServer::start();
+    serve_arrow_result(&server);
+    let path = temp_path("external_links_limited.parquet");
+
+    let sink = ParquetSink::create(&path).unwrap();
+    let (sink, summary) = backend(&server)
+        .with_download_concurrency(1)
+        .execute_streaming("SELECT id, name FROM t", &[], Some(3), 60, sink)
+        .await
+        .unwrap();
+    sink.finish(summary.truncated, None).unwrap();
+
+    assert_eq!(summary.rows, 3);
+    assert!(summary.truncated);
+    assert_eq!(read_parquet_ids(&path).1, vec![1, 2, 3]);
+    assert!(server.requests_to("/files/2").is_empty(), "chunks past the limit should not be downloaded");
+
+    let _ = fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn text_sinks_keep_inline_disposition() {
+    let server = MockServer::start();
+    serve_chunked_result(&server, true);
+
+    let path = temp_path("inline.csv");
+    let sink = CsvSink::new(fs::File::create(&path).unwrap());
+    let (sink, summary) = backend(&server)
+        .execute_streaming("SELECT n FROM t", &[], None, 60, sink)
+        .await
+        .unwrap();
+    sink.finish().unwrap();
+
+    assert_eq!(summary.rows, 5);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "n\r\n1\r\n2\r\n3\r\n4\r\n5\r\n");
+    let _ = fs::remove_file(&path);
+    let submit = &server.requests_to(STATEMENTS)[0];
+    assert!(!submit.body.contains("disposition"), "body: {}", submit.body);
+}
+
+/// A statement that is still RUNNING when submitted and on every poll.
+fn serve_running_statement(server: &MockServer) {
+    let running = r#"{"statement_id":"st-1","status":{"state":"RUNNING"}}"#;
+    server.on("POST", STATEMENTS, MockResponse::json(200, running));
+    server.on("GET", "/api/2.0/sql/statements/st-1", MockResponse::json(200, running));
+    server.on("POST", "/api/2.0/sql/statements/st-1/cancel", MockResponse::json(200, "{}"));
+}
+
+#[tokio::test]
+async fn cancel_while_polling_cancels_statement() {
+    let server = MockServer::start();
+    serve_running_statement(&server);
+    let cancel = CancelToken::new();
+
+    let trigger = cancel.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        trigger.cancel();
+    });
+    let err = backend(&server)
+        .with_cancel(cancel)
+        .execute("SELECT n FROM t", &[], None, 60)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, DbtoonError::Cancelled), "got {err:?}");
+    assert_eq!(server.requests_to("/api/2.0/sql/statements/st-1/cancel").len(), 1);
+    // The submission returns within seconds, so a cancel never waits long for the id
+    let submit = &server.requests_to(STATEMENTS)[0];
+    assert!(submit.body.contains(r#""wait_timeout":"5s""#), "body: {}", submit.body);
+}
+
+#[tokio::test]
+async fn cancel_during_submission_cancels_statement() {
+    let server = MockServer::start();
+    serve_running_statement(&server);
+    let cancel = CancelToken::new();
+    cancel.cancel();
+
+    let err = backend(&server)
+        .with_cancel(cancel)
+        .execute("SELECT n FROM t", &[], None, 60)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, DbtoonError::Cancelled), "got {err:?}");
+    assert_eq!(server.requests_to("/api/2.0/sql/statements/st-1/cancel").len(), 1);
+    assert!(server.requests_to("/api/2.0/sql/statements/st-1").is_empty(), "no polling after cancel");
+}
+
+#[test]
+fn cancelled_error_has_own_category_and_exit_code() {
+    assert_eq!(DbtoonError::Cancelled.to_string(), "cancelled: query was cancelled");
+    assert_eq!(DbtoonError::Cancelled.exit_code(), 130);
+    assert_eq!(DbtoonError::Timeout { seconds: 5 }.exit_code(), 1);
+}
+
+fn fast_retry(max_attempts: u32) -> RetryPolicy {
+    RetryPolicy {
+        max_attempts,
+        initial_backoff: Duration::from_millis(10),
+    }
+}
+
+const SUCCEEDED: &str = r#"{"statement_id":"st-1","status":{"state":"SUCCEEDED"},
+    "manifest":{"schema":{"columns":[{"name":"n","type_name":"INT"}]},"total_row_count":1,"total_chunk_count":1},
+    "result":{"data_array":[["1"]]}}"#;
+
+#[tokio::test]
+async fn submit_retries_unavailable_warehouse() {
+    let server = MockServer::start();
+    server.on("POST", STATEMENTS, MockResponse::json(503, "{}"));
+    server.on("POST", STATEMENTS, MockResponse::json(429, "{}"));
+    server.on("POST", STATEMENTS, MockResponse::json(200, SUCCEEDED));
+
+    let result = backend(&server)
+        .with_retry(fast_retry(3))
+        .execute("SELECT n FROM t", &[], None, 60)
+        .await
+        .unwrap();
+
+    assert_eq!(result.rows.len(), 1);
+    assert_eq!(server.requests_to(STATEMENTS).len(), 3);
+}
+
+#[tokio::test]
+async fn submit_does_not_retry_server_errors() {
+    let server = MockServer::start();
+    server.on("POST", STATEMENTS, MockResponse::json(500, "{}"));
+    server.on("POST", STATEMENTS, MockResponse::json(200, SUCCEEDED));
+
+    let err = backend(&server)
+        .with_retry(fast_retry(3))
+        .execute("SELECT n FROM t", &[], None, 60)
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("HTTP error: 500"), "got {err}");
+    assert_eq!(server.requests_to(STATEMENTS).len(), 1, "the statement may already be running");
+}
+
+#[tokio::test]
+async fn retry_honours_retry_after() {
+    let server = MockServer::start();
+    server.on("POST", STATEMENTS, MockResponse::json(429, "{}").with_header("Retry-After", "1"));
+    server.on("POST", STATEMENTS, MockResponse::json(200, SUCCEEDED));
+
+    let start = Instant::now();
+    backend(&server)
+        .with_retry(fast_retry(2))
+        .execute("SELECT n FROM t", &[], None, 60)
+        .await
+        .unwrap();
+
+    assert!(start.elapsed() >= Duration::from_secs(1), "waited {:?}", start.elapsed());
+}
+
+#[tokio::test]
+async fn retry_gives_up_after_max_attempts() {
+    let server = MockServer::start();
+    server.on("POST", STATEMENTS, MockResponse::json(503, "{}"));
+
+    let err = backend(&server)
+        .with_retry(fast_retry(2))
+        .execute("SELECT n FROM t", &[], None, 60)
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("HTTP error: 503"), "got {err}");
+    assert_eq!(server.requests_to(STATEMENTS).len(), 2);
+}
+
+#[tokio::test]
+async fn chunk_fetch_retries_gateway_errors() {
+    let server = MockServer::start();
+    let chunk = "/api/2.0/sql/statements/st-1/result/chunks/1";
+    // Queued ahead of the canned chunk, so the first fetch fails
+    server.on("GET", chunk, MockResponse::json(502, "{}"));
+    serve_chunked_result(&server, true);
+
+    let result = backend(&server)
+        .with_retry(fast_retry(3))
+        .execute("SELECT n FROM t", &[], None, 60)
+        .await
+        .unwrap();
+
+    assert_eq!(result.rows.len(), 5);
+    assert_eq!(server.requests_to(chunk).len(), 2);
+}
+
+#[tokio::test]
+async fn list_warehouses_retries_transient_failures() {
+    let server = MockServer::start();
+    let path = "/api/2.0/sql/warehouses/";
+    server.on("GET", path, MockResponse::json(502, "{}"));
+    server.on(
+        "GET",
+        path,
+        MockResponse::json(200, r#"{"warehouses":[{"id":"wh-1","name":"Shared","state":"RUNNING","cluster_size":"Small"}]}"#),
+    );
+
+    let warehouses = list_warehouses(&server.url(), &DatabricksAuth::Token(SecretString::from("t")), &fast_retry(2), false)
+        .await
+        .unwrap();
+
+    assert_eq!(warehouses.len(), 1);
+    assert_eq!(server.requests_to(path).len(), 2);
+}
+
+const TOKEN_ENDPOINT: &str = "/oidc/v1/token";
+
+fn m2m_backend(server: &MockServer) -> DatabricksBackend {
+    DatabricksBackend::new(
+        server.url(),
+        DatabricksAuth::OAuthM2M {
+            client_id: "sp-client".to_string(),
+            client_secret: SecretString::from("sp-secret"),
+        },
+        "wh-1".to_string(),
+        None,
+        None,
+    )
+}
+
+#[tokio::test]
+async fn m2m_fetches_and_caches_oauth_token() {
+    let server = MockServer::start();
+    server.on(
+        "POST",
+        TOKEN_ENDPOINT,
+        MockResponse::json(200, r#"{"access_token":"m2m-token","token_type":"Bearer","expires_in":3600}"#),
+    );
+    server.on("POST", STATEMENTS, MockResponse::json(200, SUCCEEDED));
+
+    for _ in 0..2 {
+        let result = m2m_backend(&server).execute("SELECT 1", &[], None, 60).await.unwrap();
+        assert_eq!(result.rows.len(), 1);
+    }
+
+    let token_requests = server.requests_to(TOKEN_ENDPOINT);
+    assert_eq!(token_requests.len(), 1, "token should be cached across queries");
+    let body = &token_requests[0].body;
+    assert!(body.contains("grant_type=client_credentials"), "body: {body}");
+    assert!(body.contains("scope=all-apis"), "body: {body}");
+    // Basic base64("sp-client:sp-secret")
+    assert_eq!(
+        token_requests[0].headers.get("authorization").map(String::as_str),
+        Some("Basic c3AtY2xpZW50OnNwLXNlY3JldA==")
+    );
+
+    for request in server.requests_to(STATEMENTS) {
+        assert_eq!(
+            request.headers.get("authorization").map(String::as_str),
+            Some("Bearer m2m-token")
+        );
+    }
+}
+
+#[tokio::test]
+async fn m2m_refreshes_token_near_expiry() {
+    let server = MockServer::start();
+    // Inside the refresh margin, so every request fetches a fresh token
+    server.on(
+        "POST",
+        TOKEN_ENDPOINT,
+        MockResponse::json(200, r#"{"access_token":"short-lived","expires_in":30}"#),
+    );
+    server.on("POST", STATEMENTS, MockResponse::json(200, SUCCEEDED));
+
+    for _ in 0..2 {
+        m2m_backend(&server).execute("SELECT 1", &[], None, 60).await.unwrap();
+    }
+
+    assert_eq!(server.requests_to(TOKEN_ENDPOINT).len(), 2);
+}
+
+#[tokio::test]
+async fn m2m_token_rejection_is_auth_error() {
+    let server = MockServer::start();
+    server.on("POST", TOKEN_ENDPOINT, MockResponse::json(401, r#"{"error":"invalid_client"}"#));
+
+    let err = m2m_backend(&server).execute("SELECT 1", &[], None, 60).await.unwrap_err();
+
+    assert!(matches!(err, DbtoonError::Auth { .. }), "got {err:?}");
+    assert!(err.to_string().contains("sp-client"), "got {err}");
+    assert!(server.requests_to(STATEMENTS).is_empty());
+}
@@ -1,11 +1,11 @@
 use std::fs;
 
-use arrow::array::{Float64Array, Int32Array, StringArray};
+use arrow::array::{Float64Array, Int32Array, Int64Array, StringArray};
 use arrow::datatypes::DataType;
 use arrow::ipc::reader::FileReader;
 
-use dbtoon::backend::{CellValue, ColumnMeta, QueryResult};
-use dbtoon::format_arrow::write_arrow;
+use dbtoon::backend::{CellValue, ColumnMeta, QueryResult, ResultSink};
+use dbtoon::format_arrow::{ArrowSink, write_arrow};
 
 fn make_column(name: &str, type_name: &str) -> ColumnMeta {
     ColumnMeta {
@@ -21,6 +21,8 @@ fn make_result(columns: Vec<ColumnMeta>, rows: Vec<Vec<CellValue>>) -> QueryResu
         rows,
         total_rows,
         truncated: false,
+        messages: Vec::new(),
+        rows_affected: None,
     }
 }
 
@@ -207,3 +209,44 @@ fn non_truncated_arrow_has_no_dbtoon_metadata() {
 
     let _ = fs::remove_file(&path);
 }
+
+// --- Streaming sink ---
+
+#[test]
+fn sink_writes_each_batch() {
+    let path = temp_arrow_path("sink_batches");
+    let mut sink = ArrowSink::create(&path).unwrap();
+    sink.begin(&[make_column("id", "INT")]).unwrap();
+    sink.write_batch(&[vec![CellValue::Int64(1)], vec![CellValue::Int64(2)]]).unwrap();
+    sink.write_batch(&[vec![CellValue::Int64(3)]]).unwrap();
+    sink.finish(false, None).unwrap();
+
+    let file = fs::File::open(&path).unwrap();
+    let reader = FileReader::try_new(file, None).unwrap();
+    let batches: Vec<_> = reader.map(|b| b.unwrap()).collect();
+    assert_eq!(batches.len(), 2);
+    assert_eq!(batches[0].schema().field(0).data_type(), &DataType::Int64);
+    let ids = batches[1].column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(ids.value(0), 3);
+
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn truncated_sink_records_truncation_in_schema_metadata() {
+    let path = temp_arrow_path("sink_truncated");
+    let mut sink = ArrowSink::create(&path).unwrap();
+    sink.begin(&[make_column("id", "INT")]).unwrap();
+    sink.write_batch(&[vec![CellValue::Text("1".into())]]).unwrap();
+    sink.write_batch(&[vec![CellValue::Text("2".into())]]).unwrap();
+    sink.finish(true, Some("Showing 2 rows.")).unwrap();
+
+    let file = fs::File::open(&path).unwrap();
+    let reader = FileReader::try_new(file, None).unwrap();
+    let schema = reader.schema();
+    assert_eq!(schema.metadata().get("dbtoon:truncated").map(String::as_str), Some("true"));
+    let total_rows: usize = reader.map(|b| b.unwrap().num_rows()).sum();
+    assert_eq!(total_rows, 2);
+
+    let _ = fs::remove_file(&path);
+}
@@ -1,6 +1,7 @@
+use arrow::array::{Array, Decimal128Array, StringArray};
 use arrow::datatypes::DataType;
 use dbtoon::backend::{CellValue, ColumnMeta, QueryResult};
-use dbtoon::format_columnar::{build_record_batch, sql_type_to_arrow};
+use dbtoon::format_columnar::{BatchEncoder, build_record_batch, sql_type_to_arrow};
 
 fn make_column(name: &str, type_name: &str) -> ColumnMeta {
     ColumnMeta {
@@ -16,6 +17,8 @@ fn make_result(columns: Vec<ColumnMeta>, rows: Vec<Vec<CellValue>>) -> QueryResu
         rows,
         total_rows,
         truncated: false,
+        messages: Vec::new(),
+        rows_affected: None,
     }
 }
 
@@ -292,3 +295,88 @@ fn build_batch_empty_result_set() {
     assert_eq!(schema.field(0).data_type(), &DataType::Int32);
     assert_eq!(schema.field(1).data_type(), &DataType::Utf8);
 }
+
+// --- BatchEncoder tests ---
+
+#[test]
+fn encoder_keeps_schema_from_first_batch() {
+    let columns = vec![make_column("id", "INT"), make_column("code", "INT")];
+    let mut encoder = BatchEncoder::new(&columns);
+
+    let first = encoder
+        .encode(&[vec![CellValue::Int64(1), CellValue::Text("42".into())]])
+        .unwrap();
+    let second = encoder
+        .encode(&[vec![CellValue::Int64(3_000_000_000), CellValue::Text("abc".into())]])
+        .unwrap();
+
+    assert_eq!(first.schema(), second.schema());
+    // Native integers are widened so later rows fit; text a later row might not parse stays text
+    assert_eq!(second.schema().field(0).data_type(), &DataType::Int64);
+    assert_eq!(second.schema().field(1).data_type(), &DataType::Utf8);
+}
+
+#[test]
+fn encoder_rejects_late_values_that_do_not_fit() {
+    let columns = vec![make_column("n", "INTEGER")];
+    let mut encoder = BatchEncoder::new(&columns);
+    encoder.encode(&[vec![CellValue::Int64(1)]]).unwrap();
+
+    let err = encoder
+        .encode(&[vec![CellValue::Int64(2)], vec![CellValue::Bytes(vec![0xff])]])
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("column 'n' has a value that does not fit type Int64"),
+        "{err}"
+    );
+}
+
+#[test]
+fn encoder_schema_before_first_batch_uses_declared_types() {
+    let encoder = BatchEncoder::new(&[make_column("ts", "DATETIME2(7)")]);
+    assert_eq!(
+        encoder.schema().field(0).data_type(),
+        &sql_type_to_arrow("DATETIME2(7)")
+    );
+}
+
+#[test]
+fn native_values_build_typed_arrays_without_parsing() {
+    let result = make_result(
+        vec![
+            make_column("id", "INT"),
+            make_column("price", "DECIMAL(10,3)"),
+            make_column("active", "BIT"),
+            make_column("day", "DATE"),
+        ],
+        vec![vec![
+            CellValue::Int64(7),
+            CellValue::Decimal { value: 1250, scale: 2 },
+            CellValue::Bool(true),
+            CellValue::Date(19783),
+        ]],
+    );
+
+    let (schema, batch) = build_record_batch(&result).unwrap();
+
+    assert_eq!(schema.field(0).data_type(), &DataType::Int32);
+    assert_eq!(schema.field(1).data_type(), &DataType::Decimal128(10, 3));
+    assert_eq!(schema.field(2).data_type(), &DataType::Boolean);
+    assert_eq!(schema.field(3).data_type(), &DataType::Date32);
+    let price = batch.column(1).as_any().downcast_ref::<Decimal128Array>().unwrap();
+    assert_eq!(price.value(0), 12500, "decimal should be rescaled to the column scale");
+}
+
+#[test]
+fn native_value_out_of_range_falls_back_to_utf8() {
+    let result = make_result(
+        vec![make_column("n", "SMALLINT")],
+        vec![vec![CellValue::Int64(70_000)]],
+    );
+
+    let (schema, batch) = build_record_batch(&result).unwrap();
+
+    assert_eq!(schema.field(0).data_type(), &DataType::Utf8);
+    let col = batch.column(0).as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!(col.value(0), "70000");
+}
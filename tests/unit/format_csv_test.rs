@@ -1,5 +1,5 @@
-use dbtoon::backend::{CellValue, ColumnMeta, QueryResult};
-use dbtoon::format_csv::write_csv_to_writer;
+use dbtoon::backend::{CellValue, ColumnMeta, QueryResult, ResultSink};
+use dbtoon::format_csv::{CsvSink, write_csv_to_writer};
 
 fn make_column(name: &str) -> ColumnMeta {
     ColumnMeta {
@@ -15,6 +15,8 @@ fn make_result(columns: Vec<ColumnMeta>, rows: Vec<Vec<CellValue>>) -> QueryResu
         rows,
         total_rows,
         truncated: false,
+        messages: Vec::new(),
+        rows_affected: None,
     }
 }
 
@@ -158,3 +160,38 @@ fn column_names_with_special_characters_are_escaped() {
         "normal,\"has, comma\",\"has \"\"quote\"\"\",\"has\nnewline\""
     );
 }
+
+#[test]
+fn sink_streams_batches_after_header() {
+    let mut buf = Vec::new();
+    let mut sink = CsvSink::new(&mut buf);
+    sink.begin(&[make_column("id"), make_column("name")]).unwrap();
+    sink.write_batch(&[vec![CellValue::Text("1".into()), CellValue::Text("a".into())]])
+        .unwrap();
+    sink.write_batch(&[vec![CellValue::Text("2".into()), CellValue::Null]]).unwrap();
+    sink.finish().unwrap();
+
+    assert_eq!(String::from_utf8(buf).unwrap(), "id,name\r\n1,a\r\n2,\r\n");
+}
+
+#[test]
+fn native_values_are_written_as_plain_text() {
+    let result = make_result(
+        vec![make_column("id"), make_column("price"), make_column("ok"), make_column("at"), make_column("raw")],
+        vec![vec![
+            CellValue::Int64(7),
+            CellValue::Decimal { value: -1250, scale: 2 },
+            CellValue::Bool(true),
+            CellValue::Timestamp(86_400_000_000),
+            CellValue::Bytes(vec![0xDE, 0xAD]),
+        ]],
+    );
+
+    let mut buf = Vec::new();
+    write_csv_to_writer(&result, &mut buf).unwrap();
+
+    assert_eq!(
+        String::from_utf8(buf).unwrap(),
+        "id,price,ok,at,raw\r\n7,-12.50,true,1970-01-02T00:00:00,0xDEAD\r\n"
+    );
+}
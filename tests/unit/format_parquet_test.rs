@@ -6,8 +6,8 @@ use arrow::datatypes::DataType;
 use parquet::arrow::arrow_reader::ParquetRecordBatchReader;
 use parquet::file::reader::{FileReader as ParquetFileReader, SerializedFileReader};
 
-use dbtoon::backend::{CellValue, ColumnMeta, QueryResult};
-use dbtoon::format_parquet::write_parquet;
+use dbtoon::backend::{CellValue, ColumnMeta, QueryResult, ResultSink};
+use dbtoon::format_parquet::{ParquetSink, write_parquet};
 
 fn make_column(name: &str, type_name: &str) -> ColumnMeta {
     ColumnMeta {
@@ -23,6 +23,8 @@ fn make_result(columns: Vec<ColumnMeta>, rows: Vec<Vec<CellValue>>) -> QueryResu
         rows,
         total_rows,
         truncated: false,
+        messages: Vec::new(),
+        rows_affected: None,
     }
 }
 
@@ -243,3 +245,46 @@ fn non_truncated_parquet_has_no_dbtoon_metadata() {
 
     let _ = fs::remove_file(&path);
 }
+
+// --- Streaming sink ---
+
+#[test]
+fn sink_writes_batches_and_truncation_metadata() {
+    let path = temp_parquet_path("sink_batches");
+    let mut sink = ParquetSink::create(&path).unwrap();
+    sink.begin(&[make_column("id", "INT"), make_column("name", "VARCHAR(10)")]).unwrap();
+    for i in 0..3 {
+        sink.write_batch(&[
+            vec![CellValue::Int64(i), CellValue::Text("a".into())],
+            vec![CellValue::Null, CellValue::Null],
+        ])
+        .unwrap();
+    }
+    sink.finish(true, Some("Showing 6 rows.")).unwrap();
+
+    let file = fs::File::open(&path).unwrap();
+    let reader = ParquetRecordBatchReader::try_new(file, 1024).unwrap();
+    assert_eq!(reader.schema().field(0).data_type(), &DataType::Int64);
+    let total_rows: usize = reader.map(|b| b.unwrap().num_rows()).sum();
+    assert_eq!(total_rows, 6);
+
+    let meta = read_parquet_kv_metadata(&path);
+    assert_eq!(meta.get("dbtoon:truncated").map(String::as_str), Some("true"));
+    assert_eq!(meta.get("dbtoon:message").map(String::as_str), Some("Showing 6 rows."));
+
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn sink_with_no_batches_writes_declared_schema() {
+    let path = temp_parquet_path("sink_empty");
+    let mut sink = ParquetSink::create(&path).unwrap();
+    sink.begin(&[make_column("id", "BIGINT")]).unwrap();
+    sink.finish(false, None).unwrap();
+
+    let file = fs::File::open(&path).unwrap();
+    let reader = ParquetRecordBatchReader::try_new(file, 1024).unwrap();
+    assert_eq!(reader.schema().field(0).data_type(), &DataType::Int64);
+
+    let _ = fs::remove_file(&path);
+}
@@ -0,0 +1,44 @@
+use dbtoon::backend::{CellValue, ColumnMeta, QueryResult};
+use dbtoon::format_table::to_table;
+
+fn column(name: &str) -> ColumnMeta {
+    ColumnMeta {
+        name: name.to_string(),
+        type_name: "TEXT".to_string(),
+    }
+}
+
+#[test]
+fn aligns_columns_and_right_aligns_numbers() {
+    let result = QueryResult {
+        columns: vec![column("id"), column("name")],
+        rows: vec![
+            vec![CellValue::Int64(7), CellValue::Text("alice".to_string())],
+            vec![CellValue::Int64(1234), CellValue::Null],
+        ],
+        ..QueryResult::default()
+    };
+    assert_eq!(
+        to_table(&result),
+        "  id  | name\n------+-------\n    7 | alice\n 1234 | NULL\n(2 rows)\n"
+    );
+}
+
+#[test]
+fn escapes_line_breaks_and_counts_one_row() {
+    let result = QueryResult {
+        columns: vec![column("note")],
+        rows: vec![vec![CellValue::Text("a\nb\tc".to_string())]],
+        ..QueryResult::default()
+    };
+    assert_eq!(to_table(&result), "  note\n---------\n a\\nb\\tc\n(1 row)\n");
+}
+
+#[test]
+fn empty_result_keeps_the_header() {
+    let result = QueryResult {
+        columns: vec![column("n")],
+        ..QueryResult::default()
+    };
+    assert_eq!(to_table(&result), " n\n---\n(0 rows)\n");
+}
@@ -1,6 +1,8 @@
-use dbtoon::backend::sqlserver::normalize_odbc_type;
+use dbtoon::backend::odbc::{normalize_odbc_type, strip_driver_prefix};
 use dbtoon::backend::{CellValue, ColumnMeta, QueryResult};
-use dbtoon::format::to_toon;
+use dbtoon::backend::ResultSink;
+use dbtoon::backend::StreamSummary;
+use dbtoon::format::{ToonResultSet, ToonSink, to_toon, to_toon_results};
 use odbc_api::DataType;
 use std::num::NonZeroUsize;
 
@@ -42,6 +44,8 @@ fn test_3_column_2_row_result() {
         ],
         total_rows: None,
         truncated: false,
+        messages: Vec::new(),
+        rows_affected: None,
     };
 
     let decoded = round_trip(&result);
@@ -57,10 +61,10 @@ fn test_3_column_2_row_result() {
     let rows = obj.get("rows").expect("should have 'rows' key")
         .as_array().expect("rows should be an array");
     assert_eq!(rows.len(), 2);
-    assert_eq!(rows[0]["id"], "1");
+    assert_eq!(rows[0]["id"], 1);
     assert_eq!(rows[0]["name"], "Alice");
     assert_eq!(rows[0]["email"], "alice@co.com");
-    assert_eq!(rows[1]["id"], "2");
+    assert_eq!(rows[1]["id"], 2);
     assert_eq!(rows[1]["name"], "Bob");
     assert_eq!(rows[1]["email"], "bob@co.com");
 }
@@ -75,6 +79,8 @@ fn test_zero_row_result() {
         rows: vec![],
         total_rows: None,
         truncated: false,
+        messages: Vec::new(),
+        rows_affected: None,
     };
 
     let decoded = round_trip(&result);
@@ -102,6 +108,8 @@ fn test_null_cell_value() {
         ],
         total_rows: None,
         truncated: false,
+        messages: Vec::new(),
+        rows_affected: None,
     };
 
     let decoded = round_trip(&result);
@@ -129,6 +137,8 @@ fn test_single_column_single_row() {
         ],
         total_rows: None,
         truncated: false,
+        messages: Vec::new(),
+        rows_affected: None,
     };
 
     let decoded = round_trip(&result);
@@ -142,7 +152,7 @@ fn test_single_column_single_row() {
     let rows = obj.get("rows").expect("should have 'rows' key")
         .as_array().expect("rows should be an array");
     assert_eq!(rows.len(), 1);
-    assert_eq!(rows[0]["count"], "42");
+    assert_eq!(rows[0]["count"], 42);
 }
 
 // --- T002: Truncated TOON output contains truncated + message keys ---
@@ -159,6 +169,8 @@ fn test_truncated_toon_has_truncated_true_and_message() {
         ],
         total_rows: None,
         truncated: true,
+        messages: Vec::new(),
+        rows_affected: None,
     };
 
     let message = "Showing 2 rows. Use --no-limit to return all rows.";
@@ -188,6 +200,8 @@ fn test_truncated_toon_zero_rows_edge_case() {
         rows: vec![],
         total_rows: None,
         truncated: true,
+        messages: Vec::new(),
+        rows_affected: None,
     };
 
     let message = "Showing 0 rows. Use --no-limit to return all rows.";
@@ -214,6 +228,8 @@ fn test_non_truncated_toon_has_truncated_false_and_no_message() {
         ],
         total_rows: None,
         truncated: false,
+        messages: Vec::new(),
+        rows_affected: None,
     };
 
     let decoded = round_trip_with_truncation(&result, false, None);
@@ -245,6 +261,8 @@ fn test_toon_file_round_trip_with_truncation() {
         ],
         total_rows: None,
         truncated: true,
+        messages: Vec::new(),
+        rows_affected: None,
     };
 
     let message = "Showing 2 rows. Use --no-limit to return all rows.";
@@ -292,6 +310,8 @@ fn test_sqlserver_normalized_types_in_output() {
         ],
         total_rows: None,
         truncated: false,
+        messages: Vec::new(),
+        rows_affected: None,
     };
 
     let decoded = round_trip(&result);
@@ -327,6 +347,8 @@ fn test_databricks_passthrough_types_in_output() {
         ],
         total_rows: None,
         truncated: false,
+        messages: Vec::new(),
+        rows_affected: None,
     };
 
     let decoded = round_trip(&result);
@@ -414,3 +436,326 @@ fn test_normalize_unknown_fallback() {
         "UNKNOWN"
     );
 }
+
+// --- Streaming ToonSink ---
+
+fn stream_toon(result: &QueryResult, batch_size: usize, truncated: bool, message: Option<&str>) -> String {
+    let mut buf = Vec::new();
+    let mut sink = ToonSink::new(&mut buf);
+    sink.begin(&result.columns).unwrap();
+    for batch in result.rows.chunks(batch_size) {
+        sink.write_batch(batch).unwrap();
+    }
+    let summary = StreamSummary { rows: result.rows.len(), truncated, ..StreamSummary::default() };
+    assert_eq!(summary.truncation_message().as_deref(), message);
+    sink.finish(&summary).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+#[test]
+fn toon_sink_matches_buffered_output_across_batches() {
+    let result = QueryResult {
+        columns: vec![
+            ColumnMeta { name: "id".to_string(), type_name: "INT".to_string() },
+            ColumnMeta { name: "full name".to_string(), type_name: "VARCHAR(50)".to_string() },
+        ],
+        rows: (0..7)
+            .map(|i| vec![
+                CellValue::Text(i.to_string()),
+                if i % 3 == 0 { CellValue::Null } else { CellValue::Text(format!("name, {i}")) },
+            ])
+            .collect(),
+        total_rows: None,
+        truncated: false,
+        messages: Vec::new(),
+        rows_affected: None,
+    };
+
+    let message = "Showing 7 rows. Use --no-limit to return all rows.";
+    assert_eq!(stream_toon(&result, 3, false, None), to_toon(&result, false, None).unwrap());
+    assert_eq!(
+        stream_toon(&result, 2, true, Some(message)),
+        to_toon(&result, true, Some(message)).unwrap()
+    );
+}
+
+#[test]
+fn toon_sink_matches_buffered_output_for_empty_result() {
+    let result = QueryResult {
+        columns: vec![ColumnMeta { name: "id".to_string(), type_name: "INT".to_string() }],
+        rows: vec![],
+        total_rows: None,
+        truncated: false,
+        messages: Vec::new(),
+        rows_affected: None,
+    };
+
+    assert_eq!(stream_toon(&result, 10, false, None), to_toon(&result, false, None).unwrap());
+}
+
+#[test]
+fn test_native_values_emit_json_numbers_and_booleans() {
+    let result = QueryResult {
+        columns: vec![
+            ColumnMeta { name: "id".to_string(), type_name: "BIGINT".to_string() },
+            ColumnMeta { name: "price".to_string(), type_name: "DECIMAL(10,2)".to_string() },
+            ColumnMeta { name: "ratio".to_string(), type_name: "FLOAT".to_string() },
+            ColumnMeta { name: "active".to_string(), type_name: "BIT".to_string() },
+            ColumnMeta { name: "day".to_string(), type_name: "DATE".to_string() },
+        ],
+        rows: vec![vec![
+            CellValue::Int64(42),
+            CellValue::Decimal { value: 1250, scale: 2 },
+            CellValue::Float64(0.25),
+            CellValue::Bool(true),
+            CellValue::Date(19783),
+        ]],
+        total_rows: None,
+        truncated: false,
+        messages: Vec::new(),
+        rows_affected: None,
+    };
+
+    let decoded = round_trip(&result);
+    let row = &decoded["rows"][0];
+    assert_eq!(row["id"], 42);
+    assert_eq!(row["price"], 12.5);
+    assert_eq!(row["ratio"], 0.25);
+    assert_eq!(row["active"], true);
+    assert_eq!(row["day"], "2024-03-01");
+}
+
+#[test]
+fn test_high_precision_decimal_stays_string() {
+    let result = QueryResult {
+        columns: vec![ColumnMeta { name: "amount".to_string(), type_name: "DECIMAL(38,2)".to_string() }],
+        rows: vec![vec![CellValue::Decimal { value: 12345678901234567890, scale: 2 }]],
+        total_rows: None,
+        truncated: false,
+        messages: Vec::new(),
+        rows_affected: None,
+    };
+
+    let decoded = round_trip(&result);
+    assert_eq!(decoded["rows"][0]["amount"], "123456789012345678.90");
+}
+
+#[test]
+fn test_text_values_follow_column_type() {
+    let columns = ["INT", "BIGINT", "DECIMAL(10,2)", "FLOAT", "BIT", "BOOLEAN", "VARCHAR(10)"];
+    let result = QueryResult {
+        columns: columns
+            .iter()
+            .enumerate()
+            .map(|(i, t)| ColumnMeta { name: format!("c{i}"), type_name: t.to_string() })
+            .collect(),
+        rows: vec![
+            ["7", "-9000000000", "12.50", "2.5", "1", "false", "42"]
+                .iter()
+                .map(|v| CellValue::Text(v.to_string()))
+                .collect(),
+        ],
+        total_rows: None,
+        truncated: false,
+        messages: Vec::new(),
+        rows_affected: None,
+    };
+
+    let decoded = round_trip(&result);
+    let row = &decoded["rows"][0];
+    assert_eq!(row["c0"], 7);
+    assert_eq!(row["c1"], -9000000000i64);
+    assert_eq!(row["c2"], 12.5);
+    assert_eq!(row["c3"], 2.5);
+    assert_eq!(row["c4"], true);
+    assert_eq!(row["c5"], false);
+    assert_eq!(row["c6"], "42", "character columns stay strings");
+}
+
+#[test]
+fn test_unparseable_and_wide_text_values_stay_strings() {
+    let result = QueryResult {
+        columns: vec![
+            ColumnMeta { name: "n".to_string(), type_name: "INT".to_string() },
+            ColumnMeta { name: "amount".to_string(), type_name: "DECIMAL(38,4)".to_string() },
+            ColumnMeta { name: "f".to_string(), type_name: "FLOAT".to_string() },
+        ],
+        rows: vec![vec![
+            CellValue::Text("n/a".to_string()),
+            CellValue::Text("12345678901234567.8901".to_string()),
+            CellValue::Text("1e400".to_string()),
+        ]],
+        total_rows: None,
+        truncated: false,
+        messages: Vec::new(),
+        rows_affected: None,
+    };
+
+    let decoded = round_trip(&result);
+    let row = &decoded["rows"][0];
+    assert_eq!(row["n"], "n/a");
+    assert_eq!(row["amount"], "12345678901234567.8901");
+    assert_eq!(row["f"], "1e400");
+}
+
+// --- Multiple result sets ---
+
+fn two_result_sets() -> (QueryResult, QueryResult) {
+    let first = QueryResult {
+        columns: vec![
+            ColumnMeta { name: "id".to_string(), type_name: "INT".to_string() },
+            ColumnMeta { name: "full name".to_string(), type_name: "VARCHAR(20)".to_string() },
+        ],
+        rows: (1..=3)
+            .map(|i| vec![CellValue::Int64(i), CellValue::Text(format!("name, {i}"))])
+            .collect(),
+        total_rows: None,
+        truncated: false,
+        messages: Vec::new(),
+        rows_affected: None,
+    };
+    let second = QueryResult {
+        columns: vec![ColumnMeta { name: "n".to_string(), type_name: "INT".to_string() }],
+        rows: vec![vec![CellValue::Int64(9)]],
+        total_rows: None,
+        truncated: false,
+        messages: Vec::new(),
+        rows_affected: None,
+    };
+    (first, second)
+}
+
+#[test]
+fn test_single_result_set_renders_like_to_toon() {
+    let (first, _) = two_result_sets();
+    let sets = [ToonResultSet { result: &first, truncated: false, message: None }];
+    assert_eq!(to_toon_results(&sets).unwrap(), to_toon(&first, false, None).unwrap());
+}
+
+#[test]
+fn test_multiple_result_sets_render_results_array() {
+    let (first, second) = two_result_sets();
+    let empty = QueryResult {
+        columns: vec![ColumnMeta { name: "x".to_string(), type_name: "BIT".to_string() }],
+        ..QueryResult::default()
+    };
+    let sets = [
+        ToonResultSet { result: &first, truncated: true, message: Some("Showing 3 rows.") },
+        ToonResultSet { result: &empty, truncated: false, message: None },
+        ToonResultSet { result: &second, truncated: false, message: None },
+    ];
+
+    let toon = to_toon_results(&sets).unwrap();
+    let decoded: serde_json::Value = toon_format::decode_no_coerce(&toon).unwrap();
+
+    let results = decoded["results"].as_array().expect("results array");
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0]["truncated"], true);
+    assert_eq!(results[0]["message"], "Showing 3 rows.");
+    assert_eq!(results[0]["types"][1], "VARCHAR(20)");
+    assert_eq!(results[0]["rows"][2]["full name"], "name, 3");
+    assert_eq!(results[1]["rows"].as_array().unwrap().len(), 0);
+    assert_eq!(results[2]["rows"][0]["n"], 9);
+    assert!(results[2].get("message").is_none());
+}
+
+#[test]
+fn toon_sink_matches_buffered_output_for_multiple_result_sets() {
+    let (first, second) = two_result_sets();
+
+    let mut buf = Vec::new();
+    let mut sink = ToonSink::new(&mut buf);
+    sink.begin(&first.columns).unwrap();
+    for batch in first.rows.chunks(2) {
+        sink.write_batch(batch).unwrap();
+    }
+    let first_summary = StreamSummary { rows: 3, truncated: true, ..StreamSummary::default() };
+    sink.next_result(first_summary.clone()).unwrap();
+    sink.begin(&second.columns).unwrap();
+    sink.write_batch(&second.rows).unwrap();
+    assert_eq!(sink.earlier_results().len(), 1);
+    sink.finish(&StreamSummary { rows: 1, ..StreamSummary::default() }).unwrap();
+
+    let message = first_summary.truncation_message().unwrap();
+    let expected = to_toon_results(&[
+        ToonResultSet { result: &first, truncated: true, message: Some(&message) },
+        ToonResultSet { result: &second, truncated: false, message: None },
+    ])
+    .unwrap();
+    assert_eq!(String::from_utf8(buf).unwrap(), expected);
+}
+
+// --- Server messages and rows affected ---
+
+#[test]
+fn test_statement_without_result_set_reports_messages_and_rows_affected() {
+    let result = QueryResult {
+        messages: vec!["Hello from PRINT".to_string(), "Warning: null value, eliminated".to_string()],
+        rows_affected: Some(2),
+        ..QueryResult::default()
+    };
+
+    let toon = to_toon(&result, false, None).unwrap();
+    assert_eq!(
+        toon,
+        "types[0]:\nrows[0]:\ntruncated: false\n\
+         messages[2]: Hello from PRINT,\"Warning: null value, eliminated\"\nrows_affected: 2"
+    );
+}
+
+#[test]
+fn test_server_info_keys_absent_when_not_reported() {
+    let (first, _) = two_result_sets();
+    let decoded = round_trip(&first);
+    assert!(decoded.get("messages").is_none());
+    assert!(decoded.get("rows_affected").is_none());
+}
+
+#[test]
+fn toon_sink_matches_buffered_output_with_server_messages() {
+    let (mut first, mut second) = two_result_sets();
+    first.messages = vec!["starting".to_string()];
+    first.rows_affected = Some(4);
+    second.messages = vec!["done".to_string()];
+
+    let mut buf = Vec::new();
+    let mut sink = ToonSink::new(&mut buf);
+    sink.begin(&first.columns).unwrap();
+    sink.write_batch(&first.rows).unwrap();
+    sink.next_result(StreamSummary {
+        rows: 3,
+        messages: first.messages.clone(),
+        rows_affected: Some(4),
+        ..StreamSummary::default()
+    })
+    .unwrap();
+    sink.begin(&second.columns).unwrap();
+    sink.write_batch(&second.rows).unwrap();
+    sink.finish(&StreamSummary { rows: 1, messages: second.messages.clone(), ..StreamSummary::default() })
+        .unwrap();
+
+    let expected = to_toon_results(&[
+        ToonResultSet { result: &first, truncated: false, message: None },
+        ToonResultSet { result: &second, truncated: false, message: None },
+    ])
+    .unwrap();
+    let toon = String::from_utf8(buf).unwrap();
+    assert_eq!(toon, expected);
+
+    let decoded: serde_json::Value = toon_format::decode_no_coerce(&toon).unwrap();
+    assert_eq!(decoded["results"][0]["messages"][0], "starting");
+    assert_eq!(decoded["results"][0]["rows_affected"], 4);
+    assert_eq!(decoded["results"][0]["rows"][1]["id"], 2);
+    assert_eq!(decoded["results"][1]["messages"][0], "done");
+    assert!(decoded["results"][1].get("rows_affected").is_none());
+}
+
+#[test]
+fn test_strip_driver_prefix() {
+    assert_eq!(
+        strip_driver_prefix("[Microsoft][ODBC Driver 18 for SQL Server][SQL Server]Hello"),
+        "Hello"
+    );
+    assert_eq!(strip_driver_prefix("no prefix [here]"), "no prefix [here]");
+    assert_eq!(strip_driver_prefix("[unterminated"), "[unterminated");
+}
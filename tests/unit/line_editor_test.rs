@@ -0,0 +1,18 @@
+use dbtoon::line_editor::LineEditor;
+
+#[test]
+fn persists_history_without_blanks_or_repeats() {
+    let path = std::env::temp_dir().join(format!("dbtoon_test_line_editor_{}_history", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut editor = LineEditor::new(Some(path.clone())).unwrap();
+    editor.add_history("SELECT 1;");
+    editor.add_history("SELECT 1;");
+    editor.add_history("   ");
+    editor.add_history("SELECT 2;");
+    assert_eq!(editor.history(), ["SELECT 1;", "SELECT 2;"]);
+
+    let reloaded = LineEditor::new(Some(path.clone())).unwrap();
+    assert_eq!(reloaded.history(), ["SELECT 1;", "SELECT 2;"]);
+    let _ = std::fs::remove_file(&path);
+}
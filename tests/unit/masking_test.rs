@@ -1,4 +1,4 @@
-use dbtoon::masking::{format_optional_secret, format_secret};
+use dbtoon::masking::{format_optional_secret, format_secret, mask_connection_string};
 use secrecy::SecretString;
 
 #[test]
@@ -42,3 +42,17 @@ fn test_optional_secret_some_exposed() {
     let output = format_optional_secret(Some(&secret), true);
     assert_eq!(output, "token123");
 }
+
+#[test]
+fn test_connection_string_credentials_masked() {
+    let masked = mask_connection_string("Driver={PostgreSQL Unicode};Server=db;UID=app;PWD={p;w}}d};SSLMode=require;");
+    assert_eq!(masked, "Driver={PostgreSQL Unicode};Server=db;UID=app;PWD=****;SSLMode=require;");
+}
+
+#[test]
+fn test_connection_string_masks_token_like_keys() {
+    let masked = mask_connection_string("DSN=snow;authenticator=oauth;token=abc123;Password=x");
+    assert!(!masked.contains("abc123"), "{masked}");
+    assert!(masked.ends_with("Password=****"), "{masked}");
+    assert!(masked.contains("authenticator=oauth"), "{masked}");
+}
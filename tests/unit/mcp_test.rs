@@ -0,0 +1,244 @@
+use std::path::PathBuf;
+
+use dbtoon::mcp::McpServer;
+use serde_json::{Value, json};
+
+use super::mock_http::{MockResponse, MockServer};
+
+fn write_temp_config(name: &str, content: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join("dbtoon-mcp-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(format!("config-{}-{}.toml", std::process::id(), name));
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+fn databricks_config(name: &str, server: &MockServer, defaults: &str) -> PathBuf {
+    write_temp_config(
+        name,
+        &format!(
+            "[defaults]\n{}\n\n[profiles.lake]\nbackend = \"databricks\"\nhost = \"{}\"\ntoken = \"test-token\"\nwarehouse_id = \"wh-1\"\n",
+            defaults,
+            server.url()
+        ),
+    )
+}
+
+async fn request(server: &McpServer, method: &str, params: Value) -> Value {
+    let line = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }).to_string();
+    server.handle_message(&line).await.expect("request should get a response")
+}
+
+async fn call_tool(server: &McpServer, name: &str, arguments: Value) -> (String, bool) {
+    let response = request(server, "tools/call", json!({ "name": name, "arguments": arguments })).await;
+    let result = &response["result"];
+    let text = result["content"][0]["text"].as_str().unwrap().to_string();
+    (text, result["isError"].as_bool().unwrap())
+}
+
+fn serve_statement(server: &MockServer) {
+    server.on(
+        "POST",
+        "/api/2.0/sql/statements/",
+        MockResponse::json(
+            200,
+            r#"{"statement_id":"st-1","status":{"state":"SUCCEEDED"},
+            "manifest":{"schema":{"columns":[{"name":"n","type_name":"INT"}]},"total_row_count":2,"total_chunk_count":1},
+            "result":{"data_array":[["1"],["2"]]}}"#,
+        ),
+    );
+}
+
+#[tokio::test]
+async fn initialize_reports_tools_capability() {
+    let server = McpServer::new(None, false);
+
+    let response = request(&server, "initialize", json!({ "protocolVersion": "2025-03-26" })).await;
+
+    assert_eq!(response["id"], 1);
+    assert_eq!(response["result"]["protocolVersion"], "2025-03-26");
+    assert!(response["result"]["capabilities"]["tools"].is_object());
+    assert_eq!(response["result"]["serverInfo"]["name"], "dbtoon");
+}
+
+#[tokio::test]
+async fn initialize_falls_back_to_supported_version() {
+    let server = McpServer::new(None, false);
+
+    let response = request(&server, "initialize", json!({ "protocolVersion": "1999-01-01" })).await;
+
+    assert_eq!(response["result"]["protocolVersion"], "2025-06-18");
+}
+
+#[tokio::test]
+async fn notifications_get_no_response() {
+    let server = McpServer::new(None, false);
+
+    let line = r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#;
+
+    assert!(server.handle_message(line).await.is_none());
+}
+
+#[tokio::test]
+async fn tools_list_names_every_tool() {
+    let server = McpServer::new(None, false);
+
+    let response = request(&server, "tools/list", json!({})).await;
+
+    let names: Vec<&str> = response["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["query", "list_profiles", "list_warehouses"]);
+    let query_props = &response["result"]["tools"][0]["inputSchema"]["properties"];
+    assert!(query_props.get("allow_write").is_none());
+}
+
+#[tokio::test]
+async fn unknown_method_is_a_protocol_error() {
+    let server = McpServer::new(None, false);
+
+    let response = request(&server, "resources/list", json!({})).await;
+
+    assert_eq!(response["error"]["code"], -32601);
+}
+
+#[tokio::test]
+async fn malformed_json_is_a_parse_error() {
+    let server = McpServer::new(None, false);
+
+    let response = server.handle_message("{not json").await.unwrap();
+
+    assert_eq!(response["error"]["code"], -32700);
+    assert_eq!(response["id"], Value::Null);
+}
+
+#[tokio::test]
+async fn unknown_tool_is_invalid_params() {
+    let server = McpServer::new(None, false);
+
+    let response = request(&server, "tools/call", json!({ "name": "drop_table" })).await;
+
+    assert_eq!(response["error"]["code"], -32602);
+}
+
+#[tokio::test]
+async fn list_profiles_returns_names_and_backends() {
+    let path = write_temp_config(
+        "profiles",
+        "[defaults]\n\n[profiles.prod]\nbackend = \"sqlserver\"\nserver = \"db\"\n\n[profiles.lake]\nbackend = \"databricks\"\n",
+    );
+    let server = McpServer::new(Some(path), false);
+
+    let (text, is_error) = call_tool(&server, "list_profiles", json!({})).await;
+
+    assert!(!is_error, "{text}");
+    assert!(text.contains("prod,sqlserver"), "{text}");
+    assert!(text.contains("lake,databricks"), "{text}");
+}
+
+#[tokio::test]
+async fn query_returns_toon_rows() {
+    let mock = MockServer::start();
+    serve_statement(&mock);
+    let server = McpServer::new(Some(databricks_config("query", &mock, "")), false);
+
+    let (text, is_error) = call_tool(
+        &server,
+        "query",
+        json!({ "profile": "lake", "sql": "SELECT n FROM t WHERE n > :min", "params": ["min=0:INT"] }),
+    )
+    .await;
+
+    assert!(!is_error, "{text}");
+    let decoded: Value = toon_format::decode_no_coerce(&text).unwrap();
+    assert_eq!(decoded["rows"], json!([{ "n": 1 }, { "n": 2 }]));
+    assert_eq!(decoded["truncated"], false);
+
+    let requests = mock.requests_to("/api/2.0/sql/statements/");
+    assert!(requests[0].body.contains(r#""name":"min""#), "{}", requests[0].body);
+}
+
+#[tokio::test]
+async fn query_rejects_writes_without_config_opt_in() {
+    let mock = MockServer::start();
+    serve_statement(&mock);
+    let server = McpServer::new(Some(databricks_config("readonly", &mock, "")), false);
+
+    let (text, is_error) =
+        call_tool(&server, "query", json!({ "profile": "lake", "sql": "DELETE FROM t" })).await;
+
+    assert!(is_error);
+    assert!(text.starts_with("error: validation:"), "{text}");
+    assert!(mock.requests_to("/api/2.0/sql/statements/").is_empty());
+}
+
+#[tokio::test]
+async fn query_allows_writes_when_config_allows() {
+    let mock = MockServer::start();
+    serve_statement(&mock);
+    let path = databricks_config("allow-write", &mock, "allow_write = true");
+    let server = McpServer::new(Some(path), false);
+
+    let (text, is_error) =
+        call_tool(&server, "query", json!({ "profile": "lake", "sql": "DELETE FROM t" })).await;
+
+    assert!(!is_error, "{text}");
+    assert_eq!(mock.requests_to("/api/2.0/sql/statements/").len(), 1);
+}
+
+#[tokio::test]
+async fn query_missing_sql_is_a_tool_error() {
+    let server = McpServer::new(None, false);
+
+    let (text, is_error) = call_tool(&server, "query", json!({ "profile": "lake" })).await;
+
+    assert!(is_error);
+    assert!(text.contains("missing required argument 'sql'"), "{text}");
+}
+
+#[tokio::test]
+async fn list_warehouses_returns_toon_table() {
+    let mock = MockServer::start();
+    mock.on(
+        "GET",
+        "/api/2.0/sql/warehouses/",
+        MockResponse::json(
+            200,
+            r#"{"warehouses":[{"id":"wh-1","name":"Shared","state":"RUNNING","cluster_size":"Small"}]}"#,
+        ),
+    );
+    let server = McpServer::new(Some(databricks_config("warehouses", &mock, "")), false);
+
+    let (text, is_error) = call_tool(&server, "list_warehouses", json!({ "profile": "lake" })).await;
+
+    assert!(!is_error, "{text}");
+    assert!(text.contains("\"wh-1\",Shared,RUNNING,Small,null"), "{text}");
+}
+
+#[tokio::test]
+async fn serve_answers_each_request_line() {
+    let server = McpServer::new(None, false);
+    let input = concat!(
+        r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#,
+        "\n",
+        r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#,
+        "\n\n",
+        r#"{"jsonrpc":"2.0","id":2,"method":"tools/list"}"#,
+        "\n",
+    );
+    let mut output = Vec::new();
+
+    server.serve(input.as_bytes(), &mut output).await.unwrap();
+
+    let lines: Vec<Value> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0], json!({ "jsonrpc": "2.0", "id": 1, "result": {} }));
+    assert_eq!(lines[1]["id"], 2);
+}
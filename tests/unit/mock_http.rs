@@ -0,0 +1,175 @@
+//! Minimal HTTP/1.1 stand-in for backend tests.
+//!
+//! Serves canned responses by method and path from a background thread and
+//! records every request it receives. Each route holds a queue of responses;
+//! the last one repeats once the queue is drained.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl MockResponse {
+    pub fn json(status: u16, body: &str) -> Self {
+        Self {
+            status,
+            headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    pub fn bytes(status: u16, content_type: &str, body: Vec<u8>) -> Self {
+        Self {
+            status,
+            headers: vec![("Content-Type".to_string(), content_type.to_string())],
+            body,
+        }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    /// The query string, without the leading `?`.
+    pub query: String,
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+type Routes = Arc<Mutex<HashMap<(String, String), Vec<MockResponse>>>>;
+
+pub struct MockServer {
+    addr: String,
+    routes: Routes,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+impl MockServer {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let routes: Routes = Arc::new(Mutex::new(HashMap::new()));
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let (r, q) = (routes.clone(), requests.clone());
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let (r, q) = (r.clone(), q.clone());
+                thread::spawn(move || handle(stream, r, q));
+            }
+        });
+
+        Self { addr, routes, requests }
+    }
+
+    /// Base URL including scheme, usable as a backend `host`.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Queue a response for `method` + `path` (path excludes the query string).
+    pub fn on(&self, method: &str, path: &str, response: MockResponse) -> &Self {
+        self.routes
+            .lock()
+            .unwrap()
+            .entry((method.to_string(), path.to_string()))
+            .or_default()
+            .push(response);
+        self
+    }
+
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    pub fn requests_to(&self, path: &str) -> Vec<RecordedRequest> {
+        self.requests().into_iter().filter(|r| r.path == path).collect()
+    }
+}
+
+fn handle(stream: TcpStream, routes: Routes, requests: Arc<Mutex<Vec<RecordedRequest>>>) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut stream = stream;
+
+    loop {
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line).unwrap_or(0) == 0 {
+            return;
+        }
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().unwrap_or_default().to_string();
+        let target = parts.next().unwrap_or_default().to_string();
+        let (path, query) = match target.split_once('?') {
+            Some((path, query)) => (path.to_string(), query.to_string()),
+            None => (target, String::new()),
+        };
+
+        let mut headers = HashMap::new();
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                return;
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((k, v)) = line.split_once(':') {
+                headers.insert(k.trim().to_lowercase(), v.trim().to_string());
+            }
+        }
+
+        let len: usize = headers
+            .get("content-length")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        let mut body = vec![0u8; len];
+        if reader.read_exact(&mut body).is_err() {
+            return;
+        }
+
+        requests.lock().unwrap().push(RecordedRequest {
+            method: method.clone(),
+            path: path.clone(),
+            query,
+            headers,
+            body: String::from_utf8_lossy(&body).to_string(),
+        });
+
+        let response = {
+            let mut routes = routes.lock().unwrap();
+            match routes.get_mut(&(method, path)) {
+                Some(queue) if queue.len() > 1 => queue.remove(0),
+                Some(queue) if !queue.is_empty() => queue[0].clone(),
+                _ => MockResponse::json(404, r#"{"message":"no route"}"#),
+            }
+        };
+
+        let mut head = format!(
+            "HTTP/1.1 {} MOCK\r\nContent-Length: {}\r\n",
+            response.status,
+            response.body.len()
+        );
+        for (name, value) in &response.headers {
+            head.push_str(&format!("{name}: {value}\r\n"));
+        }
+        head.push_str("\r\n");
+        if stream.write_all(head.as_bytes()).is_err() || stream.write_all(&response.body).is_err() {
+            return;
+        }
+    }
+}
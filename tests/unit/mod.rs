@@ -1,13 +1,37 @@
 mod cli_test;
+mod bigquery_test;
+mod clickhouse_test;
 mod config_test;
+#[cfg(unix)]
+mod daemon_test;
+mod databricks_test;
 mod format_arrow_test;
 mod init_test;
+mod line_editor_test;
 mod profile_test;
+mod retry_test;
+mod shell_test;
+mod sqlite_test;
+mod snowflake_test;
+mod sqlserver_test;
+mod trino_test;
 mod format_columnar_test;
 mod format_csv_test;
 mod format_detect_test;
 mod format_parquet_test;
+mod format_table_test;
 mod format_test;
 mod masking_test;
+mod mcp_test;
+mod mock_http;
+mod mysql_test;
+mod oauth_test;
 mod output_test;
+mod params_test;
+mod postgres_test;
 mod validation_test;
+mod value_test;
+
+/// Held by tests that close every cached connection, and by those that rely on
+/// a cached connection surviving between statements.
+pub(crate) static CONNECTION_CACHE: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
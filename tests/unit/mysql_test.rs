@@ -0,0 +1,223 @@
+use mysql_async::consts::{ColumnFlags, ColumnType};
+use mysql_async::{Column, Value};
+
+use dbtoon::backend::mysql::{MySqlBackend, bind_value, decode_value, normalize_mysql_type};
+use dbtoon::backend::{Backend, CellValue};
+use dbtoon::config::MySqlSslMode;
+use dbtoon::params::QueryParam;
+
+/// utf8mb4_0900_ai_ci, MySQL 8's default collation.
+const UTF8MB4: u16 = 255;
+
+fn column(ty: ColumnType) -> Column {
+    Column::new(ty).with_character_set(UTF8MB4)
+}
+
+fn unsigned(ty: ColumnType) -> Column {
+    column(ty).with_flags(ColumnFlags::UNSIGNED_FLAG)
+}
+
+fn binary(ty: ColumnType) -> Column {
+    Column::new(ty).with_character_set(63).with_flags(ColumnFlags::BINARY_FLAG)
+}
+
+fn text(column: &Column, value: &str) -> CellValue {
+    decode_value(column, Value::Bytes(value.as_bytes().to_vec()))
+}
+
+#[test]
+fn normalizes_types_for_columnar_output() {
+    assert_eq!(normalize_mysql_type(&column(ColumnType::MYSQL_TYPE_LONG)), "INT");
+    assert_eq!(normalize_mysql_type(&unsigned(ColumnType::MYSQL_TYPE_LONG)), "BIGINT");
+    assert_eq!(normalize_mysql_type(&column(ColumnType::MYSQL_TYPE_TINY)), "SMALLINT");
+    assert_eq!(normalize_mysql_type(&unsigned(ColumnType::MYSQL_TYPE_TINY)), "TINYINT");
+    assert_eq!(normalize_mysql_type(&column(ColumnType::MYSQL_TYPE_LONGLONG)), "BIGINT");
+    assert_eq!(normalize_mysql_type(&unsigned(ColumnType::MYSQL_TYPE_LONGLONG)), "DECIMAL(20,0)");
+    assert_eq!(normalize_mysql_type(&column(ColumnType::MYSQL_TYPE_DOUBLE)), "DOUBLE");
+    assert_eq!(normalize_mysql_type(&column(ColumnType::MYSQL_TYPE_DATETIME)), "TIMESTAMP");
+    assert_eq!(normalize_mysql_type(&column(ColumnType::MYSQL_TYPE_VAR_STRING)), "VARCHAR");
+    assert_eq!(normalize_mysql_type(&column(ColumnType::MYSQL_TYPE_BLOB)), "VARCHAR");
+    assert_eq!(normalize_mysql_type(&binary(ColumnType::MYSQL_TYPE_BLOB)), "VARBINARY");
+    assert_eq!(normalize_mysql_type(&binary(ColumnType::MYSQL_TYPE_JSON)), "JSON");
+    assert_eq!(normalize_mysql_type(&binary(ColumnType::MYSQL_TYPE_GEOMETRY)), "GEOMETRY");
+}
+
+#[test]
+fn normalizes_decimal_precision_and_bits() {
+    // DECIMAL(12,2) has a display length of 12 digits, a point and a sign
+    let decimal = column(ColumnType::MYSQL_TYPE_NEWDECIMAL).with_column_length(14).with_decimals(2);
+    assert_eq!(normalize_mysql_type(&decimal), "DECIMAL(12,2)");
+    let whole = unsigned(ColumnType::MYSQL_TYPE_NEWDECIMAL).with_column_length(10);
+    assert_eq!(normalize_mysql_type(&whole), "DECIMAL(10,0)");
+
+    let flag = binary(ColumnType::MYSQL_TYPE_BIT).with_column_length(1);
+    assert_eq!(normalize_mysql_type(&flag), "BOOLEAN");
+    let mask = binary(ColumnType::MYSQL_TYPE_BIT).with_column_length(16);
+    assert_eq!(normalize_mysql_type(&mask), "BIGINT");
+}
+
+#[test]
+fn decodes_text_protocol_values_by_column_type() {
+    assert_eq!(text(&column(ColumnType::MYSQL_TYPE_LONG), "-42"), CellValue::Int64(-42));
+    assert_eq!(
+        text(&unsigned(ColumnType::MYSQL_TYPE_LONGLONG), "18446744073709551615"),
+        CellValue::Decimal { value: 18_446_744_073_709_551_615, scale: 0 }
+    );
+    assert_eq!(
+        text(&column(ColumnType::MYSQL_TYPE_NEWDECIMAL), "-12.50"),
+        CellValue::Decimal { value: -1250, scale: 2 }
+    );
+    assert_eq!(text(&column(ColumnType::MYSQL_TYPE_DATE), "1970-01-02"), CellValue::Date(1));
+    assert_eq!(
+        text(&column(ColumnType::MYSQL_TYPE_DATETIME), "2000-01-01 00:00:01.5"),
+        CellValue::Timestamp(946_684_801_500_000)
+    );
+    assert_eq!(text(&column(ColumnType::MYSQL_TYPE_TIME), "12:30:00"), CellValue::Time(45_000_000_000));
+    assert_eq!(text(&column(ColumnType::MYSQL_TYPE_VAR_STRING), "héllo"), CellValue::Text("héllo".to_string()));
+}
+
+#[test]
+fn out_of_range_dates_and_times_stay_text() {
+    assert_eq!(
+        text(&column(ColumnType::MYSQL_TYPE_DATE), "0000-00-00"),
+        CellValue::Text("0000-00-00".to_string())
+    );
+    assert_eq!(
+        text(&column(ColumnType::MYSQL_TYPE_TIME), "-838:59:59"),
+        CellValue::Text("-838:59:59".to_string())
+    );
+    assert_eq!(
+        text(&column(ColumnType::MYSQL_TYPE_TIME), "100:00:00"),
+        CellValue::Text("100:00:00".to_string())
+    );
+    assert_eq!(
+        decode_value(&column(ColumnType::MYSQL_TYPE_TIME), Value::Time(true, 1, 2, 0, 0, 0)),
+        CellValue::Text("-26:00:00".to_string())
+    );
+}
+
+#[test]
+fn decodes_binary_protocol_values() {
+    assert_eq!(decode_value(&column(ColumnType::MYSQL_TYPE_LONG), Value::Int(7)), CellValue::Int64(7));
+    assert_eq!(
+        decode_value(&unsigned(ColumnType::MYSQL_TYPE_LONGLONG), Value::UInt(7)),
+        CellValue::Decimal { value: 7, scale: 0 }
+    );
+    assert_eq!(decode_value(&column(ColumnType::MYSQL_TYPE_FLOAT), Value::Float(0.1)), CellValue::Float64(0.1));
+    assert_eq!(
+        decode_value(&column(ColumnType::MYSQL_TYPE_DATE), Value::Date(2000, 1, 1, 0, 0, 0, 0)),
+        CellValue::Date(10_957)
+    );
+    assert_eq!(
+        decode_value(&column(ColumnType::MYSQL_TYPE_TIMESTAMP), Value::Date(1970, 1, 1, 0, 0, 1, 250)),
+        CellValue::Timestamp(1_000_250)
+    );
+    assert_eq!(
+        decode_value(&column(ColumnType::MYSQL_TYPE_TIME), Value::Time(false, 0, 1, 0, 0, 0)),
+        CellValue::Time(3_600_000_000)
+    );
+    assert_eq!(decode_value(&column(ColumnType::MYSQL_TYPE_LONG), Value::NULL), CellValue::Null);
+}
+
+#[test]
+fn decodes_bits_blobs_and_json() {
+    let flag = binary(ColumnType::MYSQL_TYPE_BIT).with_column_length(1);
+    assert_eq!(decode_value(&flag, Value::Bytes(vec![1])), CellValue::Bool(true));
+    let mask = binary(ColumnType::MYSQL_TYPE_BIT).with_column_length(16);
+    assert_eq!(decode_value(&mask, Value::Bytes(vec![1, 2])), CellValue::Int64(258));
+
+    assert_eq!(
+        decode_value(&binary(ColumnType::MYSQL_TYPE_BLOB), Value::Bytes(vec![0, 0xff])),
+        CellValue::Bytes(vec![0, 0xff])
+    );
+    assert_eq!(
+        text(&binary(ColumnType::MYSQL_TYPE_JSON), r#"{"a": 1}"#),
+        CellValue::Text(r#"{"a": 1}"#.to_string())
+    );
+}
+
+#[test]
+fn binds_typed_parameters_natively() {
+    let param = |value: &str, type_name: Option<&str>| QueryParam {
+        name: "p".to_string(),
+        value: value.to_string(),
+        type_name: type_name.map(str::to_string),
+    };
+    assert_eq!(bind_value(&param("10", Some("INT"))), Value::Int(10));
+    assert_eq!(bind_value(&param("true", Some("BOOLEAN"))), Value::Int(1));
+    assert_eq!(bind_value(&param("1.5", Some("DOUBLE"))), Value::Double(1.5));
+    assert_eq!(bind_value(&param("10", None)), Value::Bytes(b"10".to_vec()));
+    assert_eq!(bind_value(&param("2024-01-01", Some("DATE"))), Value::Bytes(b"2024-01-01".to_vec()));
+}
+
+// --- Against a live server, when DBTOON_TEST_MYSQL is set (connects with the MYSQL_* env vars) ---
+
+fn live_backend() -> Option<MySqlBackend> {
+    std::env::var_os("DBTOON_TEST_MYSQL")?;
+    let env = |key: &str| std::env::var(key).ok().filter(|v| !v.is_empty());
+    Some(MySqlBackend::new(
+        env("MYSQL_HOST").unwrap_or_else(|| "127.0.0.1".to_string()),
+        env("MYSQL_TCP_PORT").and_then(|p| p.parse().ok()).unwrap_or(3306),
+        env("MYSQL_DATABASE"),
+        env("MYSQL_USER").unwrap_or_else(|| "root".to_string()),
+        env("MYSQL_PWD").map(Into::into),
+        MySqlSslMode::Preferred,
+    ))
+}
+
+#[tokio::test]
+async fn live_query_returns_native_values() {
+    let Some(backend) = live_backend() else { return };
+    let params = [QueryParam { name: "n".to_string(), value: "2".to_string(), type_name: Some("INT".to_string()) }];
+    let result = backend
+        .execute(
+            "SELECT 1 AS id, CAST(1.50 AS DECIMAL(6,2)) AS price, DATE '2024-01-01' AS day, \
+             CONCAT('x', :n) AS label, NULL AS nothing",
+            &params,
+            None,
+            30,
+        )
+        .await
+        .unwrap();
+
+    let types: Vec<&str> = result.columns.iter().map(|c| c.type_name.as_str()).collect();
+    assert_eq!(types[..4], ["INT", "DECIMAL(6,2)", "DATE", "VARCHAR"]);
+    assert_eq!(
+        result.rows[0],
+        vec![
+            CellValue::Int64(1),
+            CellValue::Decimal { value: 150, scale: 2 },
+            CellValue::Date(19_723),
+            CellValue::Text("x2".to_string()),
+            CellValue::Null,
+        ]
+    );
+}
+
+#[tokio::test]
+async fn live_query_truncates_at_limit_and_collects_warnings() {
+    let Some(backend) = live_backend() else { return };
+    let result = backend
+        .execute(
+            "SELECT a.n FROM (SELECT 1 AS n UNION ALL SELECT 2 UNION ALL SELECT 3) a \
+             CROSS JOIN (SELECT 1 UNION ALL SELECT 2 UNION ALL SELECT 3) b",
+            &[],
+            Some(4),
+            30,
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.rows.len(), 4);
+    assert!(result.truncated);
+
+    let result = backend.execute("SELECT CAST('12abc' AS SIGNED) AS n", &[], None, 30).await.unwrap();
+    assert_eq!(result.rows[0], vec![CellValue::Int64(12)]);
+    assert!(result.messages.iter().any(|m| m.contains("Truncated incorrect")), "{:?}", result.messages);
+}
+
+#[tokio::test]
+async fn live_errors_carry_server_message() {
+    let Some(backend) = live_backend() else { return };
+    let err = backend.execute("SELECT * FROM no_such_table", &[], None, 30).await.unwrap_err();
+    assert!(err.to_string().contains("1146"), "{err}");
+}
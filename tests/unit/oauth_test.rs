@@ -0,0 +1,285 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use sha2::{Digest, Sha256};
+
+use dbtoon::backend::Backend;
+use dbtoon::backend::databricks::DatabricksBackend;
+use dbtoon::backend::oauth::{self, StoredToken, TokenCache};
+use dbtoon::config::DatabricksAuth;
+use dbtoon::error::DbtoonError;
+
+use super::mock_http::{MockResponse, MockServer};
+
+const STATEMENTS: &str = "/api/2.0/sql/statements/";
+const TOKEN_ENDPOINT: &str = "/oidc/v1/token";
+const SUCCEEDED: &str = r#"{"statement_id":"st-1","status":{"state":"SUCCEEDED"},
+    "manifest":{"schema":{"columns":[{"name":"n","type_name":"INT"}]},"total_row_count":1,"total_chunk_count":1},
+    "result":{"data_array":[["1"]]}}"#;
+
+fn cache_path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("dbtoon_test_oauth_{}_{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir.join("oauth-tokens.json")
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+fn seed_cache(path: &Path, server: &MockServer, access_token: &str, expires_at: u64) {
+    let mut cache = TokenCache::default();
+    cache.tokens.insert(
+        TokenCache::key(&server.url(), "databricks-cli"),
+        StoredToken {
+            access_token: access_token.to_string(),
+            refresh_token: Some("refresh-1".to_string()),
+            expires_at,
+        },
+    );
+    cache.save(path).unwrap();
+}
+
+fn u2m_backend(server: &MockServer, token_cache: PathBuf) -> DatabricksBackend {
+    DatabricksBackend::new(
+        server.url(),
+        DatabricksAuth::OAuthU2M {
+            client_id: "databricks-cli".to_string(),
+            token_cache,
+        },
+        "wh-1".to_string(),
+        None,
+        None,
+    )
+}
+
+fn form_value(body: &str, key: &str) -> Option<String> {
+    url::form_urlencoded::parse(body.as_bytes())
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.into_owned())
+}
+
+#[tokio::test]
+async fn u2m_uses_cached_access_token() {
+    let server = MockServer::start();
+    server.on("POST", STATEMENTS, MockResponse::json(200, SUCCEEDED));
+    let path = cache_path("cached");
+    seed_cache(&path, &server, "cached-access", unix_now() + 3600);
+
+    u2m_backend(&server, path).execute("SELECT 1", &[], None, 60).await.unwrap();
+
+    assert!(server.requests_to(TOKEN_ENDPOINT).is_empty());
+    let request = &server.requests_to(STATEMENTS)[0];
+    assert_eq!(
+        request.headers.get("authorization").map(String::as_str),
+        Some("Bearer cached-access")
+    );
+}
+
+#[tokio::test]
+async fn u2m_refreshes_expired_token_and_updates_cache() {
+    let server = MockServer::start();
+    server.on(
+        "POST",
+        TOKEN_ENDPOINT,
+        MockResponse::json(200, r#"{"access_token":"new-access","refresh_token":"refresh-2","expires_in":3600}"#),
+    );
+    server.on("POST", STATEMENTS, MockResponse::json(200, SUCCEEDED));
+    let path = cache_path("refresh");
+    seed_cache(&path, &server, "old-access", unix_now() - 10);
+
+    u2m_backend(&server, path.clone()).execute("SELECT 1", &[], None, 60).await.unwrap();
+
+    let body = &server.requests_to(TOKEN_ENDPOINT)[0].body;
+    assert_eq!(form_value(body, "grant_type").as_deref(), Some("refresh_token"));
+    assert_eq!(form_value(body, "refresh_token").as_deref(), Some("refresh-1"));
+    assert_eq!(form_value(body, "client_id").as_deref(), Some("databricks-cli"));
+    assert_eq!(
+        server.requests_to(STATEMENTS)[0].headers.get("authorization").map(String::as_str),
+        Some("Bearer new-access")
+    );
+
+    let cache = TokenCache::load(&path).unwrap();
+    let stored = &cache.tokens[&TokenCache::key(&server.url(), "databricks-cli")];
+    assert_eq!(stored.access_token, "new-access");
+    assert_eq!(stored.refresh_token.as_deref(), Some("refresh-2"));
+    assert!(stored.expires_at > unix_now() + 3000);
+}
+
+#[tokio::test]
+async fn u2m_rejected_refresh_asks_for_login() {
+    let server = MockServer::start();
+    server.on("POST", TOKEN_ENDPOINT, MockResponse::json(400, r#"{"error":"invalid_grant"}"#));
+    let path = cache_path("rejected");
+    seed_cache(&path, &server, "old-access", unix_now() - 10);
+
+    let err = u2m_backend(&server, path).execute("SELECT 1", &[], None, 60).await.unwrap_err();
+
+    assert!(matches!(err, DbtoonError::Auth { .. }), "got {err:?}");
+    assert!(err.to_string().contains("dbtoon auth login"), "got {err}");
+    assert!(server.requests_to(STATEMENTS).is_empty());
+}
+
+#[tokio::test]
+async fn u2m_without_login_is_auth_error() {
+    let server = MockServer::start();
+    let path = cache_path("missing");
+
+    let err = u2m_backend(&server, path).execute("SELECT 1", &[], None, 60).await.unwrap_err();
+
+    assert!(matches!(err, DbtoonError::Auth { .. }), "got {err:?}");
+    assert!(err.to_string().contains("not logged in"), "got {err}");
+}
+
+#[cfg(unix)]
+#[test]
+fn token_cache_is_private_to_the_user() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = cache_path("perms");
+    TokenCache::default().save(&path).unwrap();
+
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+}
+
+/// Play the browser: follow the authorization URL's redirect back to dbtoon.
+fn redirect_back(authorize_url: &str, code: &str, state_override: Option<&str>) {
+    let url = url::Url::parse(authorize_url).unwrap();
+    let query: std::collections::HashMap<String, String> = url.query_pairs().into_owned().collect();
+    let redirect = url::Url::parse(&query["redirect_uri"]).unwrap();
+    let state = state_override.unwrap_or(&query["state"]).to_string();
+    let port = redirect.port().unwrap();
+    let code = code.to_string();
+
+    std::thread::spawn(move || {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        write!(
+            stream,
+            "GET /?code={}&state={} HTTP/1.1\r\nHost: localhost\r\n\r\n",
+            code, state
+        )
+        .unwrap();
+        let mut response = String::new();
+        let _ = stream.read_to_string(&mut response);
+    });
+}
+
+#[tokio::test]
+async fn browser_login_exchanges_code_with_pkce_verifier() {
+    let server = MockServer::start();
+    server.on(
+        "POST",
+        TOKEN_ENDPOINT,
+        MockResponse::json(200, r#"{"access_token":"user-access","refresh_token":"user-refresh","expires_in":3600}"#),
+    );
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let client = reqwest::Client::new();
+    let mut authorize_url = String::new();
+
+    let tokens = oauth::browser_login(&client, &server.url(), "databricks-cli", listener, |url| {
+        authorize_url = url.to_string();
+        redirect_back(url, "auth-code", None);
+    })
+    .await
+    .unwrap();
+
+    let url = url::Url::parse(&authorize_url).unwrap();
+    assert_eq!(url.path(), "/oidc/v1/authorize");
+    let query: std::collections::HashMap<String, String> = url.query_pairs().into_owned().collect();
+    assert_eq!(query["response_type"], "code");
+    assert_eq!(query["client_id"], "databricks-cli");
+    assert_eq!(query["code_challenge_method"], "S256");
+    assert!(query["scope"].contains("offline_access"));
+
+    let body = &server.requests_to(TOKEN_ENDPOINT)[0].body;
+    assert_eq!(form_value(body, "grant_type").as_deref(), Some("authorization_code"));
+    assert_eq!(form_value(body, "code").as_deref(), Some("auth-code"));
+    assert_eq!(form_value(body, "redirect_uri"), Some(query["redirect_uri"].clone()));
+    let verifier = form_value(body, "code_verifier").unwrap();
+    assert_eq!(URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes())), query["code_challenge"]);
+
+    let path = cache_path("browser");
+    tokens.save(&server.url(), "databricks-cli", &path).unwrap();
+    let cache = TokenCache::load(&path).unwrap();
+    let stored = &cache.tokens[&TokenCache::key(&server.url(), "databricks-cli")];
+    assert_eq!(stored.refresh_token.as_deref(), Some("user-refresh"));
+}
+
+#[tokio::test]
+async fn browser_login_rejects_mismatched_state() {
+    let server = MockServer::start();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let client = reqwest::Client::new();
+
+    let result = oauth::browser_login(&client, &server.url(), "databricks-cli", listener, |url| {
+        redirect_back(url, "auth-code", Some("forged"));
+    })
+    .await;
+
+    let err = result.err().expect("login should fail");
+    assert!(err.to_string().contains("state"), "got {err}");
+    assert!(server.requests_to(TOKEN_ENDPOINT).is_empty());
+}
+
+#[tokio::test]
+async fn device_login_polls_until_approved() {
+    let server = MockServer::start();
+    let device_path = "/oidc/v1/device/authorize";
+    server.on(
+        "GET",
+        "/oidc/.well-known/oauth-authorization-server",
+        MockResponse::json(200, &format!(r#"{{"device_authorization_endpoint":"{}{}"}}"#, server.url(), device_path)),
+    );
+    server.on(
+        "POST",
+        device_path,
+        MockResponse::json(
+            200,
+            r#"{"device_code":"dev-1","user_code":"ABCD-EFGH","verification_uri":"https://example.com/device","expires_in":600,"interval":0}"#,
+        ),
+    );
+    server.on("POST", TOKEN_ENDPOINT, MockResponse::json(400, r#"{"error":"authorization_pending"}"#));
+    server.on(
+        "POST",
+        TOKEN_ENDPOINT,
+        MockResponse::json(200, r#"{"access_token":"device-access","refresh_token":"device-refresh","expires_in":3600}"#),
+    );
+    let client = reqwest::Client::new();
+    let mut user_code = String::new();
+
+    let tokens = oauth::device_login(&client, &server.url(), "databricks-cli", |device| {
+        user_code = device.user_code.clone();
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(user_code, "ABCD-EFGH");
+    let polls = server.requests_to(TOKEN_ENDPOINT);
+    assert_eq!(polls.len(), 2);
+    assert_eq!(form_value(&polls[1].body, "device_code").as_deref(), Some("dev-1"));
+
+    let path = cache_path("device");
+    tokens.save(&server.url(), "databricks-cli", &path).unwrap();
+    let cache = TokenCache::load(&path).unwrap();
+    assert_eq!(
+        cache.tokens[&TokenCache::key(&server.url(), "databricks-cli")].access_token,
+        "device-access"
+    );
+}
+
+#[tokio::test]
+async fn device_login_requires_advertised_endpoint() {
+    let server = MockServer::start();
+    server.on("GET", "/oidc/.well-known/oauth-authorization-server", MockResponse::json(200, "{}"));
+    let client = reqwest::Client::new();
+
+    let result = oauth::device_login(&client, &server.url(), "databricks-cli", |_| {}).await;
+
+    let err = result.err().expect("login should fail");
+    assert!(err.to_string().contains("device code"), "got {err}");
+}
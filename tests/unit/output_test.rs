@@ -1,14 +1,17 @@
+use std::fs;
 use std::path::Path;
 
-use dbtoon::output::{print_summary, print_truncation_warning};
+use dbtoon::backend::{CellValue, ColumnMeta, ResultSink, StreamSummary};
+use dbtoon::format_detect::OutputFormat;
+use dbtoon::output::{ResultFiles, print_summary, print_truncation_warning, summary_toon};
 
 // --- T016: TOON file output print summary contains truncation keys ---
 
 #[test]
 fn print_summary_toon_file_truncated_succeeds() {
     let path = Path::new("/tmp/test_output.toon");
-    let message = "Showing 500 rows. Use --no-limit to return all rows.";
-    let result = print_summary(500, path, true, Some(message));
+    let summary = StreamSummary { rows: 500, truncated: true, ..StreamSummary::default() };
+    let result = print_summary(path, &summary);
     assert!(result.is_ok(), "print_summary for .toon file should succeed");
 }
 
@@ -18,15 +21,16 @@ fn print_summary_truncated_produces_valid_toon() {
     // Since print_summary prints to stdout, we test the underlying logic
     // by calling it and verifying it doesn't error
     let path = Path::new("/tmp/test_output.csv");
-    let message = "Showing 500 rows. Use --no-limit to return all rows.";
-    let result = print_summary(500, path, true, Some(message));
+    let summary = StreamSummary { rows: 500, truncated: true, ..StreamSummary::default() };
+    let result = print_summary(path, &summary);
     assert!(result.is_ok(), "print_summary should succeed for truncated case");
 }
 
 #[test]
 fn print_summary_non_truncated_produces_valid_toon() {
     let path = Path::new("/tmp/test_output.csv");
-    let result = print_summary(100, path, false, None);
+    let summary = StreamSummary { rows: 100, truncated: false, ..StreamSummary::default() };
+    let result = print_summary(path, &summary);
     assert!(result.is_ok(), "print_summary should succeed for non-truncated case");
 }
 
@@ -92,6 +96,30 @@ fn print_summary_non_truncated_output_has_no_message() {
     );
 }
 
+#[test]
+fn summary_toon_includes_server_messages_and_rows_affected() {
+    let summary = StreamSummary {
+        rows: 0,
+        messages: vec!["Hello from PRINT".to_string(), "Table 't'. Scan count 1".to_string()],
+        rows_affected: Some(3),
+        ..StreamSummary::default()
+    };
+    let toon = summary_toon(Path::new("/tmp/test.csv"), &summary).unwrap();
+
+    assert!(toon.contains("\nmessages[2]: Hello from PRINT,Table 't'. Scan count 1\n"), "{toon}");
+    assert!(toon.ends_with("\nrows_affected: 3"), "{toon}");
+}
+
+#[test]
+fn summary_toon_omits_absent_server_info() {
+    let summary = StreamSummary { rows: 100, truncated: false, ..StreamSummary::default() };
+    let toon = summary_toon(Path::new("/tmp/test.csv"), &summary).unwrap();
+    let decoded: serde_json::Value = toon_format::decode_no_coerce(&toon).unwrap();
+
+    assert!(decoded.get("messages").is_none());
+    assert!(decoded.get("rows_affected").is_none());
+}
+
 // --- T018: print_truncation_warning() test ---
 
 #[test]
@@ -99,3 +127,70 @@ fn print_truncation_warning_does_not_panic() {
     // print_truncation_warning writes to stderr; verify it doesn't panic
     print_truncation_warning("Showing 500 rows. Use --no-limit to return all rows.");
 }
+
+// --- Multiple result sets written to files ---
+
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join("dbtoon_test_result_files").join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn write_set(sink: &mut ResultFiles, name: &str, value: i64) {
+    sink.begin(&[ColumnMeta { name: name.to_string(), type_name: "INT".to_string() }])
+        .unwrap();
+    sink.write_batch(&[vec![CellValue::Int64(value)]]).unwrap();
+}
+
+fn one_row() -> StreamSummary {
+    StreamSummary { rows: 1, truncated: false, ..StreamSummary::default() }
+}
+
+#[test]
+fn result_files_write_one_file_per_set_with_pattern() {
+    let dir = temp_dir("pattern");
+    let mut sink = ResultFiles::create(OutputFormat::Csv, &dir.join("out_{n}.csv")).unwrap();
+
+    write_set(&mut sink, "a", 1);
+    sink.next_result(one_row()).unwrap();
+    write_set(&mut sink, "b", 2);
+    let files = sink.finish(one_row()).unwrap();
+
+    let paths: Vec<_> = files.iter().map(|(p, _)| p.clone()).collect();
+    assert_eq!(paths, vec![dir.join("out_1.csv"), dir.join("out_2.csv")]);
+    assert_eq!(fs::read_to_string(&paths[0]).unwrap(), "a\r\n1\r\n");
+    assert_eq!(fs::read_to_string(&paths[1]).unwrap(), "b\r\n2\r\n");
+}
+
+#[test]
+fn result_files_without_pattern_reject_second_set_and_clean_up() {
+    let dir = temp_dir("no_pattern");
+    let path = dir.join("out.parquet");
+    let mut sink = ResultFiles::create(OutputFormat::Parquet, &path).unwrap();
+
+    write_set(&mut sink, "a", 1);
+    let err = sink.next_result(one_row()).unwrap_err();
+    assert!(err.to_string().contains("out_{n}.parquet"), "got: {err}");
+
+    drop(sink);
+    assert!(!path.exists(), "partial output should be removed");
+}
+
+#[test]
+fn result_files_keep_all_toon_sets_in_one_document() {
+    let dir = temp_dir("toon");
+    let path = dir.join("out.toon");
+    let mut sink = ResultFiles::create(OutputFormat::Toon, &path).unwrap();
+
+    write_set(&mut sink, "a", 1);
+    sink.next_result(one_row()).unwrap();
+    write_set(&mut sink, "b", 2);
+    let files = sink.finish(one_row()).unwrap();
+
+    assert_eq!(files.len(), 2);
+    assert!(files.iter().all(|(p, _)| p == &path));
+    let decoded: serde_json::Value =
+        toon_format::decode_no_coerce(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(decoded["results"][1]["rows"][0]["b"], 2);
+}
@@ -0,0 +1,198 @@
+use dbtoon::backend::CellValue;
+use dbtoon::params::{
+    QueryParam, parse_param, parse_params, to_at_named, to_braced, to_numbered, to_positional,
+};
+
+fn param(name: &str, value: &str) -> QueryParam {
+    QueryParam { name: name.to_string(), value: value.to_string(), type_name: None }
+}
+
+// --- parse_param ---
+
+#[test]
+fn test_parse_untyped_param() {
+    let p = parse_param("name=Alice").unwrap();
+    assert_eq!(p, param("name", "Alice"));
+    assert_eq!(p.typed_value(), CellValue::Text("Alice".to_string()));
+}
+
+#[test]
+fn test_parse_typed_param() {
+    let p = parse_param("id=42:int").unwrap();
+    assert_eq!(p.name, "id");
+    assert_eq!(p.value, "42");
+    assert_eq!(p.type_name.as_deref(), Some("INT"));
+    assert_eq!(p.typed_value(), CellValue::Int64(42));
+
+    let p = parse_param("price=12.50:DECIMAL(10,2)").unwrap();
+    assert_eq!(p.type_name.as_deref(), Some("DECIMAL(10,2)"));
+    assert_eq!(p.typed_value(), CellValue::Decimal { value: 1250, scale: 2 });
+}
+
+#[test]
+fn test_parse_value_containing_colons() {
+    // Only a known type name after the last colon is a type
+    let p = parse_param("at=2024-03-01 10:30:00").unwrap();
+    assert_eq!(p.value, "2024-03-01 10:30:00");
+    assert_eq!(p.type_name, None);
+
+    let p = parse_param("at=2024-03-01 10:30:00:TIMESTAMP").unwrap();
+    assert_eq!(p.value, "2024-03-01 10:30:00");
+    assert_eq!(p.type_name.as_deref(), Some("TIMESTAMP"));
+
+    let p = parse_param("url=http://example.com").unwrap();
+    assert_eq!(p.value, "http://example.com");
+}
+
+#[test]
+fn test_parse_value_containing_equals() {
+    let p = parse_param("expr=a=b").unwrap();
+    assert_eq!(p.value, "a=b");
+}
+
+#[test]
+fn test_parse_empty_value() {
+    assert_eq!(parse_param("s=").unwrap().value, "");
+}
+
+#[test]
+fn test_parse_leading_colon_in_name() {
+    assert_eq!(parse_param(":id=1").unwrap().name, "id");
+}
+
+#[test]
+fn test_parse_param_errors() {
+    let err = parse_param("noequals").unwrap_err();
+    assert!(err.to_string().starts_with("config: invalid --param syntax"), "got: {err}");
+
+    let err = parse_param("bad name=1").unwrap_err();
+    assert!(err.to_string().contains("invalid parameter name"), "got: {err}");
+
+    let err = parse_param("id=abc:INT").unwrap_err();
+    assert!(err.to_string().contains("'abc' is not a valid INT"), "got: {err}");
+}
+
+#[test]
+fn test_parse_params_rejects_duplicates() {
+    let specs = vec!["id=1".to_string(), "ID=2".to_string()];
+    let err = parse_params(&specs).unwrap_err();
+    assert!(err.to_string().contains("given more than once"), "got: {err}");
+}
+
+// --- to_positional ---
+
+#[test]
+fn test_named_markers_become_positional() {
+    let params = [param("id", "1"), param("name", "x")];
+    let (sql, bound) =
+        to_positional("SELECT * FROM t WHERE id = :id OR parent = :id AND name = :name", &params)
+            .unwrap();
+    assert_eq!(sql, "SELECT * FROM t WHERE id = ? OR parent = ? AND name = ?");
+    let names: Vec<&str> = bound.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, vec!["id", "id", "name"]);
+}
+
+#[test]
+fn test_markers_in_literals_and_comments_are_ignored() {
+    let params = [param("id", "1")];
+    let (sql, bound) = to_positional(
+        "SELECT ':id', \"a:id\", [b:id] -- :id\n/* :id */ FROM t WHERE x = :id AND y = 'it''s :id'",
+        &params,
+    )
+    .unwrap();
+    assert_eq!(
+        sql,
+        "SELECT ':id', \"a:id\", [b:id] -- :id\n/* :id */ FROM t WHERE x = ? AND y = 'it''s :id'"
+    );
+    assert_eq!(bound.len(), 1);
+}
+
+#[test]
+fn test_casts_and_unknown_markers_are_kept() {
+    let params = [param("id", "1")];
+    let (sql, _) = to_positional("SELECT a::int, :other FROM t WHERE id = :id", &params).unwrap();
+    assert_eq!(sql, "SELECT a::int, :other FROM t WHERE id = ?");
+}
+
+#[test]
+fn test_question_marks_bind_in_given_order() {
+    let params = [param("b", "2"), param("a", "1")];
+    let (sql, bound) = to_positional("SELECT * FROM t WHERE x = ? AND y = ?", &params).unwrap();
+    assert_eq!(sql, "SELECT * FROM t WHERE x = ? AND y = ?");
+    let names: Vec<&str> = bound.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, vec!["b", "a"]);
+}
+
+#[test]
+fn test_unused_named_param_is_an_error() {
+    let params = [param("id", "1"), param("extra", "2")];
+    let err = to_positional("SELECT * FROM t WHERE id = :id", &params).unwrap_err();
+    assert!(err.to_string().contains("parameter 'extra' is not used"), "got: {err}");
+}
+
+// --- to_numbered ---
+
+#[test]
+fn test_named_markers_become_numbered() {
+    let params = [param("id", "1"), param("name", "x")];
+    let (sql, bound) = to_numbered(
+        "SELECT * FROM t WHERE id = :id AND name = :name OR parent = :id AND x::text = ':id'",
+        &params,
+    )
+    .unwrap();
+    assert_eq!(
+        sql,
+        "SELECT * FROM t WHERE id = $1 AND name = $2 OR parent = $1 AND x::text = ':id'"
+    );
+    let names: Vec<&str> = bound.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, vec!["id", "name"]);
+}
+
+#[test]
+fn test_numbered_repeats_keep_first_number() {
+    let params = [param("a", "1"), param("b", "2")];
+    let (sql, bound) = to_numbered("SELECT :a, :a, :b, :a", &params).unwrap();
+    assert_eq!(sql, "SELECT $1, $1, $2, $1");
+    assert_eq!(bound.len(), 2);
+}
+
+#[test]
+fn test_numbered_rejects_unused_param() {
+    let params = [param("id", "1"), param("extra", "2")];
+    let err = to_numbered("SELECT :id", &params).unwrap_err();
+    assert!(err.to_string().contains("'extra' is not used"), "got: {err}");
+}
+
+// --- to_braced ---
+
+#[test]
+fn test_named_markers_become_braced_query_parameters() {
+    let params = [param("id", "1"), param("name", "x")];
+    let (sql, bound) = to_braced(
+        "SELECT * FROM t WHERE id = :id AND name = :name OR parent = :id AND s = ':id'",
+        &params,
+        |p| if p.name == "id" { "Int64".to_string() } else { "String".to_string() },
+    )
+    .unwrap();
+    assert_eq!(
+        sql,
+        "SELECT * FROM t WHERE id = {id:Int64} AND name = {name:String} OR parent = {id:Int64} AND s = ':id'"
+    );
+    let names: Vec<&str> = bound.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, vec!["id", "name"]);
+}
+
+// --- to_at_named ---
+
+#[test]
+fn test_named_markers_become_at_parameters() {
+    let params = [param("id", "1"), param("name", "x")];
+    let (sql, bound) = to_at_named(
+        "SELECT * FROM t WHERE id = :id AND name = :name OR parent = :id AND s = ':id'",
+        &params,
+    )
+    .unwrap();
+    assert_eq!(sql, "SELECT * FROM t WHERE id = @id AND name = @name OR parent = @id AND s = ':id'");
+    let names: Vec<&str> = bound.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, vec!["id", "name"]);
+}
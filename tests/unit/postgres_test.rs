@@ -0,0 +1,168 @@
+use bytes::BytesMut;
+use tokio_postgres::types::Type;
+
+use dbtoon::backend::postgres::{PostgresBackend, decode_cell, encode_param, normalize_pg_type};
+use dbtoon::backend::{Backend, CellValue};
+use dbtoon::config::PostgresSslMode;
+use dbtoon::params::QueryParam;
+
+fn encode(value: &str, ty: &Type) -> Vec<u8> {
+    let mut out = BytesMut::new();
+    encode_param(value, ty, &mut out).unwrap();
+    out.to_vec()
+}
+
+fn round_trip(value: &str, ty: &Type) -> CellValue {
+    decode_cell(ty, &encode(value, ty)).unwrap()
+}
+
+#[test]
+fn normalizes_types_for_columnar_output() {
+    assert_eq!(normalize_pg_type(&Type::INT4, -1), "INT");
+    assert_eq!(normalize_pg_type(&Type::INT8, -1), "BIGINT");
+    assert_eq!(normalize_pg_type(&Type::FLOAT8, -1), "DOUBLE");
+    assert_eq!(normalize_pg_type(&Type::BOOL, -1), "BOOLEAN");
+    assert_eq!(normalize_pg_type(&Type::TIMESTAMPTZ, -1), "TIMESTAMP");
+    assert_eq!(normalize_pg_type(&Type::BYTEA, -1), "VARBINARY");
+    assert_eq!(normalize_pg_type(&Type::UUID, -1), "UUID");
+    assert_eq!(normalize_pg_type(&Type::INT4_ARRAY, -1), "INT4[]");
+}
+
+#[test]
+fn normalizes_type_modifiers() {
+    // numeric(12,2) and varchar(40) as the server reports them
+    assert_eq!(normalize_pg_type(&Type::NUMERIC, ((12 << 16) | 2) + 4), "DECIMAL(12,2)");
+    assert_eq!(normalize_pg_type(&Type::NUMERIC, -1), "DECIMAL");
+    assert_eq!(normalize_pg_type(&Type::VARCHAR, 44), "VARCHAR(40)");
+    assert_eq!(normalize_pg_type(&Type::BPCHAR, 7), "CHAR(3)");
+}
+
+#[test]
+fn numeric_round_trips_with_scale() {
+    assert_eq!(round_trip("-1234.5600", &Type::NUMERIC), CellValue::Decimal { value: -12345600, scale: 4 });
+    assert_eq!(round_trip("0.001", &Type::NUMERIC), CellValue::Decimal { value: 1, scale: 3 });
+    assert_eq!(round_trip("100000000", &Type::NUMERIC), CellValue::Decimal { value: 100000000, scale: 0 });
+    assert_eq!(round_trip("0", &Type::NUMERIC), CellValue::Decimal { value: 0, scale: 0 });
+    assert_eq!(round_trip("NaN", &Type::NUMERIC), CellValue::Text("NaN".to_string()));
+}
+
+#[test]
+fn dates_and_times_use_unix_epoch() {
+    assert_eq!(round_trip("1970-01-02", &Type::DATE), CellValue::Date(1));
+    assert_eq!(round_trip("2000-01-01", &Type::DATE), CellValue::Date(10_957));
+    assert_eq!(
+        round_trip("2000-01-01T00:00:01", &Type::TIMESTAMP),
+        CellValue::Timestamp(946_684_801_000_000)
+    );
+    assert_eq!(round_trip("12:30:00", &Type::TIME), CellValue::Time(45_000_000_000));
+    // PostgreSQL's 'infinity' dates
+    assert_eq!(
+        decode_cell(&Type::DATE, &i32::MAX.to_be_bytes()).unwrap(),
+        CellValue::Text("infinity".to_string())
+    );
+}
+
+#[test]
+fn other_types_decode_to_text() {
+    assert_eq!(
+        round_trip("0123456789abcdef0123456789ABCDEF", &Type::UUID),
+        CellValue::Text("01234567-89ab-cdef-0123-456789abcdef".to_string())
+    );
+    assert_eq!(round_trip(r#"{"a": 1}"#, &Type::JSONB), CellValue::Text(r#"{"a": 1}"#.to_string()));
+    assert_eq!(round_trip("true", &Type::BOOL), CellValue::Bool(true));
+    assert_eq!(round_trip("0x0aff", &Type::BYTEA), CellValue::Bytes(vec![0x0a, 0xff]));
+}
+
+#[test]
+fn encoding_rejects_mismatched_values() {
+    let mut out = BytesMut::new();
+    let err = encode_param("abc", &Type::INT4, &mut out).unwrap_err();
+    assert!(err.to_string().contains("cannot convert 'abc' to int4"), "{err}");
+
+    let err = encode_param("1", &Type::POINT, &mut out).unwrap_err();
+    assert!(err.to_string().contains("CAST"), "{err}");
+}
+
+#[test]
+fn encoding_rejects_non_ascii_values_without_panicking() {
+    let mut out = BytesMut::new();
+    for (value, ty) in [
+        ("aé00000000000000000000000000000", &Type::UUID),
+        ("0xaé", &Type::BYTEA),
+        ("12:00:00.12345é", &Type::TIME),
+        ("2024-01-01 12:00:00.12345é", &Type::TIMESTAMP),
+    ] {
+        let err = encode_param(value, ty, &mut out).unwrap_err();
+        assert!(err.to_string().contains("cannot convert"), "{value}: {err}");
+    }
+}
+
+// --- Against a live server, when DBTOON_TEST_POSTGRES is set (connects with the PG* env vars) ---
+
+fn live_backend() -> Option<PostgresBackend> {
+    std::env::var_os("DBTOON_TEST_POSTGRES")?;
+    let env = |key: &str| std::env::var(key).ok().filter(|v| !v.is_empty());
+    Some(PostgresBackend::new(
+        env("PGHOST").unwrap_or_else(|| "localhost".to_string()),
+        env("PGPORT").and_then(|p| p.parse().ok()).unwrap_or(5432),
+        env("PGDATABASE"),
+        env("PGUSER").unwrap_or_else(|| "postgres".to_string()),
+        env("PGPASSWORD").map(Into::into),
+        PostgresSslMode::Disable,
+    ))
+}
+
+#[tokio::test]
+async fn live_query_returns_native_values() {
+    let Some(backend) = live_backend() else { return };
+    let params = [QueryParam { name: "n".to_string(), value: "3".to_string(), type_name: None }];
+    let result = backend
+        .execute(
+            "SELECT g AS id, g * 1.50::numeric(6,2) AS price, DATE '2024-01-01' + g AS day, \
+             'x' || g AS label, NULL::int AS nothing \
+             FROM generate_series(1, :n) AS g ORDER BY g",
+            &params,
+            None,
+            30,
+        )
+        .await
+        .unwrap();
+
+    let types: Vec<&str> = result.columns.iter().map(|c| c.type_name.as_str()).collect();
+    assert_eq!(types, vec!["INT", "DECIMAL", "DATE", "VARCHAR", "INT"]);
+    assert_eq!(result.rows.len(), 3);
+    assert_eq!(
+        result.rows[0],
+        vec![
+            CellValue::Int64(1),
+            CellValue::Decimal { value: 150, scale: 2 },
+            CellValue::Date(19_724),
+            CellValue::Text("x1".to_string()),
+            CellValue::Null,
+        ]
+    );
+}
+
+#[tokio::test]
+async fn live_query_truncates_at_limit_and_collects_notices() {
+    let Some(backend) = live_backend() else { return };
+    let result = backend
+        .execute("SELECT * FROM generate_series(1, 100000)", &[], Some(10), 30)
+        .await
+        .unwrap();
+    assert_eq!(result.rows.len(), 10);
+    assert!(result.truncated);
+
+    let result = backend
+        .execute("DO $$ BEGIN RAISE NOTICE 'hello from plpgsql'; END $$", &[], None, 30)
+        .await
+        .unwrap();
+    assert_eq!(result.messages, vec!["hello from plpgsql".to_string()]);
+}
+
+#[tokio::test]
+async fn live_errors_carry_server_message() {
+    let Some(backend) = live_backend() else { return };
+    let err = backend.execute("SELECT * FROM no_such_table", &[], None, 30).await.unwrap_err();
+    assert!(err.to_string().contains("relation \"no_such_table\" does not exist"), "{err}");
+}
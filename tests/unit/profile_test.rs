@@ -89,6 +89,25 @@ fn test_profile_create_with_set_overrides() {
     std::fs::remove_file(&path).ok();
 }
 
+#[test]
+fn test_profile_create_retry_fields_are_integers() {
+    let path = write_temp_config("[defaults]\n");
+    let result = profile::create_profile(
+        &path, "lake", "databricks",
+        &["retry_attempts=6".to_string(), "retry_backoff_ms=250".to_string()],
+    );
+    assert!(result.is_ok());
+
+    let content = std::fs::read_to_string(&path).unwrap();
+    assert!(content.contains("retry_attempts = 6\n"), "got: {}", content);
+    assert!(content.contains("retry_backoff_ms = 250\n"), "got: {}", content);
+
+    let result = profile::edit_profile(&path, "lake", &["retry_attempts=many".to_string()], &[]);
+    assert!(result.unwrap_err().to_string().contains("non-negative integer"));
+
+    std::fs::remove_file(&path).ok();
+}
+
 #[test]
 fn test_profile_create_duplicate_rejected() {
     let path = write_temp_config("[defaults]\n\n[profiles.mydb]\nbackend = \"sqlserver\"\nserver = \"localhost\"\n");
@@ -103,7 +122,7 @@ fn test_profile_create_duplicate_rejected() {
 #[test]
 fn test_profile_create_invalid_backend() {
     let path = write_temp_config("[defaults]\n");
-    let result = profile::create_profile(&path, "mydb", "postgres", &[]);
+    let result = profile::create_profile(&path, "mydb", "oracle", &[]);
     assert!(result.is_err());
     let err = result.unwrap_err().to_string();
     assert!(err.contains("unknown backend") || err.contains("backend"), "Got: {}", err);
@@ -240,6 +259,134 @@ fn test_profile_show_with_show_secrets() {
     std::fs::remove_file(&path).ok();
 }
 
+#[test]
+fn test_profile_show_masks_client_secret() {
+    let _guard = EnvGuard::new(&[("MY_CLIENT_SECRET", "sp-secret-value")]);
+    let path = write_temp_config("[defaults]\n\n[profiles.sp]\nbackend = \"databricks\"\nhost = \"h\"\nclient_id = \"sp-client\"\nclient_secret = \"$MY_CLIENT_SECRET\"\nwarehouse_id = \"wh-1\"\n");
+
+    let output = profile::show_profile(&path, "sp", false).unwrap();
+    assert!(output.contains("sp-client"), "client_id is not secret, got:\n{}", output);
+    assert!(output.contains("client_secret"), "should show client_secret field");
+    assert!(!output.contains("sp-secret-value"), "client_secret should be masked, got:\n{}", output);
+
+    std::fs::remove_file(&path).ok();
+}
+
+#[test]
+fn test_profile_show_masks_sqlserver_access_token() {
+    let path = write_temp_config("[defaults]\n\n[profiles.azure]\nbackend = \"sqlserver\"\nserver = \"db.database.windows.net\"\nauth = \"AccessToken\"\naccess_token = \"eyJ0eXAi-literal\"\n");
+
+    let output = profile::show_profile(&path, "azure", false).unwrap();
+    assert!(output.contains("AccessToken"), "auth mode is not secret, got:\n{}", output);
+    assert!(!output.contains("eyJ0eXAi-literal"), "access_token should be masked, got:\n{}", output);
+
+    std::fs::remove_file(&path).ok();
+}
+
+#[test]
+fn test_profile_extra_attributes_set_show_and_unset() {
+    let path = write_temp_config("[defaults]\n\n[profiles.mydb]\nbackend = \"sqlserver\"\nserver = \"db01\"\n");
+    let set = [
+        "extra_attributes.MultiSubnetFailover=Yes".to_string(),
+        "extra_attributes.KeyStoreSecret=hunter2".to_string(),
+    ];
+    profile::edit_profile(&path, "mydb", &set, &[]).unwrap();
+
+    let output = profile::show_profile(&path, "mydb", false).unwrap();
+    assert!(
+        output.lines().any(|l| l.starts_with("extra_attributes.MultiSubnetFailover =") && l.ends_with("\"Yes\"")),
+        "Got:\n{}",
+        output
+    );
+    assert!(output.contains("extra_attributes.KeyStoreSecret = \"****\""), "Got:\n{}", output);
+    assert!(!output.contains("hunter2"), "Got:\n{}", output);
+
+    let unset = [
+        "extra_attributes.MultiSubnetFailover".to_string(),
+        "extra_attributes.KeyStoreSecret".to_string(),
+    ];
+    profile::edit_profile(&path, "mydb", &[], &unset).unwrap();
+    let content = std::fs::read_to_string(&path).unwrap();
+    assert!(!content.contains("extra_attributes"), "Got:\n{}", content);
+
+    std::fs::remove_file(&path).ok();
+}
+
+#[test]
+fn test_profile_show_masks_odbc_connection_string_credentials() {
+    let path = write_temp_config(
+        "[defaults]\n\n[profiles.pg]\nbackend = \"odbc\"\nconnection_string = \"Driver={PostgreSQL Unicode};Server=db;PWD=hunter2;\"\n",
+    );
+    let output = profile::show_profile(&path, "pg", false).unwrap();
+    assert!(output.contains("Server=db;PWD=****;"), "Got:\n{}", output);
+    assert!(!output.contains("hunter2"), "Got:\n{}", output);
+
+    let output = profile::show_profile(&path, "pg", true).unwrap();
+    assert!(output.contains("hunter2"), "Got:\n{}", output);
+
+    std::fs::remove_file(&path).ok();
+}
+
+#[test]
+fn test_profile_create_odbc_defaults() {
+    let path = write_temp_config("[defaults]\n");
+    profile::create_profile(&path, "warehouse", "odbc", &["dialect=snowflake".to_string()]).unwrap();
+
+    let content = std::fs::read_to_string(&path).unwrap();
+    assert!(content.contains("backend = \"odbc\""), "Got:\n{}", content);
+    assert!(content.contains("dsn = "), "Got:\n{}", content);
+    assert!(content.contains("dialect = \"snowflake\""), "Got:\n{}", content);
+
+    std::fs::remove_file(&path).ok();
+}
+
+#[test]
+fn test_profile_create_postgres_defaults() {
+    let path = write_temp_config("[defaults]\n");
+    profile::create_profile(&path, "pg", "postgres", &["port=6543".to_string(), "sslmode=require".to_string()]).unwrap();
+
+    let content = std::fs::read_to_string(&path).unwrap();
+    assert!(content.contains("backend = \"postgres\""), "Got:\n{}", content);
+    assert!(content.contains("user = \"postgres\""), "Got:\n{}", content);
+    assert!(content.contains("port = 6543"), "Got:\n{}", content);
+    assert!(content.contains("sslmode = \"require\""), "Got:\n{}", content);
+
+    let err = profile::create_profile(&path, "pg2", "postgres", &["username=x".to_string()]).unwrap_err();
+    assert!(err.to_string().contains("username"), "Got: {}", err);
+
+    std::fs::remove_file(&path).ok();
+}
+
+#[test]
+fn test_profile_create_snowflake_masks_passphrase() {
+    let path = write_temp_config("[defaults]\n");
+    let set = [
+        "account=myorg-acct".to_string(),
+        "role=ANALYST".to_string(),
+        "private_key_passphrase=pass-literal".to_string(),
+    ];
+    profile::create_profile(&path, "snow", "snowflake", &set).unwrap();
+
+    let content = std::fs::read_to_string(&path).unwrap();
+    assert!(content.contains("account = \"myorg-acct\""), "Got:\n{}", content);
+    assert!(content.contains("private_key_path = \"$SNOWFLAKE_PRIVATE_KEY_PATH\""), "Got:\n{}", content);
+
+    let output = profile::show_profile(&path, "snow", false).unwrap();
+    assert!(output.contains("ANALYST"), "role is not secret, got:\n{}", output);
+    assert!(!output.contains("pass-literal"), "passphrase should be masked, got:\n{}", output);
+
+    std::fs::remove_file(&path).ok();
+}
+
+#[test]
+fn test_profile_test_accepts_client_id_without_token() {
+    let path = write_temp_config("[defaults]\n\n[profiles.sp]\nbackend = \"databricks\"\nhost = \"h\"\nclient_id = \"sp-client\"\nclient_secret = \"s\"\nwarehouse_id = \"wh-1\"\n");
+
+    profile::test_profile(&path, "sp").unwrap();
+
+    std::fs::remove_file(&path).ok();
+}
+
 #[test]
 fn test_profile_show_unset_env_warning() {
     let _guard = EnvGuard::new(&[]);
@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use dbtoon::backend::retry::{RetryPolicy, is_transient_status, retry_after};
+use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+
+fn headers(retry_after: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(RETRY_AFTER, HeaderValue::from_str(retry_after).unwrap());
+    headers
+}
+
+#[test]
+fn backoff_doubles_from_initial_delay() {
+    let policy = RetryPolicy {
+        max_attempts: 5,
+        initial_backoff: Duration::from_millis(500),
+    };
+    assert_eq!(policy.backoff(1), Duration::from_millis(500));
+    assert_eq!(policy.backoff(2), Duration::from_secs(1));
+    assert_eq!(policy.backoff(3), Duration::from_secs(2));
+}
+
+#[test]
+fn backoff_is_capped() {
+    let policy = RetryPolicy {
+        max_attempts: 50,
+        initial_backoff: Duration::from_secs(1),
+    };
+    assert_eq!(policy.backoff(40), Duration::from_secs(30));
+}
+
+#[test]
+fn retry_after_reads_delay_seconds() {
+    assert_eq!(retry_after(&headers("7")), Some(Duration::from_secs(7)));
+    assert_eq!(retry_after(&headers("86400")), Some(Duration::from_secs(120)), "capped");
+}
+
+#[test]
+fn retry_after_ignores_http_dates_and_absence() {
+    assert_eq!(retry_after(&headers("Wed, 21 Oct 2015 07:28:00 GMT")), None);
+    assert_eq!(retry_after(&HeaderMap::new()), None);
+}
+
+#[test]
+fn transient_statuses() {
+    for status in [429, 500, 502, 503, 504] {
+        assert!(is_transient_status(status), "{status}");
+    }
+    for status in [400, 401, 403, 404] {
+        assert!(!is_transient_status(status), "{status}");
+    }
+}
@@ -0,0 +1,233 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use clap::Parser;
+
+use dbtoon::backend;
+use dbtoon::cli::{Cli, Command, ShellArgs};
+use dbtoon::shell::{Display, Flow, Shell, split_statements};
+use dbtoon::validation::BackendDialect;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("dbtoon_test_shell_{}_{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// A shell on profile `main`, a SQLite file in `dir`, with `argv` after `dbtoon shell -P main`.
+fn shell(dir: &Path, argv: &[&str]) -> Shell {
+    let config = dir.join("config.toml");
+    std::fs::write(
+        &config,
+        format!(
+            "[defaults]\nrow_limit = 2\n\n[profiles.main]\nbackend = \"sqlite\"\npath = \"{}\"\n\n[profiles.other]\nbackend = \"sqlite\"\npath = \"{}\"\n",
+            dir.join("main.db").display(),
+            dir.join("other.db").display()
+        ),
+    )
+    .unwrap();
+    // Read-only sessions cannot create the database, so it starts out as an empty file
+    std::fs::write(dir.join("main.db"), "").unwrap();
+    let mut full = vec!["dbtoon", "shell", "-P", "main"];
+    full.extend_from_slice(argv);
+    let args: ShellArgs = match Cli::try_parse_from(full).unwrap().command {
+        Command::Shell(args) => args,
+        _ => unreachable!(),
+    };
+    Shell::new(&args, Some(&config), false, false).unwrap()
+}
+
+/// Feed `lines` to the shell, returning its stdout and stderr.
+async fn feed(shell: &mut Shell, lines: &[&str]) -> (String, String) {
+    let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+    for line in lines {
+        shell.handle_line(line, &mut stdout, &mut stderr).await.unwrap();
+    }
+    (String::from_utf8(stdout).unwrap(), String::from_utf8(stderr).unwrap())
+}
+
+#[tokio::test]
+async fn statements_share_one_connection() {
+    let _cache = super::CONNECTION_CACHE.lock().await;
+    backend::reuse_connections(Duration::from_secs(3600));
+    let dir = temp_dir("session");
+    let mut shell = shell(&dir, &["--allow-write"]);
+
+    let (out, err) = feed(
+        &mut shell,
+        &[
+            "CREATE TEMP TABLE t (n INTEGER, s TEXT);",
+            "INSERT INTO t VALUES",
+            "  (1, 'a;b'),",
+            "  (2, 'b'), (3, 'c');",
+            "SELECT n, s FROM t",
+            "ORDER BY n",
+            "GO",
+        ],
+    )
+    .await;
+    assert_eq!(
+        out,
+        "(0 rows affected)\n(3 rows affected)\n n |  s\n---+-----\n 1 | a;b\n 2 | b\n(2 rows)\n"
+    );
+    assert_eq!(err, "warning: Showing 2 rows. Use \\limit off to return all rows.\n");
+    backend::close_all_connections();
+}
+
+#[tokio::test]
+async fn write_statements_need_allow_write() {
+    let dir = temp_dir("allow_write");
+    let mut shell = shell(&dir, &[]);
+
+    let (out, err) = feed(&mut shell, &["CREATE TABLE t (n INTEGER);"]).await;
+    assert_eq!(out, "");
+    assert!(err.starts_with("error: validation:"), "{err}");
+
+    let (out, err) = feed(&mut shell, &["\\allow-write", "CREATE TABLE t (n INTEGER);", "\\allow-write off"]).await;
+    assert_eq!(out, "allow-write is on\n(0 rows affected)\nallow-write is off\n");
+    assert_eq!(err, "");
+    assert!(!shell.app_config().allow_write);
+}
+
+#[tokio::test]
+async fn limit_output_and_timing_commands() {
+    let dir = temp_dir("settings");
+    let mut shell = shell(&dir, &[]);
+
+    let (out, _) = feed(&mut shell, &["\\limit", "\\limit 5", "\\limit off"]).await;
+    assert_eq!(out, "row limit is 2\nrow limit is 5\nrow limit is off\n");
+    assert_eq!(shell.app_config().default_row_limit, None);
+
+    let (out, err) = feed(&mut shell, &["\\output toon", "SELECT 1 AS n, 'x' AS s;"]).await;
+    assert_eq!(out, "output is toon\ntypes[2]: BIGINT,VARCHAR\nrows[1]{n,s}:\n  1,x\ntruncated: false\n");
+    assert_eq!(err, "");
+    assert_eq!(shell.display(), Display::Toon);
+
+    let csv = dir.join("out.csv");
+    let (out, err) = feed(&mut shell, &[&format!("\\output {}", csv.display()), "SELECT 1 AS n;"]).await;
+    assert_eq!(
+        out,
+        format!(
+            "output goes to {0}\nrows_written: 1\nfile: {0}\ntruncated: false\n",
+            csv.display()
+        )
+    );
+    assert_eq!(err, "");
+    assert_eq!(std::fs::read_to_string(&csv).unwrap(), "n\r\n1\r\n");
+
+    let (_, err) = feed(&mut shell, &["\\output results.xyz"]).await;
+    assert!(err.starts_with("error:"), "{err}");
+
+    let (out, _) = feed(&mut shell, &["\\output table", "\\timing", "SELECT 1 AS n;"]).await;
+    assert!(out.starts_with("output is table\ntiming is on\n n\n---\n 1\n(1 row)\nTime: "), "{out}");
+}
+
+#[tokio::test]
+async fn profile_command_switches_profiles() {
+    let _cache = super::CONNECTION_CACHE.lock().await;
+    let dir = temp_dir("profile");
+    let mut shell = shell(&dir, &["--limit", "7"]);
+
+    let (out, err) = feed(&mut shell, &["\\profile", "\\profile other", "\\profile missing"]).await;
+    assert_eq!(out, "profile is main\nprofile is other (sqlite)\n");
+    assert!(err.starts_with("error:"), "{err}");
+    assert_eq!(shell.prompt(), "other> ");
+    // Session settings carry over to the new profile
+    assert_eq!(shell.app_config().default_row_limit, Some(7));
+}
+
+#[tokio::test]
+async fn prompt_quit_and_unknown_commands() {
+    let dir = temp_dir("prompt");
+    let mut shell = shell(&dir, &[]);
+    assert_eq!(shell.prompt(), "main> ");
+    assert_eq!(shell.history_file(), dir.join("shell-history"));
+
+    feed(&mut shell, &["SELECT 1"]).await;
+    assert_eq!(shell.prompt(), "   -> ");
+    shell.interrupt();
+    assert_eq!(shell.prompt(), "main> ");
+
+    let (_, err) = feed(&mut shell, &["\\bogus"]).await;
+    assert!(err.contains("unknown command \\bogus"), "{err}");
+
+    let (mut out, mut err) = (Vec::new(), Vec::new());
+    assert_eq!(shell.handle_line("\\q", &mut out, &mut err).await.unwrap(), Flow::Quit);
+    assert_eq!(shell.handle_line("exit", &mut out, &mut err).await.unwrap(), Flow::Quit);
+}
+
+#[tokio::test]
+async fn sql_server_blocks_wait_for_go() {
+    let dir = temp_dir("tsql");
+    let config = dir.join("config.toml");
+    std::fs::write(&config, "[profiles.main]\nbackend = \"sqlserver\"\nserver = \"db\"\nwindows_auth = true\n").unwrap();
+    let args: ShellArgs = match Cli::try_parse_from(["dbtoon", "shell", "-P", "main"]).unwrap().command {
+        Command::Shell(args) => args,
+        _ => unreachable!(),
+    };
+    let mut shell = Shell::new(&args, Some(&config), false, false).unwrap();
+
+    // Nothing runs, so nothing connects, while the `;`s are inside the block
+    let (out, err) = feed(
+        &mut shell,
+        &[
+            "IF 1 = 1",
+            "BEGIN",
+            "  DECLARE @n INT = 1;",
+            "  SELECT CASE WHEN @n = 1 THEN 'a' END AS s;",
+        ],
+    )
+    .await;
+    assert_eq!((out.as_str(), err.as_str()), ("", ""));
+    assert_eq!(shell.prompt(), "   -> ");
+
+    // The END runs the block as one statement, which fails to connect once
+    let (out, err) = feed(&mut shell, &["END;"]).await;
+    assert_eq!(out, "");
+    assert_eq!(err.lines().count(), 1, "{err}");
+    assert!(err.starts_with("error: connection:"), "{err}");
+    assert_eq!(shell.prompt(), "main> ");
+}
+
+#[test]
+fn split_statements_keeps_t_sql_blocks_whole() {
+    let (statements, rest) = split_statements(
+        "BEGIN TRAN; IF 1 = 1 BEGIN TRY SELECT 1; END TRY BEGIN CATCH SELECT 2; END CATCH; COMMIT;",
+        BackendDialect::SqlServer,
+    );
+    assert_eq!(
+        statements,
+        vec!["BEGIN TRAN", "IF 1 = 1 BEGIN TRY SELECT 1; END TRY BEGIN CATCH SELECT 2; END CATCH", "COMMIT"]
+    );
+    assert_eq!(rest, "");
+
+    // A routine's body runs to GO, and in other dialects BEGIN is just a word
+    let (statements, rest) = split_statements("SELECT 1;\nCREATE PROCEDURE p AS SELECT 1; SELECT 2;\n", BackendDialect::SqlServer);
+    assert_eq!(statements, vec!["SELECT 1"]);
+    assert_eq!(rest, "\nCREATE PROCEDURE p AS SELECT 1; SELECT 2;\n");
+    let (statements, _) = split_statements("BEGIN; SELECT 1;", BackendDialect::Postgres);
+    assert_eq!(statements, vec!["BEGIN", "SELECT 1"]);
+}
+
+#[test]
+fn split_statements_ignores_quoted_and_commented_semicolons() {
+    let (statements, rest) = split_statements(
+        "SELECT ';' AS a; -- x;\nSELECT \"b;\" /* ; */ FROM t;\nSELECT 3",
+        BackendDialect::Postgres,
+    );
+    assert_eq!(statements, vec!["SELECT ';' AS a", "-- x;\nSELECT \"b;\" /* ; */ FROM t"]);
+    assert_eq!(rest, "\nSELECT 3");
+
+    let (statements, rest) = split_statements("SELECT 'it''s; fine';\n-- trailing comment\n", BackendDialect::Postgres);
+    assert_eq!(statements, vec!["SELECT 'it''s; fine'"]);
+    assert_eq!(rest, "");
+
+    let (statements, rest) = split_statements("SELECT $body$ a; b $body$, $1;\n", BackendDialect::Postgres);
+    assert_eq!(statements, vec!["SELECT $body$ a; b $body$, $1"]);
+    assert_eq!(rest, "");
+
+    let (statements, rest) = split_statements("SELECT 'open;\n", BackendDialect::Postgres);
+    assert!(statements.is_empty());
+    assert_eq!(rest, "SELECT 'open;\n");
+}
@@ -0,0 +1,247 @@
+use std::fs::File;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use rusqlite::types::ValueRef;
+
+use dbtoon::backend::sqlite::{
+    MEMORY_PATH, SqliteBackend, file_references, normalize_sqlite_type, sqlite_cell,
+};
+use dbtoon::backend::{Backend, CellValue, ColumnMeta, QueryResult, SessionReuse};
+use dbtoon::cancel::CancelToken;
+use dbtoon::error::DbtoonError;
+use dbtoon::format_csv::write_csv;
+use dbtoon::format_parquet::write_parquet;
+use dbtoon::params::QueryParam;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("dbtoon_test_sqlite_{}_{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn memory() -> SqliteBackend {
+    SqliteBackend::new(MEMORY_PATH.to_string())
+}
+
+fn sample() -> QueryResult {
+    let column = |name: &str, type_name: &str| ColumnMeta { name: name.to_string(), type_name: type_name.to_string() };
+    QueryResult {
+        columns: vec![
+            column("id", "INT"),
+            column("price", "DECIMAL(8,2)"),
+            column("day", "DATE"),
+            column("name", "VARCHAR"),
+        ],
+        rows: vec![
+            vec![
+                CellValue::Int64(1),
+                CellValue::Decimal { value: 1250, scale: 2 },
+                CellValue::Date(19_724),
+                CellValue::Text("a".to_string()),
+            ],
+            vec![CellValue::Int64(2), CellValue::Null, CellValue::Null, CellValue::Text("b".to_string())],
+        ],
+        ..QueryResult::default()
+    }
+}
+
+#[test]
+fn finds_quoted_files_in_table_position() {
+    let sql = "SELECT 'x.csv' AS label FROM 'out.parquet' p JOIN 'b.CSV' ON 1 = 1, 'c.arrow' \
+               -- FROM 'comment.csv'\nWHERE p.f = 'skip.parquet' AND p.id IN (SELECT id FROM 'out.parquet')";
+    assert_eq!(file_references(sql), vec!["out.parquet", "b.CSV", "c.arrow"]);
+    assert!(file_references("SELECT * FROM 'notes.txt'").is_empty());
+}
+
+#[test]
+fn normalizes_declared_types() {
+    assert_eq!(normalize_sqlite_type("integer"), "BIGINT");
+    assert_eq!(normalize_sqlite_type("UNSIGNED BIG INT"), "BIGINT");
+    assert_eq!(normalize_sqlite_type("varchar(40)"), "VARCHAR(40)");
+    assert_eq!(normalize_sqlite_type("text"), "VARCHAR");
+    assert_eq!(normalize_sqlite_type("decimal(10, 2)"), "DECIMAL(10,2)");
+    assert_eq!(normalize_sqlite_type("DATETIME"), "TIMESTAMP");
+    assert_eq!(normalize_sqlite_type("blob"), "VARBINARY");
+    assert_eq!(normalize_sqlite_type("float"), "DOUBLE");
+    assert_eq!(normalize_sqlite_type("boolean"), "BOOLEAN");
+}
+
+#[test]
+fn converts_values_to_column_type() {
+    assert_eq!(sqlite_cell(ValueRef::Integer(1), "BOOLEAN"), CellValue::Bool(true));
+    assert_eq!(sqlite_cell(ValueRef::Real(12.5), "DECIMAL(8,2)"), CellValue::Decimal { value: 125, scale: 1 });
+    assert_eq!(sqlite_cell(ValueRef::Text(b"2024-01-02"), "DATE"), CellValue::Date(19_724));
+    // SQLite keeps a value's own type when it does not fit the column
+    assert_eq!(sqlite_cell(ValueRef::Text(b"n/a"), "BIGINT"), CellValue::Text("n/a".to_string()));
+    assert_eq!(sqlite_cell(ValueRef::Null, "BIGINT"), CellValue::Null);
+}
+
+#[tokio::test]
+async fn in_memory_query_infers_expression_types() {
+    let params = [QueryParam { name: "n".to_string(), value: "3".to_string(), type_name: Some("INT".to_string()) }];
+    let result = memory()
+        .execute(
+            "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < :n) \
+             SELECT x, x * 0.5 AS half, 'row ' || x AS label, NULL AS empty FROM c",
+            &params,
+            None,
+            30,
+        )
+        .await
+        .unwrap();
+
+    let types: Vec<&str> = result.columns.iter().map(|c| c.type_name.as_str()).collect();
+    assert_eq!(types, vec!["BIGINT", "DOUBLE", "VARCHAR", "VARCHAR"]);
+    assert_eq!(result.rows.len(), 3);
+    assert_eq!(
+        result.rows[2],
+        vec![CellValue::Int64(3), CellValue::Float64(1.5), CellValue::Text("row 3".to_string()), CellValue::Null]
+    );
+}
+
+#[tokio::test]
+async fn row_limit_truncates() {
+    let result = memory()
+        .execute(
+            "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < 100) SELECT x FROM c",
+            &[],
+            Some(10),
+            30,
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.rows.len(), 10);
+    assert!(result.truncated);
+}
+
+#[tokio::test]
+async fn queries_parquet_and_csv_files() {
+    let dir = temp_dir("files");
+    let parquet = dir.join("out.parquet");
+    let csv = dir.join("out.csv");
+    write_parquet(&sample(), &parquet, false, None).unwrap();
+    write_csv(&sample(), &csv).unwrap();
+
+    let sql = format!(
+        "SELECT p.id, p.price, p.day, c.name FROM '{}' p JOIN '{}' c ON c.id = p.id ORDER BY p.id",
+        parquet.display(),
+        csv.display()
+    );
+    let result = memory().execute(&sql, &[], None, 30).await.unwrap();
+
+    let types: Vec<&str> = result.columns.iter().map(|c| c.type_name.as_str()).collect();
+    assert_eq!(types, vec!["BIGINT", "DECIMAL(8,2)", "DATE", "VARCHAR"]);
+    assert_eq!(result.rows[0][0], CellValue::Int64(1));
+    assert_eq!(result.rows[0][1], CellValue::Decimal { value: 125, scale: 1 });
+    assert_eq!(result.rows[0][2], CellValue::Date(19_724));
+    assert_eq!(result.rows[1], vec![CellValue::Int64(2), CellValue::Null, CellValue::Null, CellValue::Text("b".to_string())]);
+}
+
+#[tokio::test]
+async fn cached_connection_reloads_only_modified_files() {
+    let _cache = super::CONNECTION_CACHE.lock().await;
+    dbtoon::backend::reuse_connections(Duration::from_secs(3600));
+    let dir = temp_dir("reload");
+    let csv = dir.join("out.csv");
+    write_csv(&sample(), &csv).unwrap();
+    let backend = SqliteBackend::new(dir.join("t.db").display().to_string()).with_session(SessionReuse::ReadOnly);
+    let sql = format!("SELECT count(*) AS n FROM '{}'", csv.display());
+    let count = || async { backend.execute(&sql, &[], None, 30).await.unwrap().rows };
+    assert_eq!(count().await, vec![vec![CellValue::Int64(2)]]);
+
+    // Same modification time: the loaded table is reused
+    let modified = std::fs::metadata(&csv).unwrap().modified().unwrap();
+    std::fs::write(&csv, "id\n1\n2\n3\n").unwrap();
+    File::options().write(true).open(&csv).unwrap().set_modified(modified).unwrap();
+    assert_eq!(count().await, vec![vec![CellValue::Int64(2)]]);
+
+    File::options()
+        .write(true)
+        .open(&csv)
+        .unwrap()
+        .set_modified(modified + Duration::from_secs(1))
+        .unwrap();
+    assert_eq!(count().await, vec![vec![CellValue::Int64(3)]]);
+    dbtoon::backend::close_all_connections();
+}
+
+#[tokio::test]
+async fn files_load_inside_an_open_transaction() {
+    let _cache = super::CONNECTION_CACHE.lock().await;
+    dbtoon::backend::reuse_connections(Duration::from_secs(3600));
+    let dir = temp_dir("transaction");
+    let csv = dir.join("out.csv");
+    write_csv(&sample(), &csv).unwrap();
+    let backend = SqliteBackend::new(dir.join("t.db").display().to_string()).with_session(SessionReuse::Keep);
+    let sql = format!("SELECT count(*) AS n FROM '{}'", csv.display());
+
+    backend.execute("BEGIN", &[], None, 30).await.unwrap();
+    let result = backend.execute(&sql, &[], None, 30).await.unwrap();
+    assert_eq!(result.rows, vec![vec![CellValue::Int64(2)]]);
+
+    // The rollback drops the loaded table, so the next query loads it again
+    backend.execute("ROLLBACK", &[], None, 30).await.unwrap();
+    let result = backend.execute(&sql, &[], None, 30).await.unwrap();
+    assert_eq!(result.rows, vec![vec![CellValue::Int64(2)]]);
+    dbtoon::backend::close_all_connections();
+}
+
+#[tokio::test]
+async fn only_shared_sessions_keep_temporary_tables() {
+    let _cache = super::CONNECTION_CACHE.lock().await;
+    dbtoon::backend::reuse_connections(Duration::from_secs(3600));
+    let path = temp_dir("session").join("t.db").display().to_string();
+    let backend = |session| SqliteBackend::new(path.clone()).with_session(session);
+
+    backend(SessionReuse::Keep).execute("CREATE TEMP TABLE kept (n INTEGER)", &[], None, 30).await.unwrap();
+    let result = backend(SessionReuse::ReadOnly).execute("SELECT count(*) FROM kept", &[], None, 30).await.unwrap();
+    assert_eq!(result.rows, vec![vec![CellValue::Int64(0)]]);
+
+    // The connection the temporary table lives on is closed rather than cached
+    backend(SessionReuse::Reset).execute("CREATE TEMP TABLE dropped (n INTEGER)", &[], None, 30).await.unwrap();
+    let err = backend(SessionReuse::Keep).execute("SELECT * FROM dropped", &[], None, 30).await.unwrap_err();
+    assert!(err.to_string().contains("no such table"), "got {err}");
+    dbtoon::backend::close_all_connections();
+}
+
+#[tokio::test]
+async fn missing_file_is_query_error() {
+    let err = memory().execute("SELECT * FROM 'no/such/file.parquet'", &[], None, 30).await.unwrap_err();
+    assert!(matches!(err, DbtoonError::Query { .. }), "got {err:?}");
+    assert!(err.to_string().contains("cannot read 'no/such/file.parquet'"), "got {err}");
+}
+
+#[tokio::test]
+async fn read_only_database_rejects_writes() {
+    let dir = temp_dir("read_only");
+    let path = dir.join("t.db").display().to_string();
+    let writable = SqliteBackend::new(path.clone());
+    writable.execute("CREATE TABLE t (id INTEGER)", &[], None, 30).await.unwrap();
+    let inserted = writable.execute("INSERT INTO t VALUES (1), (2)", &[], None, 30).await.unwrap();
+    assert_eq!(inserted.rows_affected, Some(2));
+
+    let read_only = SqliteBackend::new(path).with_read_only(true);
+    let err = read_only.execute("DELETE FROM t", &[], None, 30).await.unwrap_err();
+    assert!(err.to_string().contains("readonly"), "got {err}");
+    let result = read_only.execute("SELECT count(*) AS n FROM t", &[], None, 30).await.unwrap();
+    assert_eq!(result.rows, vec![vec![CellValue::Int64(2)]]);
+}
+
+#[tokio::test]
+async fn cancel_interrupts_running_query() {
+    let cancel = CancelToken::new();
+    let backend = memory().with_cancel(cancel.clone());
+    let canceller = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        cancel.cancel();
+    });
+
+    let err = backend
+        .execute("WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c) SELECT count(*) FROM c", &[], None, 60)
+        .await
+        .unwrap_err();
+    canceller.await.unwrap();
+    assert!(matches!(err, DbtoonError::Cancelled), "got {err:?}");
+}
@@ -0,0 +1,111 @@
+use dbtoon::backend::sqlserver::{SqlServerBackend, access_token_attribute};
+use dbtoon::config::{SqlServerAuth, SqlServerOptions};
+use secrecy::SecretString;
+
+fn connection_string(auth: SqlServerAuth) -> String {
+    SqlServerBackend::new("db.database.windows.net".to_string(), Some("sales".to_string()), auth, false)
+        .connection_string()
+}
+
+#[test]
+fn sql_login_connection_string() {
+    let conn = connection_string(SqlServerAuth::SqlLogin {
+        username: "sa".to_string(),
+        password: SecretString::from("p;w"),
+    });
+    assert_eq!(
+        conn,
+        "Driver={ODBC Driver 18 for SQL Server};Server=db.database.windows.net;Database=sales;UID=sa;PWD={p;w};"
+    );
+}
+
+#[test]
+fn separators_in_profile_values_are_escaped() {
+    let conn = SqlServerBackend::new(
+        "db01".to_string(),
+        Some("x;Driver={evil}".to_string()),
+        SqlServerAuth::SqlLogin {
+            username: "sa;Trusted_Connection=yes".to_string(),
+            password: SecretString::from("pw"),
+        },
+        false,
+    )
+    .connection_string();
+    assert_eq!(
+        conn,
+        "Driver={ODBC Driver 18 for SQL Server};Server=db01;Database={x;Driver={evil}}};UID={sa;Trusted_Connection=yes};PWD=pw;"
+    );
+}
+
+#[test]
+fn entra_service_principal_connection_string() {
+    let conn = connection_string(SqlServerAuth::ActiveDirectoryServicePrincipal {
+        client_id: "app-id".to_string(),
+        client_secret: SecretString::from("s3cret"),
+    });
+    assert!(conn.contains(";Authentication=ActiveDirectoryServicePrincipal;UID=app-id;PWD=s3cret;"), "{conn}");
+}
+
+#[test]
+fn entra_password_connection_string() {
+    let conn = connection_string(SqlServerAuth::ActiveDirectoryPassword {
+        username: "ana@contoso.com".to_string(),
+        password: SecretString::from("pw"),
+    });
+    assert!(conn.contains(";Authentication=ActiveDirectoryPassword;UID=ana@contoso.com;PWD=pw;"), "{conn}");
+}
+
+#[test]
+fn entra_interactive_connection_string_with_optional_user() {
+    let with_user = connection_string(SqlServerAuth::ActiveDirectoryInteractive {
+        username: Some("ana@contoso.com".to_string()),
+    });
+    assert!(with_user.ends_with(";Authentication=ActiveDirectoryInteractive;UID=ana@contoso.com;"), "{with_user}");
+
+    let without_user = connection_string(SqlServerAuth::ActiveDirectoryInteractive { username: None });
+    assert!(without_user.ends_with(";Authentication=ActiveDirectoryInteractive;"), "{without_user}");
+}
+
+#[test]
+fn entra_default_connection_string() {
+    let conn = connection_string(SqlServerAuth::ActiveDirectoryDefault);
+    assert!(conn.ends_with(";Authentication=ActiveDirectoryDefault;"), "{conn}");
+    assert!(!conn.contains("UID="), "{conn}");
+}
+
+#[test]
+fn access_token_stays_out_of_connection_string() {
+    let conn = connection_string(SqlServerAuth::AccessToken(SecretString::from("eyJ0eXAi")));
+    assert!(!conn.contains("eyJ0eXAi"), "{conn}");
+    assert!(!conn.contains("Authentication="), "{conn}");
+    assert!(!conn.contains("UID="), "{conn}");
+}
+
+#[test]
+fn access_token_attribute_is_length_prefixed_utf16() {
+    let value = access_token_attribute("ab");
+    assert_eq!(value, vec![4, 0, 0, 0, b'a', 0, b'b', 0]);
+}
+
+#[test]
+fn options_shape_driver_server_and_attributes() {
+    let options = SqlServerOptions {
+        driver: Some("ODBC Driver 17 for SQL Server".to_string()),
+        port: Some(14330),
+        instance: Some("SQLEXPRESS".to_string()),
+        encrypt: Some("strict".to_string()),
+        application_intent: Some("ReadOnly".to_string()),
+        extra_attributes: vec![
+            ("MultiSubnetFailover".to_string(), "Yes".to_string()),
+            ("APP".to_string(), "dbtoon; reports".to_string()),
+        ],
+    };
+    let conn = SqlServerBackend::new("db01".to_string(), None, SqlServerAuth::WindowsIntegrated, false)
+        .with_options(options)
+        .connection_string();
+    assert!(conn.starts_with("Driver={ODBC Driver 17 for SQL Server};Server=db01\\SQLEXPRESS,14330;"), "{conn}");
+    assert!(conn.contains(";Encrypt=strict;"), "{conn}");
+    assert!(conn.contains(";ApplicationIntent=ReadOnly;"), "{conn}");
+    assert!(conn.contains(";MultiSubnetFailover=Yes;"), "{conn}");
+    assert!(conn.contains(";APP={dbtoon; reports};"), "{conn}");
+}
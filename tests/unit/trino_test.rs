@@ -0,0 +1,251 @@
+use secrecy::SecretString;
+
+use dbtoon::backend::trino::{TrinoBackend, normalize_trino_type, trino_cell};
+use dbtoon::backend::{Backend, CellValue};
+use dbtoon::config::TrinoAuth;
+use dbtoon::error::DbtoonError;
+use dbtoon::params::parse_param;
+
+use super::mock_http::{MockResponse, MockServer};
+
+const STATEMENT: &str = "/v1/statement";
+
+fn backend(server: &MockServer, auth: TrinoAuth) -> TrinoBackend {
+    TrinoBackend::new(
+        server.url(),
+        None,
+        "analyst".to_string(),
+        auth,
+        Some("hive".to_string()),
+        Some("sales".to_string()),
+    )
+}
+
+/// A query whose 5 rows arrive over three pages after an initial page without data.
+fn serve_paged_result(server: &MockServer) {
+    let url = server.url();
+    let columns = r#"[{"name":"n","type":"bigint"},{"name":"price","type":"decimal(10,2)"}]"#;
+    server.on(
+        "POST",
+        STATEMENT,
+        MockResponse::json(200, &format!(r#"{{"id":"q1","nextUri":"{url}/v1/statement/executing/q1/1","stats":{{"state":"QUEUED"}}}}"#)),
+    );
+    server.on(
+        "GET",
+        "/v1/statement/executing/q1/1",
+        MockResponse::json(
+            200,
+            &format!(
+                r#"{{"id":"q1","nextUri":"{url}/v1/statement/executing/q1/2","columns":{columns},
+                "data":[[1,"1.50"],[2,"2.50"]],"stats":{{"state":"RUNNING"}}}}"#
+            ),
+        ),
+    );
+    server.on(
+        "GET",
+        "/v1/statement/executing/q1/2",
+        MockResponse::json(
+            200,
+            &format!(
+                r#"{{"id":"q1","nextUri":"{url}/v1/statement/executing/q1/3","columns":{columns},
+                "data":[[3,"3.50"],[4,null]],"stats":{{"state":"RUNNING"}}}}"#
+            ),
+        ),
+    );
+    server.on(
+        "GET",
+        "/v1/statement/executing/q1/3",
+        MockResponse::json(
+            200,
+            &format!(r#"{{"id":"q1","columns":{columns},"data":[[5,"5.50"]],"stats":{{"state":"FINISHED"}}}}"#),
+        ),
+    );
+    server.on("DELETE", "/v1/statement/executing/q1/3", MockResponse::json(204, ""));
+}
+
+#[test]
+fn normalizes_type_signatures_for_columnar_output() {
+    assert_eq!(normalize_trino_type("integer"), "INT");
+    assert_eq!(normalize_trino_type("tinyint"), "SMALLINT");
+    assert_eq!(normalize_trino_type("decimal(12,2)"), "DECIMAL(12,2)");
+    assert_eq!(normalize_trino_type("varchar(40)"), "VARCHAR(40)");
+    assert_eq!(normalize_trino_type("varchar"), "VARCHAR");
+    assert_eq!(normalize_trino_type("timestamp(3)"), "TIMESTAMP");
+    assert_eq!(normalize_trino_type("timestamp(3) with time zone"), "TIMESTAMP(3) WITH TIME ZONE");
+    assert_eq!(normalize_trino_type("array(integer)"), "ARRAY(INTEGER)");
+}
+
+#[test]
+fn decodes_json_values() {
+    use serde_json::json;
+    assert_eq!(trino_cell(&json!(42), "BIGINT"), CellValue::Int64(42));
+    assert_eq!(trino_cell(&json!(1.5), "DOUBLE"), CellValue::Float64(1.5));
+    assert!(matches!(trino_cell(&json!("NaN"), "DOUBLE"), CellValue::Float64(f) if f.is_nan()));
+    assert_eq!(trino_cell(&json!("-12.50"), "DECIMAL(12,2)"), CellValue::Decimal { value: -1250, scale: 2 });
+    assert_eq!(trino_cell(&json!("2024-01-01"), "DATE"), CellValue::Date(19_723));
+    assert_eq!(
+        trino_cell(&json!("2024-01-01 00:00:01.500"), "TIMESTAMP"),
+        CellValue::Timestamp(1_704_067_201_500_000)
+    );
+    assert_eq!(trino_cell(&json!("12:30:00.000"), "TIME"), CellValue::Time(45_000_000_000));
+    assert_eq!(trino_cell(&json!("Cv8="), "VARBINARY"), CellValue::Bytes(vec![0x0a, 0xff]));
+    assert_eq!(trino_cell(&json!([1, 2]), "ARRAY(INTEGER)"), CellValue::Text("[1,2]".to_string()));
+    assert_eq!(trino_cell(&json!(true), "BOOLEAN"), CellValue::Bool(true));
+    assert_eq!(trino_cell(&json!(null), "BIGINT"), CellValue::Null);
+}
+
+#[tokio::test]
+async fn follows_next_uri_until_finished() {
+    let server = MockServer::start();
+    serve_paged_result(&server);
+
+    let result = backend(&server, TrinoAuth::Password(SecretString::from("pw")))
+        .execute("SELECT n, price FROM orders", &[], None, 60)
+        .await
+        .unwrap();
+
+    let types: Vec<&str> = result.columns.iter().map(|c| c.type_name.as_str()).collect();
+    assert_eq!(types, vec!["BIGINT", "DECIMAL(10,2)"]);
+    let values: Vec<CellValue> = result.rows.iter().map(|r| r[0].clone()).collect();
+    assert_eq!(values, (1..=5).map(CellValue::Int64).collect::<Vec<_>>());
+    assert_eq!(result.rows[0][1], CellValue::Decimal { value: 150, scale: 2 });
+    assert_eq!(result.rows[3][1], CellValue::Null);
+    assert!(!result.truncated);
+
+    let submit = &server.requests_to(STATEMENT)[0];
+    assert_eq!(submit.body, "SELECT n, price FROM orders");
+    assert_eq!(submit.headers["x-trino-user"], "analyst");
+    assert_eq!(submit.headers["x-trino-catalog"], "hive");
+    assert_eq!(submit.headers["x-trino-schema"], "sales");
+    assert_eq!(submit.headers["x-trino-session"], "query_max_run_time=60s");
+    // "analyst:pw"
+    assert_eq!(submit.headers["authorization"], "Basic YW5hbHlzdDpwdw==");
+    assert!(server.requests().iter().all(|r| r.method != "DELETE"));
+}
+
+#[tokio::test]
+async fn row_limit_stops_fetching_and_cancels_query() {
+    let server = MockServer::start();
+    serve_paged_result(&server);
+
+    let result = backend(&server, TrinoAuth::None)
+        .execute("SELECT n, price FROM orders", &[], Some(3), 60)
+        .await
+        .unwrap();
+
+    assert_eq!(result.rows.len(), 3);
+    assert!(result.truncated);
+    let cancels: Vec<_> = server.requests().into_iter().filter(|r| r.method == "DELETE").collect();
+    assert_eq!(cancels.len(), 1);
+    assert_eq!(cancels[0].path, "/v1/statement/executing/q1/3");
+    assert!(server.requests_to("/v1/statement/executing/q1/3").iter().all(|r| r.method == "DELETE"));
+    assert!(!server.requests_to(STATEMENT)[0].headers.contains_key("authorization"));
+}
+
+#[tokio::test]
+async fn row_limit_equal_to_result_is_not_truncated() {
+    let server = MockServer::start();
+    serve_paged_result(&server);
+
+    let result = backend(&server, TrinoAuth::None)
+        .execute("SELECT n, price FROM orders", &[], Some(5), 60)
+        .await
+        .unwrap();
+
+    assert_eq!(result.rows.len(), 5);
+    assert!(!result.truncated);
+}
+
+#[tokio::test]
+async fn params_run_as_prepared_statement() {
+    let server = MockServer::start();
+    server.on(
+        "POST",
+        STATEMENT,
+        MockResponse::json(200, r#"{"id":"q2","columns":[{"name":"ok","type":"boolean"}],"data":[[true]]}"#),
+    );
+    let params = [
+        parse_param("id=7:INT").unwrap(),
+        parse_param("name=O'Brien").unwrap(),
+        parse_param("day=2024-01-01:DATE").unwrap(),
+    ];
+
+    let result = backend(&server, TrinoAuth::Jwt(SecretString::from("jwt-1")))
+        .execute("SELECT true AS ok FROM t WHERE id = :id AND name = :name AND day = :day", &params, None, 60)
+        .await
+        .unwrap();
+
+    assert_eq!(result.rows, vec![vec![CellValue::Bool(true)]]);
+    let submit = &server.requests_to(STATEMENT)[0];
+    assert_eq!(submit.body, "EXECUTE dbtoon_statement USING 7, 'O''Brien', DATE '2024-01-01'");
+    assert_eq!(
+        submit.headers["x-trino-prepared-statement"],
+        "dbtoon_statement=SELECT+true+AS+ok+FROM+t+WHERE+id+%3D+%3F+AND+name+%3D+%3F+AND+day+%3D+%3F"
+    );
+    assert_eq!(submit.headers["authorization"], "Bearer jwt-1");
+}
+
+#[tokio::test]
+async fn failed_query_reports_error_name() {
+    let server = MockServer::start();
+    let url = server.url();
+    server.on(
+        "POST",
+        STATEMENT,
+        MockResponse::json(200, &format!(r#"{{"id":"q3","nextUri":"{url}/v1/statement/executing/q3/1"}}"#)),
+    );
+    server.on(
+        "GET",
+        "/v1/statement/executing/q3/1",
+        MockResponse::json(
+            200,
+            r#"{"id":"q3","stats":{"state":"FAILED"},
+            "error":{"message":"line 1:15: Table 'hive.sales.nope' does not exist","errorCode":46,"errorName":"TABLE_NOT_FOUND","errorType":"USER_ERROR"}}"#,
+        ),
+    );
+
+    let err = backend(&server, TrinoAuth::None)
+        .execute("SELECT * FROM nope", &[], None, 60)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, DbtoonError::Query { .. }), "{err:?}");
+    assert!(err.to_string().contains("[TABLE_NOT_FOUND] line 1:15"), "{err}");
+}
+
+#[tokio::test]
+async fn presto_headers_and_update_count() {
+    let server = MockServer::start();
+    server.on(
+        "POST",
+        STATEMENT,
+        MockResponse::json(
+            200,
+            r#"{"id":"q4","updateType":"INSERT","updateCount":12,"columns":[{"name":"rows","type":"bigint"}],"data":[[12]],
+            "warnings":[{"message":"old syntax"}]}"#,
+        ),
+    );
+
+    let result = backend(&server, TrinoAuth::None)
+        .with_presto_headers(true)
+        .execute("INSERT INTO t SELECT * FROM s", &[], None, 60)
+        .await
+        .unwrap();
+
+    assert_eq!(result.rows_affected, Some(12));
+    assert_eq!(result.messages, vec!["old syntax".to_string()]);
+    let submit = &server.requests_to(STATEMENT)[0];
+    assert_eq!(submit.headers["x-presto-user"], "analyst");
+    assert!(!submit.headers.contains_key("x-trino-user"));
+}
+
+#[tokio::test]
+async fn rejected_credentials_are_an_auth_error() {
+    let server = MockServer::start();
+    server.on("POST", STATEMENT, MockResponse::json(401, "Unauthorized"));
+
+    let err = backend(&server, TrinoAuth::Password(SecretString::from("wrong")))
+        .execute("SELECT 1", &[], None, 60)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, DbtoonError::Auth { .. }), "{err:?}");
+}
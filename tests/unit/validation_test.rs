@@ -1,4 +1,4 @@
-use dbtoon::validation::{validate, BackendDialect, ValidationResult};
+use dbtoon::validation::{is_plain_query, validate, BackendDialect, ValidationResult};
 
 fn assert_safe(sql: &str, dialect: BackendDialect) {
     match validate(sql, dialect) {
@@ -435,3 +435,192 @@ fn test_exec_sp_help_evil_denied() {
 fn test_databricks_select_allowed() {
     assert_safe("SELECT * FROM main.default.my_table", BackendDialect::Databricks);
 }
+
+// --- ODBC dialects ---
+
+#[test]
+fn test_odbc_dialect_names() {
+    assert_eq!(BackendDialect::from_name("postgresql"), Some(BackendDialect::Postgres));
+    assert_eq!(BackendDialect::from_name("MySQL"), Some(BackendDialect::MySql));
+    assert_eq!(BackendDialect::from_name("snowflake"), Some(BackendDialect::Snowflake));
+    assert_eq!(BackendDialect::from_name("generic"), Some(BackendDialect::Generic));
+    assert_eq!(BackendDialect::from_name("oracle"), None);
+}
+
+#[test]
+fn test_postgres_cast_allowed() {
+    assert_safe("SELECT id::text FROM users WHERE tags @> ARRAY['a']", BackendDialect::Postgres);
+}
+
+#[test]
+fn test_mysql_backticks_allowed() {
+    assert_safe("SELECT `id` FROM `orders` LIMIT 10", BackendDialect::MySql);
+}
+
+#[test]
+fn test_generic_insert_denied() {
+    assert_denied("INSERT INTO t VALUES (1)", BackendDialect::Generic, "Dml");
+}
+
+#[test]
+fn test_snowflake_delete_denied() {
+    assert_denied("DELETE FROM t WHERE id = 1", BackendDialect::Snowflake, "Dml");
+}
+
+// --- SQLite dialect ---
+
+#[test]
+fn test_sqlite_file_reference_allowed() {
+    assert_safe("SELECT * FROM 'out.parquet' p JOIN 'extract.csv' e ON e.id = p.id", BackendDialect::Sqlite);
+}
+
+#[test]
+fn test_sqlite_pragma_assignment_denied() {
+    assert_denied("PRAGMA writable_schema = 1", BackendDialect::Sqlite, "Operational");
+    assert_denied("PRAGMA journal_mode = 'DELETE'", BackendDialect::Sqlite, "Operational");
+    // Keyword values do not parse, which denies them too
+    assert_denied("PRAGMA journal_mode = DELETE", BackendDialect::Sqlite, "ParseFailure");
+}
+
+#[test]
+fn test_sqlite_pragma_call_form_denied() {
+    assert_denied("PRAGMA foreign_keys(0)", BackendDialect::Sqlite, "Operational");
+    assert_denied("PRAGMA journal_mode('DELETE')", BackendDialect::Sqlite, "Operational");
+    assert_denied("PRAGMA foreign_keys(OFF)", BackendDialect::Sqlite, "ParseFailure");
+}
+
+#[test]
+fn test_sqlite_pragma_without_value_allowed() {
+    assert_safe("PRAGMA foreign_keys", BackendDialect::Sqlite);
+    assert_safe("SELECT * FROM pragma_table_info('t')", BackendDialect::Sqlite);
+}
+
+#[test]
+fn test_sqlite_attach_denied() {
+    assert_denied("ATTACH DATABASE 'other.db' AS other", BackendDialect::Sqlite, "Operational");
+}
+
+// --- Trino (generic dialect) ---
+
+#[test]
+fn test_trino_queries_allowed() {
+    for sql in [
+        "SELECT * FROM hive.sales.orders LIMIT 10",
+        "SELECT x FROM UNNEST(ARRAY[1, 2, 3]) AS t(x)",
+        "SELECT CAST(total AS varchar), approx_distinct(customer_id) FROM orders GROUP BY 1",
+        "SHOW SCHEMAS FROM hive",
+        "DESCRIBE hive.sales.orders",
+        "EXPLAIN SELECT 1",
+    ] {
+        assert_safe(sql, BackendDialect::Generic);
+    }
+}
+
+#[test]
+fn test_trino_ctas_denied() {
+    assert_denied("CREATE TABLE hive.tmp.t AS SELECT * FROM orders", BackendDialect::Generic, "Ddl");
+}
+
+// --- MySQL backend ---
+
+#[test]
+fn test_mysql_queries_allowed() {
+    for sql in [
+        "SHOW TABLES",
+        "SHOW FULL TABLES FROM app",
+        "SHOW CREATE TABLE orders",
+        "DESCRIBE orders",
+        "EXPLAIN SELECT * FROM orders WHERE id = 1",
+        "SELECT DATE_FORMAT(created_at, '%Y-%m') AS month, COUNT(*) FROM orders GROUP BY 1",
+    ] {
+        assert_safe(sql, BackendDialect::MySql);
+    }
+}
+
+#[test]
+fn test_mysql_replace_denied() {
+    assert_denied("REPLACE INTO orders VALUES (1)", BackendDialect::MySql, "Dml");
+}
+
+#[test]
+fn test_mysql_into_outfile_denied() {
+    assert!(matches!(
+        validate("SELECT * INTO OUTFILE '/tmp/orders.csv' FROM orders", BackendDialect::MySql),
+        ValidationResult::Denied { .. }
+    ));
+    assert_denied("SELECT id INTO @last FROM orders LIMIT 1", BackendDialect::MySql, "SelectInto");
+}
+
+#[test]
+fn test_mysql_lock_tables_denied() {
+    assert_denied("LOCK TABLES orders READ", BackendDialect::MySql, "Operational");
+}
+
+// --- ClickHouse backend ---
+
+#[test]
+fn test_clickhouse_queries_allowed() {
+    for sql in [
+        "SELECT * FROM events FINAL WHERE day = today() LIMIT 10",
+        "SELECT user_id, count() FROM events PREWHERE event = 'click' GROUP BY user_id",
+        "SELECT arrayJoin([1, 2, 3]) AS x",
+        "SELECT * FROM events LIMIT 1 BY user_id",
+        "SELECT count() FROM events SETTINGS max_threads = 4",
+        "SHOW TABLES",
+        "DESCRIBE TABLE events",
+        "EXPLAIN SELECT 1",
+    ] {
+        assert_safe(sql, BackendDialect::ClickHouse);
+    }
+    assert_eq!(BackendDialect::from_name("ClickHouse"), Some(BackendDialect::ClickHouse));
+}
+
+#[test]
+fn test_clickhouse_writes_denied() {
+    assert_denied("INSERT INTO events SELECT * FROM staging", BackendDialect::ClickHouse, "Dml");
+    assert_denied("OPTIMIZE TABLE events FINAL", BackendDialect::ClickHouse, "Operational");
+    for sql in ["ALTER TABLE events DELETE WHERE day < today()", "SYSTEM DROP DNS CACHE"] {
+        assert!(
+            matches!(validate(sql, BackendDialect::ClickHouse), ValidationResult::Denied { .. }),
+            "{sql}"
+        );
+    }
+}
+
+// --- BigQuery backend ---
+
+#[test]
+fn test_bigquery_queries_allowed() {
+    for sql in [
+        "SELECT * FROM `my-project.analytics.events` WHERE _PARTITIONDATE = CURRENT_DATE()",
+        "SELECT * EXCEPT (payload) FROM analytics.events",
+        "SELECT tag FROM analytics.events, UNNEST(tags) AS tag",
+        "SELECT * FROM `analytics.events_*` WHERE _TABLE_SUFFIX BETWEEN '20240101' AND '20240131'",
+        "SELECT STRUCT(1 AS a, 'x' AS b) AS s",
+    ] {
+        assert_safe(sql, BackendDialect::BigQuery);
+    }
+    assert_eq!(BackendDialect::from_name("bigquery"), Some(BackendDialect::BigQuery));
+}
+
+#[test]
+fn test_bigquery_writes_denied() {
+    assert_denied("INSERT INTO analytics.events SELECT * FROM analytics.staging", BackendDialect::BigQuery, "Dml");
+    assert_denied(
+        "EXPORT DATA OPTIONS (uri = 'gs://bucket/out/*.csv', format = 'CSV') AS SELECT * FROM analytics.events",
+        BackendDialect::BigQuery,
+        "Operational",
+    );
+    assert_denied("DROP TABLE analytics.events", BackendDialect::BigQuery, "Ddl");
+}
+
+#[test]
+fn test_plain_queries_leave_the_session_alone() {
+    assert!(is_plain_query("SELECT 1; WITH t AS (SELECT 2 AS n) SELECT n FROM t", BackendDialect::SqlServer));
+    assert!(is_plain_query("SELECT 1 UNION ALL SELECT 2", BackendDialect::Sqlite));
+    assert!(!is_plain_query("USE mydb", BackendDialect::SqlServer));
+    assert!(!is_plain_query("SELECT 1; SET NOCOUNT ON", BackendDialect::SqlServer));
+    assert!(!is_plain_query("SELECT * INTO #t FROM users", BackendDialect::SqlServer));
+    assert!(!is_plain_query("BEGIN TRANSACTION", BackendDialect::SqlServer));
+    assert!(!is_plain_query("SELEC 1", BackendDialect::Sqlite));
+}
@@ -0,0 +1,45 @@
+use dbtoon::backend::CellValue;
+
+#[test]
+fn typed_text_parses_declared_types() {
+    assert_eq!(CellValue::from_typed_text("42", "INT"), CellValue::Int64(42));
+    assert_eq!(CellValue::from_typed_text("-7", "LONG"), CellValue::Int64(-7));
+    assert_eq!(CellValue::from_typed_text("1.5", "DOUBLE"), CellValue::Float64(1.5));
+    assert_eq!(
+        CellValue::from_typed_text("-0.05", "DECIMAL(10,2)"),
+        CellValue::Decimal { value: -5, scale: 2 }
+    );
+    assert_eq!(CellValue::from_typed_text("1", "BIT"), CellValue::Bool(true));
+    assert_eq!(CellValue::from_typed_text("1970-01-02", "DATE"), CellValue::Date(1));
+    assert_eq!(
+        CellValue::from_typed_text("1970-01-01 00:00:01.5", "DATETIME2(7)"),
+        CellValue::Timestamp(1_500_000)
+    );
+    assert_eq!(CellValue::from_typed_text("01:00:00", "TIME(7)"), CellValue::Time(3_600_000_000));
+}
+
+#[test]
+fn typed_text_keeps_unparseable_and_unknown_values_as_text() {
+    assert_eq!(CellValue::from_typed_text("n/a", "INT"), CellValue::Text("n/a".into()));
+    assert_eq!(CellValue::from_typed_text("1e3", "DECIMAL"), CellValue::Text("1e3".into()));
+    assert_eq!(CellValue::from_typed_text("42", "VARCHAR(10)"), CellValue::Text("42".into()));
+}
+
+#[test]
+fn to_text_formats_native_values() {
+    let text = |v: CellValue| v.to_text().map(|t| t.into_owned());
+
+    assert_eq!(text(CellValue::Null), None);
+    assert_eq!(text(CellValue::Int64(-3)).as_deref(), Some("-3"));
+    assert_eq!(text(CellValue::Bool(false)).as_deref(), Some("false"));
+    assert_eq!(text(CellValue::Decimal { value: 1250, scale: 2 }).as_deref(), Some("12.50"));
+    assert_eq!(text(CellValue::Decimal { value: -5, scale: 3 }).as_deref(), Some("-0.005"));
+    assert_eq!(text(CellValue::Date(19783)).as_deref(), Some("2024-03-01"));
+    assert_eq!(text(CellValue::Date(-1)).as_deref(), Some("1969-12-31"));
+    assert_eq!(
+        text(CellValue::Timestamp(19783 * 86_400_000_000 + 37_800_000_250)).as_deref(),
+        Some("2024-03-01T10:30:00.000250")
+    );
+    assert_eq!(text(CellValue::Time(3_661_000_000)).as_deref(), Some("01:01:01"));
+    assert_eq!(text(CellValue::Bytes(vec![0x0A, 0xFF])).as_deref(), Some("0x0AFF"));
+}